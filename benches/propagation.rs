@@ -0,0 +1,12 @@
+//! Benchmark of the context propagation codecs.
+//!
+//! Runs the `3-propagation-codecs` example, see the example for details.
+//!
+//! Store a baseline with `cargo bench --bench propagation -- --save-baseline PATH`
+//! and compare later runs with `cargo bench --bench propagation -- --baseline PATH`.
+#[path = "../examples/3-propagation-codecs.rs"]
+mod codecs;
+
+fn main() {
+    codecs::main();
+}
@@ -6,7 +6,6 @@ use std::any::Any;
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::sync::Mutex;
-use std::time::Duration;
 
 use crossbeam_channel::unbounded;
 use rand::random;
@@ -46,21 +45,16 @@ impl ImplContext for InnerContext {
 
     fn clone(&self) -> Box<dyn ImplContext> {
         Box::new(InnerContext {
-            trace_id: self.trace_id.clone(),
-            span_id: self.span_id.clone(),
+            trace_id: self.trace_id,
+            span_id: self.span_id,
         })
     }
 
     // The aim of this function is simply to update any trace identifiers.
     // Keeping trak of references is a task for the `SpanContext`, not for the inner context.
     fn reference_span(&mut self, reference: &SpanReference) {
-        match *reference {
-            SpanReference::ChildOf(ref parent) |
-            SpanReference::FollowsFrom(ref parent) => {
-                let context = parent.impl_context::<InnerContext>().unwrap();
-                self.trace_id = context.trace_id;
-            }
-        }
+        let context = reference.context().impl_context::<InnerContext>().unwrap();
+        self.trace_id = context.trace_id;
     }
 }
 
@@ -101,7 +95,7 @@ impl TracerInterface for MemoryTracer {
                 // Decode baggage items.
                 for (key, value) in carrier.items() {
                     if key.starts_with(BAGGAGE_KEY_PREFIX) {
-                        context.set_baggage_item(key.clone(), value.clone());
+                        context.set_baggage_item(String::from(key), String::from(value));
                     }
                 }
                 Ok(Some(context))
@@ -129,22 +123,23 @@ impl TracerInterface for MemoryTracer {
         }
     }
 
-    fn span(&self, name: &str, options: StartOptions) -> Span {
+    fn span(&self, name: Arc<str>, options: StartOptions) -> Span {
         let trace_id = random::<u64>();
         let span_id = random::<u64>();
         let context = SpanContext::new(InnerContext {
             trace_id,
             span_id
         });
-        Span::new(name, context, options, self.sender.clone())
+        Span::new(name, context, options, Arc::clone(&self.sender))
     }
 }
 
 impl MemoryTracer {
+    #[allow(clippy::new_ret_no_self)]
     pub fn new() -> (Tracer, SpanReceiver, MemoryTracerStore) {
         let (sender, receiver) = unbounded();
-        let tracer = MemoryTracer { sender };
-        (Tracer::new(tracer), receiver, Mutex::new(HashMap::new()))
+        let tracer = MemoryTracer { sender: Arc::new(sender) };
+        (Tracer::new(tracer), SpanReceiver::new(receiver), Mutex::new(HashMap::new()))
     }
 
     pub fn store(store: &MemoryTracerStore, span: FinishedSpan) {
@@ -153,11 +148,7 @@ impl MemoryTracer {
             context.trace_id
         };
         let mut traces = store.lock().unwrap();
-        if traces.contains_key(&trace_id) {
-            traces.get_mut(&trace_id).unwrap().push(span);
-        } else {
-            traces.insert(trace_id, vec![span]);
-        }
+        traces.entry(trace_id).or_default().push(span);
     }
 
     pub fn print_store(store: &MemoryTracerStore) {
@@ -186,10 +177,9 @@ fn main() {
 
     let store = Arc::new(store);
     let inner_store = Arc::clone(&store);
-    let mut reporter = ReporterThread::new(receiver, move |span| {
+    let reporter = ReporterThread::new(receiver, move |span| {
         MemoryTracer::store(&inner_store, span);
     });
-    reporter.stop_delay(Duration::from_secs(2));
 
     // Do some work.
     {
@@ -19,7 +19,6 @@ fn main() {
         let mut stderr = io::stderr();
         FileTracer::write_trace(span, &mut stderr).unwrap();
     });
-    reporter.stop_delay(Duration::from_secs(2));
 
     // Now spawn some threads that create spans.
     let mut threads: Vec<thread::JoinHandle<()>> = Vec::new();
@@ -41,4 +40,7 @@ fn main() {
     for thread in threads {
         thread.join().unwrap();
     }
+
+    // Report any span left in the channel.
+    reporter.stop();
 }
@@ -0,0 +1,220 @@
+//! This example compares the context propagation codecs available in opentracing-rust.
+//!
+//! For each codec the same context (with a couple of baggage items) is injected and
+//! extracted many times and the example reports:
+//!
+//!   * The size of the encoded context (an approximation of the bytes sent on the wire).
+//!   * The average time to inject the context.
+//!   * The average time to extract the context.
+//!
+//! The results can guide the choice of propagation format and the same code
+//! runs as the `propagation` benchmark (`cargo bench --bench propagation`)
+//! to catch performance regressions in the codecs.
+//!
+//! Arguments:
+//!
+//!   * A number sets the iterations per codec (defaults to 100000).
+//!   * `--save-baseline PATH` stores the measured timings in `PATH`.
+//!   * `--baseline PATH` compares the timings with the ones stored in `PATH` and
+//!     exits with an error if any codec is more than 20% slower.
+//!
+//! Codecs measured, both through `Tracer::inject` and `Tracer::extract`:
+//!
+//!   * `native-headers`: the `FileTracer` HTTP headers format.
+//!   * `binary`: the `FileTracer` binary format (the `propagation::binary` codec).
+//!
+//! Codecs for other formats (W3C trace context, B3, ...) are implemented by concrete
+//! tracers outside of this crate: add them to `codecs` to compare them too.
+extern crate opentracingrust;
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::hint::black_box;
+use std::process;
+use std::time::Duration;
+use std::time::Instant;
+
+use opentracingrust::ExtractFormat;
+use opentracingrust::InjectFormat;
+use opentracingrust::SpanContext;
+use opentracingrust::Tracer;
+use opentracingrust::tracers::FileTracer;
+
+
+const ITERATIONS_DEFAULT: u32 = 100_000;
+
+/// Slowdown, compared to the baseline, above which the comparison fails.
+const REGRESSION_THRESHOLD: f64 = 1.2;
+
+
+/// A propagation codec to measure.
+struct Codec<'a> {
+    name: &'static str,
+    /// Encodes the context and returns the size of the payload.
+    inject: Box<dyn Fn(&SpanContext) -> usize + 'a>,
+    /// Decodes the context from the payload created by the first injection.
+    extract: Box<dyn Fn() -> Option<SpanContext> + 'a>,
+}
+
+/// Timings of a codec, as measured or loaded from a baseline.
+struct Timings {
+    name: String,
+    inject: Duration,
+    extract: Duration,
+}
+
+
+/// Builds the list of codecs to compare.
+fn codecs<'a>(tracer: &'a Tracer, context: &SpanContext) -> Vec<Codec<'a>> {
+    // Native FileTracer headers.
+    let mut headers: HashMap<String, String> = HashMap::new();
+    tracer.inject(context, InjectFormat::HttpHeaders(Box::new(&mut headers)))
+        .expect("Failed to inject context");
+    let native = Codec {
+        name: "native-headers",
+        inject: Box::new(move |context| {
+            let mut headers: HashMap<String, String> = HashMap::new();
+            tracer.inject(context, InjectFormat::HttpHeaders(Box::new(&mut headers)))
+                .expect("Failed to inject context");
+            // Each header is sent as `key: value\r\n`.
+            headers.iter().map(|(key, value)| key.len() + value.len() + 4).sum()
+        }),
+        extract: Box::new(move || {
+            tracer.extract(ExtractFormat::HttpHeaders(Box::new(&headers)))
+                .expect("Failed to extract context")
+        }),
+    };
+
+    // FileTracer binary frames.
+    let mut frame: Vec<u8> = Vec::new();
+    tracer.inject(context, InjectFormat::Binary(Box::new(&mut frame)))
+        .expect("Failed to inject context");
+    let binary = Codec {
+        name: "binary",
+        inject: Box::new(move |context| {
+            let mut frame: Vec<u8> = Vec::new();
+            tracer.inject(context, InjectFormat::Binary(Box::new(&mut frame)))
+                .expect("Failed to inject context");
+            frame.len()
+        }),
+        extract: Box::new(move || {
+            tracer.extract(ExtractFormat::Binary(Box::new(&mut &frame[..])))
+                .expect("Failed to extract context")
+        }),
+    };
+
+    vec![native, binary]
+}
+
+/// Returns the average duration of `iterations` calls to `operation`.
+fn measure<T, F: FnMut() -> T>(iterations: u32, mut operation: F) -> Duration {
+    let start = Instant::now();
+    for _ in 0..iterations {
+        // Stop the compiler from optimising away unused results.
+        black_box(operation());
+    }
+    start.elapsed() / iterations
+}
+
+/// Loads timings stored with `--save-baseline`.
+fn load_baseline(path: &str) -> Vec<Timings> {
+    let baseline = fs::read_to_string(path).expect("Failed to read baseline");
+    baseline.lines()
+        .map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let nanos = |field: &str| Duration::from_nanos(
+                field.parse().expect("Invalid baseline timing")
+            );
+            match fields.as_slice() {
+                [name, inject, extract] => Timings {
+                    name: name.to_string(),
+                    inject: nanos(inject),
+                    extract: nanos(extract),
+                },
+                _ => panic!("Invalid baseline line: {}", line),
+            }
+        })
+        .collect()
+}
+
+/// Stores timings for later runs to compare against with `--baseline`.
+fn save_baseline(path: &str, timings: &[Timings]) {
+    let baseline: String = timings.iter()
+        .map(|timings| format!(
+            "{} {} {}\n", timings.name, timings.inject.as_nanos(), timings.extract.as_nanos()
+        ))
+        .collect();
+    fs::write(path, baseline).expect("Failed to write baseline");
+}
+
+/// Compares timings with the baseline and returns `false` if any codec regressed.
+fn compare(timings: &[Timings], baseline: &[Timings]) -> bool {
+    let mut ok = true;
+    for timings in timings {
+        let base = match baseline.iter().find(|base| base.name == timings.name) {
+            Some(base) => base,
+            None => {
+                println!("{:<16} not in baseline", timings.name);
+                continue;
+            }
+        };
+        let ratio = |current: Duration, base: Duration| {
+            current.as_nanos() as f64 / base.as_nanos().max(1) as f64
+        };
+        let inject = ratio(timings.inject, base.inject);
+        let extract = ratio(timings.extract, base.extract);
+        let regressed = inject > REGRESSION_THRESHOLD || extract > REGRESSION_THRESHOLD;
+        println!(
+            "{:<16} {:>8} {:>11.0}% {:>11.0}%",
+            timings.name, if regressed { "SLOWER" } else { "ok" },
+            (inject - 1.0) * 100.0, (extract - 1.0) * 100.0
+        );
+        ok &= !regressed;
+    }
+    ok
+}
+
+
+pub fn main() {
+    // `cargo bench` passes flags to the benchmark so ignore arguments we do not know.
+    let args: Vec<String> = env::args().skip(1).collect();
+    let option = |name: &str| args.iter()
+        .position(|arg| arg == name)
+        .and_then(|index| args.get(index + 1));
+    let iterations = args.iter()
+        .find_map(|arg| arg.parse::<u32>().ok())
+        .unwrap_or(ITERATIONS_DEFAULT);
+
+    let (tracer, _receiver) = FileTracer::new();
+    let mut span = tracer.span("propagate");
+    span.set_baggage_item("user", "42");
+    span.set_baggage_item("tenant", "acme");
+    let context = span.context().clone();
+
+    println!("Measuring {} iterations per codec", iterations);
+    println!("{:<16} {:>8} {:>12} {:>12}", "codec", "bytes", "inject", "extract");
+    let mut measured = Vec::new();
+    for codec in codecs(&tracer, &context) {
+        let size = (codec.inject)(&context);
+        assert!((codec.extract)().is_some(), "Context not found");
+        let inject = measure(iterations, || (codec.inject)(black_box(&context)));
+        let extract = measure(iterations, || (codec.extract)());
+        println!(
+            "{:<16} {:>8} {:>12} {:>12}",
+            codec.name, size, format!("{:?}", inject), format!("{:?}", extract)
+        );
+        measured.push(Timings { name: codec.name.to_string(), inject, extract });
+    }
+
+    if let Some(path) = option("--save-baseline") {
+        save_baseline(path, &measured);
+    }
+    if let Some(path) = option("--baseline") {
+        println!();
+        println!("{:<16} {:>8} {:>12} {:>12}", "codec", "result", "inject", "extract");
+        if !compare(&measured, &load_baseline(path)) {
+            process::exit(1);
+        }
+    }
+}
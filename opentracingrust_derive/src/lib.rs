@@ -0,0 +1,232 @@
+//! Procedural macros for OpenTracingRust.
+//!
+//! These macros are re-exported by `opentracingrust` when the `derive` feature is enabled
+//! and should be used through that crate rather than depended upon directly.
+extern crate proc_macro;
+extern crate proc_macro2;
+extern crate quote;
+extern crate syn;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+
+use syn::Error;
+use syn::Expr;
+use syn::ExprLit;
+use syn::FnArg;
+use syn::Ident;
+use syn::ItemFn;
+use syn::Lit;
+use syn::LitStr;
+use syn::Meta;
+use syn::Pat;
+use syn::ReturnType;
+use syn::Token;
+use syn::Type;
+use syn::parse::Parser;
+use syn::parse_macro_input;
+use syn::punctuated::Punctuated;
+
+
+/// Options accepted by the `#[traced]` attribute.
+#[derive(Default)]
+struct TracedOptions {
+    child_of: Option<Expr>,
+    name: Option<LitStr>,
+    skip: Vec<Ident>,
+}
+
+impl TracedOptions {
+    /// Parses the attribute arguments.
+    fn parse(args: TokenStream) -> Result<TracedOptions, Error> {
+        let mut options = TracedOptions::default();
+        let metas = Punctuated::<Meta, Token![,]>::parse_terminated.parse(args)?;
+        for meta in metas {
+            match meta {
+                Meta::NameValue(ref value) if value.path.is_ident("child_of") => {
+                    options.child_of = Some(value.value.clone());
+                },
+                Meta::NameValue(ref value) if value.path.is_ident("name") => {
+                    match value.value {
+                        Expr::Lit(ExprLit { lit: Lit::Str(ref name), .. }) => {
+                            options.name = Some(name.clone());
+                        },
+                        ref other => return Err(Error::new_spanned(
+                            other, "the span name must be a string literal"
+                        )),
+                    }
+                },
+                Meta::List(ref list) if list.path.is_ident("skip") => {
+                    let idents = list.parse_args_with(
+                        Punctuated::<Ident, Token![,]>::parse_terminated
+                    )?;
+                    options.skip.extend(idents);
+                },
+                other => return Err(Error::new_spanned(
+                    other, "unsupported option, expected `child_of`, `name` or `skip`"
+                )),
+            }
+        }
+        Ok(options)
+    }
+}
+
+
+/// Checks if the return type of a function is a `Result`.
+fn returns_result(output: &ReturnType) -> bool {
+    match *output {
+        ReturnType::Default => false,
+        ReturnType::Type(_, ref ty) => match **ty {
+            Type::Path(ref path) => path.path.segments.last()
+                .map(|segment| segment.ident == "Result")
+                .unwrap_or(false),
+            _ => false,
+        }
+    }
+}
+
+
+/// Expands the `#[traced]` attribute on a function.
+fn expand_traced(options: TracedOptions, function: ItemFn) -> Result<TokenStream2, Error> {
+    let ItemFn { attrs, vis, sig, block } = function;
+    if let Some(asyncness) = sig.asyncness {
+        return Err(Error::new_spanned(asyncness, "#[traced] does not support async functions"));
+    }
+
+    let name = match options.name {
+        Some(name) => name.value(),
+        None => sig.ident.to_string(),
+    };
+    let start_options = match options.child_of {
+        Some(parent) => quote! {
+            ::opentracingrust::StartOptions::default().child_of((#parent).clone())
+        },
+        None => quote! { ::opentracingrust::StartOptions::default() },
+    };
+
+    // Record arguments bound to plain identifiers, unless skipped.
+    let mut tags = Vec::new();
+    for input in &sig.inputs {
+        if let FnArg::Typed(ref argument) = *input {
+            if let Pat::Ident(ref pattern) = *argument.pat {
+                let ident = &pattern.ident;
+                if options.skip.contains(ident) {
+                    continue;
+                }
+                let tag = ident.to_string();
+                let tag = tag.trim_start_matches("r#");
+                tags.push(quote! {
+                    __otr_span.tag(#tag, ::std::format!("{:?}", #ident));
+                });
+            }
+        }
+    }
+
+    // Functions returning a `Result` run in a closure so errors
+    // returned early (with `return` or `?`) can fail the span.
+    let body = if returns_result(&sig.output) {
+        let output = match sig.output {
+            ReturnType::Type(_, ref ty) => ty,
+            ReturnType::Default => unreachable!("functions returning a Result have a type"),
+        };
+        quote! {
+            #[allow(clippy::redundant_closure_call)]
+            let __otr_result: #output = (move || -> #output #block)();
+            ::opentracingrust::utils::FailSpan::fail_span(__otr_result, &mut __otr_span)
+        }
+    } else {
+        quote! { #block }
+    };
+
+    Ok(quote! {
+        #(#attrs)*
+        #vis #sig {
+            #[allow(unused_mut)]
+            let mut __otr_span = ::opentracingrust::utils::GlobalTracer::get()
+                .span_auto_with_options(#name, #start_options);
+            #(#tags)*
+            #body
+        }
+    })
+}
+
+
+/// Wraps a function body in a span created by the `GlobalTracer`.
+///
+/// The span is named after the function and is finished when the function returns.
+/// Function arguments are recorded as tags with their `Debug` representation
+/// and functions returning a `Result` fail the span on errors (see `FailSpan`).
+///
+/// The following options are supported:
+///
+///   * `child_of = expr`: makes the span a child of the given `SpanContext`.
+///   * `name = "operation"`: sets the operation name of the span.
+///   * `skip(arg, ...)`: does not record the given arguments.
+///
+/// Arguments that are not plain identifiers (patterns and `self`) are not recorded.
+/// Async functions are not supported.
+///
+/// # Panics
+///
+/// The generated code panics if the `GlobalTracer` is not initialised.
+///
+/// # Examples
+///
+/// ```
+/// extern crate opentracingrust;
+///
+/// use opentracingrust::SpanContext;
+/// use opentracingrust::TagValue;
+/// use opentracingrust::traced;
+/// use opentracingrust::tracers::NoopTracer;
+/// use opentracingrust::utils::GlobalTracer;
+///
+///
+/// #[traced(skip(password))]
+/// fn login(user: &str, password: &str) -> Result<u64, String> {
+///     if password != "secret" {
+///         return Err(String::from("invalid password"));
+///     }
+///     Ok(42)
+/// }
+///
+/// #[traced(child_of = parent, name = "load_profile", skip(parent))]
+/// fn profile(parent: &SpanContext, id: u64) -> String {
+///     format!("user-{}", id)
+/// }
+///
+/// fn main() {
+///     let (tracer, receiver) = NoopTracer::new();
+///     GlobalTracer::init(tracer);
+///
+///     assert!(login("someone", "wrong").is_err());
+///     let span = receiver.try_recv().unwrap();
+///     assert_eq!("login", span.name());
+///     assert!(span.tags().get("password").is_none());
+///     match span.tags().get("error") {
+///         Some(&TagValue::Boolean(true)) => (),
+///         _ => panic!("Error tag not set")
+///     }
+///
+///     let root = GlobalTracer::get().span("request");
+///     assert_eq!("user-42", profile(root.context(), 42));
+///     let span = receiver.try_recv().unwrap();
+///     assert_eq!("load_profile", span.name());
+///     assert!(!span.is_root());
+///     match span.tags().get("id") {
+///         Some(&TagValue::String(ref id)) => assert_eq!("42", id),
+///         _ => panic!("Argument tag not set")
+///     }
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn traced(args: TokenStream, item: TokenStream) -> TokenStream {
+    let function = parse_macro_input!(item as ItemFn);
+    let expanded = TracedOptions::parse(args)
+        .and_then(|options| expand_traced(options, function));
+    match expanded {
+        Ok(expanded) => expanded.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
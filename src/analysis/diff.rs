@@ -0,0 +1,311 @@
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::fmt;
+use std::time::Duration;
+
+use super::super::FinishedSpan;
+use super::super::SpanReference;
+
+
+/// Separator between operation names in a `SpanPath`.
+const PATH_SEPARATOR: &str = " > ";
+
+
+/// Position of a span within a trace.
+///
+/// The path of a span is made of the operation names of its ancestors and its own
+/// joined by `" > "` (for example `"GET /users > fetch_user > db.query"`).
+/// Spans with the same path (such as queries issued in a loop) are told apart
+/// by their occurrence: the order in which they started, starting at 0.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SpanPath {
+    path: String,
+    occurrence: usize,
+}
+
+impl SpanPath {
+    /// Returns the order in which spans with the same path started.
+    pub fn occurrence(&self) -> usize {
+        self.occurrence
+    }
+
+    /// Returns the operation names leading to the span.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+impl fmt::Display for SpanPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.occurrence {
+            0 => write!(f, "{}", self.path),
+            occurrence => write!(f, "{}#{}", self.path, occurrence),
+        }
+    }
+}
+
+
+/// Duration of a span found in both traces.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SpanDiff {
+    after: Duration,
+    before: Duration,
+    path: SpanPath,
+}
+
+impl SpanDiff {
+    /// Returns the duration of the span in the second trace.
+    pub fn after(&self) -> Duration {
+        self.after
+    }
+
+    /// Returns the duration of the span in the first trace.
+    pub fn before(&self) -> Duration {
+        self.before
+    }
+
+    /// Returns the change in duration, in nanoseconds.
+    ///
+    /// The delta is positive if the span is slower in the second trace
+    /// and negative if the span is faster in the second trace.
+    pub fn delta_nanos(&self) -> i128 {
+        self.after.as_nanos() as i128 - self.before.as_nanos() as i128
+    }
+
+    /// Returns the position of the span in the traces.
+    pub fn path(&self) -> &SpanPath {
+        &self.path
+    }
+}
+
+
+/// Differences between two traces, as returned by `diff_traces`.
+///
+/// All lists are sorted by `SpanPath`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceDiff {
+    matched: Vec<SpanDiff>,
+    only_in_a: Vec<SpanPath>,
+    only_in_b: Vec<SpanPath>,
+}
+
+impl TraceDiff {
+    /// Returns `true` if both traces have spans at the same paths.
+    pub fn is_same_structure(&self) -> bool {
+        self.only_in_a.is_empty() && self.only_in_b.is_empty()
+    }
+
+    /// Returns the spans found in both traces.
+    pub fn matched(&self) -> &[SpanDiff] {
+        &self.matched
+    }
+
+    /// Returns the paths of spans only found in the first trace.
+    pub fn only_in_a(&self) -> &[SpanPath] {
+        &self.only_in_a
+    }
+
+    /// Returns the paths of spans only found in the second trace.
+    pub fn only_in_b(&self) -> &[SpanPath] {
+        &self.only_in_b
+    }
+}
+
+
+/// Compares two traces to find duration changes and structural differences.
+///
+/// Spans are aligned across traces by their `SpanPath`.
+/// Parent spans are found by following the first reference of each span
+/// to a span in the same trace: this requires the tracer's `ImplContext`
+/// to support comparisons (see `ImplContextBox::hashable`).
+/// Spans with no parent in the trace are treated as roots.
+///
+/// # Examples
+///
+/// ```
+/// extern crate opentracingrust;
+///
+/// use opentracingrust::FinishedSpan;
+/// use opentracingrust::StartOptions;
+/// use opentracingrust::Tracer;
+/// use opentracingrust::analysis::diff_traces;
+/// use opentracingrust::tracers::NoopTracer;
+///
+///
+/// fn request(tracer: &Tracer, cache_hit: bool) {
+///     let root = tracer.span("GET /users");
+///     if !cache_hit {
+///         let options = StartOptions::default().child_of(root.context().clone());
+///         tracer.span_with_options("db.query", options).finish().unwrap();
+///     }
+///     root.finish().unwrap();
+/// }
+///
+/// fn main() {
+///     let (tracer, receiver) = NoopTracer::new();
+///     request(&tracer, false);
+///     let before: Vec<FinishedSpan> = receiver.try_iter().collect();
+///     request(&tracer, true);
+///     let after: Vec<FinishedSpan> = receiver.try_iter().collect();
+///
+///     let diff = diff_traces(&before, &after);
+///     assert_eq!(1, diff.matched().len());
+///     assert_eq!("GET /users > db.query", diff.only_in_a()[0].path());
+/// }
+/// ```
+pub fn diff_traces(a: &[FinishedSpan], b: &[FinishedSpan]) -> TraceDiff {
+    let a = span_durations(a);
+    let mut b = span_durations(b);
+    let mut matched = Vec::new();
+    let mut only_in_a = Vec::new();
+    for (path, before) in a {
+        match b.remove(&path) {
+            Some(after) => matched.push(SpanDiff { after, before, path }),
+            None => only_in_a.push(path),
+        }
+    }
+    let only_in_b = b.into_keys().collect();
+    TraceDiff {
+        matched,
+        only_in_a,
+        only_in_b,
+    }
+}
+
+
+/// Computes the path and duration of each span in a trace.
+fn span_durations(spans: &[FinishedSpan]) -> BTreeMap<SpanPath, Duration> {
+    let parents: Vec<Option<usize>> = spans.iter().enumerate()
+        .map(|(index, span)| {
+            let reference = span.references().first().map(SpanReference::context);
+            reference.and_then(|parent| {
+                (0..spans.len()).find(|other| {
+                    *other != index && spans[*other].context().same_span(parent)
+                })
+            })
+        })
+        .collect();
+
+    // Visit spans in start order so occurrences are numbered consistently.
+    let mut order: Vec<usize> = (0..spans.len()).collect();
+    order.sort_by_key(|index| *spans[*index].start_time());
+
+    let mut occurrences: HashMap<String, usize> = HashMap::new();
+    let mut durations = BTreeMap::new();
+    for index in order {
+        let mut names = vec![spans[index].name()];
+        let mut current = index;
+        // Limit the walk to the size of the trace in case references form a loop.
+        while let Some(parent) = parents[current] {
+            if names.len() > spans.len() {
+                break;
+            }
+            names.push(spans[parent].name());
+            current = parent;
+        }
+        names.reverse();
+        let path = names.join(PATH_SEPARATOR);
+
+        let occurrence = occurrences.entry(path.clone()).or_insert(0);
+        let path = SpanPath {
+            path,
+            occurrence: *occurrence,
+        };
+        *occurrence += 1;
+
+        durations.insert(path, spans[index].duration());
+    }
+    durations
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use std::time::SystemTime;
+    use std::time::UNIX_EPOCH;
+
+    use super::super::super::FinishedSpan;
+    use super::super::super::SpanContext;
+    use super::super::super::SpanReceiver;
+    use super::super::super::StartOptions;
+    use super::super::super::Tracer;
+    use super::super::super::tracers::NoopTracer;
+
+    use super::diff_traces;
+
+
+    fn at(millis: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_millis(millis)
+    }
+
+    fn span(
+        tracer: &Tracer, name: &str, parent: Option<&SpanContext>, start: u64, finish: u64
+    ) -> SpanContext {
+        let mut options = StartOptions::default().start_time(at(start));
+        if let Some(parent) = parent {
+            options = options.child_of(parent.clone());
+        }
+        let mut span = tracer.span_with_options(name, options);
+        span.finish_time(at(finish));
+        let context = span.context().clone();
+        span.finish().unwrap();
+        context
+    }
+
+    fn trace(tracer: &Tracer, receiver: &SpanReceiver, queries: &[u64]) -> Vec<FinishedSpan> {
+        let root = span(tracer, "root", None, 0, 100);
+        let handler = span(tracer, "handler", Some(&root), 0, 90);
+        for (index, duration) in queries.iter().enumerate() {
+            let start = index as u64 * 10;
+            span(tracer, "query", Some(&handler), start, start + duration);
+        }
+        receiver.try_iter().collect()
+    }
+
+    #[test]
+    fn identical_traces() {
+        let (tracer, receiver) = NoopTracer::new();
+        let a = trace(&tracer, &receiver, &[5, 5]);
+        let b = trace(&tracer, &receiver, &[5, 5]);
+        let diff = diff_traces(&a, &b);
+        assert!(diff.is_same_structure());
+        let paths: Vec<String> = diff.matched().iter()
+            .map(|span| span.path().to_string())
+            .collect();
+        assert_eq!(paths, [
+            "root", "root > handler", "root > handler > query", "root > handler > query#1"
+        ]);
+        assert!(diff.matched().iter().all(|span| span.delta_nanos() == 0));
+    }
+
+    #[test]
+    fn duration_deltas() {
+        let (tracer, receiver) = NoopTracer::new();
+        let a = trace(&tracer, &receiver, &[5, 8]);
+        let b = trace(&tracer, &receiver, &[7, 2]);
+        let diff = diff_traces(&a, &b);
+        let deltas: Vec<i128> = diff.matched().iter()
+            .map(|span| span.delta_nanos() / 1_000_000)
+            .collect();
+        assert_eq!(deltas, [0, 0, 2, -6]);
+        assert_eq!(Duration::from_millis(8), diff.matched()[3].before());
+        assert_eq!(Duration::from_millis(2), diff.matched()[3].after());
+    }
+
+    #[test]
+    fn structural_differences() {
+        let (tracer, receiver) = NoopTracer::new();
+        let a = trace(&tracer, &receiver, &[5, 5, 5]);
+        let b = trace(&tracer, &receiver, &[5]);
+        let diff = diff_traces(&a, &b);
+        assert!(!diff.is_same_structure());
+        assert_eq!(3, diff.matched().len());
+        let only_in_a: Vec<String> = diff.only_in_a().iter()
+            .map(|path| path.to_string())
+            .collect();
+        assert_eq!(only_in_a, ["root > handler > query#1", "root > handler > query#2"]);
+        assert!(diff.only_in_b().is_empty());
+        assert!(!diff_traces(&b, &a).only_in_b().is_empty());
+    }
+}
@@ -0,0 +1,10 @@
+//! Tools to inspect and compare finished traces.
+//!
+//! These utilities operate on the `FinishedSpan`s collected from a `SpanReceiver`
+//! and are mostly useful in tests and offline investigations.
+mod diff;
+
+pub use self::diff::diff_traces;
+pub use self::diff::SpanDiff;
+pub use self::diff::SpanPath;
+pub use self::diff::TraceDiff;
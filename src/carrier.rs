@@ -1,3 +1,4 @@
+use std::any::Any;
 use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::io;
@@ -29,7 +30,14 @@ use std::io;
 pub enum ExtractFormat<'a> {
     Binary(Box<&'a mut dyn self::io::Read>),
     HttpHeaders(Box<&'a dyn MapCarrier>),
-    TextMap(Box<&'a dyn MapCarrier>)
+    TextMap(Box<&'a dyn MapCarrier>),
+
+    /// Tracer-specific format identified by name.
+    ///
+    /// Tracers can support vendor-specific carriers by downcasting the carrier
+    /// to the type expected for the named format.
+    /// Tracers that do not support the format should return an `Error`.
+    Custom(&'a str, Box<&'a dyn Any>)
 }
 
 
@@ -56,7 +64,61 @@ pub enum ExtractFormat<'a> {
 pub enum InjectFormat<'a> {
     Binary(Box<&'a mut dyn self::io::Write>),
     HttpHeaders(Box<&'a mut dyn MapCarrier>),
-    TextMap(Box<&'a mut dyn MapCarrier>)
+    TextMap(Box<&'a mut dyn MapCarrier>),
+
+    /// Tracer-specific format identified by name.
+    ///
+    /// See `ExtractFormat::Custom` for details.
+    Custom(&'a str, Box<&'a mut dyn Any>)
+}
+
+
+/// Casing of header names injected into `HttpHeaders` carriers.
+///
+/// HTTP header names are case insensitive but some legacy middlewares are not:
+/// `Tracer::with_header_case` controls the case of injected names.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum HeaderCase {
+    /// Each dash separated word is capitalised (`X-Trace-Id`).
+    Canonical,
+
+    /// Names are lowercased (`x-trace-id`).
+    Lowercase,
+
+    /// Names are injected as the tracer sets them.
+    #[default]
+    Preserve,
+}
+
+impl HeaderCase {
+    /// Returns the header name in this case.
+    pub(crate) fn apply(self, name: &str) -> String {
+        match self {
+            HeaderCase::Canonical => name.split('-')
+                .map(|word| {
+                    let mut chars = word.chars();
+                    match chars.next() {
+                        Some(first) => first.to_ascii_uppercase().to_string()
+                            + &chars.as_str().to_ascii_lowercase(),
+                        None => String::new(),
+                    }
+                })
+                .collect::<Vec<String>>()
+                .join("-"),
+            HeaderCase::Lowercase => name.to_ascii_lowercase(),
+            HeaderCase::Preserve => String::from(name),
+        }
+    }
+}
+
+
+/// `MapCarrier` based formats, without a carrier.
+///
+/// Used to select the format of `Tracer::inject_preview`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MapFormat {
+    HttpHeaders,
+    TextMap,
 }
 
 
@@ -69,18 +131,44 @@ pub trait MapCarrier {
     ///
     /// Intended to be used by the `ExtractFormat`s to extract all the
     /// baggage items from the carrier.
-    fn items(&self) -> Vec<(&String, &String)>;
+    fn items(&self) -> Vec<(&str, &str)>;
 
     /// Attempt to fetch an exact key from the carrier.
     fn get(&self, key: &str) -> Option<String>;
 
+    /// Attempt to fetch a key from the carrier ignoring the (ASCII) case of keys.
+    ///
+    /// HTTP header names are case insensitive and HTTP/2 lowercases them in transit
+    /// so tracers should use this method to extract from `HttpHeaders` carriers.
+    ///
+    /// The default implementation looks for an exact match first and then scans all
+    /// the `items`: carriers with case insensitive keys should override this method.
+    fn get_insensitive(&self, key: &str) -> Option<String> {
+        self.get(key).or_else(|| {
+            self.items().into_iter()
+                .find(|(item, _)| item.eq_ignore_ascii_case(key))
+                .map(|(_, value)| String::from(value))
+        })
+    }
+
     /// Set a key/value pair on the carrier.
     fn set(&mut self, key: &str, value: &str);
+
+    /// Set a baggage item on the carrier under the tracer-specific `prefix`.
+    ///
+    /// Tracers should inject baggage items with this method rather than `MapCarrier::set`
+    /// so adapters can tell baggage keys, which are chosen by users, apart from
+    /// the tracer's own keys.
+    ///
+    /// The default implementation sets the item under `prefix` followed by `key`.
+    fn set_baggage(&mut self, prefix: &str, key: &str, value: &str) {
+        self.set(&format!("{}{}", prefix, key), value);
+    }
 }
 
 impl MapCarrier for HashMap<String, String> {
-    fn items(&self) -> Vec<(&String, &String)> {
-        self.iter().collect()
+    fn items(&self) -> Vec<(&str, &str)> {
+        self.iter().map(|(key, value)| (key.as_str(), value.as_str())).collect()
     }
 
     fn get(&self, key: &str) -> Option<String> {
@@ -93,8 +181,8 @@ impl MapCarrier for HashMap<String, String> {
 }
 
 impl MapCarrier for BTreeMap<String, String> {
-    fn items(&self) -> Vec<(&String, &String)> {
-        self.iter().collect()
+    fn items(&self) -> Vec<(&str, &str)> {
+        self.iter().map(|(key, value)| (key.as_str(), value.as_str())).collect()
     }
 
     fn get(&self, key: &str) -> Option<String> {
@@ -106,6 +194,104 @@ impl MapCarrier for BTreeMap<String, String> {
     }
 }
 
+/// Carrier for headers represented as lists of `(key, value)` pairs.
+///
+/// Keys are looked up with a linear scan and the first matching pair is returned.
+/// Setting a key always appends a new pair, as lists can hold repeated headers.
+impl MapCarrier for Vec<(String, String)> {
+    fn items(&self) -> Vec<(&str, &str)> {
+        self.iter().map(|(key, value)| (key.as_str(), value.as_str())).collect()
+    }
+
+    fn get(&self, key: &str) -> Option<String> {
+        self.iter().find(|(item, _)| item == key).map(|(_, value)| value.clone())
+    }
+
+    fn set(&mut self, key: &str, value: &str) {
+        self.push((String::from(key), String::from(value)));
+    }
+}
+
+/// Carrier for headers stored in fixed size lists of `(key, value)` pairs.
+///
+/// Keys are looked up with a linear scan and the first matching pair is returned.
+/// Slices can't grow so setting a key replaces the value of the first matching pair
+/// and keys that are not already in the slice are ignored.
+impl MapCarrier for &mut [(String, String)] {
+    fn items(&self) -> Vec<(&str, &str)> {
+        self.iter().map(|(key, value)| (key.as_str(), value.as_str())).collect()
+    }
+
+    fn get(&self, key: &str) -> Option<String> {
+        self.iter().find(|(item, _)| item == key).map(|(_, value)| value.clone())
+    }
+
+    fn set(&mut self, key: &str, value: &str) {
+        if let Some((_, item)) = self.iter_mut().find(|(item, _)| item == key) {
+            *item = String::from(value);
+        }
+    }
+}
+
+/// HTTP headers carrier for applications using the [http](https://crates.io/crates/http) types.
+///
+/// Requires the `http` feature.
+///
+/// HTTP header names are case insensitive so keys are matched ignoring their case.
+/// Header values that are not valid UTF-8 are ignored by `items` and `get`,
+/// keys and values that are not valid in HTTP headers are ignored by `set`.
+#[cfg(feature = "http")]
+impl MapCarrier for http::HeaderMap {
+    fn items(&self) -> Vec<(&str, &str)> {
+        self.iter()
+            .filter_map(|(name, value)| value.to_str().ok().map(|value| (name.as_str(), value)))
+            .collect()
+    }
+
+    fn get(&self, key: &str) -> Option<String> {
+        self.get(key)
+            .and_then(|value| value.to_str().ok())
+            .map(String::from)
+    }
+
+    fn get_insensitive(&self, key: &str) -> Option<String> {
+        MapCarrier::get(self, key)
+    }
+
+    fn set(&mut self, key: &str, value: &str) {
+        let name = http::header::HeaderName::from_bytes(key.as_bytes());
+        let value = http::header::HeaderValue::from_str(value);
+        if let (Ok(name), Ok(value)) = (name, value) {
+            self.insert(name, value);
+        }
+    }
+}
+
+/// Carrier for JSON objects, such as job payloads and webhook bodies.
+///
+/// Requires the `serde_json` feature.
+///
+/// Items are stored as JSON strings: values of any other JSON type are ignored
+/// by `items` and `get`.
+#[cfg(feature = "serde_json")]
+impl MapCarrier for serde_json::Map<String, serde_json::Value> {
+    fn items(&self) -> Vec<(&str, &str)> {
+        self.iter()
+            .filter_map(|(key, value)| value.as_str().map(|value| (key.as_str(), value)))
+            .collect()
+    }
+
+    fn get(&self, key: &str) -> Option<String> {
+        self.get(key)
+            .and_then(|value| value.as_str())
+            .map(String::from)
+    }
+
+    fn set(&mut self, key: &str, value: &str) {
+        self.insert(String::from(key), serde_json::Value::String(String::from(value)));
+    }
+}
+
 
 #[cfg(test)]
 mod tests {
@@ -130,7 +316,7 @@ mod tests {
             let mut items = vec![];
             for (key, value) in tree.items() {
                 if key.starts_with("a") {
-                    items.push((key.clone(), value.clone()));
+                    items.push((String::from(key), String::from(value)));
                 }
             }
             items.sort();
@@ -152,6 +338,110 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "http")]
+    mod header_map {
+        use http::HeaderMap;
+        use http::HeaderValue;
+        use super::super::MapCarrier;
+
+        #[test]
+        fn get_ignores_case() {
+            let mut headers = HeaderMap::new();
+            headers.insert("traceid", HeaderValue::from_static("123"));
+            assert_eq!("123", MapCarrier::get(&headers, "TraceID").unwrap());
+        }
+
+        #[test]
+        fn inject_keys() {
+            let mut headers = HeaderMap::new();
+            headers.set("TraceID", "123");
+            headers.set("Bad Key", "value");
+            headers.set("bad-value", "line\nbreak");
+            assert_eq!(1, headers.len());
+            assert_eq!("123", headers["traceid"]);
+        }
+
+        #[test]
+        fn items_skip_invalid_values() {
+            let mut headers = HeaderMap::new();
+            headers.insert("a", HeaderValue::from_static("b"));
+            headers.insert("c", HeaderValue::from_bytes(b"\xff").unwrap());
+            assert_eq!(headers.items(), [("a", "b")]);
+            assert!(MapCarrier::get(&headers, "c").is_none());
+        }
+    }
+
+    #[cfg(feature = "serde_json")]
+    mod json_map {
+        use serde_json::Map;
+        use serde_json::Value;
+        use super::super::MapCarrier;
+
+        #[test]
+        fn inject_keys() {
+            let mut map = Map::new();
+            map.set("TraceID", "123");
+            assert_eq!(Value::String(String::from("123")), map["TraceID"]);
+        }
+
+        #[test]
+        fn items_skip_non_strings() {
+            let mut map = Map::new();
+            map.insert(String::from("a"), Value::String(String::from("b")));
+            map.insert(String::from("c"), Value::Bool(true));
+            assert_eq!(map.items(), [("a", "b")]);
+            assert_eq!("b", MapCarrier::get(&map, "a").unwrap());
+            assert!(MapCarrier::get(&map, "c").is_none());
+        }
+    }
+
+    mod header_case {
+        use super::super::HeaderCase;
+
+        #[test]
+        fn canonical() {
+            assert_eq!("X-Trace-Id", HeaderCase::Canonical.apply("x-TRACE-id"));
+            assert_eq!("Traceid", HeaderCase::Canonical.apply("TraceID"));
+        }
+
+        #[test]
+        fn lowercase() {
+            assert_eq!("x-trace-id", HeaderCase::Lowercase.apply("X-Trace-Id"));
+        }
+    }
+
+    mod pairs {
+        use super::super::MapCarrier;
+
+        #[test]
+        fn get_first_match() {
+            let pairs = vec![
+                (String::from("a"), String::from("1")),
+                (String::from("a"), String::from("2")),
+            ];
+            assert_eq!("1", MapCarrier::get(&pairs, "a").unwrap());
+            assert_eq!(None, MapCarrier::get(&pairs, "b"));
+            assert_eq!(pairs.items(), [("a", "1"), ("a", "2")]);
+        }
+
+        #[test]
+        fn inject_appends() {
+            let mut pairs: Vec<(String, String)> = Vec::new();
+            pairs.set("a", "1");
+            pairs.set("a", "2");
+            assert_eq!(pairs.items(), [("a", "1"), ("a", "2")]);
+        }
+
+        #[test]
+        fn inject_into_slice() {
+            let mut pairs = [(String::from("a"), String::from("1"))];
+            let mut slice = &mut pairs[..];
+            slice.set("a", "2");
+            slice.set("b", "3");
+            assert_eq!(slice.items(), [("a", "2")]);
+        }
+    }
+
     mod hash_map {
         use std::collections::HashMap;
         use super::super::MapCarrier;
@@ -173,7 +463,7 @@ mod tests {
             let mut items = vec![];
             for (key, value) in map.items() {
                 if key.starts_with("a") {
-                    items.push((key.clone(), value.clone()));
+                    items.push((String::from(key), String::from(value)));
                 }
             }
             items.sort();
@@ -183,6 +473,15 @@ mod tests {
             ]);
         }
 
+        #[test]
+        fn get_insensitive() {
+            let mut map: HashMap<String, String> = HashMap::new();
+            map.insert(String::from("traceid"), String::from("123"));
+            assert_eq!(None, MapCarrier::get(&map, "TraceID"));
+            assert_eq!("123", map.get_insensitive("TraceID").unwrap());
+            assert_eq!(None, map.get_insensitive("SpanID"));
+        }
+
         #[test]
         fn inject_keys() {
             let mut map: HashMap<String, String> = HashMap::new();
@@ -6,15 +6,17 @@ use std::result;
 
 use crossbeam_channel::SendError;
 
-use super::span::FinishedSpan;
+use super::span::SpanMessage;
 
 /// Enumeration of all errors returned by OpenTracingRust.
+// Failed sends carry the unsent `SpanMessage` so the error is as large as a span.
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug)]
 pub enum Error {
     IoError(self::io::Error),
     Msg(String),
     ParseIntError(self::num::ParseIntError),
-    SendError(self::SendError<FinishedSpan>)
+    SendError(self::SendError<SpanMessage>)
 }
 
 impl fmt::Display for Error {
@@ -42,8 +44,8 @@ impl From<self::num::ParseIntError> for Error {
     }
 }
 
-impl From<self::SendError<FinishedSpan>> for Error {
-    fn from(error: self::SendError<FinishedSpan>) -> Self {
+impl From<self::SendError<SpanMessage>> for Error {
+    fn from(error: self::SendError<SpanMessage>) -> Self {
         Error::SendError(error)
     }
 }
@@ -0,0 +1,191 @@
+use std::collections::HashSet;
+
+use super::super::FinishedSpan;
+use super::super::TagValue;
+
+
+/// Largest integer a `f64` can represent exactly.
+const MAX_EXACT_FLOAT: u64 = 1 << 53;
+
+
+/// Kinds of `TagValue`s a backend can support.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TagKind {
+    Boolean,
+    Bytes,
+    Float,
+    Integer,
+    String,
+    Unsigned,
+}
+
+impl TagKind {
+    /// Returns the kind of a `TagValue`.
+    pub fn of(value: &TagValue) -> TagKind {
+        match value {
+            TagValue::Boolean(_) => TagKind::Boolean,
+            TagValue::Bytes(_) => TagKind::Bytes,
+            TagValue::Float(_) => TagKind::Float,
+            TagValue::Integer(_) => TagKind::Integer,
+            TagValue::String(_) => TagKind::String,
+            TagValue::Unsigned(_) => TagKind::Unsigned,
+        }
+    }
+}
+
+
+/// Rules describing which `TagValue` kinds an exporter's backend supports.
+///
+/// Values of unsupported kinds are converted by `CoercionRules::coerce`:
+///
+///   * Integers and unsigned integers are converted into each other when the value fits.
+///   * Integers and unsigned integers are converted to floats when the value is exact.
+///   * Everything else is converted to its string representation
+///     (bytes are hex encoded).
+///
+/// String tags are always supported.
+///
+/// # Examples
+///
+/// ```
+/// extern crate opentracingrust;
+///
+/// use opentracingrust::TagValue;
+/// use opentracingrust::export::CoercionRules;
+/// use opentracingrust::export::TagKind;
+///
+///
+/// fn main() {
+///     let rules = CoercionRules::default()
+///         .without(TagKind::Float)
+///         .without(TagKind::Unsigned);
+///     match rules.coerce(TagValue::Float(0.5)) {
+///         TagValue::String(value) => assert_eq!("0.5", value),
+///         _ => panic!("Expected a string tag"),
+///     }
+///     match rules.coerce(TagValue::Unsigned(42)) {
+///         TagValue::Integer(value) => assert_eq!(42, value),
+///         _ => panic!("Expected an integer tag"),
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct CoercionRules {
+    unsupported: HashSet<TagKind>,
+}
+
+impl CoercionRules {
+    /// Marks a kind of tag values as unsupported by the backend.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the kind is `TagKind::String`.
+    pub fn without(mut self, kind: TagKind) -> Self {
+        assert!(kind != TagKind::String, "String tags can't be unsupported");
+        self.unsupported.insert(kind);
+        self
+    }
+
+    /// Returns `true` if the backend supports the kind of tag values.
+    pub fn supports(&self, kind: TagKind) -> bool {
+        !self.unsupported.contains(&kind)
+    }
+
+    /// Converts a value into a kind supported by the backend.
+    pub fn coerce(&self, value: TagValue) -> TagValue {
+        if self.supports(TagKind::of(&value)) {
+            return value;
+        }
+        match value {
+            TagValue::Integer(v) if v >= 0 && self.supports(TagKind::Unsigned) => {
+                TagValue::Unsigned(v as u64)
+            },
+            TagValue::Integer(v) if v.unsigned_abs() <= MAX_EXACT_FLOAT &&
+                self.supports(TagKind::Float) => TagValue::Float(v as f64),
+            TagValue::Unsigned(v) if v <= i64::MAX as u64 && self.supports(TagKind::Integer) => {
+                TagValue::Integer(v as i64)
+            },
+            TagValue::Unsigned(v) if v <= MAX_EXACT_FLOAT && self.supports(TagKind::Float) => {
+                TagValue::Float(v as f64)
+            },
+            value => TagValue::String(value.to_string()),
+        }
+    }
+}
+
+
+/// Coerces the tags of finished spans into the kinds supported by a backend.
+pub fn coerce_tags(spans: Vec<FinishedSpan>, rules: &CoercionRules) -> Vec<FinishedSpan> {
+    spans.into_iter()
+        .map(|mut span| {
+            span.tags_mut().retain(|_, value| {
+                let coerced = rules.coerce(value.clone());
+                *value = coerced;
+                true
+            });
+            span
+        })
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::TagValue;
+    use super::super::super::tracers::NoopTracer;
+
+    use super::CoercionRules;
+    use super::TagKind;
+    use super::coerce_tags;
+
+
+    #[test]
+    fn coerce_integers() {
+        let rules = CoercionRules::default().without(TagKind::Integer);
+        match rules.coerce(TagValue::Integer(-3)) {
+            TagValue::Float(v) => assert_eq!(-3.0, v),
+            _ => panic!("Expected a float tag"),
+        }
+        let rules = rules.without(TagKind::Float);
+        match rules.coerce(TagValue::Integer(i64::MIN)) {
+            TagValue::String(v) => assert_eq!(i64::MIN.to_string(), v),
+            _ => panic!("Expected a string tag"),
+        }
+    }
+
+    #[test]
+    fn coerce_unsigned() {
+        let rules = CoercionRules::default().without(TagKind::Unsigned);
+        match rules.coerce(TagValue::Unsigned(u64::MAX)) {
+            TagValue::String(v) => assert_eq!(u64::MAX.to_string(), v),
+            _ => panic!("Expected a string tag"),
+        }
+    }
+
+    #[test]
+    fn coerce_span_tags() {
+        let (tracer, receiver) = NoopTracer::new();
+        let mut span = tracer.span("test");
+        span.tag("error", true);
+        span.tag("payload", vec![0u8, 255]);
+        span.finish().unwrap();
+        let rules = CoercionRules::default()
+            .without(TagKind::Boolean)
+            .without(TagKind::Bytes);
+        let spans = coerce_tags(receiver.try_iter().collect(), &rules);
+        match spans[0].tags().get("error") {
+            Some(TagValue::String(v)) => assert_eq!("true", v),
+            _ => panic!("Expected a string tag"),
+        }
+        match spans[0].tags().get("payload") {
+            Some(TagValue::String(v)) => assert_eq!("00ff", v),
+            _ => panic!("Expected a string tag"),
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "String tags can't be unsupported")]
+    fn strings_are_supported() {
+        let _ = CoercionRules::default().without(TagKind::String);
+    }
+}
@@ -0,0 +1,12 @@
+//! Tools to prepare finished traces for sharing outside the process.
+//!
+//! These utilities operate on the `FinishedSpan`s collected from a `SpanReceiver`
+//! before they are written out or handed to third parties.
+mod coerce;
+mod scrub;
+
+pub use self::coerce::CoercionRules;
+pub use self::coerce::TagKind;
+pub use self::coerce::coerce_tags;
+pub use self::scrub::scrub;
+pub use self::scrub::ScrubPolicy;
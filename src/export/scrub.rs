@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+
+use super::super::FinishedSpan;
+use super::super::LogValue;
+use super::super::SpanContext;
+use super::super::SpanReference;
+use super::super::TagValue;
+use super::super::ids::fnv1a;
+
+
+/// What to do with data matching a `ScrubPolicy`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ScrubAction {
+    Hash,
+    Remove,
+}
+
+
+/// Policy describing which span data `scrub` should strip or hash.
+///
+/// Policies match the keys of tags, of top level log fields and of baggage items.
+///
+///   * Removed keys are dropped from the span entirely.
+///   * Hashed keys have their value replaced by a hex encoded hash of the value.
+///     Equal values produce equal hashes so they can still be correlated across spans.
+///
+/// Hashing uses 64-bit FNV-1a (like `ids::hash_trace_id`) so hashes are stable across
+/// processes, platforms and Rust releases and can be correlated across services.
+/// FNV-1a is **not** a cryptographic hash: set a secret salt with `ScrubPolicy::salt`,
+/// shared by all services that must produce matching hashes, to make guessing
+/// hashed values harder.
+///
+/// # Examples
+///
+/// ```
+/// extern crate opentracingrust;
+///
+/// use opentracingrust::FinishedSpan;
+/// use opentracingrust::export::ScrubPolicy;
+/// use opentracingrust::export::scrub;
+/// use opentracingrust::tracers::NoopTracer;
+///
+///
+/// fn main() {
+///     let (tracer, receiver) = NoopTracer::new();
+///     let mut span = tracer.span("login");
+///     span.tag("user.email", "someone@example.com");
+///     span.tag("user.id", 42);
+///     span.finish().unwrap();
+///
+///     let policy = ScrubPolicy::default()
+///         .remove("user.email")
+///         .hash("user.id");
+///     let spans: Vec<FinishedSpan> = receiver.try_iter().collect();
+///     let spans = scrub(spans, &policy);
+///     assert!(spans[0].tags().get("user.email").is_none());
+///     assert!(spans[0].tags().get("user.id").is_some());
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ScrubPolicy {
+    actions: HashMap<String, ScrubAction>,
+    salt: Option<String>,
+}
+
+impl ScrubPolicy {
+    /// Replaces values for the given key with their hash.
+    pub fn hash<K: Into<String>>(mut self, key: K) -> Self {
+        self.actions.insert(key.into(), ScrubAction::Hash);
+        self
+    }
+
+    /// Removes values for the given key.
+    pub fn remove<K: Into<String>>(mut self, key: K) -> Self {
+        self.actions.insert(key.into(), ScrubAction::Remove);
+        self
+    }
+
+    /// Sets a salt mixed into all hashed values.
+    pub fn salt<S: Into<String>>(mut self, salt: S) -> Self {
+        self.salt = Some(salt.into());
+        self
+    }
+}
+
+impl ScrubPolicy {
+    /// Applies the policy to a baggage-carrying context.
+    fn scrub_context(&self, context: &mut SpanContext) {
+        context.retain_baggage(|key, value| match self.actions.get(key) {
+            None => true,
+            Some(ScrubAction::Remove) => false,
+            Some(ScrubAction::Hash) => {
+                *value = self.hash_value(value);
+                true
+            }
+        });
+    }
+
+    /// Applies the policy to a finished span.
+    fn scrub_span(&self, span: &mut FinishedSpan) {
+        span.tags_mut().retain(|key, value| match self.actions.get(key) {
+            None => true,
+            Some(ScrubAction::Remove) => false,
+            Some(ScrubAction::Hash) => {
+                *value = TagValue::String(self.hash_value(&value.to_string()));
+                true
+            }
+        });
+        for log in span.logs_mut() {
+            log.retain(|key, value| match self.actions.get(key) {
+                None => true,
+                Some(ScrubAction::Remove) => false,
+                Some(ScrubAction::Hash) => {
+                    *value = LogValue::String(self.hash_value(&value.to_string()));
+                    true
+                }
+            });
+        }
+        self.scrub_context(span.context_mut());
+        for reference in span.references_mut() {
+            match *reference {
+                SpanReference::ChildOf(ref mut parent) |
+                SpanReference::FollowsFrom(ref mut parent) |
+                SpanReference::Custom(_, ref mut parent) => self.scrub_context(parent),
+            }
+        }
+    }
+
+    /// Returns the hex encoded, salted, hash of a value.
+    fn hash_value(&self, value: &str) -> String {
+        let salt = self.salt.as_deref().unwrap_or_default();
+        // Hashing the salt length first keeps the salt and value boundary unambiguous.
+        let salt_len = (salt.len() as u64).to_le_bytes();
+        let hash = fnv1a(&[&salt_len, salt.as_bytes(), value.as_bytes()]);
+        format!("{:016x}", hash)
+    }
+}
+
+
+/// Strips or hashes span data matching the `ScrubPolicy`.
+///
+/// Tags, log fields and baggage items (including the baggage of referenced contexts)
+/// are processed according to the policy.
+/// Nested values within log fields are treated as part of the top level field.
+pub fn scrub(spans: Vec<FinishedSpan>, policy: &ScrubPolicy) -> Vec<FinishedSpan> {
+    spans.into_iter()
+        .map(|mut span| {
+            policy.scrub_span(&mut span);
+            span
+        })
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::FinishedSpan;
+    use super::super::super::Log;
+    use super::super::super::SpanReference;
+    use super::super::super::StartOptions;
+    use super::super::super::TagValue;
+    use super::super::super::tracers::NoopTracer;
+
+    use super::ScrubPolicy;
+    use super::scrub;
+
+
+    fn spans() -> Vec<FinishedSpan> {
+        let (tracer, receiver) = NoopTracer::new();
+        let mut parent = tracer.span("parent");
+        parent.set_baggage_item("tenant", "acme");
+        parent.set_baggage_item("region", "eu");
+        let options = StartOptions::default().child_of(parent.context().clone());
+        let mut span = tracer.span_with_options("child", options);
+        span.tag("user.email", "someone@example.com");
+        span.tag("user.id", 42);
+        span.tag("http.status_code", 200);
+        span.log(Log::new().log("user.email", "someone@example.com").log("event", "login"));
+        span.finish().unwrap();
+        parent.finish().unwrap();
+        receiver.try_iter().collect()
+    }
+
+    fn policy() -> ScrubPolicy {
+        ScrubPolicy::default()
+            .remove("user.email")
+            .remove("region")
+            .hash("user.id")
+            .hash("tenant")
+    }
+
+    #[test]
+    fn hashes_are_consistent() {
+        let spans = scrub(spans(), &policy());
+        let child = spans[0].context().get_baggage_item("tenant").unwrap();
+        let parent = spans[1].context().get_baggage_item("tenant").unwrap();
+        assert_ne!("acme", child);
+        assert_eq!(child, parent);
+    }
+
+    #[test]
+    fn hashes_are_stable() {
+        let spans = scrub(spans(), &policy().salt("secret"));
+        let tenant = spans[0].context().get_baggage_item("tenant").unwrap();
+        assert_eq!("e0f9fa1015d27a09", tenant);
+    }
+
+    #[test]
+    fn hashes_are_salted() {
+        let plain = scrub(spans(), &policy());
+        let salted = scrub(spans(), &policy().salt("secret"));
+        assert_ne!(
+            plain[0].context().get_baggage_item("tenant"),
+            salted[0].context().get_baggage_item("tenant")
+        );
+    }
+
+    #[test]
+    fn scrubs_baggage() {
+        let spans = scrub(spans(), &policy());
+        let child = &spans[0];
+        assert!(child.context().get_baggage_item("region").is_none());
+        let parent = match child.references()[0] {
+            SpanReference::ChildOf(ref context) => context,
+            _ => panic!("Expected a child-of reference"),
+        };
+        assert!(parent.get_baggage_item("region").is_none());
+        assert_ne!("acme", parent.get_baggage_item("tenant").unwrap());
+    }
+
+    #[test]
+    fn scrubs_logs() {
+        let spans = scrub(spans(), &policy());
+        let fields: Vec<&String> = spans[0].logs()[0].iter().map(|(key, _)| key).collect();
+        assert_eq!(fields, ["event"]);
+    }
+
+    #[test]
+    fn scrubs_tags() {
+        let spans = scrub(spans(), &policy());
+        let tags = spans[0].tags();
+        assert!(tags.get("user.email").is_none());
+        match tags.get("user.id") {
+            Some(TagValue::String(hash)) => assert_eq!(16, hash.len()),
+            _ => panic!("Expected a hashed tag")
+        }
+        match tags.get("http.status_code") {
+            Some(TagValue::Integer(200)) => (),
+            _ => panic!("Expected the tag to be retained")
+        }
+    }
+}
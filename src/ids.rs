@@ -0,0 +1,136 @@
+//! Stable hashing of trace IDs to partition spans by trace.
+//!
+//! Custom reporters can use these helpers to spread spans across files, workers
+//! or queues while keeping all the spans of a trace together.
+//!
+//! Trace IDs are hashed with 64-bit FNV-1a, which does not change across Rust
+//! releases or processes, and mapped to shards with jump consistent hashing so
+//! that changing the number of shards moves as few traces as possible.
+//!
+//! # Examples
+//!
+//! ```
+//! extern crate opentracingrust;
+//!
+//! use opentracingrust::StartOptions;
+//! use opentracingrust::ids;
+//! use opentracingrust::tracers::FileTracer;
+//!
+//!
+//! fn main() {
+//!     let (tracer, receiver) = FileTracer::new();
+//!     let root = tracer.span("root");
+//!     let options = StartOptions::default().child_of(root.context().clone());
+//!     let child = tracer.span_with_options("child", options);
+//!     child.finish().unwrap();
+//!     root.finish().unwrap();
+//!
+//!     let shards: Vec<usize> = receiver.try_iter()
+//!         .map(|span| ids::span_shard(&span, 4).unwrap())
+//!         .collect();
+//!     assert_eq!(shards[0], shards[1]);
+//! }
+//! ```
+use super::FinishedSpan;
+
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+
+/// Hashes a trace ID with 64-bit FNV-1a.
+///
+/// The hash is stable across processes, platforms and Rust releases.
+pub fn hash_trace_id(trace_id: &str) -> u64 {
+    fnv1a(&[trace_id.as_bytes()])
+}
+
+/// Hashes the concatenation of `parts` with 64-bit FNV-1a.
+pub(crate) fn fnv1a(parts: &[&[u8]]) -> u64 {
+    parts.iter().flat_map(|part| part.iter()).fold(FNV_OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(FNV_PRIME)
+    })
+}
+
+/// Maps a key to one of `buckets` buckets with jump consistent hashing.
+///
+/// When the number of buckets grows from `n` to `n + 1` only `1 / (n + 1)`
+/// of the keys move, all of them to the new bucket.
+///
+/// # Panics
+///
+/// Panics if `buckets` is `0`.
+pub fn jump_consistent_hash(mut key: u64, buckets: usize) -> usize {
+    assert!(buckets > 0, "At least one bucket is needed");
+    let mut bucket: i64 = -1;
+    let mut next: i64 = 0;
+    while next < buckets as i64 {
+        bucket = next;
+        key = key.wrapping_mul(2_862_933_555_777_941_757).wrapping_add(1);
+        let jump = f64::from(1u32 << 31) / (((key >> 33) + 1) as f64);
+        next = ((bucket + 1) as f64 * jump) as i64;
+    }
+    bucket as usize
+}
+
+/// Returns the shard, out of `shards`, for all the spans of the given trace.
+///
+/// # Panics
+///
+/// Panics if `shards` is `0`.
+pub fn shard_for(trace_id: &str, shards: usize) -> usize {
+    jump_consistent_hash(hash_trace_id(trace_id), shards)
+}
+
+/// Returns the shard, out of `shards`, for the trace of the given span.
+///
+/// Returns `None` if the tracer does not expose trace IDs (see `SpanContext::trace_id`).
+///
+/// # Panics
+///
+/// Panics if `shards` is `0`.
+pub fn span_shard(span: &FinishedSpan, shards: usize) -> Option<usize> {
+    span.context().trace_id().map(|trace_id| shard_for(&trace_id, shards))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::hash_trace_id;
+    use super::jump_consistent_hash;
+    use super::shard_for;
+
+    #[test]
+    fn hashes_are_stable() {
+        assert_eq!(0xcbf2_9ce4_8422_2325, hash_trace_id(""));
+        assert_eq!(0xaf63_dc4c_8601_ec8c, hash_trace_id("a"));
+    }
+
+    #[test]
+    fn growing_shards_moves_few_traces() {
+        let ids: Vec<String> = (0..1000).map(|id| format!("{:x}", id)).collect();
+        let before: Vec<usize> = ids.iter().map(|id| shard_for(id, 10)).collect();
+        let after: Vec<usize> = ids.iter().map(|id| shard_for(id, 11)).collect();
+        let moved = before.iter().zip(after.iter())
+            .filter(|(before, after)| before != after)
+            .map(|(_, after)| {
+                assert_eq!(10, *after, "Traces can only move to the new shard");
+            })
+            .count();
+        assert!(moved > 0 && moved < 200, "Moved {} traces", moved);
+    }
+
+    #[test]
+    fn shards_are_in_range() {
+        for key in 0..1000 {
+            assert!(jump_consistent_hash(key, 7) < 7);
+        }
+        assert_eq!(0, jump_consistent_hash(42, 1));
+    }
+
+    #[test]
+    #[should_panic(expected = "At least one bucket is needed")]
+    fn zero_shards() {
+        shard_for("abc", 0);
+    }
+}
@@ -0,0 +1,197 @@
+//! Integration with the [axum](https://crates.io/crates/axum) web framework.
+//!
+//! Requires the `axum` feature.
+use std::convert::Infallible;
+
+use axum::async_trait;
+use axum::extract::FromRequestParts;
+use axum::extract::MatchedPath;
+use axum::http::request::Parts;
+
+use super::super::AutoFinishingSpan;
+use super::super::ExtractFormat;
+use super::super::SpanContext;
+use super::super::SpanKind;
+use super::super::SpanReference;
+use super::super::StartOptions;
+use super::super::Tracer;
+use super::super::utils::GlobalTracer;
+
+
+/// Request tracing information for axum handlers.
+///
+/// The extractor reads the `SpanContext` of the caller from the request headers
+/// (if any) and starts a `SpanKind::Server` span for the request as its child.
+/// The server span is finished when the `TraceContext` is dropped.
+///
+/// Requests carrying invalid tracing information are not rejected:
+/// the span is started as a root span (see `Tracer::extract_or_new`).
+///
+/// The `Tracer` is fetched from the `GlobalTracer`, which must be initialised
+/// before requests are handled.
+///
+/// The span is finished when the handler drops the `TraceContext`, before the response
+/// is sent, so it is not tagged with the `http.status_code`.
+/// Handlers that need it should tag the span with the status they respond with
+/// (see `TraceContext::span`).
+///
+/// # Examples
+///
+/// ```ignore
+/// use opentracingrust::integrations::axum::TraceContext;
+///
+/// async fn handler(mut trace: TraceContext) -> &'static str {
+///     trace.span().tag("handler", "index");
+///     "Hello, World!"
+/// }
+/// ```
+#[derive(Debug)]
+pub struct TraceContext {
+    parent: Option<SpanContext>,
+    span: AutoFinishingSpan,
+}
+
+impl TraceContext {
+    /// Extracts the caller's context from the request and starts the request span.
+    ///
+    /// The span is named after the request method and the route that matched the request
+    /// (such as `GET /users/:id`), or only the method for requests that did not match
+    /// a route, and is tagged with the `http.method` and `http.url` tags.
+    pub fn from_parts(tracer: &Tracer, parts: &Parts) -> TraceContext {
+        let format = ExtractFormat::HttpHeaders(Box::new(&parts.headers));
+        let name = match parts.extensions.get::<MatchedPath>() {
+            Some(path) => tracer.operation_name(&format!("{} {}", parts.method, path.as_str())),
+            None => tracer.operation_name(parts.method.as_str()),
+        };
+        let options = StartOptions::default().span_kind(SpanKind::Server);
+        let mut span = tracer.extract_or_new_with_options(format, name, options);
+        span.tag("http.method", parts.method.as_str());
+        span.tag("http.url", parts.uri.to_string());
+        let parent = SpanReference::parent(span.references()).cloned();
+        TraceContext {
+            parent,
+            span: span.auto_finish(),
+        }
+    }
+}
+
+impl TraceContext {
+    /// Access the `SpanContext` of the request span.
+    pub fn context(&self) -> &SpanContext {
+        self.span.context()
+    }
+
+    /// Converts the `TraceContext` into the request span.
+    pub fn into_span(self) -> AutoFinishingSpan {
+        self.span
+    }
+
+    /// Access the caller's `SpanContext`, if the request carried one.
+    pub fn parent(&self) -> Option<&SpanContext> {
+        self.parent.as_ref()
+    }
+
+    /// Access the request span.
+    pub fn span(&mut self) -> &mut AutoFinishingSpan {
+        &mut self.span
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for TraceContext where S: Send + Sync {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let tracer = GlobalTracer::get();
+        Ok(TraceContext::from_parts(&tracer, parts))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::sync::Arc;
+    use std::task::Context;
+    use std::task::Poll;
+    use std::task::Waker;
+
+    use axum::Router;
+    use axum::body::Body;
+    use axum::http::Request;
+    use axum::http::request::Parts;
+    use axum::routing::get;
+    use tower_service::Service;
+
+    use super::super::super::SpanKind;
+    use super::super::super::TagValue;
+    use super::super::super::tracers::FileTracer;
+    use super::TraceContext;
+
+    #[test]
+    fn creates_root_span_without_headers() {
+        let (tracer, receiver) = FileTracer::new();
+        let (parts, _) = Request::get("/path?query=1").body(()).unwrap().into_parts();
+        let trace = TraceContext::from_parts(&tracer, &parts);
+        assert!(trace.parent().is_none());
+        drop(trace);
+
+        let span = receiver.recv().unwrap();
+        assert_eq!("GET", span.name());
+        assert_eq!(Some(SpanKind::Server), span.kind());
+        assert!(span.references().is_empty());
+    }
+
+    #[test]
+    fn names_span_after_matched_route() {
+        let (tracer, receiver) = FileTracer::new();
+        let tracer = Arc::new(tracer);
+        let mut router = Router::new().route("/users/:id", get(move |parts: Parts| async move {
+            drop(TraceContext::from_parts(&tracer, &parts));
+        }));
+        let request = Request::get("/users/123").body(Body::empty()).unwrap();
+        let mut future = router.call(request);
+        let mut context = Context::from_waker(Waker::noop());
+        match Pin::new(&mut future).poll(&mut context) {
+            Poll::Ready(response) => assert!(response.unwrap().status().is_success()),
+            Poll::Pending => panic!("Expected the router to respond immediately"),
+        }
+
+        let span = receiver.recv().unwrap();
+        assert_eq!("GET /users/:id", span.name());
+    }
+
+    #[test]
+    fn extracts_parent_context() {
+        let (tracer, receiver) = FileTracer::new();
+        let (parts, _) = Request::post("/path")
+            .header("traceid", "123")
+            .header("spanid", "456")
+            .body(()).unwrap().into_parts();
+        let trace = TraceContext::from_parts(&tracer, &parts);
+        assert!(trace.parent().is_some());
+        drop(trace);
+
+        let span = receiver.recv().unwrap();
+        assert_eq!(1, span.references().len());
+    }
+
+    #[test]
+    fn starts_root_span_on_invalid_headers() {
+        let (tracer, receiver) = FileTracer::new();
+        let (parts, _) = Request::get("/")
+            .header("traceid", "abc")
+            .body(()).unwrap().into_parts();
+        let trace = TraceContext::from_parts(&tracer, &parts);
+        assert!(trace.parent().is_none());
+        drop(trace);
+
+        let span = receiver.recv().unwrap();
+        assert!(span.references().is_empty());
+        match span.tags().get("trace.extract_error") {
+            Some(TagValue::Boolean(true)) => (),
+            _ => panic!("Extraction error not tagged")
+        }
+    }
+}
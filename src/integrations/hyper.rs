@@ -0,0 +1,246 @@
+//! Integration with the [hyper](https://crates.io/crates/hyper) HTTP library.
+//!
+//! Requires the `hyper` feature.
+use std::fmt::Debug;
+use std::fmt::Display;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use hyper::Request;
+use hyper::Response;
+use hyper::service::Service;
+
+use super::super::ExtractFormat;
+use super::super::SpanContext;
+use super::super::SpanKind;
+use super::super::StartOptions;
+use super::super::Tracer;
+
+
+/// A hyper `Service` wrapper that traces each request with a server span.
+///
+/// For every request the wrapper:
+///
+///   1. Extracts the caller's `SpanContext` from the request headers (if any).
+///   2. Starts a `SpanKind::Server` span, child of the caller's context, named after
+///      the request method and tagged with the `http.method` and `http.url` tags.
+///   3. Calls the inner service with the span's context in the request extensions
+///      (see `ServerSpanContext`).
+///   4. Tags the span with the `http.status_code` of the response, sets the `error` tag
+///      for server errors (5xx) and for errors returned by the inner service, and finishes it.
+///
+/// Requests carrying invalid tracing information are not rejected:
+/// the span is started as a root span (see `Tracer::extract_or_new`).
+///
+/// Spans are not named after the request path as that would create an operation
+/// per URL (such as `GET /users/123`): the path is available in the `http.url` tag.
+///
+/// # Examples
+///
+/// ```
+/// extern crate hyper;
+/// extern crate opentracingrust;
+///
+/// use std::convert::Infallible;
+/// use std::sync::Arc;
+///
+/// use hyper::Request;
+/// use hyper::Response;
+/// use hyper::service::service_fn;
+///
+/// use opentracingrust::integrations::hyper::TracedService;
+/// use opentracingrust::tracers::NoopTracer;
+///
+///
+/// fn main() {
+///     let (tracer, _) = NoopTracer::new();
+///     let hello = service_fn(|_: Request<String>| async {
+///         Ok::<_, Infallible>(Response::new(String::from("Hello, World!")))
+///     });
+///     let service = TracedService::new(Arc::new(tracer), hello);
+///     // ... serve connections with the service ...
+/// }
+/// ```
+#[derive(Clone)]
+pub struct TracedService<S> {
+    inner: S,
+    tracer: Arc<Tracer>,
+}
+
+/// `SpanContext` of the server span, stored in the request extensions by `TracedService`.
+///
+/// Handlers use it to start child spans and to propagate the trace to downstream calls.
+#[derive(Clone, Debug)]
+pub struct ServerSpanContext(Arc<Mutex<SpanContext>>);
+
+impl ServerSpanContext {
+    /// Wraps a `SpanContext` so it can be stored in request extensions.
+    pub fn new(context: SpanContext) -> ServerSpanContext {
+        ServerSpanContext(Arc::new(Mutex::new(context)))
+    }
+
+    /// Returns a copy of the server span's context.
+    pub fn context(&self) -> SpanContext {
+        self.0.lock().expect("Failed to lock ServerSpanContext").clone()
+    }
+}
+
+
+impl<S> TracedService<S> {
+    /// Wraps the `inner` service to trace requests with the given `Tracer`.
+    pub fn new(tracer: Arc<Tracer>, inner: S) -> TracedService<S> {
+        TracedService { inner, tracer }
+    }
+
+    /// Access the wrapped service.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for TracedService<S>
+    where S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+          S::Error: Debug + Display + Send + 'static,
+          S::Future: Send + 'static,
+          ResBody: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<S::Response, S::Error>> + Send>>;
+
+    fn call(&self, mut request: Request<ReqBody>) -> Self::Future {
+        let format = ExtractFormat::HttpHeaders(Box::new(request.headers()));
+        let name = request.method().as_str();
+        let options = StartOptions::default().span_kind(SpanKind::Server);
+        let mut span = self.tracer.extract_or_new_with_options(format, name, options);
+        span.tag("http.method", request.method().as_str());
+        span.tag("http.url", request.uri().to_string());
+        request.extensions_mut().insert(ServerSpanContext::new(span.context().clone()));
+
+        let response = self.inner.call(request);
+        Box::pin(async move {
+            let response = response.await;
+            match response {
+                Ok(ref response) => {
+                    let status = response.status();
+                    span.tag("http.status_code", u32::from(status.as_u16()));
+                    if status.is_server_error() {
+                        span.tag("error", true);
+                    }
+                }
+                Err(ref error) => span.log_error(error),
+            }
+            // Failing to report the span should not fail the request.
+            let _ = span.finish();
+            response
+        })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::task::Context;
+    use std::task::Poll;
+    use std::task::Waker;
+
+    use hyper::Request;
+    use hyper::Response;
+    use hyper::StatusCode;
+    use hyper::service::Service;
+    use hyper::service::service_fn;
+
+    use super::super::super::FinishedSpan;
+    use super::super::super::SpanKind;
+    use super::super::super::TagValue;
+    use super::super::super::tracers::FileTracer;
+
+    use super::ServerSpanContext;
+    use super::TracedService;
+
+
+    /// Calls a traced service that responds with the given status.
+    fn call(request: Request<String>, status: StatusCode) -> FinishedSpan {
+        let (tracer, receiver) = FileTracer::new();
+        let respond = service_fn(move |_: Request<String>| async move {
+            let mut response = Response::new(String::new());
+            *response.status_mut() = status;
+            Ok::<_, String>(response)
+        });
+        let service = TracedService::new(Arc::new(tracer), respond);
+        let mut future = service.call(request);
+        let mut context = Context::from_waker(Waker::noop());
+        match future.as_mut().poll(&mut context) {
+            Poll::Ready(response) => assert_eq!(status, response.unwrap().status()),
+            Poll::Pending => panic!("Expected the service to respond immediately"),
+        }
+        receiver.recv().unwrap()
+    }
+
+    #[test]
+    fn creates_server_span() {
+        let request = Request::get("/path?query=1").body(String::new()).unwrap();
+        let span = call(request, StatusCode::OK);
+        assert_eq!("GET", span.name());
+        assert_eq!(Some(SpanKind::Server), span.kind());
+        assert!(span.is_root());
+        match span.tags().get("http.status_code") {
+            Some(TagValue::Unsigned(200)) => (),
+            _ => panic!("Status code not tagged"),
+        }
+        assert!(span.tags().get("error").is_none());
+    }
+
+    #[test]
+    fn passes_context_to_inner_service() {
+        let (tracer, receiver) = FileTracer::new();
+        let inner = service_fn(|request: Request<String>| async move {
+            let span_id = request.extensions().get::<ServerSpanContext>()
+                .and_then(|context| context.context().span_id());
+            Ok::<_, String>(Response::new(span_id.unwrap_or_default()))
+        });
+        let service = TracedService::new(Arc::new(tracer), inner);
+        let request = Request::get("/").body(String::new()).unwrap();
+        let mut future = service.call(request);
+        let mut context = Context::from_waker(Waker::noop());
+        let inner_span_id = match future.as_mut().poll(&mut context) {
+            Poll::Ready(response) => response.unwrap().into_body(),
+            Poll::Pending => panic!("Expected the service to respond immediately"),
+        };
+        let span = receiver.recv().unwrap();
+        assert_eq!(span.context().span_id(), Some(inner_span_id));
+    }
+
+    #[test]
+    fn extracts_parent_context() {
+        let request = Request::post("/path")
+            .header("TraceId", "123")
+            .header("SpanId", "456")
+            .body(String::new()).unwrap();
+        let span = call(request, StatusCode::OK);
+        assert!(!span.is_root());
+    }
+
+    #[test]
+    fn invalid_context_is_logged() {
+        let request = Request::get("/").header("traceid", "abc").body(String::new()).unwrap();
+        let span = call(request, StatusCode::OK);
+        assert!(span.is_root());
+        assert_eq!(1, span.logs().len());
+        assert!(span.tags().get("trace.extract_error").is_some());
+        assert!(span.tags().get("error").is_none());
+    }
+
+    #[test]
+    fn server_errors_are_errors() {
+        let request = Request::get("/").body(String::new()).unwrap();
+        let span = call(request, StatusCode::BAD_GATEWAY);
+        match span.tags().get("error") {
+            Some(TagValue::Boolean(true)) => (),
+            _ => panic!("Error tag not set"),
+        }
+    }
+}
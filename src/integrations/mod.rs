@@ -0,0 +1,12 @@
+//! Integrations with third-party frameworks.
+//!
+//! Each integration is behind a cargo feature named after the framework
+//! so applications only pay for the integrations they use.
+#[cfg(feature = "axum")]
+pub mod axum;
+#[cfg(feature = "hyper")]
+pub mod hyper;
+#[cfg(feature = "reqwest")]
+pub mod reqwest;
+#[cfg(feature = "tracing")]
+pub mod tracing;
@@ -0,0 +1,186 @@
+//! Integration with the [reqwest](https://crates.io/crates/reqwest) HTTP client.
+//!
+//! Requires the `reqwest` feature.
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use http::Extensions;
+use reqwest::Request;
+use reqwest::Response;
+use reqwest_middleware::Middleware;
+use reqwest_middleware::Next;
+use reqwest_middleware::Result;
+
+use super::super::SpanContext;
+use super::super::StartOptions;
+use super::super::Tracer;
+use super::super::utils::http::finish_http_request;
+use super::super::utils::http::inject_http_request;
+
+
+/// Parent of the client span of a request, passed to `TracingMiddleware` as an extension.
+///
+/// # Examples
+///
+/// ```ignore
+/// let response = client.get("http://example.com/")
+///     .with_extension(ParentContext::new(span.context().clone()))
+///     .send()
+///     .await?;
+/// ```
+#[derive(Clone, Debug)]
+pub struct ParentContext(Arc<Mutex<SpanContext>>);
+
+impl ParentContext {
+    /// Wraps a `SpanContext` so it can be stored in request extensions.
+    pub fn new(context: SpanContext) -> ParentContext {
+        ParentContext(Arc::new(Mutex::new(context)))
+    }
+}
+
+
+/// A `reqwest_middleware` middleware that traces outgoing requests with client spans.
+///
+/// For every request the middleware starts a client span and injects its context into
+/// the request headers (see `utils::http::inject_http_request`).
+/// The span is a child of the `ParentContext` extension of the request, if set.
+///
+/// Once the request completes the span is tagged with the response status
+/// (see `utils::http::finish_http_request`), or the request error is logged, and finished.
+/// Errors sending the finished span are ignored.
+///
+/// # Examples
+///
+/// ```ignore
+/// use reqwest_middleware::ClientBuilder;
+/// use opentracingrust::integrations::reqwest::TracingMiddleware;
+/// use opentracingrust::utils::GlobalTracer;
+///
+/// let client = ClientBuilder::new(reqwest::Client::new())
+///     .with(TracingMiddleware::new(GlobalTracer::get()))
+///     .build();
+/// ```
+pub struct TracingMiddleware {
+    tracer: Arc<Tracer>,
+}
+
+impl TracingMiddleware {
+    /// Creates a middleware that traces requests with the given `Tracer`.
+    pub fn new(tracer: Arc<Tracer>) -> TracingMiddleware {
+        TracingMiddleware { tracer }
+    }
+}
+
+#[async_trait]
+impl Middleware for TracingMiddleware {
+    async fn handle(
+        &self, mut request: Request, extensions: &mut Extensions, next: Next<'_>
+    ) -> Result<Response> {
+        let mut options = StartOptions::default();
+        if let Some(parent) = extensions.get::<ParentContext>() {
+            let parent = parent.0.lock().expect("Failed to lock ParentContext").clone();
+            options = options.child_of(parent);
+        }
+        let method = request.method().clone();
+        let url = request.url().clone();
+        let mut span = inject_http_request(
+            &self.tracer, options, method.as_str(), url.as_str(), request.headers_mut()
+        );
+
+        let response = next.run(request, extensions).await;
+        let _ = match response {
+            Ok(ref response) => finish_http_request(span, response.status().as_u16()),
+            Err(ref error) => {
+                span.log_error(error);
+                span.finish()
+            }
+        };
+        response
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::future::Future;
+    use std::sync::Arc;
+    use std::sync::Mutex;
+    use std::task::Context;
+    use std::task::Poll;
+    use std::task::Waker;
+
+    use async_trait::async_trait;
+    use http::Extensions;
+    use reqwest::Request;
+    use reqwest::Response;
+    use reqwest_middleware::ClientBuilder;
+    use reqwest_middleware::Middleware;
+    use reqwest_middleware::Next;
+    use reqwest_middleware::Result;
+
+    use super::super::super::FinishedSpan;
+    use super::super::super::TagValue;
+    use super::super::super::tracers::FileTracer;
+
+    use super::ParentContext;
+    use super::TracingMiddleware;
+
+
+    /// Responds to requests without sending them, recording their headers.
+    struct Respond(Arc<Mutex<Vec<(String, String)>>>);
+
+    #[async_trait]
+    impl Middleware for Respond {
+        async fn handle(
+            &self, request: Request, _: &mut Extensions, _: Next<'_>
+        ) -> Result<Response> {
+            let mut headers = self.0.lock().unwrap();
+            for (name, value) in request.headers() {
+                headers.push((name.to_string(), value.to_str().unwrap().to_string()));
+            }
+            Ok(Response::from(http::Response::new("")))
+        }
+    }
+
+    fn send(parent: bool) -> (FinishedSpan, Vec<(String, String)>) {
+        let (tracer, receiver) = FileTracer::new();
+        let tracer = Arc::new(tracer);
+        let headers = Arc::new(Mutex::new(Vec::new()));
+        let client = ClientBuilder::new(reqwest::Client::new())
+            .with(TracingMiddleware::new(Arc::clone(&tracer)))
+            .with(Respond(Arc::clone(&headers)))
+            .build();
+        let mut request = client.get("http://example.com/path");
+        if parent {
+            let parent = tracer.span("parent");
+            request = request.with_extension(ParentContext::new(parent.context().clone()));
+        }
+        let mut future = Box::pin(request.send());
+        let mut context = Context::from_waker(Waker::noop());
+        match future.as_mut().poll(&mut context) {
+            Poll::Ready(response) => assert!(response.unwrap().status().is_success()),
+            Poll::Pending => panic!("Expected the request to complete immediately"),
+        }
+        let headers = headers.lock().unwrap().clone();
+        (receiver.recv().unwrap(), headers)
+    }
+
+    #[test]
+    fn injects_client_span() {
+        let (span, headers) = send(false);
+        assert_eq!("HTTP GET", span.name());
+        assert!(span.is_root());
+        assert!(headers.iter().any(|(name, _)| name == "traceid"));
+        match span.tags().get("http.status_code") {
+            Some(TagValue::Unsigned(200)) => (),
+            _ => panic!("Status code not tagged"),
+        }
+    }
+
+    #[test]
+    fn uses_parent_context() {
+        let (span, _) = send(true);
+        assert!(!span.is_root());
+    }
+}
@@ -0,0 +1,324 @@
+//! Compatibility layer for the [tracing](https://crates.io/crates/tracing) crate.
+//!
+//! Requires the `tracing` feature.
+use std::fmt;
+use std::mem;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+
+use tracing::Event;
+use tracing::Id;
+use tracing::Level;
+use tracing::Subscriber;
+use tracing::field::Field;
+use tracing::field::Visit;
+use tracing::span::Attributes;
+use tracing::span::Record;
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::layer::Layer;
+use tracing_subscriber::registry::LookupSpan;
+
+use super::super::Log;
+use super::super::LogValue;
+use super::super::Span;
+use super::super::StartOptions;
+use super::super::Tracer;
+
+
+/// A `tracing_subscriber::Layer` forwarding `tracing` spans and events to a `Tracer`.
+///
+/// The layer allows code instrumented with `tracing` to report to the configured tracer:
+///
+///   * Each `tracing` span starts a `Span` named after it when the `tracing` span is
+///     created and finishes it when the `tracing` span is closed.
+///     The parent of the `tracing` span, if any, becomes the parent of the `Span`.
+///   * Span fields, including fields recorded later, are set as tags.
+///   * Events within a span are attached to it as logs with the event fields
+///     and a `level` field. `ERROR` events also set the `error` tag.
+///     Events outside of spans are ignored.
+///
+/// The layer requires a subscriber that supports span lookups,
+/// such as `tracing_subscriber::Registry`.
+///
+/// Errors sending finished spans to the tracer's receiver are ignored.
+///
+/// # Examples
+///
+/// ```
+/// extern crate opentracingrust;
+/// extern crate tracing;
+/// extern crate tracing_subscriber;
+///
+/// use std::sync::Arc;
+///
+/// use opentracingrust::integrations::tracing::OpenTracingLayer;
+/// use opentracingrust::tracers::NoopTracer;
+/// use tracing_subscriber::Registry;
+/// use tracing_subscriber::layer::SubscriberExt;
+///
+///
+/// fn main() {
+///     let (tracer, receiver) = NoopTracer::new();
+///     let subscriber = Registry::default().with(OpenTracingLayer::new(Arc::new(tracer)));
+///     tracing::subscriber::with_default(subscriber, || {
+///         let span = tracing::info_span!("request", user = 42);
+///         let _guard = span.enter();
+///         tracing::info!(cache.hit = false, "Loading user");
+///     });
+///
+///     let span = receiver.try_recv().unwrap();
+///     assert_eq!("request", span.name());
+///     assert_eq!(1, span.logs().len());
+/// }
+/// ```
+pub struct OpenTracingLayer {
+    tracer: Arc<Tracer>,
+}
+
+impl OpenTracingLayer {
+    /// Creates a layer that reports to the given `Tracer`.
+    ///
+    /// To report to the `GlobalTracer` pass in `GlobalTracer::get()`.
+    pub fn new(tracer: Arc<Tracer>) -> OpenTracingLayer {
+        OpenTracingLayer { tracer }
+    }
+}
+
+impl<S> Layer<S> for OpenTracingLayer
+    where S: Subscriber + for<'lookup> LookupSpan<'lookup>
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let span = match ctx.span(id) {
+            Some(span) => span,
+            None => return,
+        };
+        let parent = span.parent().and_then(|parent| {
+            parent.extensions()
+                .get::<TracedSpan>()
+                .map(|traced| traced.lock().context().clone())
+        });
+        let mut options = StartOptions::default();
+        if let Some(parent) = parent {
+            options = options.child_of(parent);
+        }
+        let name = self.tracer.operation_name(attrs.metadata().name());
+        let mut traced = self.tracer.span_with_options(name, options);
+        attrs.record(&mut TagVisitor(&mut traced));
+        span.extensions_mut().insert(TracedSpan(Mutex::new(traced)));
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            if let Some(traced) = span.extensions().get::<TracedSpan>() {
+                values.record(&mut TagVisitor(&mut traced.lock()));
+            }
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let span = match ctx.event_span(event) {
+            Some(span) => span,
+            None => return,
+        };
+        let extensions = span.extensions();
+        let mut traced = match extensions.get::<TracedSpan>() {
+            Some(traced) => traced.lock(),
+            None => return,
+        };
+        let level = *event.metadata().level();
+        let mut visitor = LogVisitor(Log::new().log("level", level.as_str()));
+        event.record(&mut visitor);
+        if level == Level::ERROR {
+            traced.tag("error", true);
+        }
+        traced.log(visitor.0);
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(&id) {
+            if let Some(traced) = span.extensions_mut().remove::<TracedSpan>() {
+                // There is no one to report the error to from within a subscriber.
+                let _ = traced.into_span().finish();
+            }
+        }
+    }
+}
+
+
+/// A `Span` stored in the extensions of a `tracing` span.
+///
+/// Extensions must be `Sync` but `SpanContext`s are only `Send`.
+struct TracedSpan(Mutex<Span>);
+
+impl TracedSpan {
+    /// Locks the span, recovering it if a panic poisoned the lock.
+    ///
+    /// A poisoned lock only means the application panicked while the span was updated.
+    fn lock(&self) -> MutexGuard<'_, Span> {
+        match self.0.lock() {
+            Ok(span) => span,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    /// Unwraps the span, recovering it if a panic poisoned the lock.
+    fn into_span(self) -> Span {
+        match self.0.into_inner() {
+            Ok(span) => span,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+
+/// Records `tracing` fields as log fields.
+struct LogVisitor(Log);
+
+impl LogVisitor {
+    fn add<V: Into<LogValue>>(&mut self, field: &Field, value: V) {
+        let log = mem::take(&mut self.0);
+        self.0 = log.log(field.name(), value);
+    }
+}
+
+impl Visit for LogVisitor {
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.add(field, value);
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.add(field, format!("{:?}", value));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.add(field, value);
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.add(field, value);
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.add(field, value);
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.add(field, value);
+    }
+}
+
+
+/// Records `tracing` fields as span tags.
+struct TagVisitor<'a>(&'a mut Span);
+
+impl<'a> Visit for TagVisitor<'a> {
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.tag(field.name(), value);
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0.tag(field.name(), format!("{:?}", value));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.tag(field.name(), value);
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.tag(field.name(), value);
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.tag(field.name(), value);
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.tag(field.name(), value);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tracing_subscriber::Registry;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::super::super::FinishedSpan;
+    use super::super::super::LogValue;
+    use super::super::super::TagValue;
+    use super::super::super::tracers::NoopTracer;
+
+    use super::OpenTracingLayer;
+
+
+    fn traced<F: FnOnce()>(block: F) -> Vec<FinishedSpan> {
+        let (tracer, receiver) = NoopTracer::new();
+        let subscriber = Registry::default().with(OpenTracingLayer::new(Arc::new(tracer)));
+        tracing::subscriber::with_default(subscriber, block);
+        receiver.try_iter().collect()
+    }
+
+    #[test]
+    fn events_are_logs() {
+        let spans = traced(|| {
+            let _guard = tracing::info_span!("request").entered();
+            tracing::error!(code = 500, "Request failed");
+        });
+        let span = &spans[0];
+        match span.tags().get("error") {
+            Some(&TagValue::Boolean(true)) => (),
+            _ => panic!("Error tag not set"),
+        }
+        let mut fields: Vec<(&str, String)> = span.logs()[0].iter()
+            .map(|(key, value)| (&key[..], value.to_string()))
+            .collect();
+        fields.sort();
+        assert_eq!(fields, [
+            ("code", "500".to_string()),
+            ("level", "ERROR".to_string()),
+            ("message", "Request failed".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn events_outside_spans_are_ignored() {
+        let spans = traced(|| tracing::info!("Nothing to see"));
+        assert!(spans.is_empty());
+    }
+
+    #[test]
+    fn fields_are_tags() {
+        let spans = traced(|| {
+            let span = tracing::info_span!("request", user = 42, cache = tracing::field::Empty);
+            span.record("cache", "miss");
+        });
+        let tags = spans[0].tags();
+        match tags.get("user") {
+            Some(&TagValue::Integer(42)) => (),
+            _ => panic!("Field not recorded"),
+        }
+        match tags.get("cache") {
+            Some(TagValue::String(value)) => assert_eq!("miss", value),
+            _ => panic!("Late field not recorded"),
+        }
+    }
+
+    #[test]
+    fn spans_are_nested() {
+        let spans = traced(|| {
+            let _request = tracing::info_span!("request").entered();
+            let _query = tracing::info_span!("query").entered();
+            tracing::debug!(rows = 3u64);
+        });
+        let names: Vec<&str> = spans.iter().map(|span| span.name()).collect();
+        assert_eq!(names, ["query", "request"]);
+        assert!(spans[0].parent().unwrap().same_span(spans[1].context()));
+        match spans[0].logs()[0].iter().find(|&(key, _)| key == "rows") {
+            Some((_, LogValue::Unsigned(3))) => (),
+            _ => panic!("Event not logged"),
+        }
+    }
+}
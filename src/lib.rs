@@ -34,6 +34,12 @@
 //!   * `Span`: each instance represents an operation and its metadata.
 //!   * `SpanContext`: a tracer-specific identifier of a `Span`.
 //!
+//! Tags added by the crate itself, rather than by the code being traced, use the `otr.`
+//! prefix (for example `otr.clock_skew` or `otr.truncated.tags`).
+//! Tags defined by the OpenTracing semantic conventions, like `sampling.priority`,
+//! and tags backends already query for, like `timeout.forced_finish` and
+//! `trace.extract_error`, keep their established names.
+//!
 //!
 //! ## Configuraing a `Tracer`
 //! 
@@ -58,7 +64,7 @@
 //!
 //!   * Initialisation returns instance of `Tracer`.
 //!   * A `crossbeam_channel::unbounded` is used by the tracer to send
-//!     `FinishedSpan`s to a reporting thread.
+//!     `SpanMessage`s to a reporting thread through a `SpanReceiver`.
 //!
 //! The reporting thread is responsible for pushing the spans to the
 //! distributed tracer of choice.
@@ -153,39 +159,84 @@
 extern crate crossbeam_channel;
 extern crate rand;
 
+#[cfg(feature = "derive")]
+extern crate opentracingrust_derive;
+
 mod carrier;
 mod errors;
+mod macros;
 mod span;
 mod span_context;
+mod stats;
 mod tracer;
 
+pub mod analysis;
+pub mod export;
+pub mod ids;
+pub mod integrations;
+pub mod propagation;
+pub mod sampling;
+pub mod testing;
 pub mod tracers;
 pub mod utils;
 
 
 pub use self::carrier::ExtractFormat;
+pub use self::carrier::HeaderCase;
 pub use self::carrier::InjectFormat;
 pub use self::carrier::MapCarrier;
+pub use self::carrier::MapFormat;
 
 pub use self::errors::Error;
 pub use self::errors::Result;
 
+pub use self::span_context::BaggageRestrictions;
 pub use self::span_context::ImplContext;
 pub use self::span_context::ImplContextBox;
 pub use self::span_context::SpanContext;
 pub use self::span_context::SpanReferenceAware;
 
 pub use self::span::AutoFinishingSpan;
+pub use self::span::FinishOptions;
 pub use self::span::FinishedSpan;
 pub use self::span::Span;
+pub use self::span::SpanMessage;
 pub use self::span::SpanReceiver;
 pub use self::span::SpanReference;
 pub use self::span::SpanSender;
 pub use self::span::StartOptions;
 
+pub use self::span::batch::FinishedBatch;
+pub use self::span::batch::SpanTree;
+pub use self::span::clock::Clock;
+pub use self::span::clock::CoarseClock;
+pub use self::span::clock::ManualClock;
+pub use self::span::clock::SystemClock;
+pub use self::span::in_flight::InFlightPolicy;
+pub use self::span::kind::SpanKind;
+pub use self::span::limits::AfterFinishPolicy;
+pub use self::span::limits::SpanLimits;
+pub use self::span::limits::TimestampResolution;
+
 pub use self::span::log::Log;
 pub use self::span::log::LogValue;
+pub use self::span::shared::SharedSpan;
 pub use self::span::tag::TagValue;
+pub use self::span::timer::SpanTimer;
+pub use self::span::typed::DbSpan;
+pub use self::span::typed::HttpServerSpan;
+pub use self::span::typed::MessagingSpan;
+pub use self::span::warning::SpanWarning;
+pub use self::span::warning::TruncatedItem;
+pub use self::span::warning::WarningReceiver;
+pub use self::span::warning::WarningSender;
+pub use self::span::warning::warning_channel;
+
+#[cfg(feature = "derive")]
+pub use opentracingrust_derive::traced;
+
+pub use self::stats::TracerStats;
 
+pub use self::tracer::IntoOperationName;
 pub use self::tracer::Tracer;
 pub use self::tracer::TracerInterface;
@@ -0,0 +1,119 @@
+/// Runs a block of code inside an `AutoFinishingSpan` created by the `GlobalTracer`.
+///
+/// The macro evaluates to the value of the block and the span is finished once
+/// the block completes. The span can optionally be:
+///
+///   * Made available to the block with a closure-like `|span|` binding.
+///   * Tagged when created with a list of `tag => value` pairs in square brackets.
+///
+/// To create spans that are children of another span see `child_span!`.
+///
+/// # Panics
+///
+/// Panics if the `GlobalTracer` is not initialised.
+///
+/// # Examples
+///
+/// ```
+/// #[macro_use]
+/// extern crate opentracingrust;
+///
+/// use opentracingrust::tracers::NoopTracer;
+/// use opentracingrust::utils::GlobalTracer;
+///
+///
+/// fn main() {
+///     let (tracer, receiver) = NoopTracer::new();
+///     GlobalTracer::init(tracer);
+///
+///     let sum = span!("sum", { 1 + 2 });
+///     assert_eq!(3, sum);
+///
+///     let user = span!("load_user", ["user.id" => 42], |span| {
+///         span.tag("cache.hit", false);
+///         "someone"
+///     });
+///     assert_eq!("someone", user);
+///
+///     let names: Vec<String> = receiver.try_iter()
+///         .map(|span| String::from(span.name()))
+///         .collect();
+///     assert_eq!(names, ["sum", "load_user"]);
+/// }
+/// ```
+#[macro_export]
+macro_rules! span {
+    ($name:expr, |$span:ident| $body:expr) => {
+        $crate::span!($name, [], |$span| $body)
+    };
+    ($name:expr, [$($tag:expr => $value:expr),* $(,)?], |$span:ident| $body:expr) => {{
+        #[allow(unused_mut)]
+        let mut $span = $crate::utils::GlobalTracer::get().span_auto($name);
+        $( $span.tag($tag, $value); )*
+        $body
+    }};
+    ($name:expr, [$($tag:expr => $value:expr),* $(,)?], $body:block) => {
+        $crate::span!($name, [$($tag => $value),*], |span| $body)
+    };
+    ($name:expr, $body:block) => {
+        $crate::span!($name, [], |span| $body)
+    };
+}
+
+
+/// Runs a block of code inside a child `AutoFinishingSpan` created by the `GlobalTracer`.
+///
+/// Same as `span!` but the span is a child of the given `SpanContext`.
+///
+/// # Panics
+///
+/// Panics if the `GlobalTracer` is not initialised.
+///
+/// # Examples
+///
+/// ```
+/// #[macro_use]
+/// extern crate opentracingrust;
+///
+/// use opentracingrust::tracers::NoopTracer;
+/// use opentracingrust::utils::GlobalTracer;
+///
+///
+/// fn main() {
+///     let (tracer, receiver) = NoopTracer::new();
+///     GlobalTracer::init(tracer);
+///
+///     span!("request", |request| {
+///         child_span!(request.context(), "query", ["db.type" => "sql"], {
+///             // ... snip ...
+///         });
+///     });
+///
+///     let query = receiver.try_recv().unwrap();
+///     assert_eq!("query", query.name());
+///     assert!(!query.is_root());
+/// }
+/// ```
+#[macro_export]
+macro_rules! child_span {
+    ($parent:expr, $name:expr, |$span:ident| $body:expr) => {
+        $crate::child_span!($parent, $name, [], |$span| $body)
+    };
+    (
+        $parent:expr, $name:expr,
+        [$($tag:expr => $value:expr),* $(,)?], |$span:ident| $body:expr
+    ) => {{
+        let options = $crate::StartOptions::default().child_of(($parent).clone());
+        #[allow(unused_mut)]
+        let mut $span = $crate::utils::GlobalTracer::get()
+            .span_auto_with_options($name, options);
+        $( $span.tag($tag, $value); )*
+        $body
+    }};
+    ($parent:expr, $name:expr, [$($tag:expr => $value:expr),* $(,)?], $body:block) => {
+        $crate::child_span!($parent, $name, [$($tag => $value),*], |span| $body)
+    };
+    ($parent:expr, $name:expr, $body:block) => {
+        $crate::child_span!($parent, $name, [], |span| $body)
+    };
+}
@@ -0,0 +1,491 @@
+//! Versioned frame and default codec for the binary propagation format.
+//!
+//! Binary encoded contexts are wrapped in a frame that allows encoders and decoders
+//! of different versions to interoperate:
+//!
+//! ```text
+//! +---------+--------------------+-----------------+---------+
+//! | version | capabilities (u16) | length (u32)    | payload |
+//! +---------+--------------------+-----------------+---------+
+//! ```
+//!
+//! All integers are big endian. Decoders follow these rules:
+//!
+//!   * Frames with a version newer than `VERSION` are accepted: payload fields are
+//!     only ever appended so decoders read the fields they know and ignore the rest.
+//!   * Unknown optional capabilities (the low byte) are ignored.
+//!   * Unknown required capabilities (the high byte) are rejected because the
+//!     payload cannot be understood without them.
+//!
+//! `BinaryContext` is a default payload tracers can use instead of defining their own.
+//! Its fields are encoded in order, fields marked with a capability are only
+//! present if the frame has the capability:
+//!
+//! ```text
+//! trace id       u64, or u128 with TRACE_ID_128
+//! span id        u64
+//! flags          u8, with FLAGS
+//! baggage count  u16
+//! baggage items  key length (u16), key, value length (u16), value
+//! ```
+use std::convert::TryFrom;
+use std::io::ErrorKind;
+use std::io::Read;
+use std::io::Write;
+
+use super::super::Error;
+use super::super::Result;
+
+
+/// Latest version of the binary frame written by this crate.
+pub const VERSION: u8 = 1;
+
+/// `BinaryContext` flag for sampled traces.
+pub const FLAG_SAMPLED: u8 = 0x01;
+
+/// `BinaryContext` flag for debug traces.
+pub const FLAG_DEBUG: u8 = 0x02;
+
+/// `BinaryContext` flag for contexts that carry a sampling decision.
+///
+/// Without this flag a clear `FLAG_SAMPLED` means no decision was made yet,
+/// with it a clear `FLAG_SAMPLED` means the trace is explicitly not sampled.
+pub const FLAG_SAMPLING_DECIDED: u8 = 0x04;
+
+/// Maximum length of a frame payload.
+///
+/// Protects decoders from allocating large buffers when reading invalid data.
+const MAX_PAYLOAD_LEN: u32 = 64 * 1024;
+
+
+/// Set of capability flags describing the content of a `BinaryFrame` payload.
+///
+/// The low byte holds optional capabilities that decoders can ignore if unknown,
+/// the high byte holds required capabilities that decoders must understand.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Capabilities(u16);
+
+impl Capabilities {
+    /// The payload carries sampling and debug flags.
+    pub const FLAGS: Capabilities = Capabilities(0x0001);
+
+    /// The payload carries vendor specific trace state.
+    pub const TRACE_STATE: Capabilities = Capabilities(0x0002);
+
+    /// The payload carries 128-bit trace IDs.
+    pub const TRACE_ID_128: Capabilities = Capabilities(0x0100);
+
+    /// Mask of the capabilities decoders can ignore.
+    const OPTIONAL_MASK: u16 = 0x00ff;
+
+    /// Creates a set of capabilities from their bit representation.
+    pub fn from_bits(bits: u16) -> Capabilities {
+        Capabilities(bits)
+    }
+
+    /// Returns the bit representation of the capabilities.
+    pub fn bits(&self) -> u16 {
+        self.0
+    }
+
+    /// Checks if all the `other` capabilities are in this set.
+    pub fn contains(&self, other: Capabilities) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Adds the `other` capabilities to this set.
+    pub fn with(mut self, other: Capabilities) -> Self {
+        self.0 |= other.0;
+        self
+    }
+}
+
+
+/// A binary encoded context payload along with its version and capabilities.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BinaryFrame {
+    capabilities: Capabilities,
+    payload: Vec<u8>,
+    version: u8,
+}
+
+impl BinaryFrame {
+    /// Creates a frame for the latest `VERSION`.
+    pub fn new(capabilities: Capabilities, payload: Vec<u8>) -> BinaryFrame {
+        BinaryFrame {
+            capabilities,
+            payload,
+            version: VERSION,
+        }
+    }
+
+    /// Reads a frame, understanding the `known` capabilities.
+    ///
+    /// Returns `None` if the reader is empty.
+    /// Unknown optional capabilities are removed from the returned frame.
+    ///
+    /// An error is returned if the frame is truncated, has an invalid version,
+    /// its payload is too large, or it requires unknown capabilities.
+    #[allow(clippy::result_large_err)]
+    pub fn read(reader: &mut dyn Read, known: Capabilities) -> Result<Option<BinaryFrame>> {
+        let mut version = [0; 1];
+        loop {
+            match reader.read(&mut version) {
+                Ok(0) => return Ok(None),
+                Ok(_) => break,
+                Err(ref error) if error.kind() == ErrorKind::Interrupted => continue,
+                Err(error) => return Err(error.into()),
+            }
+        }
+        let version = version[0];
+        if version == 0 {
+            return Err(Error::Msg(String::from("Invalid binary context version 0")));
+        }
+
+        let mut header = [0; 6];
+        reader.read_exact(&mut header)?;
+        let capabilities = u16::from_be_bytes([header[0], header[1]]);
+        let unknown = capabilities & !known.0 & !Capabilities::OPTIONAL_MASK;
+        if unknown != 0 {
+            return Err(Error::Msg(format!(
+                "Binary context requires unsupported capabilities {:#06x}", unknown
+            )));
+        }
+        let length = u32::from_be_bytes([header[2], header[3], header[4], header[5]]);
+        if length > MAX_PAYLOAD_LEN {
+            return Err(Error::Msg(format!(
+                "Binary context payload of {} bytes exceeds the limit of {} bytes",
+                length, MAX_PAYLOAD_LEN
+            )));
+        }
+
+        let mut payload = vec![0; length as usize];
+        reader.read_exact(&mut payload)?;
+        Ok(Some(BinaryFrame {
+            capabilities: Capabilities(capabilities & known.0),
+            payload,
+            version,
+        }))
+    }
+
+    /// Writes the frame.
+    #[allow(clippy::result_large_err)]
+    pub fn write(&self, writer: &mut dyn Write) -> Result<()> {
+        if self.payload.len() > MAX_PAYLOAD_LEN as usize {
+            return Err(Error::Msg(format!(
+                "Binary context payload of {} bytes exceeds the limit of {} bytes",
+                self.payload.len(), MAX_PAYLOAD_LEN
+            )));
+        }
+        writer.write_all(&[self.version])?;
+        writer.write_all(&self.capabilities.0.to_be_bytes())?;
+        writer.write_all(&(self.payload.len() as u32).to_be_bytes())?;
+        writer.write_all(&self.payload)?;
+        Ok(())
+    }
+}
+
+impl BinaryFrame {
+    /// Returns the capabilities of the payload.
+    pub fn capabilities(&self) -> Capabilities {
+        self.capabilities
+    }
+
+    /// Returns the encoded payload.
+    pub fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+
+    /// Returns the version of the encoder that wrote the frame.
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+}
+
+
+/// Default binary encoding of a context, wrapped in a `BinaryFrame`.
+///
+/// Tracers can use this codec to support `ExtractFormat::Binary` and `InjectFormat::Binary`.
+/// See the module documentation for the wire format.
+///
+/// # Examples
+///
+/// ```
+/// extern crate opentracingrust;
+///
+/// use opentracingrust::propagation::binary::BinaryContext;
+/// use opentracingrust::propagation::binary::FLAG_SAMPLED;
+///
+///
+/// fn main() {
+///     let context = BinaryContext::new(1234, 5678)
+///         .with_flags(FLAG_SAMPLED)
+///         .baggage_item("user", "42");
+///     let mut buffer = Vec::new();
+///     context.write(&mut buffer).unwrap();
+///
+///     let decoded = BinaryContext::read(&mut &buffer[..]).unwrap().unwrap();
+///     assert_eq!(context, decoded);
+/// }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BinaryContext {
+    baggage: Vec<(String, String)>,
+    flags: u8,
+    span_id: u64,
+    trace_id: u128,
+}
+
+impl BinaryContext {
+    /// Creates a context with the given IDs, no flags and no baggage.
+    pub fn new(trace_id: u128, span_id: u64) -> BinaryContext {
+        BinaryContext {
+            baggage: Vec::new(),
+            flags: 0,
+            span_id,
+            trace_id,
+        }
+    }
+
+    /// Adds a baggage item to the context.
+    pub fn baggage_item<K, V>(mut self, key: K, value: V) -> Self
+        where K: Into<String>,
+              V: Into<String>,
+    {
+        self.baggage.push((key.into(), value.into()));
+        self
+    }
+
+    /// Sets the context flags (see `FLAG_SAMPLED`, `FLAG_SAMPLING_DECIDED` and `FLAG_DEBUG`).
+    pub fn with_flags(mut self, flags: u8) -> Self {
+        self.flags = flags;
+        self
+    }
+}
+
+impl BinaryContext {
+    /// Reads a context from a `BinaryFrame`.
+    ///
+    /// Returns `None` if the reader is empty.
+    /// Fields appended to the payload by newer versions are ignored.
+    #[allow(clippy::result_large_err)]
+    pub fn read(reader: &mut dyn Read) -> Result<Option<BinaryContext>> {
+        let known = Capabilities::FLAGS.with(Capabilities::TRACE_ID_128);
+        let frame = match BinaryFrame::read(reader, known)? {
+            Some(frame) => frame,
+            None => return Ok(None),
+        };
+        let capabilities = frame.capabilities();
+        let mut payload = frame.payload();
+        let trace_id = if capabilities.contains(Capabilities::TRACE_ID_128) {
+            u128::from_be_bytes(read_bytes(&mut payload)?)
+        } else {
+            u128::from(u64::from_be_bytes(read_bytes(&mut payload)?))
+        };
+        let span_id = u64::from_be_bytes(read_bytes(&mut payload)?);
+        let flags = if capabilities.contains(Capabilities::FLAGS) {
+            u8::from_be_bytes(read_bytes(&mut payload)?)
+        } else {
+            0
+        };
+        let count = u16::from_be_bytes(read_bytes(&mut payload)?);
+        let mut baggage = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let key = read_string(&mut payload)?;
+            let value = read_string(&mut payload)?;
+            baggage.push((key, value));
+        }
+        Ok(Some(BinaryContext { baggage, flags, span_id, trace_id }))
+    }
+
+    /// Writes the context as a `BinaryFrame`.
+    ///
+    /// Trace IDs that fit in 64 bits are encoded as such so decoders
+    /// that do not support 128-bit trace IDs can read the context.
+    #[allow(clippy::result_large_err)]
+    pub fn write(&self, writer: &mut dyn Write) -> Result<()> {
+        let mut capabilities = Capabilities::FLAGS;
+        let mut payload = Vec::new();
+        match u64::try_from(self.trace_id) {
+            Ok(trace_id) => payload.extend_from_slice(&trace_id.to_be_bytes()),
+            Err(_) => {
+                capabilities = capabilities.with(Capabilities::TRACE_ID_128);
+                payload.extend_from_slice(&self.trace_id.to_be_bytes());
+            }
+        }
+        payload.extend_from_slice(&self.span_id.to_be_bytes());
+        payload.push(self.flags);
+        let count = u16::try_from(self.baggage.len()).map_err(|_| {
+            Error::Msg(format!("Too many baggage items ({})", self.baggage.len()))
+        })?;
+        payload.extend_from_slice(&count.to_be_bytes());
+        for (key, value) in &self.baggage {
+            write_string(&mut payload, key)?;
+            write_string(&mut payload, value)?;
+        }
+        BinaryFrame::new(capabilities, payload).write(writer)
+    }
+}
+
+impl BinaryContext {
+    /// Returns the baggage items in the context.
+    pub fn baggage(&self) -> &[(String, String)] {
+        &self.baggage
+    }
+
+    /// Returns the context flags.
+    pub fn flags(&self) -> u8 {
+        self.flags
+    }
+
+    /// Returns the span ID.
+    pub fn span_id(&self) -> u64 {
+        self.span_id
+    }
+
+    /// Returns the trace ID.
+    pub fn trace_id(&self) -> u128 {
+        self.trace_id
+    }
+}
+
+
+/// Reads a fixed number of bytes from the payload.
+#[allow(clippy::result_large_err)]
+fn read_bytes<const N: usize>(payload: &mut &[u8]) -> Result<[u8; N]> {
+    let mut bytes = [0; N];
+    payload.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Reads a length-prefixed UTF-8 string from the payload.
+#[allow(clippy::result_large_err)]
+fn read_string(payload: &mut &[u8]) -> Result<String> {
+    let length = u16::from_be_bytes(read_bytes(payload)?) as usize;
+    let mut bytes = vec![0; length];
+    payload.read_exact(&mut bytes)?;
+    String::from_utf8(bytes).map_err(|_| {
+        Error::Msg(String::from("Binary context baggage is not valid UTF-8"))
+    })
+}
+
+/// Writes a length-prefixed string to the payload.
+#[allow(clippy::result_large_err)]
+fn write_string(payload: &mut Vec<u8>, value: &str) -> Result<()> {
+    let length = u16::try_from(value.len()).map_err(|_| {
+        Error::Msg(format!("Baggage of {} bytes is too long to encode", value.len()))
+    })?;
+    payload.extend_from_slice(&length.to_be_bytes());
+    payload.extend_from_slice(value.as_bytes());
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::Error;
+
+    use super::BinaryContext;
+    use super::BinaryFrame;
+    use super::Capabilities;
+    use super::FLAG_SAMPLED;
+    use super::VERSION;
+
+
+    fn read(mut bytes: &[u8], known: Capabilities) -> Option<BinaryFrame> {
+        BinaryFrame::read(&mut bytes, known).unwrap()
+    }
+
+    fn read_error(mut bytes: &[u8]) -> String {
+        match BinaryFrame::read(&mut bytes, Capabilities::default()) {
+            Err(Error::Msg(message)) => message,
+            Err(error) => panic!("Unexpected error: {:?}", error),
+            Ok(_) => panic!("Expected an error"),
+        }
+    }
+
+    #[test]
+    fn empty_carrier() {
+        assert!(read(&[], Capabilities::default()).is_none());
+    }
+
+    #[test]
+    fn newer_versions_are_accepted() {
+        let frame = read(&[VERSION + 1, 0, 0, 0, 0, 0, 2, 1, 2], Capabilities::default()).unwrap();
+        assert_eq!(VERSION + 1, frame.version());
+        assert_eq!(&[1, 2], frame.payload());
+    }
+
+    #[test]
+    fn round_trip() {
+        let capabilities = Capabilities::FLAGS.with(Capabilities::TRACE_ID_128);
+        let frame = BinaryFrame::new(capabilities, vec![1, 2, 3]);
+        let mut buffer = Vec::new();
+        frame.write(&mut buffer).unwrap();
+        assert_eq!(buffer, [VERSION, 0x01, 0x01, 0, 0, 0, 3, 1, 2, 3]);
+        assert_eq!(Some(frame), read(&buffer, capabilities));
+    }
+
+    #[test]
+    fn truncated_frames_are_rejected() {
+        let mut bytes: &[u8] = &[VERSION, 0, 0, 0, 0, 0, 4, 1];
+        match BinaryFrame::read(&mut bytes, Capabilities::default()) {
+            Err(Error::IoError(_)) => (),
+            Err(error) => panic!("Unexpected error: {:?}", error),
+            Ok(_) => panic!("Expected an error"),
+        }
+    }
+
+    #[test]
+    fn unknown_optional_capabilities_are_ignored() {
+        let frame = read(&[VERSION, 0x00, 0x83, 0, 0, 0, 0], Capabilities::FLAGS).unwrap();
+        assert_eq!(Capabilities::FLAGS, frame.capabilities());
+    }
+
+    #[test]
+    fn unknown_required_capabilities_are_rejected() {
+        let message = read_error(&[VERSION, 0x01, 0x00, 0, 0, 0, 0]);
+        assert_eq!("Binary context requires unsupported capabilities 0x0100", message);
+    }
+
+    #[test]
+    fn version_zero_is_rejected() {
+        assert_eq!("Invalid binary context version 0", read_error(&[0]));
+    }
+
+    #[test]
+    fn context_round_trip() {
+        let context = BinaryContext::new(u128::MAX, 2)
+            .with_flags(FLAG_SAMPLED)
+            .baggage_item("a", "b");
+        let mut buffer = Vec::new();
+        context.write(&mut buffer).unwrap();
+        let frame = read(&buffer, Capabilities::TRACE_ID_128.with(Capabilities::FLAGS)).unwrap();
+        assert!(frame.capabilities().contains(Capabilities::TRACE_ID_128));
+        assert_eq!(Some(context), BinaryContext::read(&mut &buffer[..]).unwrap());
+    }
+
+    #[test]
+    fn context_wire_format() {
+        let context = BinaryContext::new(1, 2).baggage_item("k", "v");
+        let mut buffer = Vec::new();
+        context.write(&mut buffer).unwrap();
+        assert_eq!(&buffer[..7], [VERSION, 0x00, 0x01, 0, 0, 0, 25]);
+        assert_eq!(&buffer[7..], [
+            0, 0, 0, 0, 0, 0, 0, 1,
+            0, 0, 0, 0, 0, 0, 0, 2,
+            0,
+            0, 1,
+            0, 1, b'k', 0, 1, b'v',
+        ]);
+    }
+
+    #[test]
+    fn context_ignores_appended_fields() {
+        let payload = vec![0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 42];
+        let mut buffer = Vec::new();
+        BinaryFrame::new(Capabilities::default(), payload).write(&mut buffer).unwrap();
+        let context = BinaryContext::read(&mut &buffer[..]).unwrap().unwrap();
+        assert_eq!(BinaryContext::new(1, 2), context);
+    }
+}
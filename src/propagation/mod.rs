@@ -0,0 +1,5 @@
+//! Reusable context propagation codecs.
+//!
+//! Concrete tracers can use these codecs to implement the `ExtractFormat`s and
+//! `InjectFormat`s instead of defining their own wire formats.
+pub mod binary;
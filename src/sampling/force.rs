@@ -0,0 +1,113 @@
+use super::super::SpanContext;
+use super::Sampler;
+
+
+/// Baggage item carrying force-trace tokens.
+pub const FORCE_TRACE_KEY: &str = "force-trace";
+
+
+/// `Sampler` that always samples traces carrying a valid force-trace token.
+///
+/// Support and debugging flows often need specific requests to be traced regardless
+/// of sampling rates: clients attach a token to the `force-trace` baggage item
+/// and the token is propagated along with the rest of the baggage.
+///
+/// Tokens are checked by the given validator so that only trusted callers can force
+/// traces (for example by verifying a signature with the application's keys).
+/// Tokens are checked before the sampling decision inherited from referenced or
+/// extracted contexts so a valid token also records traces an upstream service
+/// did not sample.
+/// Traces without a token or with an invalid token are sampled by the inner sampler.
+///
+/// # Examples
+///
+/// ```
+/// extern crate opentracingrust;
+///
+/// use opentracingrust::sampling::ForceTraceSampler;
+/// use opentracingrust::sampling::WeightedSampler;
+///
+///
+/// fn main() {
+///     let sampler = ForceTraceSampler::new(WeightedSampler::new(0.01), |token: &str| {
+///         // ... verify the token signature ...
+///         token == "trusted-token"
+///     });
+///     // ... snip ...
+/// }
+/// ```
+pub struct ForceTraceSampler<S: Sampler> {
+    inner: S,
+    validator: Box<dyn Fn(&str) -> bool + Send + Sync>,
+}
+
+impl<S: Sampler> ForceTraceSampler<S> {
+    /// Creates a sampler forcing traces with tokens accepted by `validator`.
+    pub fn new<V>(inner: S, validator: V) -> ForceTraceSampler<S>
+        where V: Fn(&str) -> bool + Send + Sync + 'static
+    {
+        ForceTraceSampler {
+            inner,
+            validator: Box::new(validator),
+        }
+    }
+}
+
+impl<S: Sampler> Sampler for ForceTraceSampler<S> {
+    fn sample(&self, context: &SpanContext) -> bool {
+        self.force(context) || self.inner.sample(context)
+    }
+
+    fn force(&self, context: &SpanContext) -> bool {
+        match context.get_baggage_item(FORCE_TRACE_KEY) {
+            Some(token) => (self.validator)(token) || self.inner.force(context),
+            None => self.inner.force(context),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::ImplContextBox;
+    use super::super::super::SpanContext;
+    use super::super::super::SpanReference;
+    use super::super::super::SpanReferenceAware;
+
+    use super::super::Sampler;
+    use super::super::WeightedSampler;
+    use super::ForceTraceSampler;
+
+
+    #[derive(Clone)]
+    struct TestContext;
+    impl SpanReferenceAware for TestContext {
+        fn reference_span(&mut self, _: &SpanReference) {}
+    }
+
+    #[test]
+    fn valid_tokens_force_sampling() {
+        let sampler = ForceTraceSampler::new(WeightedSampler::new(0.0), |token: &str| {
+            token == "valid"
+        });
+        let mut context = SpanContext::new(ImplContextBox::new(TestContext));
+        assert!(!sampler.sample(&context));
+        context.set_baggage_item(String::from("force-trace"), String::from("forged"));
+        assert!(!sampler.sample(&context));
+        context.set_baggage_item(String::from("force-trace"), String::from("valid"));
+        assert!(sampler.sample(&context));
+    }
+
+    #[test]
+    fn valid_tokens_override_inherited_decisions() {
+        let sampler = ForceTraceSampler::new(WeightedSampler::new(1.0), |token: &str| {
+            token == "valid"
+        });
+        let mut context = SpanContext::new(ImplContextBox::new(TestContext));
+        assert!(!sampler.force(&context));
+        context.set_baggage_item(String::from("force-trace"), String::from("forged"));
+        assert!(!sampler.force(&context));
+        context.set_baggage_item(String::from("force-trace"), String::from("valid"));
+        assert!(sampler.force(&context));
+    }
+}
@@ -0,0 +1,33 @@
+//! Sampling decisions for new traces.
+//!
+//! A `Sampler` configured with `Tracer::with_sampler` decides whether a trace
+//! should be recorded when its first span is started.
+//! The decision is stored in the `SpanContext` (see `SpanContext::sampled`)
+//! and inherited by all the spans in the trace, unless `Sampler::force`
+//! overrides it.
+use super::SpanContext;
+
+mod force;
+mod weighted;
+
+pub use self::force::FORCE_TRACE_KEY;
+pub use self::force::ForceTraceSampler;
+pub use self::weighted::WeightedSampler;
+
+
+/// Decides if new traces should be sampled.
+pub trait Sampler : Send + Sync {
+    /// Returns `true` if the trace starting with the given context should be sampled.
+    ///
+    /// The context already holds the baggage items of any referenced contexts.
+    fn sample(&self, context: &SpanContext) -> bool;
+
+    /// Returns `true` if the span must be sampled even if it inherited a decision.
+    ///
+    /// Called for every new span, before the sampling decision inherited from
+    /// referenced or extracted contexts is considered.
+    /// The default implementation never overrides inherited decisions.
+    fn force(&self, _context: &SpanContext) -> bool {
+        false
+    }
+}
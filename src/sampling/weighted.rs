@@ -0,0 +1,115 @@
+use rand::random;
+
+use super::super::SpanContext;
+use super::Sampler;
+
+
+/// Probabilistic `Sampler` with per-baggage-item probabilities.
+///
+/// Rules match a baggage item key and value to a sampling probability.
+/// Rules are evaluated in the order they were added and the first matching
+/// rule decides the probability; if no rule matches the default probability is used.
+///
+/// Probabilities range from `0.0` (never sample) to `1.0` (always sample).
+///
+/// # Examples
+///
+/// ```
+/// extern crate opentracingrust;
+///
+/// use opentracingrust::sampling::WeightedSampler;
+/// use opentracingrust::tracers::NoopTracer;
+///
+///
+/// fn main() {
+///     // Sample all premium traffic but only 1% of everything else.
+///     let sampler = WeightedSampler::new(0.01).rule("tier", "premium", 1.0);
+///     let (tracer, _) = NoopTracer::new();
+///     let tracer = tracer.with_sampler(sampler);
+///     let span = tracer.span("root");
+///     assert!(span.context().sampled().is_some());
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct WeightedSampler {
+    default: f64,
+    rules: Vec<(String, String, f64)>,
+}
+
+impl WeightedSampler {
+    /// Creates a sampler with no rules and the given default probability.
+    pub fn new(default: f64) -> WeightedSampler {
+        WeightedSampler {
+            default,
+            rules: Vec::new(),
+        }
+    }
+
+    /// Adds a rule sampling traces with the `key=value` baggage item with the given probability.
+    pub fn rule<K, V>(mut self, key: K, value: V, probability: f64) -> Self
+        where K: Into<String>,
+              V: Into<String>,
+    {
+        self.rules.push((key.into(), value.into(), probability));
+        self
+    }
+
+    /// Returns the probability of sampling a trace starting with the given context.
+    pub fn probability(&self, context: &SpanContext) -> f64 {
+        self.rules.iter()
+            .find(|(key, value, _)| context.get_baggage_item(key) == Some(value))
+            .map(|(_, _, probability)| *probability)
+            .unwrap_or(self.default)
+    }
+}
+
+impl Sampler for WeightedSampler {
+    fn sample(&self, context: &SpanContext) -> bool {
+        random::<f64>() < self.probability(context)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::ImplContextBox;
+    use super::super::super::SpanContext;
+    use super::super::super::SpanReference;
+    use super::super::super::SpanReferenceAware;
+
+    use super::super::Sampler;
+    use super::WeightedSampler;
+
+
+    #[derive(Clone)]
+    struct TestContext;
+    impl SpanReferenceAware for TestContext {
+        fn reference_span(&mut self, _: &SpanReference) {}
+    }
+
+    fn context(items: &[(&str, &str)]) -> SpanContext {
+        let mut context = SpanContext::new(ImplContextBox::new(TestContext));
+        for (key, value) in items {
+            context.set_baggage_item(String::from(*key), String::from(*value));
+        }
+        context
+    }
+
+    #[test]
+    fn first_matching_rule_wins() {
+        let sampler = WeightedSampler::new(0.01)
+            .rule("tier", "premium", 1.0)
+            .rule("region", "eu", 0.5);
+        let premium = context(&[("region", "eu"), ("tier", "premium")]);
+        assert_eq!(1.0, sampler.probability(&premium));
+        assert_eq!(0.5, sampler.probability(&context(&[("region", "eu")])));
+        assert_eq!(0.01, sampler.probability(&context(&[("tier", "free")])));
+    }
+
+    #[test]
+    fn extreme_probabilities() {
+        let sampler = WeightedSampler::new(0.0).rule("tier", "premium", 1.0);
+        assert!(sampler.sample(&context(&[("tier", "premium")])));
+        assert!(!sampler.sample(&context(&[])));
+    }
+}
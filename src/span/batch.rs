@@ -0,0 +1,221 @@
+use std::slice::Iter;
+use std::vec::IntoIter;
+
+use super::super::Result;
+
+use super::FinishedSpan;
+use super::Span;
+use super::SpanMessage;
+
+
+/// A group of `FinishedSpan`s sent to the tracer's receiver with a single message.
+///
+/// Batches are created by `SpanTree::finish` and `Span::finish_children_then_self`.
+/// The `SpanReceiver` unpacks batches so reporters still receive one `FinishedSpan` at a time.
+// Structure invariant: A FinishedBatch *always* contains at least one span.
+//   Batches are only created by `SpanTree::finish`, which never sends empty batches.
+#[derive(Debug)]
+pub struct FinishedBatch(Vec<FinishedSpan>);
+
+impl FinishedBatch {
+    pub(crate) fn new(spans: Vec<FinishedSpan>) -> FinishedBatch {
+        FinishedBatch(spans)
+    }
+}
+
+impl FinishedBatch {
+    /// Iterates over the spans in the batch in the order they were finished.
+    pub fn iter(&self) -> Iter<'_, FinishedSpan> {
+        self.0.iter()
+    }
+
+    /// Returns `true` if the batch holds no spans.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns the number of spans in the batch.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+impl IntoIterator for FinishedBatch {
+    type Item = FinishedSpan;
+    type IntoIter = IntoIter<FinishedSpan>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+
+/// A set of related `Span`s that are finished together.
+///
+/// Fan-out patterns often end up with many spans that complete at the same time.
+/// Instead of sending each span to the tracer's receiver individually, a `SpanTree`
+/// finishes all of them at once and sends a single `FinishedBatch`.
+///
+/// All spans in the tree are expected to be created by the same `Tracer`:
+/// the batch is sent using the channel of the first span in the tree.
+///
+/// # Examples
+///
+/// ```
+/// extern crate opentracingrust;
+///
+/// use opentracingrust::SpanTree;
+/// use opentracingrust::StartOptions;
+/// use opentracingrust::tracers::NoopTracer;
+///
+///
+/// fn main() {
+///     let (tracer, receiver) = NoopTracer::new();
+///     let root = tracer.span("root");
+///     let mut tree = SpanTree::new();
+///     for _ in 0..3 {
+///         let options = StartOptions::default().child_of(root.context().clone());
+///         tree.push(tracer.span_with_options("fan-out", options));
+///     }
+///     tree.push(root);
+///     tree.finish().unwrap();
+///     assert_eq!(4, receiver.try_iter().count());
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct SpanTree {
+    spans: Vec<Span>,
+}
+
+impl SpanTree {
+    /// Creates an empty `SpanTree`.
+    pub fn new() -> SpanTree {
+        SpanTree { spans: Vec::new() }
+    }
+}
+
+impl SpanTree {
+    /// Finishes all spans in the tree and sends them as a single `FinishedBatch`.
+    ///
+    /// Spans are finished in the order they were added to the tree.
+    /// Discarded spans (see `Span::discarded`) are left out of the batch and
+    /// finishing a tree without other spans does nothing.
+    ///
+    /// Any error sending the batch is returned to the caller.
+    #[allow(clippy::result_large_err)]
+    pub fn finish(self) -> Result<()> {
+        let mut sender = None;
+        let mut finished = Vec::with_capacity(self.spans.len());
+        for span in self.spans.into_iter().filter_map(Span::into_finished) {
+            let (span, span_sender) = span;
+            if sender.is_none() {
+                sender = Some(span_sender);
+            }
+            finished.push(span);
+        }
+        match sender {
+            None => Ok(()),
+            Some(sender) => {
+                let batch = FinishedBatch::new(finished);
+                sender.send(SpanMessage::Batch(batch))?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Returns `true` if the tree holds no spans.
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+
+    /// Returns the number of spans in the tree.
+    pub fn len(&self) -> usize {
+        self.spans.len()
+    }
+
+    /// Adds a span to the tree.
+    pub fn push(&mut self, span: Span) {
+        self.spans.push(span);
+    }
+}
+
+impl From<Vec<Span>> for SpanTree {
+    fn from(spans: Vec<Span>) -> SpanTree {
+        SpanTree { spans }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use crossbeam_channel::unbounded;
+
+    use super::super::super::ImplContextBox;
+    use super::super::super::SpanContext;
+    use super::super::super::SpanReference;
+    use super::super::super::SpanReferenceAware;
+    use super::super::super::StartOptions;
+
+    use super::super::Span;
+    use super::super::SpanMessage;
+    use super::super::SpanReceiver;
+    use super::SpanTree;
+
+
+    #[derive(Debug, Clone)]
+    struct TestContext {}
+    impl SpanReferenceAware for TestContext {
+        fn reference_span(&mut self, _: &SpanReference) {}
+    }
+
+    fn make_spans(names: &[&str]) -> (Vec<Span>, SpanReceiver) {
+        let (sender, receiver) = unbounded();
+        let sender = Arc::new(sender);
+        let spans = names.iter().map(|name| {
+            let context = SpanContext::new(ImplContextBox::new(TestContext {}));
+            Span::new(*name, context, StartOptions::default(), Arc::clone(&sender))
+        }).collect();
+        (spans, SpanReceiver::new(receiver))
+    }
+
+    #[test]
+    fn empty_tree_sends_nothing() {
+        let (_, receiver) = make_spans(&[]);
+        SpanTree::new().finish().unwrap();
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn finish_children_then_self() {
+        let (mut spans, receiver) = make_spans(&["root", "child1", "child2"]);
+        let root = spans.remove(0);
+        root.finish_children_then_self(spans).unwrap();
+        let names: Vec<String> = receiver.try_iter()
+            .map(|span| String::from(span.name()))
+            .collect();
+        assert_eq!(names, ["child1", "child2", "root"]);
+    }
+
+    #[test]
+    fn tree_is_sent_as_one_batch() {
+        let (sender, receiver) = unbounded();
+        let sender = Arc::new(sender);
+        let mut tree = SpanTree::new();
+        for name in &["a", "b", "c"] {
+            let context = SpanContext::new(ImplContextBox::new(TestContext {}));
+            tree.push(Span::new(*name, context, StartOptions::default(), Arc::clone(&sender)));
+        }
+        tree.finish().unwrap();
+        match receiver.recv_timeout(Duration::from_secs(1)).unwrap() {
+            SpanMessage::Batch(batch) => {
+                let names: Vec<&str> = batch.iter().map(|span| span.name()).collect();
+                assert_eq!(names, ["a", "b", "c"]);
+            },
+            SpanMessage::Finished(_) => panic!("Expected a batch message")
+        }
+        assert!(receiver.try_recv().is_err());
+    }
+}
@@ -0,0 +1,233 @@
+use std::fmt::Debug;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::thread::Builder;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use crossbeam_channel::RecvTimeoutError;
+use crossbeam_channel::Sender;
+use crossbeam_channel::unbounded;
+
+
+const REFRESH_INTERVAL_MSEC_DEFAULT: u64 = 1;
+
+
+/// Source of the current time for spans and logs.
+///
+/// Spans read the current time from a `Clock` when they start, when they finish
+/// and when logs without a timestamp are attached to them.
+/// `Tracer`s use the `SystemClock` unless one is set with `Tracer::with_clock`.
+///
+/// Implementations must be cheap to call as spans read the clock often.
+pub trait Clock: Debug + Send + Sync {
+    /// Returns the current time.
+    fn now(&self) -> SystemTime;
+}
+
+
+/// A `Clock` reading the system clock.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+
+/// A `Clock` that only moves when told to.
+///
+/// Useful in tests to assert exact timestamps.
+///
+/// # Examples
+///
+/// ```
+/// extern crate opentracingrust;
+///
+/// use std::sync::Arc;
+/// use std::time::Duration;
+/// use std::time::UNIX_EPOCH;
+///
+/// use opentracingrust::ManualClock;
+/// use opentracingrust::tracers::NoopTracer;
+///
+///
+/// fn main() {
+///     let (tracer, receiver) = NoopTracer::new();
+///     let clock = Arc::new(ManualClock::new(UNIX_EPOCH));
+///     let tracer = tracer.with_clock(clock.clone());
+///     let span = tracer.span("test");
+///     clock.advance(Duration::from_secs(2));
+///     span.finish().unwrap();
+///
+///     let span = receiver.recv().unwrap();
+///     assert_eq!(UNIX_EPOCH, *span.start_time());
+///     assert_eq!(Duration::from_secs(2), span.duration());
+/// }
+/// ```
+#[derive(Debug)]
+pub struct ManualClock {
+    now: Mutex<SystemTime>,
+}
+
+impl ManualClock {
+    /// Creates a clock stopped at the given time.
+    pub fn new(now: SystemTime) -> ManualClock {
+        ManualClock {
+            now: Mutex::new(now),
+        }
+    }
+
+    /// Moves the clock forward by the given duration.
+    pub fn advance(&self, duration: Duration) {
+        *self.lock() += duration;
+    }
+
+    /// Moves the clock to the given time.
+    pub fn set(&self, now: SystemTime) {
+        *self.lock() = now;
+    }
+
+    fn lock(&self) -> MutexGuard<'_, SystemTime> {
+        self.now.lock().expect("Failed to lock ManualClock")
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> SystemTime {
+        *self.lock()
+    }
+}
+
+
+/// A coarse clock that caches the current time and refreshes it periodically.
+///
+/// Reading the system clock is a syscall on most platforms and spans read it at least
+/// twice (when they start and when they finish).
+/// Hot paths that do not need microsecond precision can configure the `Tracer` with
+/// a `CoarseClock` (see `Tracer::with_clock`) so spans read a cached time instead.
+///
+/// A background thread refreshes the cached time every millisecond by default
+/// and stops when the clock is dropped.
+/// Cached times are truncated to the millisecond.
+///
+/// # Examples
+///
+/// ```
+/// extern crate opentracingrust;
+///
+/// use std::sync::Arc;
+///
+/// use opentracingrust::CoarseClock;
+/// use opentracingrust::tracers::NoopTracer;
+///
+///
+/// fn main() {
+///     let (tracer, _receiver) = NoopTracer::new();
+///     let tracer = tracer.with_clock(Arc::new(CoarseClock::new()));
+///     tracer.span("hot_path").finish().unwrap();
+/// }
+/// ```
+#[derive(Debug)]
+pub struct CoarseClock {
+    now: Arc<AtomicU64>,
+    stop_sender: Option<Sender<()>>,
+    thread_handle: Option<JoinHandle<()>>,
+}
+
+impl CoarseClock {
+    /// Creates a clock refreshed every millisecond.
+    pub fn new() -> CoarseClock {
+        CoarseClock::new_with_interval(Duration::from_millis(REFRESH_INTERVAL_MSEC_DEFAULT))
+    }
+
+    /// Creates a clock refreshed at the given interval.
+    pub fn new_with_interval(interval: Duration) -> CoarseClock {
+        let now = Arc::new(AtomicU64::new(epoch_millis()));
+        let inner_now = Arc::clone(&now);
+        let (stop_sender, stop_receiver) = unbounded::<()>();
+        let thread = Builder::new().name("OpenTracingClock".into()).spawn(move || {
+            while let Err(RecvTimeoutError::Timeout) = stop_receiver.recv_timeout(interval) {
+                inner_now.store(epoch_millis(), Ordering::Relaxed);
+            }
+        }).expect("Failed to spawn clock thread");
+        CoarseClock {
+            now,
+            stop_sender: Some(stop_sender),
+            thread_handle: Some(thread),
+        }
+    }
+
+    /// Returns the cached current time.
+    pub fn now(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_millis(self.now.load(Ordering::Relaxed))
+    }
+}
+
+impl Clock for CoarseClock {
+    fn now(&self) -> SystemTime {
+        CoarseClock::now(self)
+    }
+}
+
+impl Default for CoarseClock {
+    fn default() -> CoarseClock {
+        CoarseClock::new()
+    }
+}
+
+impl Drop for CoarseClock {
+    fn drop(&mut self) {
+        self.stop_sender.take();
+        if let Some(thread) = self.thread_handle.take() {
+            thread.join().expect("Failed to join clock thread");
+        }
+    }
+}
+
+
+/// Returns the milliseconds elapsed since the UNIX epoch.
+fn epoch_millis() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH)
+        .map(|since_epoch| since_epoch.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use std::time::Duration;
+    use std::time::SystemTime;
+    use std::time::UNIX_EPOCH;
+
+    use super::Clock;
+    use super::CoarseClock;
+    use super::ManualClock;
+
+    #[test]
+    fn manual_clock() {
+        let clock = ManualClock::new(UNIX_EPOCH);
+        clock.advance(Duration::from_millis(5));
+        assert_eq!(UNIX_EPOCH + Duration::from_millis(5), Clock::now(&clock));
+        clock.set(UNIX_EPOCH);
+        assert_eq!(UNIX_EPOCH, Clock::now(&clock));
+    }
+
+    #[test]
+    fn refreshes_cached_time() {
+        let clock = CoarseClock::new_with_interval(Duration::from_millis(5));
+        let first = clock.now();
+        assert!(first <= SystemTime::now());
+        assert_eq!(0, first.duration_since(UNIX_EPOCH).unwrap().subsec_nanos() % 1_000_000);
+        thread::sleep(Duration::from_millis(50));
+        assert!(clock.now() > first);
+    }
+}
@@ -0,0 +1,103 @@
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+use super::Span;
+use super::SpanReference;
+use super::TagValue;
+
+
+/// Tag set by `InFlightPolicy::TagRoot` with the number of spans in flight.
+const IN_FLIGHT_EXCEEDED_TAG: &str = "otr.in_flight.exceeded";
+
+
+/// What a `Tracer` does with new spans once too many spans are in flight.
+///
+/// See `Tracer::with_max_in_flight`.
+pub enum InFlightPolicy {
+    /// New spans do not record so their tags and logs are dropped and they are never
+    /// reported (see `Span::is_recording`).
+    ///
+    /// The refusal only affects the new span: its sampling decision is left alone
+    /// so it is not inherited by children or propagated to other services.
+    RefuseRecording,
+
+    /// New root spans are tagged with `otr.in_flight.exceeded` set to the
+    /// number of spans in flight.
+    ///
+    /// Only root spans are tagged so affected traces can be found
+    /// without tagging every span in them.
+    TagRoot,
+
+    /// Calls the function with the number of spans in flight for every new span.
+    Warn(Box<dyn Fn(usize) + Send + Sync>),
+}
+
+impl fmt::Debug for InFlightPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InFlightPolicy::RefuseRecording => write!(f, "RefuseRecording"),
+            InFlightPolicy::TagRoot => write!(f, "TagRoot"),
+            InFlightPolicy::Warn(_) => write!(f, "Warn(..)"),
+        }
+    }
+}
+
+
+/// Limit on the number of spans a `Tracer` has in flight at once.
+#[derive(Debug)]
+pub(crate) struct InFlightLimit {
+    count: Arc<AtomicUsize>,
+    max: usize,
+    policy: InFlightPolicy,
+}
+
+impl InFlightLimit {
+    pub(crate) fn new(max: usize, policy: InFlightPolicy) -> InFlightLimit {
+        InFlightLimit {
+            count: Arc::new(AtomicUsize::new(0)),
+            max,
+            policy,
+        }
+    }
+
+    /// Returns the number of spans in flight.
+    pub(crate) fn count(&self) -> usize {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    /// Returns the shared counter of spans in flight.
+    pub(crate) fn counter(&self) -> Arc<AtomicUsize> {
+        Arc::clone(&self.count)
+    }
+
+    /// Tracks a new span until it is finished or dropped and applies the policy if needed.
+    pub(crate) fn track(&self, span: &mut Span) {
+        let count = self.count.fetch_add(1, Ordering::Relaxed) + 1;
+        span.in_flight = Some(InFlightGuard(Arc::clone(&self.count)));
+        if count <= self.max {
+            return;
+        }
+        match self.policy {
+            InFlightPolicy::RefuseRecording => span.refuse_recording(),
+            InFlightPolicy::TagRoot => {
+                if SpanReference::parent(span.references()).is_none() {
+                    span.tag(IN_FLIGHT_EXCEEDED_TAG, TagValue::Unsigned(count as u64));
+                }
+            },
+            InFlightPolicy::Warn(ref warn) => warn(count),
+        }
+    }
+}
+
+
+/// Decrements the number of spans in flight when the span is finished or dropped.
+#[derive(Debug)]
+pub(crate) struct InFlightGuard(Arc<AtomicUsize>);
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
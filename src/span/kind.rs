@@ -0,0 +1,43 @@
+use std::fmt;
+
+
+/// Enumeration of the roles a `Span` can play in a trace.
+///
+/// The kind of a span is set with `StartOptions::span_kind` and
+/// replaces the OpenTracing `span.kind` tag convention.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SpanKind {
+    /// The span covers the client side of an RPC or remote request.
+    Client,
+
+    /// The span covers the server side of an RPC or remote request.
+    Server,
+
+    /// The span covers the sending of a message to a broker.
+    Producer,
+
+    /// The span covers the processing of a message received from a broker.
+    Consumer,
+
+    /// The span covers an operation internal to the process.
+    Internal,
+}
+
+impl SpanKind {
+    /// Returns the value of the OpenTracing `span.kind` tag for this kind.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SpanKind::Client => "client",
+            SpanKind::Server => "server",
+            SpanKind::Producer => "producer",
+            SpanKind::Consumer => "consumer",
+            SpanKind::Internal => "internal",
+        }
+    }
+}
+
+impl fmt::Display for SpanKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
@@ -0,0 +1,244 @@
+use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use super::clock::Clock;
+use super::clock::SystemClock;
+use super::tag::SpanTags;
+use super::tag::TagValue;
+
+
+/// Tag set on finished spans that dropped logs because of `SpanLimits::max_logs`.
+const TRUNCATED_LOGS_TAG: &str = "otr.truncated.logs";
+
+/// Tag set on finished spans that dropped references because of `SpanLimits::max_references`.
+const TRUNCATED_REFERENCES_TAG: &str = "otr.truncated.references";
+
+/// Tag set on finished spans that dropped tags because of `SpanLimits::max_tags`.
+const TRUNCATED_TAGS_TAG: &str = "otr.truncated.tags";
+
+
+/// What a span does with tags and references added after its finish time was set.
+///
+/// Setting the finish time with `Span::finish_time` allows the span to be populated
+/// after the operation completed but changes made after that point can also be
+/// a sign of confusing instrumentation, such as a child operation being attached
+/// to a parent that already finished.
+///
+/// Warnings are reported as `SpanWarning::TagAfterFinish` and
+/// `SpanWarning::ReferenceAfterFinish` (see `Tracer::with_warnings`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AfterFinishPolicy {
+    /// Changes are applied silently.
+    Allow,
+
+    /// Changes are applied and a warning is reported.
+    Warn,
+
+    /// Changes are dropped and a warning is reported.
+    Reject,
+}
+
+
+/// Precision of the timestamps captured by spans.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimestampResolution {
+    /// Timestamps are stored with the full precision of the system clock.
+    Nanosecond,
+
+    /// Timestamps are truncated to the millisecond.
+    Millisecond,
+}
+
+
+/// Limits and trade-offs applied to spans as they are populated.
+///
+/// Limits are configured on the `Tracer` with `Tracer::with_span_limits` and apply
+/// to all spans it creates unless overridden for a span with `StartOptions::span_limits`.
+///
+/// The default limits capture timestamps at full resolution for spans and logs
+/// and do not restrict the number of tags, logs and references.
+///
+/// When a span drops data because of these limits the number of dropped items is
+/// recorded on the finished span with the `otr.truncated.tags`, `otr.truncated.logs`
+/// and `otr.truncated.references` tags so missing data can be told apart from dropped data.
+///
+/// # Examples
+///
+/// ```
+/// extern crate opentracingrust;
+///
+/// use opentracingrust::SpanLimits;
+/// use opentracingrust::StartOptions;
+/// use opentracingrust::TimestampResolution;
+/// use opentracingrust::tracers::NoopTracer;
+///
+///
+/// fn main() {
+///     let (tracer, _) = NoopTracer::new();
+///     let limits = SpanLimits::default()
+///         .log_timestamps(false)
+///         .timestamp_resolution(TimestampResolution::Millisecond);
+///     let options = StartOptions::default().span_limits(limits);
+///     let span = tracer.span_with_options("hot_path", options);
+///     // ... snip ...
+/// }
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct SpanLimits {
+    pub(crate) after_finish: AfterFinishPolicy,
+    pub(crate) log_timestamps: bool,
+    pub(crate) max_logs: Option<usize>,
+    pub(crate) max_references: Option<usize>,
+    pub(crate) max_tags: Option<usize>,
+    pub(crate) progress_interval: Duration,
+    pub(crate) timestamp_resolution: TimestampResolution,
+}
+
+impl SpanLimits {
+    /// Sets what the span does with tags and references added after its finish time was set.
+    pub fn after_finish(mut self, policy: AfterFinishPolicy) -> Self {
+        self.after_finish = policy;
+        self
+    }
+
+    /// Sets whether logs attached without a timestamp are timestamped by the span.
+    ///
+    /// Skipping log timestamps avoids reading the clock for every log
+    /// event at the cost of not knowing when the event happened.
+    pub fn log_timestamps(mut self, log_timestamps: bool) -> Self {
+        self.log_timestamps = log_timestamps;
+        self
+    }
+
+    /// Sets the maximum number of logs attached to the span.
+    ///
+    /// Logs attached once the limit is reached are dropped.
+    pub fn max_logs(mut self, max_logs: usize) -> Self {
+        self.max_logs = Some(max_logs);
+        self
+    }
+
+    /// Sets the maximum number of references recorded by the span.
+    ///
+    /// References added once the limit is reached are not recorded but
+    /// the span's context and baggage are still updated to reference the span.
+    pub fn max_references(mut self, max_references: usize) -> Self {
+        self.max_references = Some(max_references);
+        self
+    }
+
+    /// Sets the maximum number of distinct tags attached to the span.
+    ///
+    /// Existing tags can still be updated once the limit is reached but new tags are dropped.
+    pub fn max_tags(mut self, max_tags: usize) -> Self {
+        self.max_tags = Some(max_tags);
+        self
+    }
+
+    /// Sets the minimum time between progress logs recorded with `Span::progress`.
+    pub fn progress_interval(mut self, interval: Duration) -> Self {
+        self.progress_interval = interval;
+        self
+    }
+
+    /// Sets the precision of timestamps captured by the span.
+    ///
+    /// Explicitly provided timestamps (start, finish and log times) are not altered.
+    pub fn timestamp_resolution(mut self, resolution: TimestampResolution) -> Self {
+        self.timestamp_resolution = resolution;
+        self
+    }
+}
+
+impl SpanLimits {
+    /// Returns the current time at the configured resolution.
+    ///
+    /// The time is read from the given clock, if any, or from the `SystemClock`.
+    pub(crate) fn now(&self, clock: Option<&dyn Clock>) -> SystemTime {
+        let now = clock.unwrap_or(&SystemClock).now();
+        match self.timestamp_resolution {
+            TimestampResolution::Nanosecond => now,
+            TimestampResolution::Millisecond => {
+                match now.duration_since(UNIX_EPOCH) {
+                    Ok(since_epoch) => {
+                        let millis = since_epoch.subsec_millis();
+                        let truncated = Duration::new(since_epoch.as_secs(), millis * 1_000_000);
+                        UNIX_EPOCH + truncated
+                    },
+                    Err(_) => now
+                }
+            }
+        }
+    }
+}
+
+impl Default for SpanLimits {
+    /// Returns the default `SpanLimits`.
+    ///
+    /// By default spans will:
+    ///
+    ///   * Apply, and warn about, tags and references added after the finish time was set.
+    ///   * Timestamp logs that do not have a timestamp when attached.
+    ///   * Log progress (see `Span::progress`) at most once a second.
+    ///   * Capture timestamps at `TimestampResolution::Nanosecond`.
+    fn default() -> SpanLimits {
+        SpanLimits {
+            after_finish: AfterFinishPolicy::Warn,
+            log_timestamps: true,
+            max_logs: None,
+            max_references: None,
+            max_tags: None,
+            progress_interval: Duration::from_secs(1),
+            timestamp_resolution: TimestampResolution::Nanosecond,
+        }
+    }
+}
+
+
+/// Counts of items dropped by a span because of its `SpanLimits`.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Truncated {
+    pub(crate) logs: u64,
+    pub(crate) references: u64,
+    pub(crate) tags: u64,
+}
+
+impl Truncated {
+    /// Sets the truncation marker tags for any dropped items.
+    ///
+    /// Items dropped earlier, and already marked, are added to the counts.
+    pub(crate) fn mark(&self, tags: &mut SpanTags) {
+        let markers = [
+            (TRUNCATED_LOGS_TAG, self.logs),
+            (TRUNCATED_REFERENCES_TAG, self.references),
+            (TRUNCATED_TAGS_TAG, self.tags),
+        ];
+        for &(tag, dropped) in markers.iter() {
+            if dropped > 0 {
+                let marked = match tags.get(tag) {
+                    Some(TagValue::Unsigned(marked)) => *marked,
+                    _ => 0,
+                };
+                tags.tag(tag, TagValue::Unsigned(marked + dropped));
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::time::UNIX_EPOCH;
+
+    use super::SpanLimits;
+    use super::TimestampResolution;
+
+    #[test]
+    fn millisecond_resolution() {
+        let limits = SpanLimits::default()
+            .timestamp_resolution(TimestampResolution::Millisecond);
+        let now = limits.now(None).duration_since(UNIX_EPOCH).unwrap();
+        assert_eq!(0, now.subsec_nanos() % 1_000_000);
+    }
+}
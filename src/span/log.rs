@@ -1,8 +1,13 @@
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::collections::hash_map::Iter;
+use std::fmt;
 
 use std::time::SystemTime;
 
+use super::clock::Clock;
+use super::clock::SystemClock;
+
 
 /// Structured logging information to attach to spans.
 ///
@@ -40,7 +45,7 @@ use std::time::SystemTime;
 ///     span.log(log);
 /// }
 /// ```
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 pub struct Log {
     fields: LogFileds,
     timestamp: Option<SystemTime>,
@@ -65,8 +70,13 @@ impl Log {
 
     /// Sets the timestamp to now if not set.
     pub fn at_or_now(&mut self) {
+        self.at_or_now_with(&SystemClock)
+    }
+
+    /// Sets the timestamp to the current time of the given `Clock` if not set.
+    pub fn at_or_now_with(&mut self, clock: &dyn Clock) {
         if self.timestamp.is_none() {
-            self.timestamp = Some(SystemTime::now())
+            self.timestamp = Some(clock.now())
         }
     }
 
@@ -79,7 +89,7 @@ impl Log {
     }
 
     /// Access an iterator over stored fields.
-    pub fn iter(&self) -> Iter<String, LogValue> {
+    pub fn iter(&self) -> Iter<'_, String, LogValue> {
         self.fields.iter()
     }
 
@@ -89,9 +99,18 @@ impl Log {
     }
 }
 
+impl Log {
+    /// Retains only the fields for which `keep` returns `true`.
+    ///
+    /// The closure can also modify the value of retained fields.
+    pub(crate) fn retain<F: FnMut(&String, &mut LogValue) -> bool>(&mut self, keep: F) {
+        self.fields.retain(keep);
+    }
+}
+
 
 /// Structured log fields container.
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 struct LogFileds(HashMap<String, LogValue>);
 
 impl LogFileds {
@@ -106,19 +125,81 @@ impl LogFileds {
     }
 
     /// Access an iterator over fields.
-    pub fn iter(&self) -> Iter<String, LogValue> {
+    pub fn iter(&self) -> Iter<'_, String, LogValue> {
         self.0.iter()
     }
+
+    /// Retains only the fields for which `keep` returns `true`.
+    pub fn retain<F: FnMut(&String, &mut LogValue) -> bool>(&mut self, keep: F) {
+        self.0.retain(keep);
+    }
 }
 
 
 /// Enumeration of valid types for log values.
-#[derive(Debug, PartialEq)]
+///
+/// Structured payloads (request bodies, stack traces, ...) can be logged
+/// using nested `Array` and `Map` values instead of flattening them to strings.
+#[derive(Clone, Debug, PartialEq)]
 pub enum LogValue {
+    Array(Vec<LogValue>),
     Boolean(bool),
+    Bytes(Vec<u8>),
     Float(f64),
     Integer(i64),
+    Map(BTreeMap<String, LogValue>),
     String(String),
+    Unsigned(u64),
+}
+
+impl fmt::Display for LogValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LogValue::Array(values) => {
+                write!(f, "[")?;
+                for (idx, value) in values.iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                write!(f, "]")
+            },
+            LogValue::Boolean(v) => fmt::Display::fmt(v, f),
+            LogValue::Bytes(v) => {
+                for byte in v {
+                    write!(f, "{:02x}", byte)?;
+                }
+                Ok(())
+            },
+            LogValue::Float(v) => fmt::Display::fmt(v, f),
+            LogValue::Integer(v) => fmt::Display::fmt(v, f),
+            LogValue::Map(values) => {
+                write!(f, "{{")?;
+                for (idx, (key, value)) in values.iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: {}", key, value)?;
+                }
+                write!(f, "}}")
+            },
+            LogValue::String(v) => fmt::Display::fmt(v, f),
+            LogValue::Unsigned(v) => fmt::Display::fmt(v, f),
+        }
+    }
+}
+
+impl From<Vec<LogValue>> for LogValue {
+    fn from(value: Vec<LogValue>) -> LogValue {
+        LogValue::Array(value)
+    }
+}
+
+impl From<BTreeMap<String, LogValue>> for LogValue {
+    fn from(value: BTreeMap<String, LogValue>) -> LogValue {
+        LogValue::Map(value)
+    }
 }
 
 impl From<bool> for LogValue {
@@ -127,18 +208,48 @@ impl From<bool> for LogValue {
     }
 }
 
+impl<'a> From<&'a [u8]> for LogValue {
+    fn from(value: &'a [u8]) -> LogValue {
+        LogValue::Bytes(value.to_vec())
+    }
+}
+
+impl From<Vec<u8>> for LogValue {
+    fn from(value: Vec<u8>) -> LogValue {
+        LogValue::Bytes(value)
+    }
+}
+
 impl From<f64> for LogValue {
     fn from(value: f64) -> LogValue {
         LogValue::Float(value)
     }
 }
 
+impl From<i32> for LogValue {
+    fn from(value: i32) -> LogValue {
+        LogValue::Integer(i64::from(value))
+    }
+}
+
 impl From<i64> for LogValue {
     fn from(value: i64) -> LogValue {
         LogValue::Integer(value)
     }
 }
 
+impl From<u32> for LogValue {
+    fn from(value: u32) -> LogValue {
+        LogValue::Unsigned(u64::from(value))
+    }
+}
+
+impl From<u64> for LogValue {
+    fn from(value: u64) -> LogValue {
+        LogValue::Unsigned(value)
+    }
+}
+
 impl<'a> From<&'a str> for LogValue {
     fn from(value: &'a str) -> LogValue {
         LogValue::String(String::from(value))
@@ -154,9 +265,12 @@ impl From<String> for LogValue {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeMap;
     use std::time::Duration;
     use std::time::SystemTime;
+    use std::time::UNIX_EPOCH;
 
+    use super::super::clock::ManualClock;
     use super::Log;
     use super::LogValue;
 
@@ -169,6 +283,18 @@ mod tests {
         ]);
     }
 
+    #[test]
+    fn add_nested_field() {
+        let mut request = BTreeMap::new();
+        request.insert(String::from("path"), LogValue::from("/"));
+        request.insert(String::from("sizes"), LogValue::from(vec![
+            LogValue::from(1), LogValue::from(2)
+        ]));
+        let log = Log::new().log("request", request);
+        let (_, value) = log.iter().next().unwrap();
+        assert_eq!(value.to_string(), "{path: /, sizes: [1, 2]}");
+    }
+
     #[test]
     fn defults_to_no_time() {
         match Log::new().timestamp() {
@@ -190,6 +316,15 @@ mod tests {
     }
 
     #[test]
+    fn set_timestamp_from_clock() {
+        let clock = ManualClock::new(UNIX_EPOCH);
+        let mut log = Log::new();
+        log.at_or_now_with(&clock);
+        assert_eq!(&UNIX_EPOCH, log.timestamp().unwrap());
+    }
+
+    #[test]
+    #[allow(clippy::clone_on_copy)]
     fn set_log_timestamp() {
         let time = SystemTime::now();
         let log = Log::new().at(time.clone());
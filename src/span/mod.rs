@@ -1,19 +1,60 @@
+use std::collections::VecDeque;
+use std::fmt::Debug;
+use std::fmt::Display;
+use std::mem;
 use std::ops::Deref;
 use std::ops::DerefMut;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
 use std::time::SystemTime;
 
 use crossbeam_channel::Receiver;
+use crossbeam_channel::RecvError;
+use crossbeam_channel::RecvTimeoutError;
 use crossbeam_channel::Sender;
+use crossbeam_channel::TryRecvError;
 
+use super::BaggageRestrictions;
 use super::Result;
 use super::SpanContext;
 
+pub mod batch;
+pub mod clock;
+pub mod in_flight;
+pub mod kind;
+pub mod limits;
 pub mod log;
+pub mod shared;
 pub mod tag;
-
+pub mod timer;
+pub mod typed;
+pub mod warning;
+
+use self::batch::FinishedBatch;
+use self::batch::SpanTree;
+use self::clock::Clock;
+use self::in_flight::InFlightGuard;
+use self::kind::SpanKind;
+use self::limits::AfterFinishPolicy;
+use self::limits::SpanLimits;
+use self::limits::Truncated;
 use self::log::Log;
 use self::tag::SpanTags;
 use self::tag::TagValue;
+use self::timer::PendingTimings;
+use self::timer::SpanTimer;
+use self::warning::SpanWarning;
+use self::warning::SpanWarnings;
+use self::warning::TruncatedItem;
+use self::warning::WarningSender;
+
+
+/// Tag set on finished spans whose finish time is before their start time.
+const CLOCK_SKEW_TAG: &str = "otr.clock_skew";
+
+/// Tag set on spans that retry a previous attempt (see `Span::link_previous_attempt`).
+const RETRY_TAG: &str = "otr.retry";
 
 
 /// A `Span` wrapper that finishes a span when dropped.
@@ -77,54 +118,178 @@ impl Drop for AutoFinishingSpan {
 
 /// A `Span` that represents a finished operation.
 ///
-/// The span can no longer be altered since the operation is finished.
+/// The operation is finished so its timing and relationships can no longer be altered.
+/// Span processors and reporters can still enrich or scrub the span before it is
+/// exported with `FinishedSpan::add_tag`, `FinishedSpan::remove_tag`,
+/// `FinishedSpan::set_name` and `FinishedSpan::truncate_logs`.
+///
 /// `Tracer`s must provide a way to submit `FinishedSpan`a to the distributed tracer.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct FinishedSpan {
     context: SpanContext,
     finish_time: SystemTime,
+    kind: Option<SpanKind>,
     logs: Vec<Log>,
-    name: String,
+    name: Arc<str>,
     references: Vec<SpanReference>,
     start_time: SystemTime,
     tags: SpanTags,
 }
 
 impl FinishedSpan {
+    /// Returns a copy of the span with the given context and references.
+    pub(crate) fn with_context(
+        &self, context: SpanContext, references: Vec<SpanReference>
+    ) -> FinishedSpan {
+        FinishedSpan {
+            context,
+            references,
+            ..self.clone()
+        }
+    }
+
+    /// Sets a tag on the span, replacing any tag with the same name.
+    pub fn add_tag<TV: Into<TagValue>>(&mut self, tag: &str, value: TV) {
+        self.tags.tag(tag, value.into());
+    }
+
     /// Access the operation's `SpanContext`.
     pub fn context(&self) -> &SpanContext {
         &self.context
     }
 
+    /// Returns the time elapsed between the start and the finish of the span.
+    ///
+    /// If the system clock moved backwards while the span was running the finish time
+    /// may precede the start time: the duration is then zero and the span is tagged
+    /// with `otr.clock_skew` set to `true`.
+    pub fn duration(&self) -> Duration {
+        self.finish_time.duration_since(self.start_time).unwrap_or_default()
+    }
+
     /// Access the `SystemTime` the `Span` was finished.
     pub fn finish_time(&self) -> &SystemTime {
         &self.finish_time
     }
 
+    /// Checks if the span is the root of its trace.
+    ///
+    /// Root spans have no `ChildOf` references: they may still
+    /// follow other spans with `FollowsFrom` references.
+    pub fn is_root(&self) -> bool {
+        self.parent().is_none()
+    }
+
+    /// Access the `SpanKind` of the operation, if one was set.
+    pub fn kind(&self) -> Option<SpanKind> {
+        self.kind
+    }
+
+    /// Renders the span's IDs as `trace_id > parent_span_id > span_id` for debugging.
+    ///
+    /// See `Span::lineage_string` for details.
+    pub fn lineage_string(&self) -> String {
+        lineage_string(&self.context, self.parent())
+    }
+
     /// Access the logs attached to this span.
     pub fn logs(&self) -> &Vec<Log> {
         &self.logs
     }
 
     /// Access the name of the operation.
-    pub fn name(&self) -> &String {
+    pub fn name(&self) -> &str {
         &self.name
     }
 
+    /// Access the `SpanContext` of the parent, if any.
+    ///
+    /// The parent is the first `ChildOf` reference of the span.
+    pub fn parent(&self) -> Option<&SpanContext> {
+        SpanReference::parent(&self.references)
+    }
+
+    /// Deterministically selects a single parent for backends that only support one.
+    ///
+    /// The primary parent is the first `ChildOf` reference of the span or,
+    /// if the span has no `ChildOf` references, the first `FollowsFrom` reference.
+    /// `SpanReference::Custom` references are never selected.
+    pub fn primary_parent(&self) -> Option<&SpanContext> {
+        self.parent().or_else(|| {
+            self.references.iter().find_map(|reference| match *reference {
+                SpanReference::FollowsFrom(ref parent) => Some(parent),
+                SpanReference::ChildOf(_) |
+                SpanReference::Custom(_, _) => None,
+            })
+        })
+    }
+
     /// Access all the `SpanContext`s and their relationship with this span.
+    ///
+    /// References are in the order they were added: first the `StartOptions`
+    /// references, in order, and then those added to the `Span` while it was running.
+    /// References dropped because of `SpanLimits` are the last ones added
+    /// so the earliest references, usually the parent, are always kept.
     pub fn references(&self) -> &Vec<SpanReference> {
         &self.references
     }
 
+    /// Removes a tag from the span and returns its value, if it was set.
+    pub fn remove_tag(&mut self, tag: &str) -> Option<TagValue> {
+        self.tags.remove(tag)
+    }
+
+    /// Renames the operation.
+    pub fn set_name<N: Into<Arc<str>>>(&mut self, name: N) {
+        self.name = name.into();
+    }
+
     /// Access the `SystemTime` the `Span` was started.
     pub fn start_time(&self) -> &SystemTime {
         &self.start_time
     }
 
     /// Access the tags attached to this span.
+    ///
+    /// Tags are not ordered: exporters that need a stable order must sort them.
     pub fn tags(&self) -> &SpanTags {
         &self.tags
     }
+
+    /// Keeps only the first `max_logs` logs of the span.
+    ///
+    /// Dropped logs are counted in the `otr.truncated.logs` tag, like logs
+    /// dropped because of the span's `SpanLimits`.
+    pub fn truncate_logs(&mut self, max_logs: usize) {
+        if self.logs.len() <= max_logs {
+            return;
+        }
+        let dropped = (self.logs.len() - max_logs) as u64;
+        self.logs.truncate(max_logs);
+        Truncated { logs: dropped, ..Truncated::default() }.mark(&mut self.tags);
+    }
+}
+
+impl FinishedSpan {
+    /// Mutable access to the operation's `SpanContext`.
+    pub(crate) fn context_mut(&mut self) -> &mut SpanContext {
+        &mut self.context
+    }
+
+    /// Mutable access to the logs attached to this span.
+    pub(crate) fn logs_mut(&mut self) -> &mut Vec<Log> {
+        &mut self.logs
+    }
+
+    /// Mutable access to the references of this span.
+    pub(crate) fn references_mut(&mut self) -> &mut Vec<SpanReference> {
+        &mut self.references
+    }
+
+    /// Mutable access to the tags attached to this span.
+    pub(crate) fn tags_mut(&mut self) -> &mut SpanTags {
+        &mut self.tags
+    }
 }
 
 
@@ -139,14 +304,23 @@ impl FinishedSpan {
 /// Once an operation is complete the span should be finished with `Span::finished`.
 #[derive(Debug)]
 pub struct Span {
+    clock: Option<Arc<dyn Clock>>,
     context: SpanContext,
     finish_time: Option<SystemTime>,
+    in_flight: Option<InFlightGuard>,
+    kind: Option<SpanKind>,
+    last_progress: Option<SystemTime>,
+    limits: SpanLimits,
     logs: Vec<Log>,
-    name: String,
+    name: Arc<str>,
+    recording_refused: bool,
     references: Vec<SpanReference>,
-    sender: SpanSender,
+    sender: Option<SpanSender>,
     start_time: SystemTime,
     tags: SpanTags,
+    timings: Option<PendingTimings>,
+    truncated: Truncated,
+    warnings: Option<SpanWarnings>,
 }
 
 impl Span {
@@ -155,28 +329,190 @@ impl Span {
     /// This function is for use by `TracerInterface` implementations in their
     /// `TracerInterface::span` method.
     ///
-    /// The `sender` argument is the sending end of an `crossbeam_channel::unbounded`.
+    /// The `sender` argument is the shared sending end of an `crossbeam_channel::unbounded`.
+    /// Tracers should create it once and pass `Arc::clone`s of it to new spans.
     /// The receiving end of this channel, usually returned by the tracer's initialisation
     /// routine, will gather `FinishedSpan`s so they can be shipped to the distributed tracer.
-    pub fn new(
-        name: &str, context: SpanContext, options: StartOptions,
+    ///
+    /// The operation name can be anything convertible into an `Arc<str>` so
+    /// spans with the same name can share it (see `Tracer::operation_name`).
+    pub fn new<N: Into<Arc<str>>>(
+        name: N, context: SpanContext, options: StartOptions,
         sender: SpanSender
     ) -> Span {
+        Span::build(name.into(), context, options, Some(sender))
+    }
+
+    /// Creates a `Span` that records nothing and is never reported.
+    ///
+    /// This function is for use by `TracerInterface` implementations that disable
+    /// tracing, such as `NoopTracer::disabled`.
+    /// Tags, logs, references and baggage items are dropped instead of stored
+    /// and finishing the span does not send anything.
+    pub fn discarded<N: Into<Arc<str>>>(
+        name: N, context: SpanContext, options: StartOptions
+    ) -> Span {
+        Span::build(name.into(), context, options, None)
+    }
+
+    fn build(
+        name: Arc<str>, context: SpanContext, options: StartOptions,
+        sender: Option<SpanSender>
+    ) -> Span {
+        let limits = options.span_limits.unwrap_or_default();
+        let start_time = options.start_time
+            .unwrap_or_else(|| limits.now(options.clock.as_deref()));
+        let warnings = options.warnings
+            .map(|sender| SpanWarnings::new(Arc::clone(&name), sender));
         let mut span = Span {
+            clock: options.clock,
             context,
             finish_time: None,
+            in_flight: None,
+            kind: options.span_kind,
+            last_progress: None,
+            limits,
             logs: Vec::new(),
-            name: String::from(name),
+            name,
+            recording_refused: false,
             references: Vec::new(),
             sender,
-            start_time: options.start_time.unwrap_or_else(SystemTime::now),
+            start_time,
             tags: SpanTags::new(),
+            timings: None,
+            truncated: Truncated::default(),
+            warnings,
         };
         for reference in options.references {
             span.reference_span(reference);
         }
+        for (key, value) in options.baggage {
+            span.context.set_baggage_item(key, value);
+        }
+        // Start tags are kept even if the span is not recording (yet) in case
+        // the sampler forces the trace to be recorded (see `Sampler::force`).
+        if !span.is_discarded() {
+            for (tag, value) in options.tags.iter() {
+                span.tag_limited(None, tag, value.clone());
+            }
+        }
         span
     }
+
+    /// Applies the `AfterFinishPolicy` to a change made after the finish time was set.
+    ///
+    /// Changes made `at` an explicit time no later than the finish time are always allowed.
+    /// Returns `false` if the change must be dropped.
+    fn allow_after_finish<F>(&self, at: Option<SystemTime>, warning: F) -> bool
+        where F: FnOnce(String) -> SpanWarning
+    {
+        let late = match (self.finish_time, at) {
+            (None, _) => false,
+            (Some(finish_time), Some(at)) => at > finish_time,
+            (Some(_), None) => true,
+        };
+        if !late {
+            return true;
+        }
+        match self.limits.after_finish {
+            AfterFinishPolicy::Allow => true,
+            AfterFinishPolicy::Warn => {
+                self.warn(warning);
+                true
+            },
+            AfterFinishPolicy::Reject => {
+                self.warn(warning);
+                false
+            },
+        }
+    }
+
+    /// Tags the span, applying the `SpanLimits` and `AfterFinishPolicy`.
+    fn tag_checked(&mut self, at: Option<SystemTime>, tag: &str, value: TagValue) {
+        if self.is_recording() {
+            self.tag_limited(at, tag, value);
+        }
+    }
+
+    /// Tags the span, applying the `SpanLimits` and `AfterFinishPolicy` but even if the
+    /// span is not recording.
+    fn tag_limited(&mut self, at: Option<SystemTime>, tag: &str, value: TagValue) {
+        if !self.allow_after_finish(at, |span| SpanWarning::TagAfterFinish(span, tag.into())) {
+            return;
+        }
+        if let Some(max_tags) = self.limits.max_tags {
+            if self.tags.len() >= max_tags && self.tags.get(tag).is_none() {
+                self.truncated.tags += 1;
+                self.warn(|span| SpanWarning::Truncated(span, TruncatedItem::Tag));
+                return;
+            }
+        }
+        self.tags.tag(tag, value);
+    }
+
+    /// Reports a `SpanWarning` if the tracer is configured to receive them.
+    fn warn<F: FnOnce(String) -> SpanWarning>(&self, warning: F) {
+        if let Some(ref warnings) = self.warnings {
+            warnings.warn(warning);
+        }
+    }
+
+    /// Subjects the span's baggage to the given `BaggageRestrictions`.
+    pub(crate) fn restrict_baggage(&mut self, restrictions: Arc<BaggageRestrictions>) {
+        self.context.restrict_baggage(restrictions);
+    }
+
+    /// Stops recording this span without changing its sampling decision.
+    ///
+    /// The decision is local to this span: it is not propagated to children
+    /// or injected into carriers and sampler overrides do not undo it.
+    pub(crate) fn refuse_recording(&mut self) {
+        self.recording_refused = true;
+    }
+
+    /// Checks if the span was created with `Span::discarded`.
+    fn is_discarded(&self) -> bool {
+        self.sender.is_none()
+    }
+
+    /// Converts the span into a `FinishedSpan` and the sender to report it with.
+    ///
+    /// Returns `None` if the span is not recording (see `Span::is_recording`).
+    pub(crate) fn into_finished(mut self) -> Option<(FinishedSpan, SpanSender)> {
+        if let Some(ref mut warnings) = self.warnings {
+            warnings.finished();
+        }
+        if !self.is_recording() {
+            return None;
+        }
+        if let Some(timings) = self.timings.take() {
+            let mut timings = match timings.lock() {
+                Ok(timings) => timings,
+                Err(poisoned) => poisoned.into_inner(),
+            };
+            for log in mem::take(&mut *timings) {
+                self.log(log);
+            }
+        }
+        let limits = self.limits;
+        let clock = self.clock;
+        self.truncated.mark(&mut self.tags);
+        let finish_time = self.finish_time.unwrap_or_else(|| limits.now(clock.as_deref()));
+        if finish_time < self.start_time {
+            self.tags.tag(CLOCK_SKEW_TAG, TagValue::Boolean(true));
+        }
+        let finished = FinishedSpan {
+            context: self.context,
+            finish_time,
+            kind: self.kind,
+            logs: self.logs,
+            name: self.name,
+            references: self.references,
+            start_time: self.start_time,
+            tags: self.tags,
+        };
+        self.sender.map(|sender| (finished, sender))
+    }
 }
 
 impl Span {
@@ -205,12 +541,25 @@ impl Span {
         &self.context
     }
 
+    /// Returns the time elapsed since the span started.
+    ///
+    /// The current time is read from the tracer's clock, if one is set.
+    /// Returns zero if the start time is in the future.
+    pub fn elapsed(&self) -> Duration {
+        self.limits.now(self.clock.as_deref())
+            .duration_since(self.start_time)
+            .unwrap_or_default()
+    }
+
     /// Set the span finish time.
     /// 
     /// This method allows to set the finish time of an operation explicitly
     /// and still manipulate the span further.
     /// This allows to time the operation first and the populate the span with
     /// any available detail without obfuscating the duration of the real operation.
+    ///
+    /// Tags and references added after the finish time is set are subject to
+    /// the span's `AfterFinishPolicy` (see `SpanLimits::after_finish`).
     pub fn finish_time(&mut self, finish_time: SystemTime) {
         self.finish_time = Some(finish_time);
     }
@@ -218,24 +567,56 @@ impl Span {
     /// Finished a span and sends it to the tracer's receiver..
     ///
     /// Consumes a `Span` to create a `FinishedSpan`.
-    /// The finished span is then send to the tracer's `crossbeam_channel::Receiver`
+    /// The finished span is then send to the tracer's `SpanReceiver`
     /// associated with the span at the time of creation.
     ///
+    /// Spans that are not recording are dropped instead (see `Span::is_recording`).
+    ///
     /// Any error sending the span is returned to the caller.
+    #[allow(clippy::result_large_err)]
     pub fn finish(self) -> Result<()> {
-        let finished = FinishedSpan {
-            context: self.context,
-            finish_time: self.finish_time.unwrap_or_else(SystemTime::now),
-            logs: self.logs,
-            name: self.name,
-            references: self.references,
-            start_time: self.start_time,
-            tags: self.tags,
-        };
-        self.sender.send(finished)?;
+        if let Some((finished, sender)) = self.into_finished() {
+            sender.send(SpanMessage::Finished(finished))?;
+        }
         Ok(())
     }
 
+    /// Finishes the span after applying the closing metadata in the `FinishOptions`.
+    ///
+    /// Tags and logs are attached as of the finish time so closing metadata
+    /// is never reported as added after the span finished, even if the finish
+    /// time was already set with `Span::finish_time`.
+    #[allow(clippy::result_large_err)]
+    pub fn finish_with_options(mut self, options: FinishOptions) -> Result<()> {
+        let finish_time = options.finish_time
+            .or(self.finish_time)
+            .unwrap_or_else(|| self.limits.now(self.clock.as_deref()));
+        self.finish_time = Some(finish_time);
+        for (tag, value) in options.tags {
+            self.tag_at(finish_time, &tag, value);
+        }
+        for log in options.logs {
+            let log = match log.timestamp() {
+                Some(_) => log,
+                None => log.at(finish_time),
+            };
+            self.log(log);
+        }
+        self.finish()
+    }
+
+    /// Finishes the given children and then this span with a single send.
+    ///
+    /// All spans are sent to the tracer's receiver as one `FinishedBatch`
+    /// with the children first, in the given order, followed by this span.
+    /// See `SpanTree` for details.
+    #[allow(clippy::result_large_err)]
+    pub fn finish_children_then_self(self, children: Vec<Span>) -> Result<()> {
+        let mut tree = SpanTree::from(children);
+        tree.push(self);
+        tree.finish()
+    }
+
     /// Marks this span as a follower of the given context.
     pub fn follows(&mut self, parent: SpanContext) {
         self.reference_span(SpanReference::FollowsFrom(parent));
@@ -248,26 +629,231 @@ impl Span {
         self.context.get_baggage_item(key)
     }
 
+    /// Checks if the span will be exported.
+    ///
+    /// Spans are not recorded if they are `Span::discarded`, their trace
+    /// is not sampled (see `SpanContext::sampled`) or the tracer refused to record
+    /// them (see `InFlightPolicy::RefuseRecording`).
+    /// Spans without a sampling decision are recorded.
+    ///
+    /// Tags and logs added while the span is not recording are dropped (except tags
+    /// set with `StartOptions::tag`) and spans that are not recording when finished
+    /// are never reported.
+    pub fn is_recording(&self) -> bool {
+        !self.is_discarded() && !self.recording_refused &&
+            self.context.sampled() != Some(false)
+    }
+
+    /// Returns the `SpanKind` of the operation, if one was set.
+    pub fn kind(&self) -> Option<SpanKind> {
+        self.kind
+    }
+
+    /// Marks this span as a retry of the operation traced by the `previous` context.
+    ///
+    /// The span follows the previous attempt and is tagged with `otr.retry`
+    /// so backends can group all attempts of the same logical operation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate opentracingrust;
+    ///
+    /// use opentracingrust::tracers::NoopTracer;
+    ///
+    ///
+    /// fn main() {
+    ///     let (tracer, _) = NoopTracer::new();
+    ///     let first = tracer.span("fetch");
+    ///     // ... the first attempt fails ...
+    ///     let mut second = tracer.span("fetch");
+    ///     second.link_previous_attempt(first.context());
+    ///     // ... snip ...
+    /// }
+    /// ```
+    pub fn link_previous_attempt(&mut self, previous: &SpanContext) {
+        self.follows(previous.clone());
+        self.tag(RETRY_TAG, true);
+    }
+
+    /// Renders the span's IDs as `trace_id > parent_span_id > span_id` for debugging.
+    ///
+    /// The parent is the first `ChildOf` reference and is omitted for root spans.
+    /// IDs are provided by the tracer (see `SpanContext::trace_id` and `SpanContext::span_id`)
+    /// and rendered as `?` for tracers that do not expose them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate opentracingrust;
+    ///
+    /// use opentracingrust::tracers::FileTracer;
+    ///
+    ///
+    /// fn main() {
+    ///     let (tracer, _) = FileTracer::new();
+    ///     let parent = tracer.span("parent");
+    ///     let mut span = tracer.span("child");
+    ///     span.child_of(parent.context().clone());
+    ///     println!("{}", span.lineage_string());
+    /// }
+    /// ```
+    pub fn lineage_string(&self) -> String {
+        lineage_string(&self.context, SpanReference::parent(&self.references))
+    }
+
+    /// Tags the span as failed and logs the details of the error.
+    ///
+    /// The `error` tag is set and an `error` event is logged
+    /// following the [OpenTracing specification].
+    ///
+    /// Any value that can be displayed and debugged can be logged as an error,
+    /// including `Box<dyn Error>` and error wrappers that do not implement `Error`.
+    ///
+    /// [OpenTracing specification]: https://github.com/opentracing/specification/blob/master/semantic_conventions.md#log-fields-table
+    pub fn log_error<E: Debug + Display + ?Sized>(&mut self, error: &E) {
+        self.tag("error", true);
+        self.log(Log::new()
+            .log("event", "error")
+            .log("message", format!("{}", error))
+            .log("error.kind", error.to_string())
+            .log("error.object", format!("{:?}", error))
+        );
+    }
+
     /// Attach a log event to the span.
+    ///
+    /// Logs without a timestamp are timestamped now unless
+    /// the span's `SpanLimits` disable log timestamps.
     pub fn log(&mut self, mut log: Log) {
-        log.at_or_now();
+        if !self.is_recording() {
+            return;
+        }
+        if let Some(max_logs) = self.limits.max_logs {
+            if self.logs.len() >= max_logs {
+                self.truncated.logs += 1;
+                self.warn(|span| SpanWarning::Truncated(span, TruncatedItem::Log));
+                return;
+            }
+        }
+        if self.limits.log_timestamps && log.timestamp().is_none() {
+            log = log.at(self.limits.now(self.clock.as_deref()));
+        }
         self.logs.push(log);
     }
 
+    /// Attach a log event that happened at the given time, to replay recorded events.
+    ///
+    /// The timestamp replaces any timestamp already set on the log and is recorded as is:
+    /// the span's clock, `SpanLimits::log_timestamps` and `SpanLimits::timestamp_resolution`
+    /// do not apply to it. The log still counts towards `SpanLimits::max_logs`.
+    pub fn log_at(&mut self, timestamp: SystemTime, log: Log) {
+        self.log(log.at(timestamp));
+    }
+
     /// Returns the operation name.
     pub fn operation_name(&self) -> &str {
         &self.name
     }
 
+    /// Logs the progress of a long-running operation, such as a batch job.
+    ///
+    /// Progress logs have the following fields:
+    ///
+    ///   * `event`: always `progress`.
+    ///   * `progress.done`: the number of items processed.
+    ///   * `progress.total`: the total number of items to process.
+    ///   * `progress.percent`: the percentage of items processed.
+    ///
+    /// To avoid flooding the span with logs when reporting progress after each item,
+    /// progress is logged at most once every `SpanLimits::progress_interval`.
+    /// The first update and the update completing the operation are always logged.
+    ///
+    /// Returns `true` if the progress was logged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate opentracingrust;
+    ///
+    /// use opentracingrust::tracers::NoopTracer;
+    ///
+    ///
+    /// fn main() {
+    ///     let (tracer, receiver) = NoopTracer::new();
+    ///     let mut span = tracer.span("import");
+    ///     let total = 10000;
+    ///     for done in 1..=total {
+    ///         // ... import an item ...
+    ///         span.progress(done, total);
+    ///     }
+    ///     span.finish().unwrap();
+    ///
+    ///     // Logs the first and last items and once a second in between.
+    ///     let span = receiver.recv().unwrap();
+    ///     assert!(span.logs().len() < 10);
+    /// }
+    /// ```
+    pub fn progress(&mut self, done: u64, total: u64) -> bool {
+        let complete = done >= total;
+        let now = self.limits.now(self.clock.as_deref());
+        let due = match self.last_progress {
+            None => true,
+            Some(last) => {
+                now.duration_since(last).unwrap_or_default() >= self.limits.progress_interval
+            },
+        };
+        if !complete && !due {
+            return false;
+        }
+        self.last_progress = Some(now);
+        let percent = match total {
+            0 => 100.0,
+            total => done as f64 * 100.0 / total as f64,
+        };
+        self.log(Log::new()
+            .log("event", "progress")
+            .log("progress.done", done)
+            .log("progress.total", total)
+            .log("progress.percent", percent)
+        );
+        true
+    }
+
     /// Adds a reference to a `SpanContext`.
     pub fn reference_span(&mut self, reference: SpanReference) {
+        if self.is_discarded() {
+            return;
+        }
+        let kind = reference.kind();
+        let warning = |span| SpanWarning::ReferenceAfterFinish(span, kind.into());
+        if !self.allow_after_finish(None, warning) {
+            return;
+        }
         self.context.reference_span(&reference);
         match reference {
+            SpanReference::Custom(_, _) => (),
             SpanReference::ChildOf(ref parent) |
             SpanReference::FollowsFrom(ref parent) => {
                 for (key, value) in parent.baggage_items() {
                     self.context.set_baggage_item(key.clone(), value.clone())
                 }
+                if self.context.deadline().is_none() {
+                    self.context.set_deadline(parent.deadline());
+                }
+                if self.context.sampled().is_none() {
+                    self.context.set_sampled(parent.sampled());
+                }
+                if self.context.trace_budget().is_none() {
+                    self.context.set_trace_budget(parent.trace_budget());
+                }
+            }
+        }
+        if let Some(max_references) = self.limits.max_references {
+            if self.references.len() >= max_references {
+                self.truncated.references += 1;
+                self.warn(|span| SpanWarning::Truncated(span, TruncatedItem::Reference));
+                return;
             }
         }
         self.references.push(reference);
@@ -285,12 +871,33 @@ impl Span {
     ///
     /// Baggage items are **NOT** propagated backwards to `Span`s that reference this `Span`.
     pub fn set_baggage_item(&mut self, key: &str, value: &str) {
+        if self.is_discarded() {
+            return;
+        }
         self.context.set_baggage_item(String::from(key), String::from(value));
     }
 
+    /// Sets or clears the request deadline (see `SpanContext::deadline`).
+    pub fn set_deadline(&mut self, deadline: Option<SystemTime>) {
+        self.context.set_deadline(deadline);
+    }
+
     /// Updates the operation name.
-    pub fn set_operation_name(&mut self, name: &str) {
-        self.name = String::from(name);
+    pub fn set_operation_name<N: Into<Arc<str>>>(&mut self, name: N) {
+        self.name = name.into();
+        if let Some(ref mut warnings) = self.warnings {
+            warnings.rename(Arc::clone(&self.name));
+        }
+    }
+
+    /// Sets or clears the sampling decision (see `SpanContext::sampled`).
+    pub fn set_sampled(&mut self, sampled: Option<bool>) {
+        self.context.set_sampled(sampled);
+    }
+
+    /// Sets or clears the trace budget hint (see `SpanContext::trace_budget`).
+    pub fn set_trace_budget(&mut self, budget: Option<u32>) {
+        self.context.set_trace_budget(budget);
     }
 
     /// Append a tag to the span.
@@ -312,7 +919,55 @@ impl Span {
     /// }
     /// ```
     pub fn tag<TV: Into<TagValue>>(&mut self, tag: &str, value: TV) {
-        self.tags.tag(tag, value.into());
+        self.tag_checked(None, tag, value.into());
+    }
+
+    /// Tags the span as of the given time, to replay recorded events.
+    ///
+    /// Tags do not carry a timestamp: the time is only used to decide if the tag is
+    /// added after the span's finish time.
+    /// Tags added at a time no later than the finish time set with `Span::finish_time`
+    /// are not subject to the span's `AfterFinishPolicy`.
+    pub fn tag_at<TV: Into<TagValue>>(&mut self, timestamp: SystemTime, tag: &str, value: TV) {
+        self.tag_checked(Some(timestamp), tag, value.into());
+    }
+
+    /// Tags the span with a value computed only if the span is recording.
+    ///
+    /// Use this for expensive values, such as serialised payloads or formatted
+    /// queries, so they are not computed for spans that will not be exported.
+    /// See `Span::is_recording`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate opentracingrust;
+    ///
+    /// use opentracingrust::tracers::NoopTracer;
+    ///
+    ///
+    /// fn main() {
+    ///     let tracer = NoopTracer::disabled();
+    ///     let mut span = tracer.span("query");
+    ///     span.tag_lazy("db.statement", || -> String { panic!("Not computed") });
+    ///     span.finish().unwrap();
+    /// }
+    /// ```
+    pub fn tag_lazy<TV, F>(&mut self, tag: &str, value: F)
+        where TV: Into<TagValue>,
+              F: FnOnce() -> TV
+    {
+        if self.is_recording() {
+            self.tag(tag, value());
+        }
+    }
+
+    /// Starts a named `SpanTimer` that logs its duration in this span when dropped.
+    ///
+    /// See `SpanTimer` for details.
+    pub fn timer(&mut self, name: &str) -> SpanTimer {
+        let timings = self.timings.get_or_insert_with(PendingTimings::default);
+        SpanTimer::new(name, self.clock.clone(), Arc::clone(timings))
     }
 }
 
@@ -326,23 +981,240 @@ impl AsMut<Span> for Span {
 /// Enumerates all known relationships among `SpanContext`s.
 ///
 /// Each relationship also carries the `SpanContext` it relates to.
+///
+/// New relationships may be added in the future so matches must handle unknown variants.
 #[derive(Clone, Debug)]
+#[non_exhaustive]
 pub enum SpanReference {
     ChildOf(SpanContext),
-    FollowsFrom(SpanContext)
+    FollowsFrom(SpanContext),
+
+    /// A tracer-specific relationship, identified by name, such as batch "links".
+    ///
+    /// Unlike `ChildOf` and `FollowsFrom` references, custom references do not
+    /// propagate baggage, deadlines, sampling decisions or trace budgets:
+    /// tracers decide what the relationship means in `SpanReferenceAware::reference_span`.
+    /// The bundled tracers keep spans in their own trace.
+    Custom(&'static str, SpanContext),
+}
+
+impl SpanReference {
+    /// Access the `SpanContext` the reference relates to.
+    pub fn context(&self) -> &SpanContext {
+        match self {
+            SpanReference::ChildOf(context) |
+            SpanReference::FollowsFrom(context) |
+            SpanReference::Custom(_, context) => context,
+        }
+    }
+
+    /// Returns the parent among the given references: the first `ChildOf` reference.
+    pub(crate) fn parent(references: &[SpanReference]) -> Option<&SpanContext> {
+        references.iter().find_map(|reference| match *reference {
+            SpanReference::ChildOf(ref parent) => Some(parent),
+            SpanReference::FollowsFrom(_) |
+            SpanReference::Custom(_, _) => None,
+        })
+    }
+
+    /// Returns the name of the relationship.
+    ///
+    /// The names of the standard relationships are `child_of` and `follows_from`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SpanReference::ChildOf(_) => "child_of",
+            SpanReference::FollowsFrom(_) => "follows_from",
+            SpanReference::Custom(kind, _) => kind,
+        }
+    }
 }
 
 
-/// Type alias for an `crossbeam_channel::Receiver` of `FinishedSpan`s.
-pub type SpanReceiver = Receiver<FinishedSpan>;
+/// Messages sent by finishing spans to the tracer's `SpanReceiver`.
+// Single spans are the common message: boxing them would allocate for every span.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug)]
+pub enum SpanMessage {
+    /// A single finished span.
+    Finished(FinishedSpan),
 
-/// Type alias for an `crossbeam_channel::Sender` of `FinishedSpan`s.
-pub type SpanSender = Sender<FinishedSpan>;
+    /// A group of spans finished together.
+    Batch(FinishedBatch),
+}
 
 
-/// Additional options that are passed to `Tracer::span`.
+/// Receiving end of the channel `FinishedSpan`s are sent to.
 ///
-/// These options specify initial attributes of a span.
+/// Tracers create an `crossbeam_channel::unbounded` channel of `SpanMessage`s and
+/// wrap the receiving end in a `SpanReceiver` for the users to collect spans from.
+///
+/// The receiver unpacks `FinishedBatch`es so that callers always receive
+/// one `FinishedSpan` at a time, in the order they were finished.
+/// Clones of a `SpanReceiver` share the channel but not spans already unpacked
+/// from a batch: each batch is fully delivered by the receiver that got it.
+#[derive(Debug)]
+pub struct SpanReceiver {
+    pending: Mutex<VecDeque<FinishedSpan>>,
+    receiver: Receiver<SpanMessage>,
+}
+
+impl SpanReceiver {
+    /// Wraps the receiving end of a `SpanMessage` channel.
+    pub fn new(receiver: Receiver<SpanMessage>) -> SpanReceiver {
+        SpanReceiver {
+            pending: Mutex::new(VecDeque::new()),
+            receiver,
+        }
+    }
+}
+
+impl SpanReceiver {
+    /// Returns an iterator that blocks waiting for spans until the channel is disconnected.
+    pub fn iter(&self) -> impl Iterator<Item = FinishedSpan> + '_ {
+        ::std::iter::from_fn(move || self.recv().ok())
+    }
+
+    /// Blocks until a `FinishedSpan` is received.
+    ///
+    /// Fails if the channel is empty and all senders are dropped.
+    pub fn recv(&self) -> ::std::result::Result<FinishedSpan, RecvError> {
+        if let Some(span) = self.pop_pending() {
+            return Ok(span);
+        }
+        let message = self.receiver.recv()?;
+        Ok(self.unpack(message))
+    }
+
+    /// Waits for a `FinishedSpan` for at most the given `timeout`.
+    pub fn recv_timeout(
+        &self, timeout: Duration
+    ) -> ::std::result::Result<FinishedSpan, RecvTimeoutError> {
+        if let Some(span) = self.pop_pending() {
+            return Ok(span);
+        }
+        let message = self.receiver.recv_timeout(timeout)?;
+        Ok(self.unpack(message))
+    }
+
+    /// Attempts to receive a `FinishedSpan` without blocking.
+    pub fn try_recv(&self) -> ::std::result::Result<FinishedSpan, TryRecvError> {
+        if let Some(span) = self.pop_pending() {
+            return Ok(span);
+        }
+        let message = self.receiver.try_recv()?;
+        Ok(self.unpack(message))
+    }
+
+    /// Returns an iterator over the spans that can be received without blocking.
+    pub fn try_iter(&self) -> impl Iterator<Item = FinishedSpan> + '_ {
+        ::std::iter::from_fn(move || self.try_recv().ok())
+    }
+}
+
+impl SpanReceiver {
+    /// Access the underlying channel, to wait for messages with `crossbeam_channel::select!`.
+    ///
+    /// Received messages must be passed to `SpanReceiver::unpack` and
+    /// any span left pending must be collected with `SpanReceiver::pop_pending`.
+    pub(crate) fn channel(&self) -> &Receiver<SpanMessage> {
+        &self.receiver
+    }
+
+    /// Returns the next span unpacked from a previously received batch, if any.
+    pub(crate) fn pop_pending(&self) -> Option<FinishedSpan> {
+        self.pending.lock().expect("Failed to lock SpanReceiver").pop_front()
+    }
+
+    /// Returns the first span in the message and queues any other for later.
+    pub(crate) fn unpack(&self, message: SpanMessage) -> FinishedSpan {
+        match message {
+            SpanMessage::Finished(span) => span,
+            SpanMessage::Batch(batch) => {
+                let mut spans = batch.into_iter();
+                let first = spans.next().expect("FinishedBatch should never be empty");
+                let mut pending = self.pending.lock().expect("Failed to lock SpanReceiver");
+                pending.extend(spans);
+                first
+            }
+        }
+    }
+}
+
+impl Clone for SpanReceiver {
+    fn clone(&self) -> Self {
+        SpanReceiver::new(self.receiver.clone())
+    }
+}
+
+/// Type alias for a shared `crossbeam_channel::Sender` of `SpanMessage`s.
+///
+/// The sender is wrapped in an `Arc` so that creating a `Span` only
+/// increments a reference count instead of cloning the channel's sender.
+pub type SpanSender = Arc<Sender<SpanMessage>>;
+
+
+/// Closing metadata passed to `Span::finish_with_options`.
+///
+/// These options are applied to the span when it is finished.
+/// All values are optional.
+///
+/// # Examples
+///
+/// ```
+/// extern crate opentracingrust;
+///
+/// use std::time::SystemTime;
+///
+/// use opentracingrust::FinishOptions;
+/// use opentracingrust::Log;
+/// use opentracingrust::tracers::NoopTracer;
+///
+///
+/// fn main() {
+///     let (tracer, _receiver) = NoopTracer::new();
+///     let span = tracer.span("request");
+///     let done = SystemTime::now();
+///     // ... snip ...
+///     let options = FinishOptions::default()
+///         .finish_time(done)
+///         .tag("http.status_code", 200)
+///         .log(Log::new().log("event", "response_sent"));
+///     span.finish_with_options(options).unwrap();
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct FinishOptions {
+    finish_time: Option<SystemTime>,
+    logs: Vec<Log>,
+    tags: Vec<(String, TagValue)>,
+}
+
+impl FinishOptions {
+    /// Sets the finish time for the operation.
+    pub fn finish_time(mut self, finish_time: SystemTime) -> Self {
+        self.finish_time = Some(finish_time);
+        self
+    }
+
+    /// Attaches a log event to the span when it finishes.
+    ///
+    /// Logs without a timestamp are timestamped with the finish time.
+    pub fn log(mut self, log: Log) -> Self {
+        self.logs.push(log);
+        self
+    }
+
+    /// Tags the span when it finishes.
+    pub fn tag<TV: Into<TagValue>>(mut self, tag: &str, value: TV) -> Self {
+        self.tags.push((String::from(tag), value.into()));
+        self
+    }
+}
+
+
+/// Additional options that are passed to `Tracer::span`.
+///
+/// These options specify initial attributes of a span.
 /// All values are optional.
 ///
 /// # Examples
@@ -368,16 +1240,39 @@ pub type SpanSender = Sender<FinishedSpan>;
 /// }
 /// ```
 pub struct StartOptions {
-    references: Vec<SpanReference>,
+    baggage: Vec<(String, String)>,
+    pub(crate) clock: Option<Arc<dyn Clock>>,
+    pub(crate) references: Vec<SpanReference>,
+    span_kind: Option<SpanKind>,
+    pub(crate) span_limits: Option<SpanLimits>,
     start_time: Option<SystemTime>,
+    tags: SpanTags,
+    pub(crate) warnings: Option<WarningSender>,
 }
 
 impl StartOptions {
+    /// Sets a baggage item on the `Span` to be.
+    ///
+    /// Items are set after the references are added so they replace
+    /// items with the same key inherited from the referenced contexts.
+    pub fn baggage_item(mut self, key: &str, value: &str) -> Self {
+        self.baggage.push((String::from(key), String::from(value)));
+        self
+    }
+
     /// Declares a `ChildOf` relationship for the `Span` to be.
     pub fn child_of(self, parent: SpanContext) -> Self {
         self.reference_span(SpanReference::ChildOf(parent))
     }
 
+    /// Sets the `Clock` the `Span` to be will read the current time from.
+    ///
+    /// The clock replaces the one configured on the `Tracer`.
+    pub fn clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+
     /// Declares a `FollowsFrom` relationship for the `Span` to be.
     pub fn follows(self, parent: SpanContext) -> Self {
         self.reference_span(SpanReference::FollowsFrom(parent))
@@ -389,11 +1284,41 @@ impl StartOptions {
         self
     }
 
+    /// Sets the `SpanKind` for the `Span` to be.
+    pub fn span_kind(mut self, kind: SpanKind) -> Self {
+        self.span_kind = Some(kind);
+        self
+    }
+
+    /// Sets the `SpanLimits` for the `Span` to be.
+    ///
+    /// These limits replace the ones configured on the `Tracer`.
+    pub fn span_limits(mut self, limits: SpanLimits) -> Self {
+        self.span_limits = Some(limits);
+        self
+    }
+
     /// Sets the start time for the operation.
     pub fn start_time(mut self, start_time: SystemTime) -> Self {
         self.start_time = Some(start_time);
         self
     }
+
+    /// Tags the `Span` to be.
+    ///
+    /// Tags are subject to the span's `SpanLimits` like tags added after creation.
+    pub fn tag<TV: Into<TagValue>>(mut self, tag: &str, value: TV) -> Self {
+        self.tags.tag(tag, value.into());
+        self
+    }
+
+    /// Access the tags for the `Span` to be.
+    ///
+    /// Concrete tracers can use them in `TracerInterface::span`, for example
+    /// to make sampling decisions.
+    pub fn tags(&self) -> &SpanTags {
+        &self.tags
+    }
 }
 
 impl Default for StartOptions {
@@ -402,18 +1327,42 @@ impl Default for StartOptions {
     /// By default the `Span` will:
     ///
     ///   * Have no references, which will make it a root span.
+    ///   * Have no tags and no baggage items other than the ones inherited from references.
+    ///   * Have no `SpanKind`.
+    ///   * Have the `Clock` and the `SpanLimits` configured on the `Tracer`.
     ///   * Have have a start time of when `Tracer::span` is called.
     fn default() -> StartOptions {
         StartOptions {
+            baggage: Vec::new(),
+            clock: None,
             references: Vec::new(),
+            span_kind: None,
+            span_limits: None,
             start_time: None,
+            tags: SpanTags::new(),
+            warnings: None,
         }
     }
 }
 
 
+/// Renders `trace_id > parent_span_id > span_id` for `Span::lineage_string`.
+fn lineage_string(context: &SpanContext, parent: Option<&SpanContext>) -> String {
+    let id = |id: Option<String>| id.unwrap_or_else(|| String::from("?"));
+    let mut lineage = id(context.trace_id());
+    if let Some(parent) = parent {
+        lineage.push_str(" > ");
+        lineage.push_str(&id(parent.span_id()));
+    }
+    lineage.push_str(" > ");
+    lineage.push_str(&id(context.span_id()));
+    lineage
+}
+
+
 #[cfg(test)]
 mod tests {
+    use std::sync::Arc;
     use std::time::Duration;
 
     use crossbeam_channel::unbounded;
@@ -435,16 +1384,22 @@ mod tests {
         pub id: String
     }
     impl TestContext {
+        #[allow(clippy::new_ret_no_self)]
         fn new(options: StartOptions) -> (Span, SpanReceiver) {
             let (sender, receiver) = unbounded();
             let context = SpanContext::new(ImplContextBox::new(TestContext {
                 id: String::from("test-id")
             }));
-            (Span::new("test-span", context, options, sender), receiver)
+            let span = Span::new("test-span", context, options, Arc::new(sender));
+            (span, SpanReceiver::new(receiver))
         }
     }
     impl SpanReferenceAware for TestContext {
         fn reference_span(&mut self, _: &SpanReference) {}
+
+        fn span_id(&self) -> Option<String> {
+            Some(self.id.clone())
+        }
     }
 
 
@@ -492,8 +1447,9 @@ mod tests {
             id: String::from("test-id")
         }));
         let options = StartOptions::default();
-        let span: Span = Span::new("test-span", context, options, sender);
+        let span: Span = Span::new("test-span", context, options, Arc::new(sender));
         span.finish().unwrap();
+        let receiver = SpanReceiver::new(receiver);
         let _finished: FinishedSpan = receiver.recv().unwrap();
     }
 
@@ -504,11 +1460,24 @@ mod tests {
             id: String::from("test-id")
         }));
         let options = StartOptions::default();
-        let mut span = Span::new("test-span", context, options, sender);
+        let mut span = Span::new("test-span", context, options, Arc::new(sender));
         span.set_operation_name("some-other-name");
         assert_eq!("some-other-name", span.operation_name());
     }
 
+    #[test]
+    fn span_with_start_baggage() {
+        let (mut parent, _) = TestContext::new(StartOptions::default());
+        parent.set_baggage_item("inherited", "parent");
+        parent.set_baggage_item("replaced", "parent");
+        let options = StartOptions::default()
+            .child_of(parent.context().clone())
+            .baggage_item("replaced", "child");
+        let (span, _) = TestContext::new(options);
+        assert_eq!("parent", span.get_baggage_item("inherited").unwrap());
+        assert_eq!("child", span.get_baggage_item("replaced").unwrap());
+    }
+
     #[test]
     fn span_child_of_another() {
         let (sender, _) = unbounded();
@@ -516,14 +1485,14 @@ mod tests {
             id: String::from("test-id-1")
         }));
         let options = StartOptions::default();
-        let mut span = Span::new("test-span", context, options, sender);
+        let mut span = Span::new("test-span", context, options, Arc::new(sender));
         let mut context = SpanContext::new(ImplContextBox::new(TestContext {
             id: String::from("test-id-2")
         }));
         context.set_baggage_item(String::from("a"), String::from("b"));
         span.child_of(context.clone());
-        match span.references().get(0).unwrap() {
-            &SpanReference::ChildOf(ref context) => {
+        match span.references().first().unwrap() {
+            SpanReference::ChildOf(context) => {
                 let span = context.impl_context::<TestContext>().unwrap();
                 assert_eq!(span.id, "test-id-2");
             },
@@ -540,14 +1509,14 @@ mod tests {
             id: String::from("test-id-1")
         }));
         let options = StartOptions::default();
-        let mut span = Span::new("test-span", context, options, sender);
+        let mut span = Span::new("test-span", context, options, Arc::new(sender));
         let mut context = SpanContext::new(ImplContextBox::new(TestContext {
             id: String::from("test-id-2")
         }));
         context.set_baggage_item(String::from("a"), String::from("b"));
         span.follows(context.clone());
-        match span.references().get(0).unwrap() {
-            &SpanReference::FollowsFrom(ref context) => {
+        match span.references().first().unwrap() {
+            SpanReference::FollowsFrom(context) => {
                 let span = context.impl_context::<TestContext>().unwrap();
                 assert_eq!(span.id, "test-id-2");
             },
@@ -563,6 +1532,7 @@ mod tests {
         use super::super::SpanContext;
         use super::super::SpanReference;
         use super::super::StartOptions;
+        use super::super::TagValue;
 
         use super::TestContext;
 
@@ -575,7 +1545,7 @@ mod tests {
             let options = StartOptions::default()
                 .child_of(parent);
             let (span, _) = TestContext::new(options);
-            match span.references().get(0) {
+            match span.references().first() {
                 Some(&SpanReference::ChildOf(_)) => (),
                 Some(_) => panic!("Invalid span reference"),
                 None => panic!("Missing span reference")
@@ -590,13 +1560,104 @@ mod tests {
             let options = StartOptions::default()
                 .follows(parent);
             let (span, _) = TestContext::new(options);
-            match span.references().get(0) {
+            match span.references().first() {
                 Some(&SpanReference::FollowsFrom(_)) => (),
                 Some(_) => panic!("Invalid span reference"),
                 None => panic!("Missing span reference")
             }
         }
 
+        #[test]
+        fn custom() {
+            let mut parent = SpanContext::new(ImplContextBox::new(TestContext {
+                id: String::from("test-id")
+            }));
+            parent.set_baggage_item(String::from("a"), String::from("b"));
+            let options = StartOptions::default()
+                .reference_span(SpanReference::Custom("link", parent));
+            let (span, _) = TestContext::new(options);
+            let reference = span.references().first().expect("Missing span reference");
+            assert_eq!("link", reference.kind());
+            assert_eq!(Some(&String::from("b")), reference.context().get_baggage_item("a"));
+            assert!(span.get_baggage_item("a").is_none());
+        }
+
+        #[test]
+        fn link_previous_attempt() {
+            let previous = SpanContext::new(ImplContextBox::new(TestContext {
+                id: String::from("test-id")
+            }));
+            let (mut span, receiver) = TestContext::new(StartOptions::default());
+            span.link_previous_attempt(&previous);
+            span.finish().unwrap();
+            let span = receiver.recv().unwrap();
+            match span.references().first() {
+                Some(&SpanReference::FollowsFrom(_)) => (),
+                Some(_) => panic!("Invalid span reference"),
+                None => panic!("Missing span reference")
+            }
+            match span.tags().get("otr.retry") {
+                Some(&TagValue::Boolean(true)) => (),
+                _ => panic!("Retry tag not set")
+            }
+        }
+
+        #[test]
+        fn finished_span_parent() {
+            let parent = SpanContext::new(ImplContextBox::new(TestContext {
+                id: String::from("parent")
+            }));
+            let other = SpanContext::new(ImplContextBox::new(TestContext {
+                id: String::from("other")
+            }));
+            let options = StartOptions::default()
+                .follows(other)
+                .child_of(parent);
+            let (span, receiver) = TestContext::new(options);
+            span.finish().unwrap();
+            let span = receiver.recv().unwrap();
+            assert!(!span.is_root());
+            let parent = span.parent().unwrap().impl_context::<TestContext>().unwrap();
+            assert_eq!("parent", parent.id);
+            let primary = span.primary_parent().unwrap().impl_context::<TestContext>().unwrap();
+            assert_eq!("parent", primary.id);
+        }
+
+        #[test]
+        fn finished_span_root() {
+            let other = SpanContext::new(ImplContextBox::new(TestContext {
+                id: String::from("other")
+            }));
+            let options = StartOptions::default().follows(other);
+            let (span, receiver) = TestContext::new(options);
+            span.finish().unwrap();
+            let span = receiver.recv().unwrap();
+            assert!(span.is_root());
+            assert!(span.parent().is_none());
+            let primary = span.primary_parent().unwrap().impl_context::<TestContext>().unwrap();
+            assert_eq!("other", primary.id);
+        }
+
+        #[test]
+        fn references_keep_order() {
+            let context = |id: &str| SpanContext::new(ImplContextBox::new(TestContext {
+                id: String::from(id)
+            }));
+            let options = StartOptions::default()
+                .child_of(context("first"))
+                .follows(context("second"));
+            let (mut span, receiver) = TestContext::new(options);
+            span.child_of(context("third"));
+            span.finish().unwrap();
+            let span = receiver.recv().unwrap();
+            let ids: Vec<String> = span.references().iter()
+                .map(|reference| {
+                    reference.context().impl_context::<TestContext>().unwrap().id.clone()
+                })
+                .collect();
+            assert_eq!(vec!["first", "second", "third"], ids);
+        }
+
         #[test]
         fn multi_refs() {
             let parent = SpanContext::new(ImplContextBox::new(TestContext {
@@ -606,7 +1667,7 @@ mod tests {
                 .child_of(parent.clone())
                 .follows(parent);
             let (span, _) = TestContext::new(options);
-            match span.references().get(0) {
+            match span.references().first() {
                 Some(&SpanReference::ChildOf(_)) => (),
                 Some(_) => panic!("Invalid span reference"),
                 None => panic!("Missing span reference")
@@ -619,7 +1680,53 @@ mod tests {
         }
     }
 
+    mod kind {
+        use super::super::StartOptions;
+        use super::super::kind::SpanKind;
+        use super::TestContext;
+
+        #[test]
+        fn defaults_to_no_kind() {
+            let (span, receiver) = TestContext::new(StartOptions::default());
+            span.finish().unwrap();
+            let span = receiver.recv().unwrap();
+            assert_eq!(None, span.kind());
+        }
+
+        #[test]
+        fn set_kind() {
+            let options = StartOptions::default().span_kind(SpanKind::Server);
+            let (span, receiver) = TestContext::new(options);
+            assert_eq!(Some(SpanKind::Server), span.kind());
+            span.finish().unwrap();
+            let span = receiver.recv().unwrap();
+            assert_eq!(Some(SpanKind::Server), span.kind());
+            assert_eq!("server", span.kind().unwrap().as_str());
+        }
+    }
+
     mod logs {
+        use super::super::log::LogValue;
+        use super::super::StartOptions;
+        use super::super::TagValue;
+        use super::TestContext;
+
+        #[test]
+        fn log_error() {
+            let (mut span, receiver) = TestContext::new(StartOptions::default());
+            let error = "abc".parse::<i64>().unwrap_err();
+            span.log_error(&error);
+            span.finish().unwrap();
+            let span = receiver.recv().unwrap();
+            match span.tags().get("error") {
+                Some(&TagValue::Boolean(v)) => assert!(v),
+                Some(_) => panic!("Invalid tag type"),
+                None => panic!("Tag not found")
+            }
+            let event = span.logs()[0].iter().find(|&(k, _)| k == "event");
+            assert_eq!(Some(&LogValue::String(String::from("error"))), event.map(|(_, v)| v));
+        }
+
         // TODO: add and get logs with time.
         // TODO: add and get logs without time.
         // TODO: reject logs with time older then start.
@@ -632,6 +1739,63 @@ mod tests {
 
         use super::TestContext;
 
+        #[test]
+        fn tag_lazy_when_recording() {
+            let (mut span, receiver) = TestContext::new(StartOptions::default());
+            assert!(span.is_recording());
+            span.tag_lazy("a", || 1);
+            span.set_sampled(Some(false));
+            assert!(!span.is_recording());
+            span.tag_lazy("b", || -> i64 { panic!("Computed tag for unsampled span") });
+            span.set_sampled(Some(true));
+            span.finish().unwrap();
+            let span = receiver.recv().unwrap();
+            assert!(span.tags().get("a").is_some());
+            assert!(span.tags().get("b").is_none());
+        }
+
+        #[test]
+        fn unsampled_spans_are_not_reported() {
+            let (mut span, receiver) = TestContext::new(StartOptions::default());
+            span.set_sampled(Some(false));
+            span.tag("a", 1);
+            span.finish().unwrap();
+            assert!(receiver.try_recv().is_err());
+        }
+
+        #[test]
+        fn enrich_finished_span() {
+            let (mut span, receiver) = TestContext::new(StartOptions::default());
+            span.tag("user.email", "user@example.com");
+            span.finish().unwrap();
+            let mut span = receiver.recv().unwrap();
+            span.add_tag("host", "web-1");
+            span.set_name("renamed");
+            match span.remove_tag("user.email") {
+                Some(TagValue::String(_)) => (),
+                _ => panic!("Invalid removed tag"),
+            }
+            assert_eq!("renamed", span.name());
+            assert!(span.tags().get("user.email").is_none());
+            match span.tags().get("host") {
+                Some(TagValue::String(host)) => assert_eq!("web-1", host),
+                _ => panic!("Invalid host tag"),
+            }
+        }
+
+        #[test]
+        fn add_start_options_tags() {
+            let options = StartOptions::default().tag("key", "value").tag("answer", 42);
+            assert!(options.tags().get("answer").is_some());
+            let (span, receiver) = TestContext::new(options);
+            span.finish().unwrap();
+            let span = receiver.recv().unwrap();
+            match span.tags().get("key") {
+                Some(TagValue::String(v)) => assert_eq!(v, "value"),
+                _ => panic!("Start tag not found")
+            }
+        }
+
         #[test]
         fn add_generic_tag() {
             let (mut span, receiver) = TestContext::new(StartOptions::default());
@@ -639,7 +1803,7 @@ mod tests {
             span.finish().unwrap();
             let span = receiver.recv().unwrap();
             match span.tags().get("key") {
-                Some(&TagValue::String(ref v)) => assert_eq!(v, "value"),
+                Some(TagValue::String(v)) => assert_eq!(v, "value"),
                 Some(_) => panic!("Invalid tag type"),
                 None => panic!("Tag not found")
             }
@@ -652,7 +1816,7 @@ mod tests {
             span.finish().unwrap();
             let span = receiver.recv().unwrap();
             match span.tags().get("key") {
-                Some(&TagValue::Boolean(v)) => assert_eq!(v, true),
+                Some(&TagValue::Boolean(v)) => assert!(v),
                 Some(_) => panic!("Invalid tag type"),
                 None => panic!("Tag not found")
             }
@@ -684,6 +1848,19 @@ mod tests {
             }
         }
 
+        #[test]
+        fn add_unsigned_tag() {
+            let (mut span, receiver) = TestContext::new(StartOptions::default());
+            span.tag("key", 8080u32);
+            span.finish().unwrap();
+            let span = receiver.recv().unwrap();
+            match span.tags().get("key") {
+                Some(&TagValue::Unsigned(v)) => assert_eq!(v, 8080),
+                Some(_) => panic!("Invalid tag type"),
+                None => panic!("Tag not found")
+            }
+        }
+
         #[test]
         fn add_str_tag() {
             let (mut span, receiver) = TestContext::new(StartOptions::default());
@@ -691,7 +1868,7 @@ mod tests {
             span.finish().unwrap();
             let span = receiver.recv().unwrap();
             match span.tags().get("key") {
-                Some(&TagValue::String(ref v)) => assert_eq!(v, "value"),
+                Some(TagValue::String(v)) => assert_eq!(v, "value"),
                 Some(_) => panic!("Invalid tag type"),
                 None => panic!("Tag not found")
             }
@@ -704,7 +1881,7 @@ mod tests {
             span.finish().unwrap();
             let span = receiver.recv().unwrap();
             match span.tags().get("key") {
-                Some(&TagValue::String(ref v)) => assert_eq!(v, "value"),
+                Some(TagValue::String(v)) => assert_eq!(v, "value"),
                 Some(_) => panic!("Invalid tag type"),
                 None => panic!("Tag not found")
             }
@@ -712,25 +1889,104 @@ mod tests {
     }
 
     mod times {
+        use std::sync::Arc;
         use std::time::Duration;
         use std::time::SystemTime;
+        use std::time::UNIX_EPOCH;
 
+        use super::super::AfterFinishPolicy;
+        use super::super::FinishOptions;
+        use super::super::Log;
+        use super::super::SpanLimits;
         use super::super::StartOptions;
+        use super::super::TagValue;
+        use super::super::clock::ManualClock;
+        use super::super::limits::TimestampResolution;
+        use super::super::warning::warning_channel;
         use super::TestContext;
 
 
         #[test]
-        fn finish_span_on_finish() {
-            // Can't mock SystemTime::now() to a fixed value.
-            // Check finish time is in a range [now, now + ten minutes].
-            let about_now = SystemTime::now();
+        fn duration_clamped_on_clock_skew() {
             let options = StartOptions::default();
+            let (mut span, receiver) = TestContext::new(options);
+            span.finish_time(SystemTime::now() - Duration::from_secs(600));
+            span.finish().unwrap();
+            let span = receiver.recv().unwrap();
+            assert_eq!(Duration::default(), span.duration());
+            match span.tags().get("otr.clock_skew") {
+                Some(&TagValue::Boolean(true)) => (),
+                _ => panic!("Expected the clock skew tag"),
+            }
+        }
+
+        #[test]
+        fn duration_of_finished_span() {
+            let start = SystemTime::now();
+            let options = StartOptions::default().start_time(start);
+            let (mut span, receiver) = TestContext::new(options);
+            span.finish_time(start + Duration::from_millis(20));
+            span.finish().unwrap();
+            let span = receiver.recv().unwrap();
+            assert_eq!(Duration::from_millis(20), span.duration());
+            assert!(span.tags().get("otr.clock_skew").is_none());
+        }
+
+        #[test]
+        fn elapsed_since_start() {
+            let ten_minutes_ago = SystemTime::now() - Duration::from_secs(600);
+            let options = StartOptions::default().start_time(ten_minutes_ago);
+            let (span, _) = TestContext::new(options);
+            assert!(span.elapsed() >= Duration::from_secs(600));
+
+            let in_ten_minutes = SystemTime::now() + Duration::from_secs(600);
+            let options = StartOptions::default().start_time(in_ten_minutes);
+            let (span, _) = TestContext::new(options);
+            assert_eq!(Duration::default(), span.elapsed());
+        }
+
+        #[test]
+        fn finish_span_on_finish() {
+            let clock = Arc::new(ManualClock::new(UNIX_EPOCH));
+            let options = StartOptions::default().clock(clock.clone());
             let (span, receiver) = TestContext::new(options);
-            let about_soon = about_now + Duration::from_secs(600);
+            clock.advance(Duration::from_secs(600));
             span.finish().unwrap();
             let span = receiver.recv().unwrap();
-            assert!(about_now <= span.finish_time, "Finish time too old");
-            assert!(span.finish_time <= about_soon, "Finish time too new");
+            assert_eq!(UNIX_EPOCH + Duration::from_secs(600), span.finish_time);
+        }
+
+        #[test]
+        fn finish_span_with_options() {
+            let options = StartOptions::default();
+            let (span, receiver) = TestContext::new(options);
+            let options = FinishOptions::default()
+                .finish_time(UNIX_EPOCH)
+                .tag("key", "value")
+                .log(Log::new().log("event", "done"));
+            span.finish_with_options(options).unwrap();
+            let span = receiver.recv().unwrap();
+            assert_eq!(UNIX_EPOCH, span.finish_time);
+            assert_eq!(Some(&UNIX_EPOCH), span.logs()[0].timestamp());
+            match span.tags().get("key") {
+                Some(TagValue::String(value)) => assert_eq!("value", value),
+                _ => panic!("Expected the closing tag"),
+            }
+        }
+
+        #[test]
+        fn finish_span_with_options_after_finish_time() {
+            let limits = SpanLimits::default().after_finish(AfterFinishPolicy::Reject);
+            let (warnings, received) = warning_channel();
+            let mut options = StartOptions::default().span_limits(limits);
+            options.warnings = Some(warnings);
+            let (mut span, receiver) = TestContext::new(options);
+            span.finish_time(UNIX_EPOCH);
+            span.finish_with_options(FinishOptions::default().tag("key", "value")).unwrap();
+            let span = receiver.recv().unwrap();
+            assert_eq!(UNIX_EPOCH, span.finish_time);
+            assert!(span.tags().get("key").is_some());
+            assert!(received.try_recv().is_err());
         }
 
         #[test]
@@ -744,25 +2000,325 @@ mod tests {
             assert_eq!(span.finish_time, in_ten_minutes);
         }
 
+        #[test]
+        fn replay_recorded_events() {
+            let start = UNIX_EPOCH + Duration::from_secs(10);
+            let limits = SpanLimits::default()
+                .after_finish(AfterFinishPolicy::Reject)
+                .timestamp_resolution(TimestampResolution::Millisecond);
+            let options = StartOptions::default().start_time(start).span_limits(limits);
+            let (mut span, receiver) = TestContext::new(options);
+            span.finish_time(start + Duration::from_secs(5));
+            let event = start + Duration::from_nanos(1);
+            span.log_at(event, Log::new().log("event", "recorded").at(UNIX_EPOCH));
+            span.tag_at(event, "recorded", true);
+            span.tag_at(start + Duration::from_secs(6), "late", true);
+            span.tag("now", true);
+            span.finish().unwrap();
+            let span = receiver.recv().unwrap();
+            assert_eq!(Some(&event), span.logs()[0].timestamp());
+            assert!(span.tags().get("recorded").is_some());
+            assert!(span.tags().get("late").is_none());
+            assert!(span.tags().get("now").is_none());
+        }
+
+        #[test]
+        fn log_timestamps_from_clock() {
+            let clock = Arc::new(ManualClock::new(UNIX_EPOCH));
+            let options = StartOptions::default().clock(clock.clone());
+            let (mut span, receiver) = TestContext::new(options);
+            clock.advance(Duration::from_secs(1));
+            span.log(Log::new().log("key", "value"));
+            span.finish().unwrap();
+            let span = receiver.recv().unwrap();
+            let expected = UNIX_EPOCH + Duration::from_secs(1);
+            assert_eq!(Some(&expected), span.logs()[0].timestamp());
+        }
+
         #[test]
         fn starts_now_by_default() {
-            // Can't mock SystemTime::now() to a fixed value.
-            // Check start time is in a range [now, now + ten minutes].
-            let about_now = SystemTime::now();
-            let options = StartOptions::default();
+            let now = SystemTime::now();
+            let options = StartOptions::default().clock(Arc::new(ManualClock::new(now)));
             let (span, _) = TestContext::new(options);
-            let about_soon = about_now + Duration::from_secs(600);
-            assert!(about_now <= span.start_time, "Start time too old");
-            assert!(span.start_time <= about_soon, "Start time too new");
+            assert_eq!(now, span.start_time);
+        }
+
+        #[test]
+        fn log_timestamps_can_be_skipped() {
+            let limits = SpanLimits::default().log_timestamps(false);
+            let options = StartOptions::default().span_limits(limits);
+            let (mut span, receiver) = TestContext::new(options);
+            span.log(Log::new().log("key", "value"));
+            span.finish().unwrap();
+            let span = receiver.recv().unwrap();
+            assert!(span.logs()[0].timestamp().is_none());
+        }
+
+        #[test]
+        fn millisecond_resolution() {
+            let limits = SpanLimits::default()
+                .timestamp_resolution(TimestampResolution::Millisecond);
+            let options = StartOptions::default().span_limits(limits);
+            let (span, receiver) = TestContext::new(options);
+            span.finish().unwrap();
+            let span = receiver.recv().unwrap();
+            let start = span.start_time().duration_since(UNIX_EPOCH).unwrap();
+            let finish = span.finish_time().duration_since(UNIX_EPOCH).unwrap();
+            assert_eq!(0, start.subsec_nanos() % 1_000_000);
+            assert_eq!(0, finish.subsec_nanos() % 1_000_000);
         }
 
         #[test]
         fn start_time_set() {
             let ten_minutes_ago = SystemTime::now() - Duration::from_secs(600);
             let options = StartOptions::default()
-                .start_time(ten_minutes_ago.clone());
+                .start_time(ten_minutes_ago);
             let (span, _) = TestContext::new(options);
             assert_eq!(span.start_time, ten_minutes_ago);
         }
     }
+
+    mod truncation {
+        use std::time::UNIX_EPOCH;
+
+        use super::super::super::ImplContextBox;
+
+        use super::super::Log;
+        use super::super::SpanContext;
+        use super::super::SpanLimits;
+        use super::super::StartOptions;
+        use super::super::TagValue;
+        use super::TestContext;
+
+
+        #[test]
+        fn nothing_dropped_no_markers() {
+            let limits = SpanLimits::default().max_logs(1).max_references(1).max_tags(1);
+            let options = StartOptions::default().span_limits(limits);
+            let (mut span, receiver) = TestContext::new(options);
+            span.tag("a", 1);
+            span.log(Log::new().log("event", "a"));
+            span.finish().unwrap();
+            let span = receiver.recv().unwrap();
+            assert_eq!(1, span.tags().iter().count());
+        }
+
+        #[test]
+        fn drop_logs() {
+            let limits = SpanLimits::default().max_logs(1);
+            let options = StartOptions::default().span_limits(limits);
+            let (mut span, receiver) = TestContext::new(options);
+            for _ in 0..3 {
+                span.log(Log::new().log("event", "a"));
+            }
+            span.log_at(UNIX_EPOCH, Log::new().log("event", "replayed"));
+            span.finish().unwrap();
+            let span = receiver.recv().unwrap();
+            assert_eq!(1, span.logs().len());
+            match span.tags().get("otr.truncated.logs") {
+                Some(&TagValue::Unsigned(3)) => (),
+                _ => panic!("Invalid truncation marker")
+            }
+        }
+
+        #[test]
+        fn drop_references() {
+            let limits = SpanLimits::default().max_references(1);
+            let options = StartOptions::default().span_limits(limits);
+            let (mut span, receiver) = TestContext::new(options);
+            for _ in 0..2 {
+                let mut parent = SpanContext::new(ImplContextBox::new(TestContext {
+                    id: String::from("parent")
+                }));
+                parent.set_baggage_item(String::from("key"), String::from("value"));
+                span.child_of(parent);
+            }
+            assert_eq!("value", span.get_baggage_item("key").unwrap());
+            span.finish().unwrap();
+            let span = receiver.recv().unwrap();
+            assert_eq!(1, span.references().len());
+            match span.tags().get("otr.truncated.references") {
+                Some(&TagValue::Unsigned(1)) => (),
+                _ => panic!("Invalid truncation marker")
+            }
+        }
+
+        #[test]
+        fn drop_tags() {
+            let limits = SpanLimits::default().max_tags(2);
+            let options = StartOptions::default().span_limits(limits);
+            let (mut span, receiver) = TestContext::new(options);
+            span.tag("a", 1);
+            span.tag("b", 2);
+            span.tag("c", 3);
+            span.tag("d", 4);
+            span.tag("a", 5);
+            span.finish().unwrap();
+            let span = receiver.recv().unwrap();
+            match span.tags().get("a") {
+                Some(&TagValue::Integer(5)) => (),
+                _ => panic!("Existing tags should be updated")
+            }
+            assert!(span.tags().get("c").is_none());
+            match span.tags().get("otr.truncated.tags") {
+                Some(&TagValue::Unsigned(2)) => (),
+                _ => panic!("Invalid truncation marker")
+            }
+        }
+
+        #[test]
+        fn truncate_finished_logs() {
+            let limits = SpanLimits::default().max_logs(3);
+            let options = StartOptions::default().span_limits(limits);
+            let (mut span, receiver) = TestContext::new(options);
+            for _ in 0..4 {
+                span.log(Log::new().log("event", "a"));
+            }
+            span.finish().unwrap();
+            let mut span = receiver.recv().unwrap();
+            span.truncate_logs(5);
+            assert_eq!(3, span.logs().len());
+            span.truncate_logs(1);
+            assert_eq!(1, span.logs().len());
+            match span.tags().get("otr.truncated.logs") {
+                Some(&TagValue::Unsigned(3)) => (),
+                _ => panic!("Invalid truncation marker")
+            }
+        }
+    }
+
+    mod lineage {
+        use super::super::StartOptions;
+        use super::TestContext;
+
+
+        #[test]
+        fn root_span() {
+            let (span, _receiver) = TestContext::new(StartOptions::default());
+            assert_eq!("? > test-id", span.lineage_string());
+        }
+
+        #[test]
+        fn child_span() {
+            let (parent, _receiver) = TestContext::new(StartOptions::default());
+            let options = StartOptions::default().child_of(parent.context().clone());
+            let (span, receiver) = TestContext::new(options);
+            assert_eq!("? > test-id > test-id", span.lineage_string());
+            span.finish().unwrap();
+            let span = receiver.recv().unwrap();
+            assert_eq!("? > test-id > test-id", span.lineage_string());
+        }
+    }
+
+    mod timers {
+        use std::sync::Arc;
+        use std::time::Duration;
+        use std::time::UNIX_EPOCH;
+
+        use super::super::clock::ManualClock;
+        use super::super::log::LogValue;
+        use super::super::StartOptions;
+        use super::TestContext;
+
+
+        #[test]
+        fn overlapping_timers() {
+            let (mut span, receiver) = TestContext::new(StartOptions::default());
+            let render = span.timer("render");
+            let template = span.timer("template");
+            drop(template);
+            drop(render);
+            let _running = span.timer("running");
+            span.finish().unwrap();
+
+            let span = receiver.recv().unwrap();
+            let fields: Vec<&str> = span.logs().iter()
+                .flat_map(|log| log.iter().map(|(key, _)| key.as_str()))
+                .collect();
+            assert_eq!(fields, ["template.duration_ms", "render.duration_ms"]);
+            for log in span.logs() {
+                assert!(log.timestamp().is_some());
+                match log.iter().next() {
+                    Some((_, &LogValue::Float(duration))) => assert!(duration >= 0.0),
+                    _ => panic!("Duration not logged")
+                }
+            }
+        }
+
+        #[test]
+        fn timers_read_the_span_clock() {
+            let clock = Arc::new(ManualClock::new(UNIX_EPOCH));
+            let options = StartOptions::default().clock(clock.clone());
+            let (mut span, receiver) = TestContext::new(options);
+            let timer = span.timer("render");
+            clock.advance(Duration::from_millis(250));
+            assert_eq!(Duration::from_millis(250), timer.elapsed());
+            drop(timer);
+            span.finish().unwrap();
+
+            let span = receiver.recv().unwrap();
+            let log = &span.logs()[0];
+            let expected = UNIX_EPOCH + Duration::from_millis(250);
+            assert_eq!(Some(&expected), log.timestamp());
+            match log.iter().next() {
+                Some((_, &LogValue::Float(duration))) => assert_eq!(250.0, duration),
+                _ => panic!("Duration not logged")
+            }
+        }
+    }
+
+    mod progress {
+        use std::sync::Arc;
+        use std::time::Duration;
+        use std::time::UNIX_EPOCH;
+
+        use super::super::clock::ManualClock;
+        use super::super::SpanLimits;
+        use super::super::StartOptions;
+        use super::super::log::LogValue;
+        use super::TestContext;
+
+
+        #[test]
+        fn rate_limited() {
+            let limits = SpanLimits::default().progress_interval(Duration::from_secs(3600));
+            let options = StartOptions::default().span_limits(limits);
+            let (mut span, receiver) = TestContext::new(options);
+            assert!(span.progress(1, 4));
+            assert!(!span.progress(2, 4));
+            assert!(!span.progress(3, 4));
+            assert!(span.progress(4, 4));
+            span.finish().unwrap();
+
+            let span = receiver.recv().unwrap();
+            assert_eq!(2, span.logs().len());
+            let mut fields: Vec<(&str, String)> = span.logs()[0].iter()
+                .map(|(key, value)| (&key[..], value.to_string()))
+                .collect();
+            fields.sort();
+            assert_eq!(fields, [
+                ("event", String::from("progress")),
+                ("progress.done", String::from("1")),
+                ("progress.percent", String::from("25")),
+                ("progress.total", String::from("4")),
+            ]);
+            match span.logs()[1].iter().find(|&(key, _)| key == "progress.percent") {
+                Some((_, &LogValue::Float(percent))) => assert_eq!(100.0, percent),
+                _ => panic!("Completion not logged")
+            }
+        }
+
+        #[test]
+        fn reads_the_span_clock() {
+            let clock = Arc::new(ManualClock::new(UNIX_EPOCH));
+            let limits = SpanLimits::default().progress_interval(Duration::from_secs(60));
+            let options = StartOptions::default().span_limits(limits).clock(clock.clone());
+            let (mut span, _receiver) = TestContext::new(options);
+            assert!(span.progress(1, 4));
+            clock.advance(Duration::from_secs(30));
+            assert!(!span.progress(2, 4));
+            clock.advance(Duration::from_secs(30));
+            assert!(span.progress(3, 4));
+        }
+    }
 }
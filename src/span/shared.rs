@@ -0,0 +1,162 @@
+use std::fmt::Debug;
+use std::fmt::Display;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+
+use super::super::Result;
+use super::super::SpanContext;
+
+use super::Span;
+use super::log::Log;
+use super::tag::TagValue;
+
+
+/// A `Span` that can be cloned into callbacks and closures that all need to update it.
+///
+/// Clones share the same span and updates are synchronised with a lock.
+///
+/// The span is finished once, by the first call to `SharedSpan::finish` from any clone.
+/// After that the span is gone: updates from other clones are ignored and further
+/// calls to `SharedSpan::finish` succeed without doing anything.
+///
+/// # Examples
+///
+/// ```
+/// extern crate opentracingrust;
+///
+/// use opentracingrust::SharedSpan;
+/// use opentracingrust::tracers::NoopTracer;
+///
+///
+/// fn main() {
+///     let (tracer, receiver) = NoopTracer::new();
+///     let span = SharedSpan::new(tracer.span("request"));
+///
+///     let on_data = {
+///         let span = span.clone();
+///         move |bytes: u64| span.tag("response.bytes", bytes)
+///     };
+///     let on_done = {
+///         let span = span.clone();
+///         move || span.finish().unwrap()
+///     };
+///     on_data(42);
+///     on_done();
+///     span.finish().unwrap();
+///
+///     assert_eq!(1, receiver.try_iter().count());
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct SharedSpan(Arc<Mutex<Option<Span>>>);
+
+impl SharedSpan {
+    /// Shares a span.
+    pub fn new(span: Span) -> SharedSpan {
+        SharedSpan(Arc::new(Mutex::new(Some(span))))
+    }
+}
+
+impl SharedSpan {
+    /// Returns a copy of the `SpanContext` of the span, if it was not finished yet.
+    pub fn context(&self) -> Option<SpanContext> {
+        self.with(|span| span.context().clone())
+    }
+
+    /// Finishes the span unless another clone already did.
+    #[allow(clippy::result_large_err)]
+    pub fn finish(&self) -> Result<()> {
+        match self.lock().take() {
+            Some(span) => span.finish(),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks if the span was finished.
+    pub fn is_finished(&self) -> bool {
+        self.lock().is_none()
+    }
+
+    /// Logs an event on the span (see `Span::log`).
+    pub fn log(&self, log: Log) {
+        self.with(|span| span.log(log));
+    }
+
+    /// Marks the span as failed (see `Span::log_error`).
+    pub fn log_error<E: Debug + Display + ?Sized>(&self, error: &E) {
+        self.with(|span| span.log_error(error));
+    }
+
+    /// Sets a baggage item on the span (see `Span::set_baggage_item`).
+    pub fn set_baggage_item(&self, key: &str, value: &str) {
+        self.with(|span| span.set_baggage_item(key, value));
+    }
+
+    /// Tags the span (see `Span::tag`).
+    pub fn tag<TV: Into<TagValue>>(&self, tag: &str, value: TV) {
+        self.with(|span| span.tag(tag, value));
+    }
+
+    /// Calls `update` with the span, while holding the lock, to access the full `Span` API.
+    ///
+    /// Returns `None` without calling `update` if the span was finished.
+    pub fn with<R, F: FnOnce(&mut Span) -> R>(&self, update: F) -> Option<R> {
+        self.lock().as_mut().map(update)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Option<Span>> {
+        self.0.lock().expect("Failed to lock SharedSpan")
+    }
+}
+
+impl From<Span> for SharedSpan {
+    fn from(span: Span) -> SharedSpan {
+        SharedSpan::new(span)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::super::super::TagValue;
+    use super::super::super::tracers::NoopTracer;
+
+    use super::SharedSpan;
+
+    #[test]
+    fn finish_once() {
+        let (tracer, receiver) = NoopTracer::new();
+        let span = SharedSpan::new(tracer.span("shared"));
+        let other = span.clone();
+        other.finish().unwrap();
+        assert!(span.is_finished());
+        span.tag("late", true);
+        assert!(span.context().is_none());
+        span.finish().unwrap();
+        let spans: Vec<_> = receiver.try_iter().collect();
+        assert_eq!(1, spans.len());
+        assert!(spans[0].tags().get("late").is_none());
+    }
+
+    #[test]
+    fn update_from_threads() {
+        let (tracer, receiver) = NoopTracer::new();
+        let span = SharedSpan::new(tracer.span("shared"));
+        let threads: Vec<_> = (0..4u64).map(|id| {
+            let span = span.clone();
+            thread::spawn(move || span.tag(&format!("thread.{}", id), id))
+        }).collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        span.finish().unwrap();
+        let span = receiver.recv().unwrap();
+        match span.tags().get("thread.3") {
+            Some(TagValue::Unsigned(id)) => assert_eq!(3, *id),
+            _ => panic!("Invalid thread.3 tag"),
+        }
+    }
+}
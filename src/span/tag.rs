@@ -1,11 +1,12 @@
 use std::collections::HashMap;
 use std::collections::hash_map::Iter;
+use std::fmt;
 
 
 /// Map strings to `TagValue`s.
 ///
 /// This structure is a tailored wrapper around `HashMap`s.
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 pub struct SpanTags(HashMap<String, TagValue>);
 
 impl SpanTags {
@@ -22,7 +23,7 @@ impl SpanTags {
     }
 
     /// Returns an iteratore over all tags.
-    pub fn iter(&self) -> Iter<String, TagValue> {
+    pub fn iter(&self) -> Iter<'_, String, TagValue> {
         self.0.iter()
     }
 
@@ -32,14 +33,53 @@ impl SpanTags {
     }
 }
 
+impl SpanTags {
+    /// Returns the number of tags.
+    pub(crate) fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Removes a tag and returns its value.
+    pub(crate) fn remove(&mut self, tag: &str) -> Option<TagValue> {
+        self.0.remove(tag)
+    }
+
+    /// Retains only the tags for which `keep` returns `true`.
+    ///
+    /// The closure can also modify the value of retained tags.
+    pub(crate) fn retain<F: FnMut(&String, &mut TagValue) -> bool>(&mut self, keep: F) {
+        self.0.retain(keep);
+    }
+}
+
 
 /// Enumeration of valid types for tag values.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum TagValue {
     Boolean(bool),
+    Bytes(Vec<u8>),
     Float(f64),
     Integer(i64),
     String(String),
+    Unsigned(u64),
+}
+
+impl fmt::Display for TagValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TagValue::Boolean(v) => fmt::Display::fmt(v, f),
+            TagValue::Bytes(v) => {
+                for byte in v {
+                    write!(f, "{:02x}", byte)?;
+                }
+                Ok(())
+            },
+            TagValue::Float(v) => fmt::Display::fmt(v, f),
+            TagValue::Integer(v) => fmt::Display::fmt(v, f),
+            TagValue::String(v) => fmt::Display::fmt(v, f),
+            TagValue::Unsigned(v) => fmt::Display::fmt(v, f),
+        }
+    }
 }
 
 impl From<bool> for TagValue {
@@ -48,18 +88,48 @@ impl From<bool> for TagValue {
     }
 }
 
+impl<'a> From<&'a [u8]> for TagValue {
+    fn from(value: &'a [u8]) -> TagValue {
+        TagValue::Bytes(value.to_vec())
+    }
+}
+
+impl From<Vec<u8>> for TagValue {
+    fn from(value: Vec<u8>) -> TagValue {
+        TagValue::Bytes(value)
+    }
+}
+
 impl From<f64> for TagValue {
     fn from(value: f64) -> TagValue {
         TagValue::Float(value)
     }
 }
 
+impl From<i32> for TagValue {
+    fn from(value: i32) -> TagValue {
+        TagValue::Integer(i64::from(value))
+    }
+}
+
 impl From<i64> for TagValue {
     fn from(value: i64) -> TagValue {
         TagValue::Integer(value)
     }
 }
 
+impl From<u32> for TagValue {
+    fn from(value: u32) -> TagValue {
+        TagValue::Unsigned(u64::from(value))
+    }
+}
+
+impl From<u64> for TagValue {
+    fn from(value: u64) -> TagValue {
+        TagValue::Unsigned(value)
+    }
+}
+
 impl<'a> From<&'a str> for TagValue {
     fn from(value: &'a str) -> TagValue {
         TagValue::String(String::from(value))
@@ -79,6 +149,7 @@ mod tests {
     use super::TagValue;
 
     #[test]
+    #[allow(clippy::single_match)]
     fn get_missing_tag() {
         let tags = SpanTags::new();
         match tags.get("key") {
@@ -100,6 +171,12 @@ mod tests {
         }
     }
 
+    #[test]
+    fn display_bytes() {
+        let value = TagValue::from(vec![0u8, 15, 255]);
+        assert_eq!(value.to_string(), "000fff");
+    }
+
     #[test]
     fn set_tag() {
         let mut tags = SpanTags::new();
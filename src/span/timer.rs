@@ -0,0 +1,98 @@
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
+
+use super::clock::Clock;
+use super::log::Log;
+
+
+/// Logs recorded by `SpanTimer`s and waiting to be added to their `Span`.
+pub(crate) type PendingTimings = Arc<Mutex<Vec<Log>>>;
+
+
+/// A named stopwatch for part of the operation traced by a `Span`.
+///
+/// Timers are started with `Span::timer` and stop when dropped.
+/// When a timer stops a log with a `<name>.duration_ms` field is recorded
+/// in the span, timestamped at the time the timer stopped.
+/// Timers read the span's `Clock`, if one is set, for both the duration and the timestamp.
+///
+/// Timers do not borrow the span so several timers can overlap.
+/// Timers still running when the span is finished are not recorded.
+///
+/// # Examples
+///
+/// ```
+/// extern crate opentracingrust;
+///
+/// use opentracingrust::tracers::NoopTracer;
+///
+///
+/// fn main() {
+///     let (tracer, receiver) = NoopTracer::new();
+///     let mut span = tracer.span("request");
+///     {
+///         let _render = span.timer("render");
+///         let _template = span.timer("template");
+///         // ... snip ...
+///     }
+///     span.finish().unwrap();
+///
+///     let span = receiver.recv().unwrap();
+///     assert_eq!(2, span.logs().len());
+/// }
+/// ```
+pub struct SpanTimer {
+    clock: Option<Arc<dyn Clock>>,
+    field: String,
+    start: Instant,
+    start_time: SystemTime,
+    timings: PendingTimings,
+}
+
+impl SpanTimer {
+    /// Starts a timer that records into the given pending timings.
+    pub(crate) fn new(
+        name: &str, clock: Option<Arc<dyn Clock>>, timings: PendingTimings
+    ) -> SpanTimer {
+        let start_time = SpanTimer::now(clock.as_deref());
+        SpanTimer {
+            clock,
+            field: format!("{}.duration_ms", name),
+            start: Instant::now(),
+            start_time,
+            timings,
+        }
+    }
+
+    /// Returns the time elapsed since the timer started.
+    ///
+    /// Without a `Clock` the elapsed time is measured with the monotonic `Instant`.
+    pub fn elapsed(&self) -> Duration {
+        match self.clock {
+            Some(ref clock) => clock.now().duration_since(self.start_time).unwrap_or_default(),
+            None => self.start.elapsed(),
+        }
+    }
+
+    /// Reads the current time from the clock, or the system clock if none is set.
+    fn now(clock: Option<&dyn Clock>) -> SystemTime {
+        clock.map(Clock::now).unwrap_or_else(SystemTime::now)
+    }
+}
+
+impl Drop for SpanTimer {
+    fn drop(&mut self) {
+        let duration_ms = self.elapsed().as_secs_f64() * 1000.0;
+        let now = SpanTimer::now(self.clock.as_deref());
+        let log = Log::new().log(&self.field, duration_ms).at(now);
+        // A poisoned lock only means another timer panicked while recording.
+        let mut timings = match self.timings.lock() {
+            Ok(timings) => timings,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        timings.push(log);
+    }
+}
@@ -0,0 +1,315 @@
+use std::fmt::Debug;
+use std::fmt::Display;
+
+use super::super::Result;
+use super::super::SpanContext;
+use super::super::StartOptions;
+use super::super::Tracer;
+
+use super::Span;
+use super::kind::SpanKind;
+use super::log::Log;
+
+
+/// Implements the operations shared by all typed span wrappers.
+///
+/// Wrappers are started with the `Tracer` and `StartOptions` followed by
+/// the arguments specific to the convention.
+/// Tags set when a wrapper starts are passed as `StartOptions::tag`s so concrete
+/// tracers can see them in `TracerInterface::span` (see `StartOptions::tags`).
+macro_rules! typed_span {
+    ($wrapper:ident) => {
+        impl $wrapper {
+            /// Access the `SpanContext` of the wrapped span.
+            pub fn context(&self) -> &SpanContext {
+                self.0.context()
+            }
+
+            /// Finishes the wrapped span (see `Span::finish`).
+            #[allow(clippy::result_large_err)]
+            pub fn finish(self) -> Result<()> {
+                self.0.finish()
+            }
+
+            /// Returns the wrapped span to access the full `Span` API.
+            pub fn into_inner(self) -> Span {
+                self.0
+            }
+
+            /// Logs an event on the wrapped span (see `Span::log`).
+            pub fn log(&mut self, log: Log) {
+                self.0.log(log);
+            }
+
+            /// Marks the wrapped span as failed (see `Span::log_error`).
+            pub fn log_error<E: Debug + Display + ?Sized>(&mut self, error: &E) {
+                self.0.log_error(error);
+            }
+        }
+    }
+}
+
+
+/// A `SpanKind::Server` span for an incoming HTTP request.
+///
+/// The span is named after the request method and tagged with the `http.method`
+/// and `http.url` tags when started.
+/// Only setters for HTTP server conventions are exposed.
+///
+/// # Examples
+///
+/// ```
+/// extern crate opentracingrust;
+///
+/// use opentracingrust::HttpServerSpan;
+/// use opentracingrust::StartOptions;
+/// use opentracingrust::tracers::NoopTracer;
+///
+///
+/// fn main() {
+///     let (tracer, receiver) = NoopTracer::new();
+///     let mut span = HttpServerSpan::start(
+///         &tracer, StartOptions::default(), "get", "/users/42"
+///     );
+///     span.route("/users/{id}");
+///     span.status_code(200);
+///     span.finish().unwrap();
+///
+///     let span = receiver.recv().unwrap();
+///     assert_eq!("HTTP GET", span.name());
+/// }
+/// ```
+#[derive(Debug)]
+pub struct HttpServerSpan(Span);
+
+impl HttpServerSpan {
+    /// Starts a span for an incoming request.
+    pub fn start(tracer: &Tracer, options: StartOptions, method: &str, url: &str) -> Self {
+        let method = method.to_uppercase();
+        let options = options.span_kind(SpanKind::Server)
+            .tag("http.method", method.as_str())
+            .tag("http.url", url);
+        HttpServerSpan(tracer.span_with_options(format!("HTTP {}", method), options))
+    }
+
+    /// Sets the `peer.address` tag with the address of the client.
+    pub fn peer_address(&mut self, address: &str) {
+        self.0.tag("peer.address", address);
+    }
+
+    /// Sets the `http.route` tag with the route template that matched the request.
+    pub fn route(&mut self, route: &str) {
+        self.0.tag("http.route", route);
+    }
+
+    /// Sets the `http.status_code` tag with the response status.
+    ///
+    /// The `error` tag is set for server errors (5xx).
+    pub fn status_code(&mut self, status: u16) {
+        self.0.tag("http.status_code", u32::from(status));
+        if status >= 500 {
+            self.0.tag("error", true);
+        }
+    }
+}
+
+typed_span!(HttpServerSpan);
+
+
+/// A `SpanKind::Client` span for a database call.
+///
+/// The span is tagged with the `db.type` tag when started.
+/// Only setters for database conventions are exposed.
+///
+/// # Examples
+///
+/// ```
+/// extern crate opentracingrust;
+///
+/// use opentracingrust::DbSpan;
+/// use opentracingrust::StartOptions;
+/// use opentracingrust::tracers::NoopTracer;
+///
+///
+/// fn main() {
+///     let (tracer, _receiver) = NoopTracer::new();
+///     let mut span = DbSpan::start(&tracer, StartOptions::default(), "load user", "sql");
+///     span.instance("users");
+///     span.statement("SELECT * FROM users WHERE id = ?");
+///     span.finish().unwrap();
+/// }
+/// ```
+#[derive(Debug)]
+pub struct DbSpan(Span);
+
+impl DbSpan {
+    /// Starts a span for a call to a database of the given type (`sql`, `redis`, ...).
+    pub fn start(tracer: &Tracer, options: StartOptions, name: &str, db_type: &str) -> Self {
+        let options = options.span_kind(SpanKind::Client).tag("db.type", db_type);
+        DbSpan(tracer.span_with_options(name, options))
+    }
+
+    /// Sets the `db.instance` tag with the name of the database.
+    pub fn instance(&mut self, instance: &str) {
+        self.0.tag("db.instance", instance);
+    }
+
+    /// Sets the `db.statement` tag with the statement executed.
+    pub fn statement(&mut self, statement: &str) {
+        self.0.tag("db.statement", statement);
+    }
+
+    /// Sets the `db.user` tag with the user accessing the database.
+    pub fn user(&mut self, user: &str) {
+        self.0.tag("db.user", user);
+    }
+}
+
+typed_span!(DbSpan);
+
+
+/// A span for sending or receiving a message through a message bus.
+///
+/// Spans are `SpanKind::Producer` or `SpanKind::Consumer` spans, named after the
+/// destination and tagged with the `message_bus.destination` tag when started.
+/// Only setters for messaging conventions are exposed.
+///
+/// # Examples
+///
+/// ```
+/// extern crate opentracingrust;
+///
+/// use opentracingrust::MessagingSpan;
+/// use opentracingrust::StartOptions;
+/// use opentracingrust::tracers::NoopTracer;
+///
+///
+/// fn main() {
+///     let (tracer, receiver) = NoopTracer::new();
+///     let mut span = MessagingSpan::producer(&tracer, StartOptions::default(), "orders");
+///     span.peer_service("kafka");
+///     span.finish().unwrap();
+///
+///     let span = receiver.recv().unwrap();
+///     assert_eq!("send orders", span.name());
+/// }
+/// ```
+#[derive(Debug)]
+pub struct MessagingSpan(Span);
+
+impl MessagingSpan {
+    /// Starts a span for processing a message received from the destination.
+    pub fn consumer(tracer: &Tracer, options: StartOptions, destination: &str) -> Self {
+        MessagingSpan::start(tracer, options, SpanKind::Consumer, "receive", destination)
+    }
+
+    /// Starts a span for sending a message to the destination.
+    pub fn producer(tracer: &Tracer, options: StartOptions, destination: &str) -> Self {
+        MessagingSpan::start(tracer, options, SpanKind::Producer, "send", destination)
+    }
+
+    /// Sets the `peer.address` tag with the address of the broker.
+    pub fn peer_address(&mut self, address: &str) {
+        self.0.tag("peer.address", address);
+    }
+
+    /// Sets the `peer.service` tag with the name of the message bus.
+    pub fn peer_service(&mut self, service: &str) {
+        self.0.tag("peer.service", service);
+    }
+
+    fn start(
+        tracer: &Tracer, options: StartOptions, kind: SpanKind, action: &str, destination: &str
+    ) -> Self {
+        let options = options.span_kind(kind).tag("message_bus.destination", destination);
+        MessagingSpan(tracer.span_with_options(format!("{} {}", action, destination), options))
+    }
+}
+
+typed_span!(MessagingSpan);
+
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::super::super::ExtractFormat;
+    use super::super::super::InjectFormat;
+    use super::super::super::Result;
+    use super::super::super::Span;
+    use super::super::super::SpanContext;
+    use super::super::super::StartOptions;
+    use super::super::super::TagValue;
+    use super::super::super::Tracer;
+    use super::super::super::TracerInterface;
+    use super::super::super::tracers::NoopTracer;
+
+    use super::super::kind::SpanKind;
+
+    use super::DbSpan;
+    use super::HttpServerSpan;
+    use super::MessagingSpan;
+
+    #[test]
+    fn db_span() {
+        let (tracer, receiver) = NoopTracer::new();
+        let mut span = DbSpan::start(&tracer, StartOptions::default(), "query", "sql");
+        span.statement("SELECT 1");
+        span.finish().unwrap();
+        let span = receiver.recv().unwrap();
+        assert_eq!(Some(SpanKind::Client), span.kind());
+        match span.tags().get("db.statement") {
+            Some(TagValue::String(statement)) => assert_eq!("SELECT 1", statement),
+            _ => panic!("Invalid db.statement tag"),
+        }
+    }
+
+    /// Checks the start options tags and delegates to a `NoopTracer`.
+    struct StartTagsTracer(Tracer);
+
+    impl TracerInterface for StartTagsTracer {
+        fn extract(&self, fmt: ExtractFormat) -> Result<Option<SpanContext>> {
+            self.0.extract(fmt)
+        }
+
+        fn inject(&self, context: &SpanContext, fmt: InjectFormat) -> Result<()> {
+            self.0.inject(context, fmt)
+        }
+
+        fn span(&self, name: Arc<str>, options: StartOptions) -> Span {
+            assert!(options.tags().get("http.method").is_some());
+            assert!(options.tags().get("http.url").is_some());
+            self.0.span_with_options(name, options)
+        }
+    }
+
+    #[test]
+    fn tags_visible_to_tracers() {
+        let tracer = Tracer::new(StartTagsTracer(NoopTracer::new().0));
+        HttpServerSpan::start(&tracer, StartOptions::default(), "get", "/");
+    }
+
+    #[test]
+    fn http_server_error() {
+        let (tracer, receiver) = NoopTracer::new();
+        let mut span = HttpServerSpan::start(&tracer, StartOptions::default(), "post", "/");
+        span.status_code(503);
+        span.finish().unwrap();
+        let span = receiver.recv().unwrap();
+        assert_eq!(Some(SpanKind::Server), span.kind());
+        match span.tags().get("error") {
+            Some(TagValue::Boolean(error)) => assert!(error),
+            _ => panic!("Invalid error tag"),
+        }
+    }
+
+    #[test]
+    fn messaging_consumer() {
+        let (tracer, receiver) = NoopTracer::new();
+        let span = MessagingSpan::consumer(&tracer, StartOptions::default(), "orders");
+        span.finish().unwrap();
+        let span = receiver.recv().unwrap();
+        assert_eq!("receive orders", span.name());
+        assert_eq!(Some(SpanKind::Consumer), span.kind());
+    }
+}
@@ -0,0 +1,151 @@
+use std::fmt;
+use std::sync::Arc;
+
+use crossbeam_channel::Receiver;
+use crossbeam_channel::Sender;
+use crossbeam_channel::unbounded;
+
+
+/// Sending end of the channel spans report `SpanWarning`s to.
+pub type WarningSender = Sender<SpanWarning>;
+
+/// Receiving end of the channel spans report `SpanWarning`s to.
+pub type WarningReceiver = Receiver<SpanWarning>;
+
+
+/// Creates a channel to receive `SpanWarning`s from a `Tracer` (see `Tracer::with_warnings`).
+pub fn warning_channel() -> (WarningSender, WarningReceiver) {
+    unbounded()
+}
+
+
+/// Kinds of data dropped by a span because of its `SpanLimits`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TruncatedItem {
+    Log,
+    Reference,
+    Tag,
+}
+
+impl fmt::Display for TruncatedItem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TruncatedItem::Log => write!(f, "log"),
+            TruncatedItem::Reference => write!(f, "reference"),
+            TruncatedItem::Tag => write!(f, "tag"),
+        }
+    }
+}
+
+
+/// Diagnostics about questionable use of spans by instrumentation.
+///
+/// Warnings are reported at runtime, as they happen, to the `WarningSender`
+/// configured on the `Tracer` with `Tracer::with_warnings`.
+/// Tests can assert that no warning is received to keep instrumentation clean.
+///
+/// # Examples
+///
+/// ```
+/// extern crate opentracingrust;
+///
+/// use opentracingrust::SpanWarning;
+/// use opentracingrust::tracers::NoopTracer;
+/// use opentracingrust::warning_channel;
+///
+///
+/// fn main() {
+///     let (tracer, _receiver) = NoopTracer::new();
+///     let (sender, warnings) = warning_channel();
+///     let tracer = tracer.with_warnings(sender);
+///     {
+///         let _span = tracer.span("forgotten");
+///     }
+///     let warnings: Vec<SpanWarning> = warnings.try_iter().collect();
+///     assert_eq!(warnings, [SpanWarning::UnfinishedSpanDropped(String::from("forgotten"))]);
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SpanWarning {
+    /// A span referenced another span after its finish time was set.
+    ///
+    /// Includes the name of the span and the kind of reference (see `SpanReference::kind`).
+    ReferenceAfterFinish(String, String),
+
+    /// A span was tagged after its finish time was set.
+    ///
+    /// Includes the name of the span and of the tag.
+    TagAfterFinish(String, String),
+
+    /// A span dropped data because of its `SpanLimits`.
+    ///
+    /// Includes the name of the span and the kind of data dropped.
+    Truncated(String, TruncatedItem),
+
+    /// A span was dropped without being finished so it was never reported.
+    ///
+    /// Includes the name of the span.
+    UnfinishedSpanDropped(String),
+}
+
+impl fmt::Display for SpanWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SpanWarning::ReferenceAfterFinish(span, kind) => write!(
+                f, "Span '{}' added a {} reference after its finish time was set", span, kind
+            ),
+            SpanWarning::TagAfterFinish(span, tag) => write!(
+                f, "Span '{}' tagged with '{}' after its finish time was set", span, tag
+            ),
+            SpanWarning::Truncated(span, item) => write!(
+                f, "Span '{}' dropped a {} because of its limits", span, item
+            ),
+            SpanWarning::UnfinishedSpanDropped(span) => write!(
+                f, "Span '{}' dropped without being finished", span
+            ),
+        }
+    }
+}
+
+
+/// Reports warnings for a span and detects spans dropped without being finished.
+#[derive(Debug)]
+pub(crate) struct SpanWarnings {
+    finished: bool,
+    name: Arc<str>,
+    sender: WarningSender,
+}
+
+impl SpanWarnings {
+    pub(crate) fn new(name: Arc<str>, sender: WarningSender) -> SpanWarnings {
+        SpanWarnings {
+            finished: false,
+            name,
+            sender,
+        }
+    }
+
+    /// Marks the span as finished so dropping it is not a warning.
+    pub(crate) fn finished(&mut self) {
+        self.finished = true;
+    }
+
+    /// Updates the span name reported with warnings.
+    pub(crate) fn rename(&mut self, name: Arc<str>) {
+        self.name = name;
+    }
+
+    /// Reports a warning about the span.
+    pub(crate) fn warn<F: FnOnce(String) -> SpanWarning>(&self, warning: F) {
+        // Nobody is listening if the receiver was dropped.
+        let _ = self.sender.send(warning(String::from(&*self.name)));
+    }
+}
+
+impl Drop for SpanWarnings {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.warn(SpanWarning::UnfinishedSpanDropped);
+        }
+    }
+}
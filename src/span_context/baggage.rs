@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+
+/// Policy restricting the baggage items that can be stored in a `SpanContext`.
+///
+/// Baggage items are propagated to every span in a trace and across process
+/// boundaries so unrestricted baggage can quickly become expensive.
+/// Restrictions are configured on the `Tracer` with `Tracer::with_baggage_restrictions`
+/// and are consulted every time a baggage item is set on a span created
+/// or a context extracted by the tracer:
+///
+///   * Items with keys that are not allowed or longer than the maximum key length are dropped.
+///   * Items that would exceed the maximum number of items are dropped.
+///   * Values longer than the maximum value length are truncated.
+///
+/// By default all keys are allowed and no limits are set.
+///
+/// # Examples
+///
+/// ```
+/// extern crate opentracingrust;
+///
+/// use opentracingrust::BaggageRestrictions;
+/// use opentracingrust::tracers::NoopTracer;
+///
+///
+/// fn main() {
+///     let (tracer, _) = NoopTracer::new();
+///     let tracer = tracer.with_baggage_restrictions(
+///         BaggageRestrictions::default()
+///             .allow_key("tenant")
+///             .max_value_length(4)
+///     );
+///     let mut span = tracer.span("test");
+///     span.set_baggage_item("tenant", "acme-corp");
+///     span.set_baggage_item("user", "someone");
+///     assert_eq!("acme", span.get_baggage_item("tenant").unwrap());
+///     assert!(span.get_baggage_item("user").is_none());
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct BaggageRestrictions {
+    allowed_keys: Option<HashSet<String>>,
+    max_items: Option<usize>,
+    max_key_length: Option<usize>,
+    max_value_length: Option<usize>,
+}
+
+impl BaggageRestrictions {
+    /// Adds a key to the allow-list.
+    ///
+    /// Once a key is allowed, all keys not in the allow-list are dropped.
+    pub fn allow_key<K: Into<String>>(mut self, key: K) -> Self {
+        self.allowed_keys.get_or_insert_with(HashSet::new).insert(key.into());
+        self
+    }
+
+    /// Sets the maximum number of baggage items in a `SpanContext`.
+    pub fn max_items(mut self, max_items: usize) -> Self {
+        self.max_items = Some(max_items);
+        self
+    }
+
+    /// Sets the maximum length, in bytes, of baggage keys.
+    pub fn max_key_length(mut self, max_key_length: usize) -> Self {
+        self.max_key_length = Some(max_key_length);
+        self
+    }
+
+    /// Sets the maximum length, in bytes, of baggage values.
+    pub fn max_value_length(mut self, max_value_length: usize) -> Self {
+        self.max_value_length = Some(max_value_length);
+        self
+    }
+}
+
+impl BaggageRestrictions {
+    /// Checks a baggage item against the restrictions.
+    ///
+    /// Returns the item to store in the baggage, with the value truncated if needed,
+    /// or `None` if the item should be dropped.
+    pub(crate) fn restrict(
+        &self, baggage: &HashMap<String, String>, key: String, mut value: String
+    ) -> Option<(String, String)> {
+        if let Some(ref allowed) = self.allowed_keys {
+            if !allowed.contains(&key) {
+                return None;
+            }
+        }
+        if let Some(max_key_length) = self.max_key_length {
+            if key.len() > max_key_length {
+                return None;
+            }
+        }
+        if let Some(max_items) = self.max_items {
+            if !baggage.contains_key(&key) && baggage.len() >= max_items {
+                return None;
+            }
+        }
+        if let Some(max_value_length) = self.max_value_length {
+            if value.len() > max_value_length {
+                let mut length = max_value_length;
+                while !value.is_char_boundary(length) {
+                    length -= 1;
+                }
+                value.truncate(length);
+            }
+        }
+        Some((key, value))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::BaggageRestrictions;
+
+    fn restrict(
+        restrictions: &BaggageRestrictions, key: &str, value: &str
+    ) -> Option<(String, String)> {
+        let mut baggage = HashMap::new();
+        baggage.insert(String::from("existing"), String::from("value"));
+        restrictions.restrict(&baggage, String::from(key), String::from(value))
+    }
+
+    #[test]
+    fn allows_everything_by_default() {
+        let restrictions = BaggageRestrictions::default();
+        let item = restrict(&restrictions, "key", "value");
+        assert_eq!(Some((String::from("key"), String::from("value"))), item);
+    }
+
+    #[test]
+    fn drops_keys_not_allowed() {
+        let restrictions = BaggageRestrictions::default().allow_key("key");
+        assert!(restrict(&restrictions, "key", "value").is_some());
+        assert!(restrict(&restrictions, "other", "value").is_none());
+    }
+
+    #[test]
+    fn drops_long_keys() {
+        let restrictions = BaggageRestrictions::default().max_key_length(3);
+        assert!(restrict(&restrictions, "key", "value").is_some());
+        assert!(restrict(&restrictions, "long-key", "value").is_none());
+    }
+
+    #[test]
+    fn drops_items_over_count() {
+        let restrictions = BaggageRestrictions::default().max_items(1);
+        assert!(restrict(&restrictions, "existing", "update").is_some());
+        assert!(restrict(&restrictions, "key", "value").is_none());
+    }
+
+    #[test]
+    fn truncates_long_values() {
+        let restrictions = BaggageRestrictions::default().max_value_length(2);
+        let item = restrict(&restrictions, "key", "value");
+        assert_eq!(Some((String::from("key"), String::from("va"))), item);
+        let item = restrict(&restrictions, "key", "€uro");
+        assert_eq!(Some((String::from("key"), String::from(""))), item);
+    }
+}
@@ -1,5 +1,7 @@
 use std::any::Any;
 use std::boxed::Box;
+use std::hash::Hash;
+use std::hash::Hasher;
 use std::marker::Send;
 
 use super::super::SpanReference;
@@ -51,13 +53,9 @@ use super::super::SpanReference;
 ///     }
 ///
 ///     fn reference_span(&mut self, reference: &SpanReference) {
-///         match *reference {
-///             SpanReference::ChildOf(ref parent) |
-///             SpanReference::FollowsFrom(ref parent) => {
-///                 let context = parent.impl_context::<SomeTracerContext>().unwrap();
-///                 self.trace_id = context.trace_id;
-///             }
-///         }
+///         let parent = reference.context();
+///         let context = parent.impl_context::<SomeTracerContext>().unwrap();
+///         self.trace_id = context.trace_id;
 ///     }
 /// }
 ///
@@ -88,6 +86,36 @@ pub trait ImplContext : Send {
     /// When a reference is added to a `SpanContext` this method will be called
     /// so that the tracer's `ImplContext` can update its internal references.
     fn reference_span(&mut self, reference: &SpanReference);
+
+    /// Compares this `ImplContext` with another one.
+    ///
+    /// This method is used to implement `PartialEq` for `SpanContext`s.
+    /// Implementations that do not opt into comparisons are never
+    /// equal to any other context, including themselves.
+    fn impl_eq(&self, _other: &dyn ImplContext) -> bool {
+        false
+    }
+
+    /// Feeds this `ImplContext` into the given `Hasher`.
+    ///
+    /// This method is used to implement `Hash` for `SpanContext`s.
+    /// Implementations that override `impl_eq` should override this method too
+    /// so that equal contexts produce equal hashes.
+    fn impl_hash(&self, _state: &mut dyn Hasher) {}
+
+    /// Returns the ID of the span, formatted for humans, if the tracer exposes it.
+    ///
+    /// Used to render debugging helpers such as `Span::lineage_string`.
+    fn span_id(&self) -> Option<String> {
+        None
+    }
+
+    /// Returns the ID of the trace, formatted for humans, if the tracer exposes it.
+    ///
+    /// Used to render debugging helpers such as `Span::lineage_string`.
+    fn trace_id(&self) -> Option<String> {
+        None
+    }
 }
 
 
@@ -129,14 +157,38 @@ pub trait ImplContext : Send {
 ///     // ... snip ...
 /// }
 /// ```
+///
+/// Structures that also implement `Eq` and `Hash` can be wrapped with
+/// `ImplContextBox::hashable` so that `SpanContext`s holding them can be
+/// compared and used as keys in hash maps.
 pub struct ImplContextBox<T: Any + Clone + Send + SpanReferenceAware> {
-    inner: T
+    eq: Option<fn(&T, &T) -> bool>,
+    hash: Option<fn(&T, &mut dyn Hasher)>,
+    inner: T,
 }
 
 impl<T: Any + Clone + Send + SpanReferenceAware> ImplContextBox<T> {
     /// Wrap a compatible value into a `ImplContextBox`.
     pub fn new(inner: T) -> ImplContextBox<T> {
-        ImplContextBox { inner }
+        ImplContextBox {
+            eq: None,
+            hash: None,
+            inner,
+        }
+    }
+}
+
+impl<T: Any + Clone + Eq + Hash + Send + SpanReferenceAware> ImplContextBox<T> {
+    /// Wrap a comparable value into a `ImplContextBox`.
+    ///
+    /// The `ImplContextBox` implements `ImplContext::impl_eq` and
+    /// `ImplContext::impl_hash` using the wrapped value's `Eq` and `Hash`.
+    pub fn hashable(inner: T) -> ImplContextBox<T> {
+        ImplContextBox {
+            eq: Some(|left, right| left == right),
+            hash: Some(|inner, mut state| inner.hash(&mut state)),
+            inner,
+        }
     }
 }
 
@@ -147,13 +199,36 @@ impl<T: Any + Clone + Send + SpanReferenceAware> ImplContext for ImplContextBox<
 
     fn clone(&self) -> Box<dyn ImplContext> {
         Box::new(ImplContextBox {
-            inner: self.inner.clone()
+            eq: self.eq,
+            hash: self.hash,
+            inner: self.inner.clone(),
         })
     }
 
     fn reference_span(&mut self, reference: &SpanReference) {
         self.inner.reference_span(reference);
     }
+
+    fn impl_eq(&self, other: &dyn ImplContext) -> bool {
+        match (self.eq, other.impl_context().downcast_ref::<T>()) {
+            (Some(eq), Some(other)) => eq(&self.inner, other),
+            _ => false,
+        }
+    }
+
+    fn impl_hash(&self, state: &mut dyn Hasher) {
+        if let Some(hash) = self.hash {
+            hash(&self.inner, state);
+        }
+    }
+
+    fn span_id(&self) -> Option<String> {
+        self.inner.span_id()
+    }
+
+    fn trace_id(&self) -> Option<String> {
+        self.inner.trace_id()
+    }
 }
 
 
@@ -163,6 +238,16 @@ impl<T: Any + Clone + Send + SpanReferenceAware> ImplContext for ImplContextBox<
 pub trait SpanReferenceAware {
     /// See `ImplContext::reference_span`
     fn reference_span(&mut self, reference: &SpanReference);
+
+    /// See `ImplContext::span_id`
+    fn span_id(&self) -> Option<String> {
+        None
+    }
+
+    /// See `ImplContext::trace_id`
+    fn trace_id(&self) -> Option<String> {
+        None
+    }
 }
 
 
@@ -173,7 +258,7 @@ mod tests {
     use super::ImplContextBox;
     use super::SpanReferenceAware;
 
-    #[derive(Debug, Clone)]
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
     struct TestContext {
         pub id: String
     }
@@ -197,6 +282,24 @@ mod tests {
         }
     }
 
+    #[test]
+    fn compare_contexts() {
+        let context = ImplContextBox::hashable(TestContext { id: "ABC".to_owned() });
+        let same = ImplContextBox::hashable(TestContext { id: "ABC".to_owned() });
+        let other = ImplContextBox::hashable(TestContext { id: "DEF".to_owned() });
+        assert!(context.impl_eq(&same));
+        assert!(context.impl_eq(&*context.clone()));
+        assert!(!context.impl_eq(&other));
+    }
+
+    #[test]
+    fn compare_contexts_not_hashable() {
+        let context = ImplContextBox::new(TestContext { id: "ABC".to_owned() });
+        let same = ImplContextBox::hashable(TestContext { id: "ABC".to_owned() });
+        assert!(!context.impl_eq(&same));
+        assert!(!context.impl_eq(&context));
+    }
+
     #[test]
     fn unwrap_context() {
         let context = ImplContextBox::new(TestContext { id: "ABC".to_owned() });
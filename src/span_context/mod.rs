@@ -3,9 +3,17 @@ use std::boxed::Box;
 use std::collections::HashMap;
 use std::collections::hash_map::Iter;
 use std::fmt;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::ptr;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::SystemTime;
 
+mod baggage;
 mod impl_context;
 
+pub use self::baggage::BaggageRestrictions;
 pub use self::impl_context::ImplContext;
 pub use self::impl_context::ImplContextBox;
 pub use self::impl_context::SpanReferenceAware;
@@ -40,6 +48,8 @@ use super::SpanReference;
 /// extern crate crossbeam_channel;
 /// extern crate opentracingrust;
 ///
+/// use std::sync::Arc;
+///
 /// use crossbeam_channel::unbounded;
 ///
 /// use opentracingrust::ImplContextBox;
@@ -70,7 +80,7 @@ use super::SpanReference;
 ///         "test",
 ///         SpanContext::new(ImplContextBox::new(Context {})),
 ///         StartOptions::default().child_of(context.clone()),
-///         sender
+///         Arc::new(sender)
 ///     );
 ///     span.set_baggage_item("key2", "value2");
 ///
@@ -90,7 +100,11 @@ use super::SpanReference;
 /// ```
 pub struct SpanContext {
     baggage: HashMap<String, String>,
+    deadline: Option<SystemTime>,
     inner: Box<dyn ImplContext>,
+    restrictions: Option<Arc<BaggageRestrictions>>,
+    sampled: Option<bool>,
+    trace_budget: Option<u32>,
 }
 
 impl SpanContext {
@@ -101,7 +115,11 @@ impl SpanContext {
     pub fn new<Context: ImplContext + 'static>(inner: Context) -> SpanContext {
         SpanContext {
             inner: Box::new(inner),
-            baggage: HashMap::new()
+            baggage: HashMap::new(),
+            deadline: None,
+            restrictions: None,
+            sampled: None,
+            trace_budget: None,
         }
     }
 }
@@ -110,7 +128,11 @@ impl Clone for SpanContext {
     fn clone(&self) -> Self {
         SpanContext {
             inner: self.inner.clone(),
-            baggage: self.baggage.clone()
+            baggage: self.baggage.clone(),
+            deadline: self.deadline,
+            restrictions: self.restrictions.clone(),
+            sampled: self.sampled,
+            trace_budget: self.trace_budget,
         }
     }
 }
@@ -124,6 +146,34 @@ impl fmt::Debug for SpanContext {
     }
 }
 
+/// `SpanContext`s are equal if they hold the same baggage items and equal `ImplContext`s.
+///
+/// Comparison of the tracer details is delegated to `ImplContext::impl_eq`:
+/// contexts are only ever equal to other contexts for `ImplContext`s that opt into
+/// comparisons (for example by using `ImplContextBox::hashable`).
+/// For other `ImplContext`s a `SpanContext` is only equal to itself (not to its clones).
+impl PartialEq for SpanContext {
+    fn eq(&self, other: &SpanContext) -> bool {
+        if ptr::eq(self, other) {
+            return true;
+        }
+        self.baggage == other.baggage &&
+            self.inner.impl_eq(&*other.inner) &&
+            other.inner.impl_eq(&*self.inner)
+    }
+}
+
+impl Eq for SpanContext {}
+
+impl Hash for SpanContext {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let mut items: Vec<(&String, &String)> = self.baggage.iter().collect();
+        items.sort();
+        items.hash(state);
+        self.inner.impl_hash(state);
+    }
+}
+
 impl SpanContext {
     /// Attempt to access the `SpanContext`'s tracer details.
     ///
@@ -140,10 +190,24 @@ impl SpanContext {
         self.inner.impl_context().downcast_ref::<T>()
     }
 
+    /// Returns the ID of the span, formatted for humans, if the tracer exposes it.
+    ///
+    /// IDs are intended for debugging output: use `Tracer::inject` to propagate contexts.
+    pub fn span_id(&self) -> Option<String> {
+        self.inner.span_id()
+    }
+
+    /// Returns the ID of the trace, formatted for humans, if the tracer exposes it.
+    ///
+    /// IDs are intended for debugging output: use `Tracer::inject` to propagate contexts.
+    pub fn trace_id(&self) -> Option<String> {
+        self.inner.trace_id()
+    }
+
     /// Iterates over baggage items.
     ///
     /// The method returns an iterator over `(key, value)` tuples.
-    pub fn baggage_items(&self) -> Iter<String, String> {
+    pub fn baggage_items(&self) -> Iter<'_, String, String> {
         self.baggage.iter()
     }
 
@@ -164,6 +228,77 @@ impl SpanContext {
         self.inner.reference_span(reference);
     }
 
+    /// Returns the deadline of the request being traced, if one was set or extracted.
+    ///
+    /// Deadlines are almost always propagated along with the trace context so they
+    /// are carried by the `SpanContext` for services to honour.
+    /// The deadline does not change how spans are created or reported.
+    ///
+    /// Spans inherit the deadline from the first referenced context that carries one
+    /// and `Tracer::inject`/`Tracer::extract` propagate it, with millisecond precision,
+    /// in the `HttpHeaders` and `TextMap` formats.
+    pub fn deadline(&self) -> Option<SystemTime> {
+        self.deadline
+    }
+
+    /// Returns the time left before the deadline, if a deadline is set.
+    ///
+    /// Expired deadlines have `Duration::ZERO` time left.
+    pub fn remaining(&self) -> Option<Duration> {
+        self.deadline.map(|deadline| {
+            deadline.duration_since(SystemTime::now()).unwrap_or(Duration::ZERO)
+        })
+    }
+
+    /// Sets or clears the deadline for this context.
+    ///
+    /// See `SpanContext::deadline` for details.
+    pub fn set_deadline(&mut self, deadline: Option<SystemTime>) {
+        self.deadline = deadline;
+    }
+
+    /// Returns the sampling decision for the trace, if one was made or extracted.
+    ///
+    /// The decision is made by the `Sampler` configured on the `Tracer` when a span
+    /// without a decision is started (see `Tracer::with_sampler`).
+    /// Spans of unsampled traces are not recorded: their tags and logs are dropped
+    /// and they are not reported when finished (see `Span::is_recording`).
+    ///
+    /// Spans inherit the decision from the first referenced context that carries one
+    /// and `Tracer::inject`/`Tracer::extract` propagate it in the `HttpHeaders`
+    /// and `TextMap` formats.
+    pub fn sampled(&self) -> Option<bool> {
+        self.sampled
+    }
+
+    /// Sets or clears the sampling decision for this context.
+    ///
+    /// See `SpanContext::sampled` for details.
+    pub fn set_sampled(&mut self, sampled: Option<bool>) {
+        self.sampled = sampled;
+    }
+
+    /// Sets or clears the trace budget hint for this context.
+    ///
+    /// See `SpanContext::trace_budget` for details.
+    pub fn set_trace_budget(&mut self, budget: Option<u32>) {
+        self.trace_budget = budget;
+    }
+
+    /// Returns the trace budget hint, if one was set or extracted.
+    ///
+    /// The trace budget is an optional hint of how many more spans upstream services
+    /// expect the trace to record: samplers in downstream services can consume it to
+    /// avoid over-sampling traces that fan-out heavily.
+    /// The hint does not change how spans are created or reported.
+    ///
+    /// Spans inherit the hint from the first referenced context that carries one
+    /// and `Tracer::inject`/`Tracer::extract` propagate it in the `HttpHeaders`
+    /// and `TextMap` formats.
+    pub fn trace_budget(&self) -> Option<u32> {
+        self.trace_budget
+    }
+
     /// Adds or updates the baggage items with the given key/value pair.
     ///
     /// Baggage items are forwarded to `Span`s that reference this `SpanContext`
@@ -171,22 +306,68 @@ impl SpanContext {
     ///
     /// Baggage items are **NOT** propagated backwards to
     /// `Span`s that reference this `SpanContext`.
+    ///
+    /// If the context is subject to `BaggageRestrictions` the item may be
+    /// dropped or its value truncated (see `BaggageRestrictions`).
     pub fn set_baggage_item(&mut self, key: String, value: String) {
-        self.baggage.insert(key, value);
+        let item = match self.restrictions {
+            None => Some((key, value)),
+            Some(ref restrictions) => restrictions.restrict(&self.baggage, key, value),
+        };
+        if let Some((key, value)) = item {
+            self.baggage.insert(key, value);
+        }
+    }
+
+    /// Removes a baggage item, returning its value if it was set.
+    pub fn remove_baggage_item(&mut self, key: &str) -> Option<String> {
+        self.baggage.remove(key)
+    }
+
+    /// Retains only the baggage items for which `keep` returns `true`.
+    ///
+    /// The closure can also modify the value of retained items.
+    pub(crate) fn retain_baggage<F: FnMut(&String, &mut String) -> bool>(&mut self, keep: F) {
+        self.baggage.retain(keep);
+    }
+
+    /// Checks if this context and `other` identify the same span.
+    ///
+    /// Baggage items are ignored and contexts whose `ImplContext` does not
+    /// support comparisons never identify the same span.
+    pub(crate) fn same_span(&self, other: &SpanContext) -> bool {
+        self.inner.impl_eq(&*other.inner)
+    }
+
+    /// Subjects this context to the given `BaggageRestrictions`.
+    ///
+    /// Baggage items already in the context are checked against
+    /// the restrictions in key order and dropped or truncated as needed.
+    pub(crate) fn restrict_baggage(&mut self, restrictions: Arc<BaggageRestrictions>) {
+        let mut items: Vec<(String, String)> = self.baggage.drain().collect();
+        items.sort();
+        self.restrictions = Some(restrictions);
+        for (key, value) in items {
+            self.set_baggage_item(key, value);
+        }
     }
 }
 
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashSet;
+    use std::sync::Arc;
+
     use super::super::SpanReference;
     use super::impl_context::SpanReferenceAware;
 
+    use super::BaggageRestrictions;
     use super::ImplContextBox;
     use super::SpanContext;
 
 
-    #[derive(Clone)]
+    #[derive(Clone, PartialEq, Eq, Hash)]
     struct TestContext {
         pub id: String
     }
@@ -205,6 +386,50 @@ mod tests {
         assert_eq!(format, "SpanContext { inner: Box<ImplContext>, baggage: {} }");
     }
 
+    #[test]
+    fn compare_contexts() {
+        let mut context = SpanContext::new(
+            ImplContextBox::hashable(TestContext{id: "A".to_owned()})
+        );
+        context.set_baggage_item(String::from("key"), String::from("value"));
+        let mut other = context.clone();
+        assert!(context == other);
+        other.set_baggage_item(String::from("key"), String::from("other"));
+        assert!(context != other);
+    }
+
+    #[test]
+    fn compare_contexts_not_hashable() {
+        let context = SpanContext::new(ImplContextBox::new(TestContext{id: "A".to_owned()}));
+        let same = &context;
+        assert!(context == *same);
+        assert!(context != context.clone());
+    }
+
+    #[test]
+    fn compare_contexts_mixed_hashable() {
+        let hashable = SpanContext::new(
+            ImplContextBox::hashable(TestContext{id: "A".to_owned()})
+        );
+        let other = SpanContext::new(ImplContextBox::new(TestContext{id: "A".to_owned()}));
+        assert!(hashable != other);
+        assert!(other != hashable);
+    }
+
+    #[test]
+    fn contexts_as_set_items() {
+        let mut contexts = HashSet::new();
+        for id in &["A", "B", "A"] {
+            let mut context = SpanContext::new(
+                ImplContextBox::hashable(TestContext{id: id.to_string()})
+            );
+            context.set_baggage_item(String::from("a"), String::from("1"));
+            context.set_baggage_item(String::from("b"), String::from("2"));
+            contexts.insert(context);
+        }
+        assert_eq!(2, contexts.len());
+    }
+
     #[test]
     fn debug_formatting() {
         let mut context = SpanContext::new(
@@ -228,6 +453,21 @@ mod tests {
         }
     }
 
+    #[test]
+    fn restrict_existing_baggage() {
+        let inner = ImplContextBox::new(TestContext{id: "some-id".to_owned()});
+        let mut context = SpanContext::new(inner);
+        context.set_baggage_item(String::from("a"), String::from("value"));
+        context.set_baggage_item(String::from("b"), String::from("value"));
+        context.restrict_baggage(Arc::new(BaggageRestrictions::default().max_items(1)));
+        context.set_baggage_item(String::from("c"), String::from("value"));
+        let baggage: Vec<(String, String)> = context.baggage_items()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        let expected = vec![(String::from("a"), String::from("value"))];
+        assert_eq!(baggage, expected);
+    }
+
     #[test]
     fn set_baggage_item() {
         let inner = ImplContextBox::new(TestContext{id: "some-id".to_owned()});
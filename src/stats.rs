@@ -0,0 +1,38 @@
+use std::sync::Arc;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+
+/// Shared handle to the statistics of a `Tracer` (see `Tracer::stats`).
+///
+/// Handles are cheap to clone and can be moved to other threads, such as
+/// reporters (see `utils::ReporterThread::new_with_stats`), without keeping
+/// the `Tracer` or its spans alive.
+#[derive(Clone, Debug, Default)]
+pub struct TracerStats {
+    in_flight: Option<Arc<AtomicUsize>>,
+    started: Arc<AtomicU64>,
+}
+
+impl TracerStats {
+    /// Returns the number of spans in flight if a limit is set with `Tracer::with_max_in_flight`.
+    pub fn in_flight_spans(&self) -> Option<usize> {
+        self.in_flight.as_ref().map(|count| count.load(Ordering::Relaxed))
+    }
+
+    /// Returns the number of spans started by the tracer.
+    pub fn spans_started(&self) -> u64 {
+        self.started.load(Ordering::Relaxed)
+    }
+
+    /// Counts a new span started by the tracer.
+    pub(crate) fn span_started(&self) {
+        self.started.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Tracks the number of spans in flight with the given counter.
+    pub(crate) fn track_in_flight(&mut self, count: Arc<AtomicUsize>) {
+        self.in_flight = Some(count);
+    }
+}
@@ -0,0 +1,66 @@
+//! Utilities for tests of traced code and of reporters.
+use std::time::Duration;
+
+use super::FinishedSpan;
+use super::SpanReceiver;
+
+
+/// Collects spans from the `receiver` until no span is received for `timeout`.
+///
+/// Spans are returned in the order they are received.
+/// Collection also stops once the channel is empty and all the senders are dropped.
+///
+/// Tests can use this to wait for spans finished by other threads without sleeping
+/// for a fixed (and often too short or too long) time.
+///
+/// # Examples
+///
+/// ```
+/// extern crate opentracingrust;
+///
+/// use std::thread;
+/// use std::time::Duration;
+///
+/// use opentracingrust::testing::drain_all;
+/// use opentracingrust::tracers::NoopTracer;
+///
+///
+/// fn main() {
+///     let (tracer, receiver) = NoopTracer::new();
+///     let worker = thread::spawn(move || tracer.span("work").finish().unwrap());
+///     let spans = drain_all(&receiver, Duration::from_millis(100));
+///     worker.join().unwrap();
+///     assert_eq!(1, spans.len());
+/// }
+/// ```
+pub fn drain_all(receiver: &SpanReceiver, timeout: Duration) -> Vec<FinishedSpan> {
+    let mut spans = Vec::new();
+    while let Ok(span) = receiver.recv_timeout(timeout) {
+        spans.push(span);
+    }
+    spans
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use std::time::Duration;
+
+    use super::super::tracers::NoopTracer;
+    use super::drain_all;
+
+    #[test]
+    fn collects_until_quiet() {
+        let (tracer, receiver) = NoopTracer::new();
+        let worker = thread::spawn(move || {
+            for _ in 0..3 {
+                thread::sleep(Duration::from_millis(10));
+                tracer.span("work").finish().unwrap();
+            }
+        });
+        let spans = drain_all(&receiver, Duration::from_millis(500));
+        worker.join().unwrap();
+        assert_eq!(3, spans.len());
+    }
+}
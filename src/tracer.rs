@@ -1,10 +1,34 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::sync::RwLock;
+use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use super::AutoFinishingSpan;
+use super::BaggageRestrictions;
+use super::Clock;
+use super::Error;
 use super::ExtractFormat;
+use super::HeaderCase;
+use super::InFlightPolicy;
 use super::InjectFormat;
+use super::Log;
+use super::MapCarrier;
+use super::MapFormat;
 
 use super::Result;
 use super::Span;
 use super::SpanContext;
+use super::SpanLimits;
+use super::SpanReference;
 use super::StartOptions;
+use super::TracerStats;
+use super::WarningSender;
+use super::sampling::Sampler;
+use super::span::in_flight::InFlightLimit;
 
 
 /// Smallest set of operations that a concrete tracer must implement.
@@ -38,16 +62,85 @@ use super::StartOptions;
 ///   * Example `1-custom-tracer.rs`, which implements an in-memory tracer.
 pub trait TracerInterface : Send + Sync {
     /// Attempt to extract a SpanContext from a carrier.
+    #[allow(clippy::result_large_err)]
     fn extract(&self, fmt: ExtractFormat) -> Result<Option<SpanContext>>;
 
     /// Inject tracing information into a carrier.
+    #[allow(clippy::result_large_err)]
     fn inject(&self, context: &SpanContext, fmt: InjectFormat) -> Result<()>;
 
     /// Create a new `Span` with the given operation name and starting options.
-    fn span(&self, name: &str, options: StartOptions) -> Span;
+    ///
+    /// The operation name is shared so tracers can pass it on to `Span::new`
+    /// without allocating a new string for every span.
+    fn span(&self, name: Arc<str>, options: StartOptions) -> Span;
+
+    /// Create the `SpanContext` of a span that references the given contexts.
+    ///
+    /// No `Span` is started: tracers that wrap other tracers (like the `MultiTracer`)
+    /// use this to build a context of the wrapped tracer for each span they start.
+    /// The default implementation takes the context of a `Span` created with
+    /// `TracerInterface::span`, tracers should override it to skip creating the span.
+    fn context(&self, references: &[SpanReference]) -> SpanContext {
+        let options = references.iter().cloned()
+            .fold(StartOptions::default(), StartOptions::reference_span);
+        self.span(Arc::from(""), options).context().clone()
+    }
+
+    /// Deliver any span buffered by the tracer.
+    ///
+    /// Tracers that buffer spans (or connections to the distributed tracer) should
+    /// block until buffered data is delivered. The default implementation does nothing.
+    #[allow(clippy::result_large_err)]
+    fn flush(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Flush buffered spans and release resources held by the tracer.
+    ///
+    /// The tracer is not used after it is closed.
+    /// The default implementation flushes the tracer.
+    #[allow(clippy::result_large_err)]
+    fn close(&self) -> Result<()> {
+        self.flush()
+    }
 }
 
 
+/// Maximum number of operation names cached by a `Tracer`.
+///
+/// Names requested once the cache is full are still returned but not cached.
+/// This prevents unbounded growth for applications that generate operation names dynamically.
+const OPERATION_NAMES_CACHE_SIZE: usize = 1024;
+
+/// Tag set on spans started by `Tracer::extract_or_new` when extraction fails.
+const EXTRACT_ERROR_TAG: &str = "trace.extract_error";
+
+/// Carrier key used to propagate `SpanContext::deadline`s.
+///
+/// Deadlines are encoded as milliseconds since the UNIX epoch.
+const DEADLINE_KEY: &str = "ot-deadline";
+
+/// Carrier key used to propagate `SpanContext::sampled` decisions.
+const SAMPLED_KEY: &str = "ot-sampled";
+
+/// Tag set on spans for which the `Sampler` made a sampling decision.
+const SAMPLING_PRIORITY_TAG: &str = "sampling.priority";
+
+/// Carrier key used to propagate `SpanContext::trace_budget` hints.
+const TRACE_BUDGET_KEY: &str = "ot-trace-budget";
+
+
+/// Closure called with every `SpanContext` extracted by a `Tracer` (see `Tracer::on_extract`).
+type ExtractTransform = Box<dyn Fn(&mut SpanContext) + Send + Sync>;
+
+/// Closure called when a `Tracer` is flushed or closed (see `Tracer::on_flush`).
+type FlushHook = Box<dyn Fn() + Send + Sync>;
+
+/// Closure called with every `Span` started by a `Tracer` (see `Tracer::on_span_start`).
+type SpanStartHook = Box<dyn Fn(&mut Span) + Send + Sync>;
+
+
 /// The library users interface to tracing.
 ///
 /// This structure is the focus point for clients to use in combination with `SpanContext`.
@@ -55,16 +148,232 @@ pub trait TracerInterface : Send + Sync {
 ///
 /// The `Tracer` structure also provides some utility methods to make common operations easier.
 pub struct Tracer {
-    tracer: Box<dyn TracerInterface>
+    baggage_restrictions: Option<Arc<BaggageRestrictions>>,
+    clock: Option<Arc<dyn Clock>>,
+    extract_transforms: Vec<ExtractTransform>,
+    flush_hooks: Vec<FlushHook>,
+    header_case: HeaderCase,
+    in_flight: Option<InFlightLimit>,
+    operation_names: RwLock<HashSet<Arc<str>>>,
+    sampler: Option<Box<dyn Sampler>>,
+    span_limits: SpanLimits,
+    span_start_hooks: Vec<SpanStartHook>,
+    stats: TracerStats,
+    tracer: Box<dyn TracerInterface>,
+    warnings: Option<WarningSender>,
 }
 
 impl Tracer {
     /// Creates a new `Tracer` for a concrete tracer.
     pub fn new<T: TracerInterface + 'static>(tracer: T) -> Tracer {
         Tracer {
-            tracer: Box::new(tracer)
+            baggage_restrictions: None,
+            clock: None,
+            extract_transforms: Vec::new(),
+            flush_hooks: Vec::new(),
+            header_case: HeaderCase::default(),
+            in_flight: None,
+            operation_names: RwLock::new(HashSet::new()),
+            sampler: None,
+            span_limits: SpanLimits::default(),
+            span_start_hooks: Vec::new(),
+            stats: TracerStats::default(),
+            tracer: Box::new(tracer),
+            warnings: None,
         }
     }
+
+    /// Registers a transform applied to every `SpanContext` extracted by this tracer.
+    ///
+    /// Transforms run after the concrete tracer decoded the context from the carrier,
+    /// and before `BaggageRestrictions` are applied, in the order they are registered.
+    /// They centralise migration shims such as renaming legacy baggage keys
+    /// or removing deprecated ones.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate opentracingrust;
+    ///
+    /// use std::collections::HashMap;
+    ///
+    /// use opentracingrust::ExtractFormat;
+    /// use opentracingrust::tracers::FileTracer;
+    ///
+    ///
+    /// fn main() {
+    ///     let (tracer, _) = FileTracer::new();
+    ///     let tracer = tracer.on_extract(|context| {
+    ///         if let Some(user) = context.remove_baggage_item("Baggage-user_id") {
+    ///             context.set_baggage_item(String::from("Baggage-user"), user);
+    ///         }
+    ///     });
+    ///
+    ///     let mut headers: HashMap<String, String> = HashMap::new();
+    ///     headers.insert(String::from("TraceID"), String::from("123"));
+    ///     headers.insert(String::from("SpanID"), String::from("456"));
+    ///     headers.insert(String::from("Baggage-user_id"), String::from("42"));
+    ///     let context = tracer.extract(ExtractFormat::HttpHeaders(Box::new(&headers)))
+    ///         .unwrap().unwrap();
+    ///     assert_eq!("42", context.get_baggage_item("Baggage-user").unwrap());
+    /// }
+    /// ```
+    pub fn on_extract<F>(mut self, transform: F) -> Tracer
+        where F: Fn(&mut SpanContext) + Send + Sync + 'static
+    {
+        self.extract_transforms.push(Box::new(transform));
+        self
+    }
+
+    /// Registers a closure called when this tracer is flushed or closed.
+    ///
+    /// Hooks are called in the order they are registered, after the concrete tracer
+    /// is flushed, and should block until spans already sent to the `SpanReceiver`
+    /// are delivered. This connects `Tracer::flush` to the reporter draining the receiver.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate opentracingrust;
+    ///
+    /// use opentracingrust::tracers::NoopTracer;
+    /// use opentracingrust::utils::ReporterThread;
+    ///
+    ///
+    /// fn main() {
+    ///     let (tracer, receiver) = NoopTracer::new();
+    ///     let reporter = ReporterThread::new(receiver, |span| NoopTracer::report(span));
+    ///     let flusher = reporter.flusher();
+    ///     let tracer = tracer.on_flush(move || flusher.flush());
+    ///
+    ///     tracer.span("request").finish().unwrap();
+    ///     tracer.flush().unwrap();
+    /// }
+    /// ```
+    pub fn on_flush<F>(mut self, hook: F) -> Tracer
+        where F: Fn() + Send + Sync + 'static
+    {
+        self.flush_hooks.push(Box::new(hook));
+        self
+    }
+
+    /// Registers a closure called with every new `Span` started by this tracer.
+    ///
+    /// Hooks can populate spans with data the code starting them does not have access to,
+    /// for example tagging spans with the current request id stored in a thread local.
+    /// Hooks are called in the order they are registered, after the span is sampled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate opentracingrust;
+    ///
+    /// use opentracingrust::tracers::NoopTracer;
+    ///
+    ///
+    /// fn main() {
+    ///     let (tracer, _) = NoopTracer::new();
+    ///     let tracer = tracer.on_span_start(|span| span.tag("service.version", "1.2.3"));
+    ///     let span = tracer.span("request");
+    ///     // ... snip ...
+    /// }
+    /// ```
+    pub fn on_span_start<F>(mut self, hook: F) -> Tracer
+        where F: Fn(&mut Span) + Send + Sync + 'static
+    {
+        self.span_start_hooks.push(Box::new(hook));
+        self
+    }
+
+    /// Sets the `BaggageRestrictions` for spans created and contexts extracted by this tracer.
+    pub fn with_baggage_restrictions(mut self, restrictions: BaggageRestrictions) -> Tracer {
+        self.baggage_restrictions = Some(Arc::new(restrictions));
+        self
+    }
+
+    /// Sets the clock used to timestamp spans created by this tracer.
+    ///
+    /// Spans read the `SystemClock` by default.
+    /// Explicitly provided timestamps (start, finish and log times) are not altered.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Tracer {
+        self.clock = Some(clock);
+        self
+    }
+
+    /// Sets the case of header names injected into `HttpHeaders` carriers.
+    ///
+    /// Header names are preserved as set by the concrete tracer by default.
+    /// Baggage keys always keep their case: only the prefix the concrete tracer
+    /// adds to them is recased.
+    /// Extraction from `HttpHeaders` ignores the case of header names either way.
+    pub fn with_header_case(mut self, case: HeaderCase) -> Tracer {
+        self.header_case = case;
+        self
+    }
+
+    /// Limits the number of spans created by this tracer that are in flight at once.
+    ///
+    /// Spans are in flight from the moment they are created until they are finished
+    /// or dropped.
+    /// Spans created while `max` spans are already in flight are still returned
+    /// but are handled according to the `InFlightPolicy`.
+    ///
+    /// Limiting in-flight spans protects long-lived processes from instrumentation
+    /// that creates spans and never finishes them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate opentracingrust;
+    ///
+    /// use opentracingrust::InFlightPolicy;
+    /// use opentracingrust::tracers::NoopTracer;
+    ///
+    ///
+    /// fn main() {
+    ///     let (tracer, _receiver) = NoopTracer::new();
+    ///     let tracer = tracer.with_max_in_flight(1, InFlightPolicy::RefuseRecording);
+    ///     let first = tracer.span("first");
+    ///     let second = tracer.span("second");
+    ///     assert!(!second.is_recording());
+    ///     assert_eq!(Some(2), tracer.in_flight_spans());
+    /// }
+    /// ```
+    pub fn with_max_in_flight(mut self, max: usize, policy: InFlightPolicy) -> Tracer {
+        let in_flight = InFlightLimit::new(max, policy);
+        self.stats.track_in_flight(in_flight.counter());
+        self.in_flight = Some(in_flight);
+        self
+    }
+
+    /// Sets the `Sampler` deciding which traces are sampled.
+    ///
+    /// The sampler is evaluated when a span is started without inheriting a sampling
+    /// decision from the contexts it references (usually the root span of a trace).
+    /// The decision is stored in the span context (see `SpanContext::sampled`).
+    /// Sampled spans are tagged with `sampling.priority` set to `1` while
+    /// unsampled spans are not recorded at all.
+    pub fn with_sampler<S: Sampler + 'static>(mut self, sampler: S) -> Tracer {
+        self.sampler = Some(Box::new(sampler));
+        self
+    }
+
+    /// Sets the `SpanLimits` applied to all spans created by this tracer.
+    ///
+    /// Individual spans can override these limits with `StartOptions::span_limits`.
+    pub fn with_span_limits(mut self, limits: SpanLimits) -> Tracer {
+        self.span_limits = limits;
+        self
+    }
+
+    /// Reports `SpanWarning`s about spans created by this tracer to the given sender.
+    ///
+    /// Warnings are not reported by default.
+    /// Create a channel with `warning_channel`.
+    pub fn with_warnings(mut self, sender: WarningSender) -> Tracer {
+        self.warnings = Some(sender);
+        self
+    }
 }
 
 impl Tracer {
@@ -75,28 +384,653 @@ impl Tracer {
     ///
     /// If the method fails to extract a context because the carrier fails or because
     /// the tracing information is incorrectly formatted an `Error` is returned.
+    ///
+    /// For the `HttpHeaders` and `TextMap` formats the deadline, sampling decision and
+    /// trace budget hint (see `SpanContext::deadline`, `SpanContext::sampled` and
+    /// `SpanContext::trace_budget`) are also extracted from the carrier.
+    /// These are optional hints: invalid values are ignored rather than failing the extraction.
+    #[allow(clippy::result_large_err)]
     pub fn extract(&self, fmt: ExtractFormat) -> Result<Option<SpanContext>> {
-        self.tracer.extract(fmt)
+        let fields = match fmt {
+            ExtractFormat::HttpHeaders(ref carrier) => {
+                PropagatedFields::extract(&***carrier, true)
+            },
+            ExtractFormat::TextMap(ref carrier) => PropagatedFields::extract(&***carrier, false),
+            ExtractFormat::Binary(_) |
+            ExtractFormat::Custom(..) => PropagatedFields::default(),
+        };
+        let mut context = self.tracer.extract(fmt)?;
+        if let Some(context) = &mut context {
+            fields.apply(context);
+            for transform in &self.extract_transforms {
+                transform(context);
+            }
+            if let Some(restrictions) = &self.baggage_restrictions {
+                context.restrict_baggage(Arc::clone(restrictions));
+            }
+        }
+        Ok(context)
+    }
+
+    /// Attempt to extract a `SpanContext` from each of the given formats in order.
+    ///
+    /// Useful for servers accepting requests from clients that propagate contexts
+    /// in different ways (for example with different sets of headers).
+    ///
+    /// The first extracted context is returned and the remaining formats are skipped.
+    /// Formats that fail to extract a context are skipped as well but, if no context
+    /// is found, the error from the first failing format is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate opentracingrust;
+    ///
+    /// use std::collections::HashMap;
+    ///
+    /// use opentracingrust::ExtractFormat;
+    /// use opentracingrust::tracers::FileTracer;
+    ///
+    ///
+    /// fn main() {
+    ///     let (tracer, _) = FileTracer::new();
+    ///     let headers: HashMap<String, String> = HashMap::new();
+    ///     let mut legacy: HashMap<String, String> = HashMap::new();
+    ///     legacy.insert(String::from("TraceID"), String::from("123"));
+    ///     legacy.insert(String::from("SpanID"), String::from("456"));
+    ///
+    ///     let context = tracer.extract_first(vec![
+    ///         ExtractFormat::HttpHeaders(Box::new(&headers)),
+    ///         ExtractFormat::HttpHeaders(Box::new(&legacy)),
+    ///     ]).unwrap();
+    ///     assert!(context.is_some());
+    /// }
+    /// ```
+    #[allow(clippy::result_large_err)]
+    pub fn extract_first<'a, I>(&self, formats: I) -> Result<Option<SpanContext>>
+        where I: IntoIterator<Item = ExtractFormat<'a>>
+    {
+        let mut first_error = None;
+        for fmt in formats {
+            match self.extract(fmt) {
+                Ok(Some(context)) => return Ok(Some(context)),
+                Ok(None) => (),
+                Err(error) => {
+                    first_error.get_or_insert(error);
+                }
+            }
+        }
+        match first_error {
+            Some(error) => Err(error),
+            None => Ok(None),
+        }
+    }
+
+    /// Extracts the caller's context and starts a new `Span` for the operation.
+    ///
+    /// Same as `Tracer::extract_or_new_with_options` with default starting options.
+    pub fn extract_or_new<N: IntoOperationName>(&self, fmt: ExtractFormat, name: N) -> Span {
+        self.extract_or_new_with_options(fmt, name, StartOptions::default())
+    }
+
+    /// Extracts the caller's context and starts a new `Span` for the operation.
+    ///
+    /// The span is a child of the extracted context or a root span if the carrier
+    /// has no context.
+    /// If extraction fails, because the carrier holds malformed data, the span is started
+    /// as a root span tagged with `trace.extract_error` and the error is logged on it.
+    /// The span is not tagged with `error`: a malformed header sent by the caller does not
+    /// mean the operation failed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate opentracingrust;
+    ///
+    /// use std::collections::HashMap;
+    ///
+    /// use opentracingrust::ExtractFormat;
+    /// use opentracingrust::tracers::FileTracer;
+    ///
+    ///
+    /// fn main() {
+    ///     let (tracer, _) = FileTracer::new();
+    ///     let mut headers: HashMap<String, String> = HashMap::new();
+    ///     headers.insert(String::from("TraceID"), String::from("not-a-number"));
+    ///
+    ///     let format = ExtractFormat::HttpHeaders(Box::new(&headers));
+    ///     let span = tracer.extract_or_new(format, "request");
+    ///     assert!(span.references().is_empty());
+    ///     // ... snip ...
+    /// }
+    /// ```
+    pub fn extract_or_new_with_options<N: IntoOperationName>(
+        &self, fmt: ExtractFormat, name: N, mut options: StartOptions
+    ) -> Span {
+        let error = match self.extract(fmt) {
+            Ok(Some(parent)) => {
+                options = options.child_of(parent);
+                None
+            },
+            Ok(None) => None,
+            Err(error) => Some(error),
+        };
+        let mut span = self.span_with_options(name, options);
+        if let Some(error) = error {
+            span.tag(EXTRACT_ERROR_TAG, true);
+            span.log(Log::new()
+                .log("event", "error")
+                .log("message", error.to_string())
+                .log("error.object", format!("{:?}", error))
+            );
+        }
+        span
     }
 
     /// Inject tracing information into a carrier.
     ///
     /// If the method fails to inject the context because the carrier fails.
+    ///
+    /// For the `HttpHeaders` and `TextMap` formats the deadline, sampling decision and
+    /// trace budget hint (see `SpanContext::deadline`, `SpanContext::sampled` and
+    /// `SpanContext::trace_budget`) are also injected into the carrier.
+    #[allow(clippy::result_large_err)]
     pub fn inject(
-        &self, context: &SpanContext, fmt: InjectFormat
+        &self, context: &SpanContext, mut fmt: InjectFormat
     ) -> Result<()> {
+        let case = self.header_case;
+        match fmt {
+            InjectFormat::HttpHeaders(ref mut carrier) if case != HeaderCase::Preserve => {
+                let mut carrier = CasedCarrier {
+                    carrier: &mut ***carrier,
+                    case,
+                };
+                PropagatedFields::inject(context, &mut carrier);
+                let fmt = InjectFormat::HttpHeaders(Box::new(&mut carrier));
+                return self.tracer.inject(context, fmt);
+            },
+            InjectFormat::HttpHeaders(ref mut carrier) |
+            InjectFormat::TextMap(ref mut carrier) => {
+                PropagatedFields::inject(context, &mut ***carrier);
+            },
+            InjectFormat::Binary(_) |
+            InjectFormat::Custom(..) => (),
+        }
         self.tracer.inject(context, fmt)
     }
 
+    /// Delivers spans buffered by the concrete tracer.
+    ///
+    /// Applications should flush (or close) the tracer before exiting to guarantee that
+    /// spans are delivered.
+    /// Spans already sent to the tracer's `SpanReceiver` are delivered by the reporter:
+    /// register a hook with `Tracer::on_flush` (see `utils::ReporterThread::flusher`)
+    /// so flushing the tracer also waits for the reporter.
+    #[allow(clippy::result_large_err)]
+    pub fn flush(&self) -> Result<()> {
+        self.tracer.flush()?;
+        for hook in &self.flush_hooks {
+            hook();
+        }
+        Ok(())
+    }
+
+    /// Flushes the concrete tracer and releases its resources.
+    ///
+    /// Hooks registered with `Tracer::on_flush` are called once the tracer is closed.
+    /// Spans should not be started, injected, or extracted once the tracer is closed.
+    #[allow(clippy::result_large_err)]
+    pub fn close(&self) -> Result<()> {
+        self.tracer.close()?;
+        for hook in &self.flush_hooks {
+            hook();
+        }
+        Ok(())
+    }
+
+    /// Returns the key/value pairs that `Tracer::inject` would set on a carrier.
+    ///
+    /// The context is injected into an empty map and the items are returned sorted by key.
+    /// Useful to log outbound propagation while debugging and to check it in tests.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate opentracingrust;
+    ///
+    /// use opentracingrust::MapFormat;
+    /// use opentracingrust::tracers::FileTracer;
+    ///
+    ///
+    /// fn main() {
+    ///     let (tracer, _) = FileTracer::new();
+    ///     let span = tracer.span("request");
+    ///     let items = tracer.inject_preview(span.context(), MapFormat::HttpHeaders).unwrap();
+    ///     for (key, value) in items {
+    ///         println!("{}: {}", key, value);
+    ///     }
+    /// }
+    /// ```
+    #[allow(clippy::result_large_err)]
+    pub fn inject_preview(
+        &self, context: &SpanContext, format: MapFormat
+    ) -> Result<Vec<(String, String)>> {
+        let mut items: BTreeMap<String, String> = BTreeMap::new();
+        let fmt = match format {
+            MapFormat::HttpHeaders => InjectFormat::HttpHeaders(Box::new(&mut items)),
+            MapFormat::TextMap => InjectFormat::TextMap(Box::new(&mut items)),
+        };
+        self.inject(context, fmt)?;
+        Ok(items.into_iter().collect())
+    }
+
+    /// Returns a shared copy of the given operation name.
+    ///
+    /// The `Tracer` caches operation names so that hot paths creating many
+    /// identically named spans can share a single allocation.
+    /// Names passed to `Tracer::span` as `&str` are looked up automatically,
+    /// names built at runtime can be shared explicitly:
+    ///
+    /// ```
+    /// extern crate opentracingrust;
+    ///
+    /// use opentracingrust::tracers::NoopTracer;
+    ///
+    ///
+    /// fn main() {
+    ///     let (tracer, _) = NoopTracer::new();
+    ///     let queue = "orders";
+    ///     let name = tracer.operation_name(&format!("consume.{}", queue));
+    ///     for _ in 0..10 {
+    ///         let span = tracer.span(name.clone());
+    ///         // ... snip ...
+    ///     }
+    /// }
+    /// ```
+    pub fn operation_name(&self, name: &str) -> Arc<str> {
+        {
+            let names = self.operation_names.read()
+                .expect("Failed to read lock operation names cache");
+            if let Some(name) = names.get(name) {
+                return Arc::clone(name);
+            }
+        }
+        let mut names = self.operation_names.write()
+            .expect("Failed to write lock operation names cache");
+        if let Some(name) = names.get(name) {
+            return Arc::clone(name);
+        }
+        let name: Arc<str> = Arc::from(name);
+        if names.len() < OPERATION_NAMES_CACHE_SIZE {
+            names.insert(Arc::clone(&name));
+        }
+        name
+    }
+
+    /// Create the `SpanContext` of a span that references the given contexts.
+    ///
+    /// Unlike `Tracer::span_with_options` no `Span` is started so samplers, hooks
+    /// and the in-flight limit are not involved (see `TracerInterface::context`).
+    pub fn context(&self, references: &[SpanReference]) -> SpanContext {
+        self.tracer.context(references)
+    }
+
+    /// Parses a `SpanContext` from a string created by `Tracer::context_to_string`.
+    ///
+    /// Returns `Ok(None)` if the string does not carry tracing information
+    /// and an `Error` if the string is incorrectly formatted.
+    #[allow(clippy::result_large_err)]
+    pub fn context_from_string(&self, context: &str) -> Result<Option<SpanContext>> {
+        let mut carrier: BTreeMap<String, String> = BTreeMap::new();
+        for pair in context.split('&').filter(|pair| !pair.is_empty()) {
+            let mut parts = pair.splitn(2, '=');
+            let key = parts.next().unwrap_or_default();
+            let value = parts.next().ok_or_else(|| Error::Msg(
+                format!("Invalid context string item '{}'", pair)
+            ))?;
+            carrier.insert(unescape_context_item(key)?, unescape_context_item(value)?);
+        }
+        self.extract(ExtractFormat::TextMap(Box::new(&carrier)))
+    }
+
+    /// Renders a `SpanContext` into a single compact string.
+    ///
+    /// The string can be passed around where carriers are not available
+    /// (command line arguments, environment variables, job queues, ...)
+    /// and turned back into a `SpanContext` with `Tracer::context_from_string`.
+    ///
+    /// The context is rendered by injecting it in the `TextMap` format
+    /// and joining the `key=value` pairs with `&`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate opentracingrust;
+    ///
+    /// use opentracingrust::tracers::NoopTracer;
+    ///
+    ///
+    /// fn main() {
+    ///     let (tracer, _) = NoopTracer::new();
+    ///     let span = tracer.span("job");
+    ///     let context = tracer.context_to_string(span.context()).unwrap();
+    ///     // ... send the context to the job queue ...
+    ///     let parent = tracer.context_from_string(&context).unwrap();
+    /// }
+    /// ```
+    #[allow(clippy::result_large_err)]
+    pub fn context_to_string(&self, context: &SpanContext) -> Result<String> {
+        let mut carrier: BTreeMap<String, String> = BTreeMap::new();
+        self.inject(context, InjectFormat::TextMap(Box::new(&mut carrier)))?;
+        let items: Vec<String> = carrier.iter()
+            .map(|(key, value)| format!(
+                "{}={}", escape_context_item(key), escape_context_item(value)
+            ))
+            .collect();
+        Ok(items.join("&"))
+    }
+
+    /// Create a new `Span` that aggregates work from all the given contexts.
+    ///
+    /// The span has a `FollowsFrom` reference to each context, in the given order,
+    /// which is the usual shape of spans processing a batch of messages
+    /// coming from many traces.
+    /// The span has no `ChildOf` references so it is a root span (see `FinishedSpan::is_root`)
+    /// but the tracer decides which trace it belongs to: the bundled tracers move spans
+    /// into the trace of the contexts they follow so the span joins the trace of the
+    /// last context.
+    /// Reference the contexts with `SpanReference::Custom` links instead to keep the span
+    /// in a trace of its own.
+    ///
+    /// References in excess of the `SpanLimits` are dropped as for any other span.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate opentracingrust;
+    ///
+    /// use opentracingrust::tracers::NoopTracer;
+    ///
+    ///
+    /// fn main() {
+    ///     let (tracer, receiver) = NoopTracer::new();
+    ///     let messages = vec![tracer.span("produce"), tracer.span("produce")];
+    ///     let contexts: Vec<_> = messages.iter().map(|span| span.context().clone()).collect();
+    ///     tracer.aggregate_span("consume_batch", &contexts).finish().unwrap();
+    ///     assert_eq!(2, receiver.recv().unwrap().references().len());
+    /// }
+    /// ```
+    pub fn aggregate_span<N: IntoOperationName>(&self, name: N, contexts: &[SpanContext]) -> Span {
+        let options = contexts.iter().fold(StartOptions::default(), |options, context| {
+            options.follows(context.clone())
+        });
+        self.span_with_options(name, options)
+    }
+
     /// Create a new `Span` with the given operation name and default starting options.
-    pub fn span(&self, name: &str) -> Span {
+    pub fn span<N: IntoOperationName>(&self, name: N) -> Span {
         self.span_with_options(name, StartOptions::default())
     }
 
+    /// Create a new `AutoFinishingSpan` with the given operation name and default starting options.
+    ///
+    /// The span is finished when the returned value goes out of scope
+    /// which makes tracing a scope a single expression.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate opentracingrust;
+    ///
+    /// use opentracingrust::tracers::NoopTracer;
+    ///
+    ///
+    /// fn main() {
+    ///     let (tracer, receiver) = NoopTracer::new();
+    ///     {
+    ///         let _span = tracer.span_auto("scope");
+    ///         // ... snip ...
+    ///     }
+    ///     assert_eq!("scope", receiver.try_recv().unwrap().name());
+    /// }
+    /// ```
+    pub fn span_auto<N: IntoOperationName>(&self, name: N) -> AutoFinishingSpan {
+        self.span(name).auto_finish()
+    }
+
+    /// Create a new `AutoFinishingSpan` with the given operation name and starting options.
+    pub fn span_auto_with_options<N: IntoOperationName>(
+        &self, name: N, options: StartOptions
+    ) -> AutoFinishingSpan {
+        self.span_with_options(name, options).auto_finish()
+    }
+
     /// Create a new `Span` with the given operation name and starting options.
-    pub fn span_with_options(&self, name: &str, options: StartOptions) -> Span {
-        self.tracer.span(name, options)
+    pub fn span_with_options<N: IntoOperationName>(
+        &self, name: N, mut options: StartOptions
+    ) -> Span {
+        if options.span_limits.is_none() {
+            options.span_limits = Some(self.span_limits);
+        }
+        if options.clock.is_none() {
+            options.clock = self.clock.clone();
+        }
+        if options.warnings.is_none() {
+            options.warnings = self.warnings.clone();
+        }
+        let mut span = self.tracer.span(name.into_operation_name(self), options);
+        self.stats.span_started();
+        if let Some(ref restrictions) = self.baggage_restrictions {
+            span.restrict_baggage(Arc::clone(restrictions));
+        }
+        if let Some(ref in_flight) = self.in_flight {
+            in_flight.track(&mut span);
+        }
+        if let Some(ref sampler) = self.sampler {
+            if span.context().sampled() != Some(true) && sampler.force(span.context()) {
+                span.set_sampled(Some(true));
+                span.tag(SAMPLING_PRIORITY_TAG, 1u32);
+            } else if span.context().sampled().is_none() {
+                let sampled = sampler.sample(span.context());
+                span.set_sampled(Some(sampled));
+                if sampled {
+                    span.tag(SAMPLING_PRIORITY_TAG, 1u32);
+                }
+            }
+        }
+        for hook in &self.span_start_hooks {
+            hook(&mut span);
+        }
+        span
+    }
+
+    /// Returns the number of spans in flight if a limit is set with `Tracer::with_max_in_flight`.
+    pub fn in_flight_spans(&self) -> Option<usize> {
+        self.in_flight.as_ref().map(InFlightLimit::count)
+    }
+
+    /// Access the `SpanLimits` applied to spans created by this tracer.
+    pub fn span_limits(&self) -> &SpanLimits {
+        &self.span_limits
+    }
+
+    /// Returns a shared handle to the statistics of this tracer.
+    ///
+    /// Handles only see the in-flight limit if they are created after
+    /// `Tracer::with_max_in_flight` is called.
+    pub fn stats(&self) -> TracerStats {
+        self.stats.clone()
+    }
+}
+
+
+/// Types that can be used as operation names by `Tracer::span` and related methods.
+///
+/// Borrowed names are looked up with `Tracer::operation_name` so spans with the
+/// same name share one allocation.
+/// Owned names are used as they are: they are often built at runtime and
+/// would only fill the cache.
+pub trait IntoOperationName {
+    /// Converts the name into a shared operation name for spans of the `tracer`.
+    fn into_operation_name(self, tracer: &Tracer) -> Arc<str>;
+}
+
+impl IntoOperationName for &str {
+    fn into_operation_name(self, tracer: &Tracer) -> Arc<str> {
+        tracer.operation_name(self)
+    }
+}
+
+impl IntoOperationName for &String {
+    fn into_operation_name(self, tracer: &Tracer) -> Arc<str> {
+        tracer.operation_name(self)
+    }
+}
+
+impl IntoOperationName for Arc<str> {
+    fn into_operation_name(self, _: &Tracer) -> Arc<str> {
+        self
+    }
+}
+
+impl IntoOperationName for Box<str> {
+    fn into_operation_name(self, _: &Tracer) -> Arc<str> {
+        Arc::from(self)
+    }
+}
+
+impl IntoOperationName for Cow<'_, str> {
+    fn into_operation_name(self, tracer: &Tracer) -> Arc<str> {
+        match self {
+            Cow::Borrowed(name) => tracer.operation_name(name),
+            Cow::Owned(name) => Arc::from(name),
+        }
+    }
+}
+
+impl IntoOperationName for String {
+    fn into_operation_name(self, _: &Tracer) -> Arc<str> {
+        Arc::from(self)
+    }
+}
+
+
+/// Adapter changing the case of keys set on an `HttpHeaders` carrier.
+///
+/// Baggage keys are chosen by users and extracted as they are injected so
+/// only the prefix the tracer adds to them (see `MapCarrier::set_baggage`) is recased.
+struct CasedCarrier<'a> {
+    carrier: &'a mut dyn MapCarrier,
+    case: HeaderCase,
+}
+
+impl MapCarrier for CasedCarrier<'_> {
+    fn items(&self) -> Vec<(&str, &str)> {
+        self.carrier.items()
+    }
+
+    fn get(&self, key: &str) -> Option<String> {
+        self.carrier.get(key)
+    }
+
+    fn get_insensitive(&self, key: &str) -> Option<String> {
+        self.carrier.get_insensitive(key)
     }
+
+    fn set(&mut self, key: &str, value: &str) {
+        self.carrier.set(&self.case.apply(key), value);
+    }
+
+    fn set_baggage(&mut self, prefix: &str, key: &str, value: &str) {
+        let key = self.case.apply(prefix) + key;
+        self.carrier.set(&key, value);
+    }
+}
+
+
+/// Context fields propagated by the `Tracer` itself rather than by the concrete tracers.
+#[derive(Default)]
+struct PropagatedFields {
+    budget: Option<u32>,
+    deadline: Option<SystemTime>,
+    sampled: Option<bool>,
+}
+
+impl PropagatedFields {
+    /// Reads the propagated fields from a carrier.
+    ///
+    /// Keys are matched ignoring their case for `headers` carriers.
+    /// Invalid values are ignored so a bad hint does not drop the trace.
+    fn extract(carrier: &dyn MapCarrier, headers: bool) -> PropagatedFields {
+        let get = |key| if headers { carrier.get_insensitive(key) } else { carrier.get(key) };
+        let budget = get(TRACE_BUDGET_KEY).and_then(|budget| budget.parse::<u32>().ok());
+        let deadline = get(DEADLINE_KEY)
+            .and_then(|deadline| deadline.parse::<u64>().ok())
+            .and_then(|millis| UNIX_EPOCH.checked_add(Duration::from_millis(millis)));
+        let sampled = match get(SAMPLED_KEY).as_deref() {
+            Some("1") => Some(true),
+            Some("0") => Some(false),
+            _ => None,
+        };
+        PropagatedFields { budget, deadline, sampled }
+    }
+
+    /// Sets the extracted fields on the context.
+    fn apply(self, context: &mut SpanContext) {
+        if self.budget.is_some() {
+            context.set_trace_budget(self.budget);
+        }
+        if self.deadline.is_some() {
+            context.set_deadline(self.deadline);
+        }
+        if self.sampled.is_some() {
+            context.set_sampled(self.sampled);
+        }
+    }
+
+    /// Writes the fields of the context into a carrier.
+    fn inject(context: &SpanContext, carrier: &mut dyn MapCarrier) {
+        if let Some(deadline) = context.deadline() {
+            // Deadlines before the epoch are long expired: send the epoch itself.
+            let millis = deadline.duration_since(UNIX_EPOCH)
+                .map(|since| since.as_millis())
+                .unwrap_or(0);
+            carrier.set(DEADLINE_KEY, &millis.to_string());
+        }
+        if let Some(sampled) = context.sampled() {
+            carrier.set(SAMPLED_KEY, if sampled { "1" } else { "0" });
+        }
+        if let Some(budget) = context.trace_budget() {
+            carrier.set(TRACE_BUDGET_KEY, &budget.to_string());
+        }
+    }
+}
+
+
+/// Escapes the characters with special meaning in context strings.
+fn escape_context_item(item: &str) -> String {
+    item.replace('%', "%25").replace('&', "%26").replace('=', "%3D")
+}
+
+/// Reverses `escape_context_item`.
+#[allow(clippy::result_large_err)]
+fn unescape_context_item(item: &str) -> Result<String> {
+    let mut unescaped = String::with_capacity(item.len());
+    let mut chars = item.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            unescaped.push(c);
+            continue;
+        }
+        let code: String = chars.by_ref().take(2).collect();
+        match code.as_str() {
+            "25" => unescaped.push('%'),
+            "26" => unescaped.push('&'),
+            "3D" => unescaped.push('='),
+            _ => return Err(Error::Msg(format!("Invalid escape sequence '%{}'", code)))
+        }
+    }
+    Ok(unescaped)
 }
 
 
@@ -105,20 +1039,43 @@ mod tests {
     use std::collections::HashMap;
     use std::io;
     use std::io::BufRead;
+    use std::sync::Arc;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering;
+    use std::time::Duration;
+    use std::time::UNIX_EPOCH;
 
     use crossbeam_channel::unbounded;
 
+    use super::super::AfterFinishPolicy;
+    use super::super::BaggageRestrictions;
+    use super::super::CoarseClock;
+    use super::super::Error;
     use super::super::ExtractFormat;
+    use super::super::HeaderCase;
+    use super::super::InFlightPolicy;
     use super::super::InjectFormat;
+    use super::super::Log;
 
     use super::super::ImplContextBox;
+    use super::super::MapFormat;
     use super::super::Result;
     use super::super::Span;
     use super::super::SpanContext;
+    use super::super::SpanKind;
+    use super::super::SpanMessage;
     use super::super::SpanReference;
+    use super::super::SpanLimits;
     use super::super::SpanReferenceAware;
     use super::super::SpanSender;
+    use super::super::SpanWarning;
     use super::super::StartOptions;
+    use super::super::TagValue;
+    use super::super::TruncatedItem;
+    use super::super::warning_channel;
+    use super::super::sampling::ForceTraceSampler;
+    use super::super::sampling::WeightedSampler;
+    use super::super::tracers::FileTracer;
 
     use super::Tracer;
     use super::TracerInterface;
@@ -159,8 +1116,8 @@ mod tests {
                         TestContext { name: carrier.get("Span-Name").unwrap() }
                     ));
                     for (key, value) in carrier.items() {
-                        if key.starts_with("Baggage-") {
-                            context.set_baggage_item(String::from(&key[8..]), value.clone());
+                        if let Some(key) = key.strip_prefix("Baggage-") {
+                            context.set_baggage_item(String::from(key), String::from(value));
                         }
                     }
                     Ok(Some(context))
@@ -171,12 +1128,21 @@ mod tests {
                         TestContext { name: carrier.get("span-name").unwrap() }
                     ));
                     for (key, value) in carrier.items() {
-                        if key.starts_with("baggage-") {
-                            context.set_baggage_item(String::from(&key[8..]), value.clone());
+                        if let Some(key) = key.strip_prefix("baggage-") {
+                            context.set_baggage_item(String::from(key), String::from(value));
                         }
                     }
                     Ok(Some(context))
                 }
+
+                ExtractFormat::Custom("span-name", carrier) => {
+                    let name = carrier.downcast_ref::<String>().unwrap().clone();
+                    Ok(Some(SpanContext::new(ImplContextBox::new(TestContext { name }))))
+                }
+
+                ExtractFormat::Custom(name, _) => {
+                    Err(Error::Msg(format!("Unsupported format {}", name)))
+                }
             }
         }
 
@@ -201,8 +1167,7 @@ mod tests {
                     carrier.set("Trace-Id", "123");
                     carrier.set("Span-Name", &inner.name);
                     for (key, value) in context.baggage_items() {
-                        let key = format!("Baggage-{}", key);
-                        carrier.set(&key, value);
+                        carrier.set_baggage("Baggage-", key, value);
                     }
                     Ok(())
                 }
@@ -212,35 +1177,578 @@ mod tests {
                     carrier.set("trace-id", "123");
                     carrier.set("span-name", &inner.name);
                     for (key, value) in context.baggage_items() {
-                        let key = format!("baggage-{}", key);
-                        carrier.set(&key, value);
+                        carrier.set_baggage("baggage-", key, value);
                     }
                     Ok(())
                 }
+
+                InjectFormat::Custom(_, carrier) => {
+                    let inner = context.impl_context::<TestContext>().unwrap();
+                    *carrier.downcast_mut::<String>().unwrap() = inner.name.clone();
+                    Ok(())
+                }
             }
         }
 
-        fn span(&self, name: &str, options: StartOptions) -> Span {
+        fn span(&self, name: Arc<str>, options: StartOptions) -> Span {
             let context = SpanContext::new(ImplContextBox::new(TestContext {
                 name: String::from("test-span")
             }));
-            Span::new(name, context, options, self.sender.clone())
+            Span::new(name, context, options, Arc::clone(&self.sender))
+        }
+    }
+
+
+    #[test]
+    fn create_aggregate_span() {
+        let (sender, receiver) = unbounded();
+        let tracer = Tracer::new(TestTracer { sender: Arc::new(sender) });
+        let first = tracer.span("first");
+        let second = tracer.span("second");
+        let contexts = [first.context().clone(), second.context().clone()];
+        tracer.aggregate_span("batch", &contexts).finish().unwrap();
+        match receiver.try_recv().unwrap() {
+            SpanMessage::Finished(span) => {
+                assert!(span.is_root());
+                match span.references().as_slice() {
+                    [SpanReference::FollowsFrom(_), SpanReference::FollowsFrom(_)] => (),
+                    _ => panic!("Expected two FollowsFrom references"),
+                }
+            },
+            SpanMessage::Batch(_) => panic!("Expected a finished span")
         }
+
+        let (tracer, _) = FileTracer::new();
+        let first = tracer.span("first");
+        let second = tracer.span("second");
+        let contexts = [first.context().clone(), second.context().clone()];
+        let batch = tracer.aggregate_span("batch", &contexts);
+        assert_eq!(second.context().trace_id(), batch.context().trace_id());
+    }
+
+    #[test]
+    fn create_span_over_in_flight_limit() {
+        let (sender, receiver) = unbounded();
+        let tracer = Tracer::new(TestTracer { sender: Arc::new(sender) })
+            .with_max_in_flight(1, InFlightPolicy::TagRoot);
+        let first = tracer.span("first");
+        let options = StartOptions::default().child_of(first.context().clone());
+        let child = tracer.span_with_options("child", options);
+        let root = tracer.span("root");
+        assert_eq!(Some(3), tracer.in_flight_spans());
+        drop(child);
+        root.finish().unwrap();
+        first.finish().unwrap();
+        assert_eq!(Some(0), tracer.in_flight_spans());
+        let tags: Vec<Option<TagValue>> = receiver.try_iter()
+            .map(|message| match message {
+                SpanMessage::Finished(span) => span.tags().get("otr.in_flight.exceeded").cloned(),
+                SpanMessage::Batch(_) => panic!("Expected a finished span")
+            })
+            .collect();
+        match tags.as_slice() {
+            [Some(TagValue::Unsigned(3)), None] => (),
+            _ => panic!("Expected only the second root span to be tagged"),
+        }
+    }
+
+    #[test]
+    fn refuse_recording_over_in_flight_limit() {
+        let (sender, receiver) = unbounded();
+        let tracer = Tracer::new(TestTracer { sender: Arc::new(sender) })
+            .with_max_in_flight(1, InFlightPolicy::RefuseRecording);
+        let first = tracer.span("first");
+        let mut refused = tracer.span("refused");
+        assert!(!refused.is_recording());
+        assert_eq!(None, refused.context().sampled());
+        refused.tag("payload", "large");
+        refused.log(Log::new().log("event", "large"));
+        refused.finish().unwrap();
+        first.finish().unwrap();
+        let names: Vec<String> = receiver.try_iter()
+            .map(|message| match message {
+                SpanMessage::Finished(span) => String::from(span.name()),
+                SpanMessage::Batch(_) => panic!("Expected a finished span")
+            })
+            .collect();
+        assert_eq!(names, ["first"]);
     }
 
+    #[test]
+    fn refused_recording_is_not_propagated() {
+        let (sender, receiver) = unbounded();
+        let sampler = ForceTraceSampler::new(WeightedSampler::new(0.0), |token: &str| {
+            token == "valid"
+        });
+        let tracer = Tracer::new(TestTracer { sender: Arc::new(sender) })
+            .with_max_in_flight(1, InFlightPolicy::RefuseRecording)
+            .with_sampler(sampler);
+        let mut parent = SpanContext::new(ImplContextBox::new(TestContext {
+            name: String::from("parent")
+        }));
+        parent.set_baggage_item(String::from("force-trace"), String::from("valid"));
+        let first = tracer.span("first");
+        let options = StartOptions::default().child_of(parent);
+        let refused = tracer.span_with_options("refused", options);
+        assert!(!refused.is_recording());
+        assert_eq!(Some(true), refused.context().sampled());
+        let context = refused.context().clone();
+        refused.finish().unwrap();
+        first.finish().unwrap();
+
+        let options = StartOptions::default().child_of(context);
+        let child = tracer.span_with_options("child", options);
+        assert!(child.is_recording());
+        child.finish().unwrap();
+        let names: Vec<String> = receiver.try_iter()
+            .map(|message| match message {
+                SpanMessage::Finished(span) => String::from(span.name()),
+                SpanMessage::Batch(_) => panic!("Expected a finished span")
+            })
+            .collect();
+        assert_eq!(names, ["child"]);
+    }
+
+    #[test]
+    fn report_span_warnings() {
+        let (sender, _receiver) = unbounded();
+        let (warning_sender, warnings) = warning_channel();
+        let tracer = Tracer::new(TestTracer { sender: Arc::new(sender) })
+            .with_span_limits(SpanLimits::default().max_tags(1))
+            .with_warnings(warning_sender);
+        let mut span = tracer.span("test-span");
+        span.tag("first", 1);
+        span.tag("second", 2);
+        span.finish_time(UNIX_EPOCH);
+        span.set_operation_name("renamed");
+        span.tag("first", 3);
+        span.finish().unwrap();
+        tracer.span("clean").finish().unwrap();
+        let warnings: Vec<SpanWarning> = warnings.try_iter().collect();
+        assert_eq!(warnings, [
+            SpanWarning::Truncated(String::from("test-span"), TruncatedItem::Tag),
+            SpanWarning::TagAfterFinish(String::from("renamed"), String::from("first")),
+        ]);
+    }
+
+    #[test]
+    fn reject_changes_after_finish_time() {
+        let (sender, receiver) = unbounded();
+        let (warning_sender, warnings) = warning_channel();
+        let limits = SpanLimits::default().after_finish(AfterFinishPolicy::Reject);
+        let tracer = Tracer::new(TestTracer { sender: Arc::new(sender) })
+            .with_span_limits(limits)
+            .with_warnings(warning_sender);
+        let parent = tracer.span("parent");
+        let mut span = tracer.span("child");
+        span.finish_time(UNIX_EPOCH);
+        span.child_of(parent.context().clone());
+        span.tag("late", true);
+        span.finish().unwrap();
+        let span = match receiver.recv().unwrap() {
+            SpanMessage::Finished(span) => span,
+            SpanMessage::Batch(_) => panic!("Expected a finished span")
+        };
+        assert!(span.references().is_empty());
+        assert!(span.tags().get("late").is_none());
+        let warnings: Vec<SpanWarning> = warnings.try_iter().collect();
+        assert_eq!(warnings, [
+            SpanWarning::ReferenceAfterFinish(String::from("child"), String::from("child_of")),
+            SpanWarning::TagAfterFinish(String::from("child"), String::from("late")),
+        ]);
+    }
+
+    #[test]
+    fn warn_over_in_flight_limit() {
+        let (sender, _) = unbounded();
+        let warnings = Arc::new(AtomicUsize::new(0));
+        let inner_warnings = Arc::clone(&warnings);
+        let tracer = Tracer::new(TestTracer { sender: Arc::new(sender) })
+            .with_max_in_flight(1, InFlightPolicy::Warn(Box::new(move |count| {
+                inner_warnings.store(count, Ordering::Relaxed);
+            })));
+        let _first = tracer.span("first");
+        assert_eq!(0, warnings.load(Ordering::Relaxed));
+        let _second = tracer.span("second");
+        assert_eq!(2, warnings.load(Ordering::Relaxed));
+    }
 
     #[test]
     fn create_span() {
         let (sender, _) = unbounded();
-        let tracer = Tracer::new(TestTracer {sender});
+        let tracer = Tracer::new(TestTracer { sender: Arc::new(sender) });
         let _span: Span = tracer.span("test-span");
     }
 
+    #[test]
+    fn create_span_with_clock() {
+        let (sender, receiver) = unbounded();
+        let clock = Arc::new(CoarseClock::new_with_interval(Duration::from_secs(60)));
+        let tracer = Tracer::new(TestTracer { sender: Arc::new(sender) })
+            .with_clock(clock.clone());
+        tracer.span("test-span").finish().unwrap();
+        match receiver.try_recv().unwrap() {
+            SpanMessage::Finished(span) => {
+                assert_eq!(clock.now(), *span.start_time());
+                assert_eq!(clock.now(), *span.finish_time());
+            },
+            SpanMessage::Batch(_) => panic!("Expected a finished span")
+        }
+    }
+
+    #[test]
+    fn create_span_with_hooks() {
+        let (sender, receiver) = unbounded();
+        let tracer = Tracer::new(TestTracer { sender: Arc::new(sender) })
+            .on_span_start(|span| span.tag("request.id", "abc"))
+            .on_span_start(|span| span.tag("request.id", "def"));
+        tracer.span("test-span").finish().unwrap();
+        match receiver.try_recv().unwrap() {
+            SpanMessage::Finished(span) => match span.tags().get("request.id") {
+                Some(TagValue::String(id)) => assert_eq!("def", id),
+                _ => panic!("Hooks did not tag the span"),
+            },
+            SpanMessage::Batch(_) => panic!("Expected a finished span")
+        }
+    }
+
+    #[test]
+    fn create_span_with_shared_name() {
+        let (sender, _) = unbounded();
+        let tracer = Tracer::new(TestTracer { sender: Arc::new(sender) });
+        let name: Arc<str> = Arc::from("test-span");
+        let span = tracer.span(Arc::clone(&name));
+        assert_eq!("test-span", span.operation_name());
+    }
+
+    #[test]
+    fn create_auto_finishing_span() {
+        let (sender, receiver) = unbounded();
+        let tracer = Tracer::new(TestTracer { sender: Arc::new(sender) });
+        {
+            let options = StartOptions::default().span_kind(SpanKind::Internal);
+            let span = tracer.span_auto_with_options("test-span", options);
+            assert_eq!(Some(SpanKind::Internal), span.kind());
+        }
+        match receiver.try_recv().unwrap() {
+            SpanMessage::Finished(span) => assert_eq!("test-span", span.name()),
+            SpanMessage::Batch(_) => panic!("Expected a finished span")
+        }
+    }
+
+    #[test]
+    fn extract_first_skips_failures() {
+        let (sender, _) = unbounded();
+        let tracer = Tracer::new(TestTracer { sender: Arc::new(sender) });
+        let invalid = String::from("caller");
+        let mut valid = HashMap::new();
+        valid.insert(String::from("span-name"), String::from("caller"));
+        let context = tracer.extract_first(vec![
+            ExtractFormat::Custom("other", Box::new(&invalid)),
+            ExtractFormat::TextMap(Box::new(&valid)),
+        ]).unwrap().unwrap();
+        assert_eq!("caller", context.impl_context::<TestContext>().unwrap().name);
+
+        match tracer.extract_first(vec![ExtractFormat::Custom("other", Box::new(&invalid))]) {
+            Err(Error::Msg(_)) => (),
+            _ => panic!("Expected an error")
+        }
+        assert!(tracer.extract_first(Vec::new()).unwrap().is_none());
+    }
+
+    #[test]
+    fn extract_or_new_child() {
+        let (sender, _) = unbounded();
+        let tracer = Tracer::new(TestTracer { sender: Arc::new(sender) });
+        let mut map = HashMap::new();
+        map.insert(String::from("span-name"), String::from("caller"));
+        let span = tracer.extract_or_new(ExtractFormat::TextMap(Box::new(&map)), "test-span");
+        assert_eq!(1, span.references().len());
+    }
+
+    #[test]
+    fn extract_with_transforms() {
+        let (tracer, _) = FileTracer::new();
+        let tracer = tracer
+            .on_extract(|context| {
+                if let Some(value) = context.remove_baggage_item("Baggage-legacy") {
+                    context.set_baggage_item(String::from("Baggage-key"), value);
+                }
+            })
+            .on_extract(|context| {
+                context.remove_baggage_item("Baggage-deprecated");
+            });
+        let mut headers: HashMap<String, String> = HashMap::new();
+        headers.insert(String::from("TraceID"), String::from("123"));
+        headers.insert(String::from("SpanID"), String::from("456"));
+        headers.insert(String::from("Baggage-legacy"), String::from("value"));
+        headers.insert(String::from("Baggage-deprecated"), String::from("value"));
+        let context = tracer.extract(ExtractFormat::HttpHeaders(Box::new(&headers)))
+            .unwrap().unwrap();
+        let mut items: Vec<(&String, &String)> = context.baggage_items().collect();
+        items.sort();
+        assert_eq!(items, [(&String::from("Baggage-key"), &String::from("value"))]);
+    }
+
+    #[test]
+    fn extract_or_new_on_error() {
+        let (sender, receiver) = unbounded();
+        let tracer = Tracer::new(TestTracer { sender: Arc::new(sender) });
+        let carrier = String::from("caller");
+        let format = ExtractFormat::Custom("other", Box::new(&carrier));
+        let span = tracer.extract_or_new(format, "test-span");
+        assert!(span.references().is_empty());
+        span.finish().unwrap();
+        let span = match receiver.try_recv().unwrap() {
+            SpanMessage::Finished(span) => span,
+            SpanMessage::Batch(_) => panic!("Expected a finished span")
+        };
+        match span.tags().get("trace.extract_error") {
+            Some(TagValue::Boolean(true)) => (),
+            _ => panic!("Extraction error not tagged")
+        }
+        assert!(span.tags().get("error").is_none());
+        assert_eq!(1, span.logs().len());
+    }
+
+    #[test]
+    fn operation_names_are_cached() {
+        let (sender, _) = unbounded();
+        let tracer = Tracer::new(TestTracer { sender: Arc::new(sender) });
+        let name1 = tracer.operation_name("test-span");
+        let name2 = tracer.operation_name("test-span");
+        let other = tracer.operation_name("other-span");
+        assert!(Arc::ptr_eq(&name1, &name2));
+        assert!(!Arc::ptr_eq(&name1, &other));
+    }
+
+    #[test]
+    fn span_names_use_the_cache() {
+        let (sender, _) = unbounded();
+        let tracer = Tracer::new(TestTracer { sender: Arc::new(sender) });
+        let first = tracer.span("test-span");
+        let second = tracer.span_with_options("test-span", StartOptions::default());
+        let cached = tracer.operation_name("test-span");
+        assert_eq!(first.operation_name().as_ptr(), cached.as_ptr());
+        assert_eq!(second.operation_name().as_ptr(), cached.as_ptr());
+    }
+
+    #[test]
+    fn context_string_round_trip() {
+        let (sender, _) = unbounded();
+        let tracer = Tracer::new(TestTracer { sender: Arc::new(sender) });
+        let mut span = tracer.span("test-span");
+        span.set_baggage_item("a&b", "c=d%");
+
+        let context = tracer.context_to_string(span.context()).unwrap();
+        assert_eq!(context, "baggage-a%26b=c%3Dd%25&span-name=test-span&trace-id=123");
+        let context = tracer.context_from_string(&context).unwrap().unwrap();
+        let inner = context.impl_context::<TestContext>().unwrap();
+        assert_eq!("test-span", inner.name);
+        assert_eq!("c=d%", context.get_baggage_item("a&b").unwrap());
+    }
+
+    #[test]
+    fn context_string_invalid() {
+        let (sender, _) = unbounded();
+        let tracer = Tracer::new(TestTracer { sender: Arc::new(sender) });
+        match tracer.context_from_string("span-name") {
+            Err(Error::Msg(_)) => (),
+            _ => panic!("Expected an error")
+        }
+        match tracer.context_from_string("span-name=%zz") {
+            Err(Error::Msg(_)) => (),
+            _ => panic!("Expected an error")
+        }
+    }
+
+    #[test]
+    fn extract_restricts_baggage() {
+        let mut map = HashMap::new();
+        map.insert(String::from("Span-Name"), String::from("2"));
+        map.insert(String::from("Baggage-a"), String::from("b"));
+        map.insert(String::from("Baggage-c"), String::from("d"));
+        let (sender, _) = unbounded();
+        let tracer = Tracer::new(TestTracer { sender: Arc::new(sender) })
+            .with_baggage_restrictions(BaggageRestrictions::default().allow_key("a"));
+        let context = tracer.extract(ExtractFormat::HttpHeaders(Box::new(&map))).unwrap().unwrap();
+        let items: Vec<(String, String)> = context.baggage_items()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        assert_eq!(items, vec![(String::from("a"), String::from("b"))]);
+    }
+
+    #[test]
+    fn extract_trace_budget() {
+        let mut map = HashMap::new();
+        map.insert(String::from("span-name"), String::from("2"));
+        map.insert(String::from("ot-trace-budget"), String::from("10"));
+        let (sender, _) = unbounded();
+        let tracer = Tracer::new(TestTracer { sender: Arc::new(sender) });
+        let context = tracer.extract(ExtractFormat::TextMap(Box::new(&map))).unwrap().unwrap();
+        assert_eq!(Some(10), context.trace_budget());
+
+        let options = StartOptions::default().child_of(context);
+        let span = tracer.span_with_options("child", options);
+        assert_eq!(Some(10), span.context().trace_budget());
+
+        map.insert(String::from("ot-trace-budget"), String::from("lots"));
+        let context = tracer.extract(ExtractFormat::TextMap(Box::new(&map))).unwrap().unwrap();
+        assert_eq!(None, context.trace_budget());
+    }
+
+    #[test]
+    fn deadline_round_trip() {
+        let (sender, _) = unbounded();
+        let tracer = Tracer::new(TestTracer { sender: Arc::new(sender) });
+        let deadline = UNIX_EPOCH + Duration::from_millis(1_500_000_000_123);
+        let mut root = tracer.span("root");
+        root.set_deadline(Some(deadline));
+        let child = tracer.span_with_options(
+            "child", StartOptions::default().child_of(root.context().clone())
+        );
+        assert_eq!(Some(deadline), child.context().deadline());
+        assert_eq!(Some(Duration::ZERO), child.context().remaining());
+
+        let mut map: HashMap<String, String> = HashMap::new();
+        tracer.inject(child.context(), InjectFormat::TextMap(Box::new(&mut map))).unwrap();
+        assert_eq!("1500000000123", map["ot-deadline"]);
+        let context = tracer.extract(ExtractFormat::TextMap(Box::new(&map))).unwrap().unwrap();
+        assert_eq!(Some(deadline), context.deadline());
+
+        map.insert(String::from("ot-deadline"), String::from("soon"));
+        let context = tracer.extract(ExtractFormat::TextMap(Box::new(&map))).unwrap().unwrap();
+        assert_eq!(None, context.deadline());
+
+        // Deadlines the platform can't represent are ignored instead of panicking.
+        map.insert(String::from("ot-deadline"), u64::MAX.to_string());
+        assert!(tracer.extract(ExtractFormat::TextMap(Box::new(&map))).unwrap().is_some());
+    }
+
+    #[test]
+    fn extract_sampling_decision() {
+        let mut map = HashMap::new();
+        map.insert(String::from("span-name"), String::from("2"));
+        map.insert(String::from("ot-sampled"), String::from("0"));
+        let (sender, _) = unbounded();
+        let tracer = Tracer::new(TestTracer { sender: Arc::new(sender) })
+            .with_sampler(WeightedSampler::new(1.0));
+        let context = tracer.extract(ExtractFormat::TextMap(Box::new(&map))).unwrap().unwrap();
+        assert_eq!(Some(false), context.sampled());
+
+        let options = StartOptions::default().child_of(context);
+        let span = tracer.span_with_options("child", options);
+        assert_eq!(Some(false), span.context().sampled());
+
+        let mut injected: HashMap<String, String> = HashMap::new();
+        tracer.inject(span.context(), InjectFormat::TextMap(Box::new(&mut injected))).unwrap();
+        assert_eq!("0", injected["ot-sampled"]);
+
+        map.insert(String::from("ot-sampled"), String::from("yes"));
+        let context = tracer.extract(ExtractFormat::TextMap(Box::new(&map))).unwrap().unwrap();
+        assert_eq!(None, context.sampled());
+    }
+
+    #[test]
+    fn sampler_decides_root_spans() {
+        let (sender, receiver) = unbounded();
+        let tracer = Tracer::new(TestTracer { sender: Arc::new(sender) })
+            .with_sampler(WeightedSampler::new(0.0));
+        let root = tracer.span("root");
+        assert_eq!(Some(false), root.context().sampled());
+
+        let mut parent = SpanContext::new(ImplContextBox::new(TestContext {
+            name: String::from("parent")
+        }));
+        parent.set_sampled(Some(true));
+        let child = tracer.span_with_options("child", StartOptions::default().child_of(parent));
+        assert_eq!(Some(true), child.context().sampled());
+
+        root.finish().unwrap();
+        child.finish().unwrap();
+        let priorities: Vec<Option<u64>> = receiver.try_iter()
+            .map(|message| match message {
+                SpanMessage::Finished(span) => match span.tags().get("sampling.priority") {
+                    Some(TagValue::Unsigned(priority)) => Some(*priority),
+                    _ => None
+                },
+                SpanMessage::Batch(_) => panic!("Expected a finished span")
+            })
+            .collect();
+        assert_eq!(priorities, [None]);
+    }
+
+    #[test]
+    fn sampler_tags_sampled_root_spans() {
+        let (sender, receiver) = unbounded();
+        let tracer = Tracer::new(TestTracer { sender: Arc::new(sender) })
+            .with_sampler(WeightedSampler::new(1.0));
+        let root = tracer.span("root");
+        assert_eq!(Some(true), root.context().sampled());
+
+        root.finish().unwrap();
+        match receiver.try_recv().unwrap() {
+            SpanMessage::Finished(span) => match span.tags().get("sampling.priority") {
+                Some(TagValue::Unsigned(priority)) => assert_eq!(1, *priority),
+                _ => panic!("Sampling priority not recorded"),
+            },
+            SpanMessage::Batch(_) => panic!("Expected a finished span"),
+        }
+    }
+
+    #[test]
+    fn sampler_forces_unsampled_traces() {
+        let (sender, receiver) = unbounded();
+        let sampler = ForceTraceSampler::new(WeightedSampler::new(0.0), |token: &str| {
+            token == "valid"
+        });
+        let tracer = Tracer::new(TestTracer { sender: Arc::new(sender) }).with_sampler(sampler);
+        let mut parent = SpanContext::new(ImplContextBox::new(TestContext {
+            name: String::from("parent")
+        }));
+        parent.set_sampled(Some(false));
+        parent.set_baggage_item(String::from("force-trace"), String::from("valid"));
+        let options = StartOptions::default().child_of(parent).tag("user", "42");
+        let child = tracer.span_with_options("child", options);
+        assert_eq!(Some(true), child.context().sampled());
+
+        child.finish().unwrap();
+        match receiver.try_recv().unwrap() {
+            SpanMessage::Finished(span) => {
+                match span.tags().get("user") {
+                    Some(TagValue::String(user)) => assert_eq!("42", user),
+                    _ => panic!("Start tag not recorded"),
+                }
+                assert!(span.tags().get("sampling.priority").is_some());
+            },
+            SpanMessage::Batch(_) => panic!("Expected a finished span"),
+        }
+    }
+
+    #[test]
+    fn custom_format() {
+        let (sender, _) = unbounded();
+        let tracer = Tracer::new(TestTracer { sender: Arc::new(sender) });
+        let span = tracer.span("test-span");
+        let mut carrier = String::new();
+        tracer.inject(span.context(), InjectFormat::Custom("span-name", Box::new(&mut carrier)))
+            .unwrap();
+        assert_eq!("test-span", carrier);
+
+        let format = ExtractFormat::Custom("span-name", Box::new(&carrier));
+        let context = tracer.extract(format).unwrap().unwrap();
+        assert_eq!("test-span", context.impl_context::<TestContext>().unwrap().name);
+        match tracer.extract(ExtractFormat::Custom("other", Box::new(&carrier))) {
+            Err(Error::Msg(_)) => (),
+            _ => panic!("Expected an error")
+        }
+    }
+
     #[test]
     fn extract_binary() {
         let mut buffer = io::Cursor::new("test-span\na:b\n");
         let (sender, _) = unbounded();
-        let tracer = Tracer::new(TestTracer {sender});
+        let tracer = Tracer::new(TestTracer { sender: Arc::new(sender) });
         let context = tracer.extract(
             ExtractFormat::Binary(Box::new(&mut buffer))
         ).unwrap().unwrap();
@@ -258,7 +1766,7 @@ mod tests {
         map.insert(String::from("Span-Name"), String::from("2"));
         map.insert(String::from("Baggage-a"), String::from("b"));
         let (sender, _) = unbounded();
-        let tracer = Tracer::new(TestTracer {sender});
+        let tracer = Tracer::new(TestTracer { sender: Arc::new(sender) });
         let context = tracer.extract(ExtractFormat::HttpHeaders(Box::new(&map))).unwrap().unwrap();
         let inner = context.impl_context::<TestContext>().unwrap();
         assert_eq!("2", inner.name);
@@ -274,7 +1782,7 @@ mod tests {
         map.insert(String::from("span-name"), String::from("2"));
         map.insert(String::from("baggage-a"), String::from("b"));
         let (sender, _) = unbounded();
-        let tracer = Tracer::new(TestTracer {sender});
+        let tracer = Tracer::new(TestTracer { sender: Arc::new(sender) });
         let context = tracer.extract(ExtractFormat::TextMap(Box::new(&map))).unwrap().unwrap();
         let inner = context.impl_context::<TestContext>().unwrap();
         assert_eq!("2", inner.name);
@@ -287,7 +1795,7 @@ mod tests {
     #[test]
     fn inject_binary() {
         let (sender, _) = unbounded();
-        let tracer = Tracer::new(TestTracer {sender});
+        let tracer = Tracer::new(TestTracer { sender: Arc::new(sender) });
         let mut span = tracer.span("test-span");
         span.set_baggage_item("a", "b");
 
@@ -302,7 +1810,7 @@ mod tests {
     #[test]
     fn inject_http_headers() {
         let (sender, _) = unbounded();
-        let tracer = Tracer::new(TestTracer {sender});
+        let tracer = Tracer::new(TestTracer { sender: Arc::new(sender) });
         let mut span = tracer.span("test-span");
         span.set_baggage_item("a", "b");
 
@@ -320,10 +1828,82 @@ mod tests {
         ]);
     }
 
+    #[test]
+    fn header_case_ignores_colliding_baggage_keys() {
+        let (tracer, _) = FileTracer::new();
+        let tracer = tracer.with_header_case(HeaderCase::Lowercase);
+        let mut span = tracer.span("client");
+        span.set_baggage_item("ID", "value");
+
+        let mut headers: HashMap<String, String> = HashMap::new();
+        let format = InjectFormat::HttpHeaders(Box::new(&mut headers));
+        tracer.inject(span.context(), format).unwrap();
+        let mut keys: Vec<&str> = headers.keys().map(String::as_str).collect();
+        keys.sort();
+        assert_eq!(keys, ["baggage-ID", "spanid", "traceid"]);
+    }
+
+    #[test]
+    fn header_case_round_trip() {
+        let cases = [
+            (HeaderCase::Canonical, ["Baggage-userID", "Ot-Trace-Budget", "Spanid", "Traceid"]),
+            (HeaderCase::Lowercase, ["baggage-userID", "ot-trace-budget", "spanid", "traceid"]),
+        ];
+        for (case, expected) in cases.iter() {
+            let (tracer, _) = FileTracer::new();
+            let tracer = tracer.with_header_case(*case);
+            let mut span = tracer.span("client");
+            span.set_baggage_item("userID", "value");
+            span.set_trace_budget(Some(5));
+
+            let mut headers: HashMap<String, String> = HashMap::new();
+            let format = InjectFormat::HttpHeaders(Box::new(&mut headers));
+            tracer.inject(span.context(), format).unwrap();
+            let mut keys: Vec<&str> = headers.keys().map(String::as_str).collect();
+            keys.sort();
+            assert_eq!(&keys, expected);
+
+            let format = ExtractFormat::HttpHeaders(Box::new(&headers));
+            let context = tracer.extract(format).unwrap().unwrap();
+            assert!(context.same_span(span.context()));
+            assert_eq!(Some(5), context.trace_budget());
+            // FileTracer keeps the header prefix in extracted baggage keys.
+            let baggage: Vec<(&String, &String)> = context.baggage_items().collect();
+            assert_eq!(baggage, [(&String::from(expected[0]), &String::from("value"))]);
+        }
+    }
+
+    #[test]
+    fn inject_preview() {
+        let (sender, _) = unbounded();
+        let tracer = Tracer::new(TestTracer { sender: Arc::new(sender) });
+        let mut span = tracer.span("test-span");
+        span.set_baggage_item("a", "b");
+
+        let items = tracer.inject_preview(span.context(), MapFormat::TextMap).unwrap();
+        assert_eq!(items, [
+            (String::from("baggage-a"), String::from("b")),
+            (String::from("span-name"), String::from("test-span")),
+            (String::from("trace-id"), String::from("123"))
+        ]);
+    }
+
+    #[test]
+    fn inject_trace_budget() {
+        let (sender, _) = unbounded();
+        let tracer = Tracer::new(TestTracer { sender: Arc::new(sender) });
+        let mut span = tracer.span("test-span");
+        span.set_trace_budget(Some(5));
+
+        let mut map = HashMap::new();
+        tracer.inject(span.context(), InjectFormat::HttpHeaders(Box::new(&mut map))).unwrap();
+        assert_eq!("5", map.get("ot-trace-budget").unwrap());
+    }
+
     #[test]
     fn inject_textmap() {
         let (sender, _) = unbounded();
-        let tracer = Tracer::new(TestTracer {sender});
+        let tracer = Tracer::new(TestTracer { sender: Arc::new(sender) });
         let mut span = tracer.span("test-span");
         span.set_baggage_item("a", "b");
 
@@ -1,14 +1,19 @@
+use std::convert::TryFrom;
 use std::io;
 use std::io::Write;
+use std::sync::Arc;
+use std::time::SystemTime;
 use std::time::UNIX_EPOCH;
 
 use crossbeam_channel::unbounded;
 use rand::random;
 
+use super::super::Error;
 use super::super::ImplContextBox;
 use super::super::Result;
 
 use super::super::FinishedSpan;
+use super::super::Log;
 use super::super::LogValue;
 use super::super::Span;
 use super::super::SpanContext;
@@ -23,6 +28,9 @@ use super::super::ExtractFormat;
 use super::super::InjectFormat;
 use super::super::Tracer;
 use super::super::TracerInterface;
+use super::super::propagation::binary::BinaryContext;
+use super::super::propagation::binary::FLAG_SAMPLED;
+use super::super::propagation::binary::FLAG_SAMPLING_DECIDED;
 
 
 const BAGGAGE_KEY_PREFIX: &str = "Baggage-";
@@ -70,27 +78,52 @@ pub struct FileTracer {
 }
 
 impl TracerInterface for FileTracer {
-    /// Extract a span context from a text map or HTTP headers.
+    /// Extract a span context from a text map, HTTP headers or a binary stream.
     ///
-    /// Note that the binary extraction format is not supported by `FileTracer`.
+    /// Binary streams are decoded with the shared `propagation::binary::BinaryContext` codec.
+    /// Custom formats are not supported by `FileTracer`.
     fn extract(&self, fmt: ExtractFormat) -> Result<Option<SpanContext>> {
         match fmt {
-            ExtractFormat::HttpHeaders(carrier) => {
+            ExtractFormat::Binary(carrier) => {
+                let binary = match BinaryContext::read(&mut **carrier)? {
+                    Some(binary) => binary,
+                    None => return Ok(None),
+                };
+                let trace_id = u64::try_from(binary.trace_id()).map_err(|_| Error::Msg(
+                    format!("Trace ID {} does not fit in 64 bits", binary.trace_id())
+                ))?;
+                let mut context = SpanContext::new(ImplContextBox::hashable(
+                    FileTracerContext {
+                        trace_id,
+                        span_id: binary.span_id(),
+                    }
+                ));
+                for (key, value) in binary.baggage() {
+                    context.set_baggage_item(key.clone(), value.clone());
+                }
+                let sampled = binary.flags() & FLAG_SAMPLED != 0;
+                if sampled || binary.flags() & FLAG_SAMPLING_DECIDED != 0 {
+                    context.set_sampled(Some(sampled));
+                }
+                Ok(Some(context))
+            },
+            ExtractFormat::HttpHeaders(carrier) |
+            ExtractFormat::TextMap(carrier) => {
                 // Decode trace and span IDs.
-                let trace_id = carrier.get(TRACE_ID_KEY);
+                let trace_id = carrier.get_insensitive(TRACE_ID_KEY);
                 if trace_id.is_none() {
                     return Ok(None);
                 }
                 let trace_id = trace_id.unwrap().parse::<u64>()?;
 
-                let span_id = carrier.get(SPAN_ID_KEY);
+                let span_id = carrier.get_insensitive(SPAN_ID_KEY);
                 if span_id.is_none() {
                     return Ok(None);
                 }
                 let span_id = span_id.unwrap().parse::<u64>()?;
 
                 // Create a mutable context to load baggage items.
-                let mut context = SpanContext::new(ImplContextBox::new(
+                let mut context = SpanContext::new(ImplContextBox::hashable(
                     FileTracerContext {
                         trace_id,
                         span_id
@@ -99,19 +132,23 @@ impl TracerInterface for FileTracer {
 
                 // Decode baggage items.
                 for (key, value) in carrier.items() {
-                    if key.starts_with(BAGGAGE_KEY_PREFIX) {
-                        context.set_baggage_item(key.clone(), value.clone());
+                    let prefix = key.get(..BAGGAGE_KEY_PREFIX.len()).unwrap_or("");
+                    if prefix.eq_ignore_ascii_case(BAGGAGE_KEY_PREFIX) {
+                        context.set_baggage_item(String::from(key), String::from(value));
                     }
                 }
                 Ok(Some(context))
             },
-            _ => panic!("Unsupported extraction format")
+            ExtractFormat::Custom(name, _) => {
+                Err(Error::Msg(format!("Unsupported extraction format {}", name)))
+            },
         }
     }
 
-    /// Inject the span context into a text map or HTTP headers.
+    /// Inject the span context into a text map, HTTP headers or a binary stream.
     ///
-    /// Note that the binary injection format is not supported by `FileTracer`.
+    /// Binary streams are encoded with the shared `propagation::binary::BinaryContext` codec.
+    /// Custom formats are not supported by `FileTracer`.
     fn inject(&self, context: &SpanContext, fmt: InjectFormat) -> Result<()> {
         let span_context = context;
         let context = span_context.impl_context::<FileTracerContext>();
@@ -119,127 +156,307 @@ impl TracerInterface for FileTracer {
             "Unsupported span, was it created by FileTracer?"
         );
         match fmt {
+            InjectFormat::Binary(carrier) => {
+                let flags = match span_context.sampled() {
+                    Some(true) => FLAG_SAMPLING_DECIDED | FLAG_SAMPLED,
+                    Some(false) => FLAG_SAMPLING_DECIDED,
+                    None => 0,
+                };
+                let mut binary = BinaryContext::new(u128::from(context.trace_id), context.span_id)
+                    .with_flags(flags);
+                for (key, value) in span_context.baggage_items() {
+                    binary = binary.baggage_item(key.clone(), value.clone());
+                }
+                binary.write(&mut **carrier)
+            },
             InjectFormat::HttpHeaders(carrier) |
             InjectFormat::TextMap(carrier) => {
                 carrier.set(TRACE_ID_KEY, &context.trace_id.to_string());
                 carrier.set(SPAN_ID_KEY, &context.span_id.to_string());
                 for (key, value) in span_context.baggage_items() {
-                    let key = format!("{}{}", BAGGAGE_KEY_PREFIX, key);
-                    carrier.set(&key, value);
+                    carrier.set_baggage(BAGGAGE_KEY_PREFIX, key, value);
                 }
                 Ok(())
             },
-            _ => panic!("Unsupported injection format")
+            InjectFormat::Custom(name, _) => {
+                Err(Error::Msg(format!("Unsupported injection format {}", name)))
+            },
         }
     }
 
-    fn span(&self, name: &str, options: StartOptions) -> Span {
-        let trace_id = random::<u64>();
-        let span_id = random::<u64>();
-        let context = SpanContext::new(ImplContextBox::new(FileTracerContext {
-            trace_id,
-            span_id
-        }));
-        Span::new(name, context, options, self.sender.clone())
+    fn span(&self, name: Arc<str>, options: StartOptions) -> Span {
+        Span::new(name, FileTracer::new_context(), options, Arc::clone(&self.sender))
+    }
+
+    fn context(&self, references: &[SpanReference]) -> SpanContext {
+        let mut context = FileTracer::new_context();
+        for reference in references {
+            context.reference_span(reference);
+        }
+        context
     }
 }
 
 impl FileTracer {
+    /// Creates a context with random trace and span IDs.
+    fn new_context() -> SpanContext {
+        SpanContext::new(ImplContextBox::hashable(FileTracerContext {
+            trace_id: random::<u64>(),
+            span_id: random::<u64>(),
+        }))
+    }
+
     /// Instantiate a new file tracer.
+    #[allow(clippy::new_ret_no_self)]
     pub fn new() -> (Tracer, SpanReceiver) {
         let (sender, receiver) = unbounded();
-        let tracer = FileTracer { sender };
-        (Tracer::new(tracer), receiver)
+        let tracer = FileTracer { sender: Arc::new(sender) };
+        (Tracer::new(tracer), SpanReceiver::new(receiver))
     }
 
     /// Function to write a `FinishedSpan` to a stream.
     ///
     /// Used to send `FinishedSpan`s to an `std::io::Write` stream.
+    /// Spans are written in the `FileTraceFormat::Verbose` format.
     pub fn write_trace<W: Write>(
         span: FinishedSpan, file: &mut W
+    ) -> io::Result<()> {
+        FileTracer::write_trace_with_options(span, file, &FileTracerOptions::default())
+    }
+
+    /// Version of `write_trace` that formats the span according to the given options.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate opentracingrust;
+    ///
+    /// use opentracingrust::tracers::FileTraceFormat;
+    /// use opentracingrust::tracers::FileTracer;
+    /// use opentracingrust::tracers::FileTracerOptions;
+    ///
+    ///
+    /// fn main() {
+    ///     let (tracer, receiver) = FileTracer::new();
+    ///     tracer.span("test").finish().unwrap();
+    ///
+    ///     let options = FileTracerOptions::default()
+    ///         .format(FileTraceFormat::Compact)
+    ///         .prefix("[trace] ");
+    ///     let mut output = Vec::new();
+    ///     let span = receiver.recv().unwrap();
+    ///     FileTracer::write_trace_with_options(span, &mut output, &options).unwrap();
+    ///     assert_eq!(1, String::from_utf8(output).unwrap().lines().count());
+    /// }
+    /// ```
+    pub fn write_trace_with_options<W: Write>(
+        span: FinishedSpan, file: &mut W, options: &FileTracerOptions
     ) -> io::Result<()> {
         let context = span.context().impl_context::<FileTracerContext>();
         let context = context.expect(
             "Unsupported span, was it created by FileTracer?"
         );
+        let trace = match options.format {
+            FileTraceFormat::Compact => compact_trace(&span, context),
+            FileTraceFormat::Verbose => verbose_trace(&span, context),
+        };
         let mut buffer = String::new();
-        buffer.push_str(&format!("==>> Trace ID: {}\n", context.trace_id));
-        buffer.push_str(&format!("===> Span ID: {}\n", context.span_id));
-
-        let finish = span.finish_time();
-        let start = span.start_time();
-        let duration = finish.duration_since(*start).unwrap();
-        let secs = duration.as_secs() as f64;
-        let delta = secs + duration.subsec_nanos() as f64 * 1e-9;
-        buffer.push_str(&format!("===> Span Duration: {}\n", delta));
-
-        buffer.push_str("===> References: [\n");
-        for reference in span.references() {
-            let ref_id = match reference {
-                &SpanReference::ChildOf(ref parent) |
-                &SpanReference::FollowsFrom(ref parent) => {
-                    let context = parent.impl_context::<FileTracerContext>();
-                    let context = context.expect(
-                        "Unsupported span context, was it created by FileTracer?"
-                    );
-                    context.span_id
-                }
-            };
-            let ref_type = match reference {
-                SpanReference::ChildOf(_) => "Child of span ID",
-                SpanReference::FollowsFrom(_) => "Follows from span ID"
-            };
-            buffer.push_str(&format!("===>   * {}: {}\n", ref_type, ref_id));
+        for line in trace.lines() {
+            buffer.push_str(&options.prefix);
+            buffer.push_str(line);
+            buffer.push('\n');
         }
-        buffer.push_str("===> ]\n");
+        file.write_all(buffer.as_bytes())
+    }
+}
+
+
+/// Layouts `FileTracer::write_trace_with_options` can write spans in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileTraceFormat {
+    /// Each span is written on a single line of `key=value` pairs, suited to CI logs.
+    ///
+    /// Names, keys and values that are empty or contain spaces, separators or quotes
+    /// are double quoted, with quotes, backslashes and control characters escaped,
+    /// so each span is always a single line that can be split back into pairs.
+    Compact,
+
+    /// Each span is written over several lines, one for each detail, for local debugging.
+    Verbose,
+}
+
 
-        buffer.push_str("===> Baggage items: [\n");
-        for (key, value) in span.context().baggage_items() {
-            buffer.push_str(&format!("===>   * {}: {}\n", key, value));
+/// Formatting options for `FileTracer::write_trace_with_options`.
+///
+/// The default options write spans in the `FileTraceFormat::Verbose` format without a prefix.
+#[derive(Clone, Debug)]
+pub struct FileTracerOptions {
+    format: FileTraceFormat,
+    prefix: String,
+}
+
+impl FileTracerOptions {
+    /// Sets the layout spans are written in.
+    pub fn format(mut self, format: FileTraceFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Sets a prefix written at the start of every line.
+    pub fn prefix(mut self, prefix: &str) -> Self {
+        self.prefix = String::from(prefix);
+        self
+    }
+}
+
+impl Default for FileTracerOptions {
+    fn default() -> FileTracerOptions {
+        FileTracerOptions {
+            format: FileTraceFormat::Verbose,
+            prefix: String::new(),
         }
-        buffer.push_str("===> ]\n");
-
-        let mut tags: Vec<(&String, &TagValue)> = span.tags().iter().collect();
-        tags.sort_by_key(|&(k, _)| k);
-        buffer.push_str("===> Tags: [\n");
-        for (tag, value) in tags {
-            let value = match value {
-                TagValue::Boolean(v) => v.to_string(),
-                TagValue::Float(v) => v.to_string(),
-                TagValue::Integer(v) => v.to_string(),
-                TagValue::String(ref v) => v.clone(),
-            };
-            buffer.push_str(&format!("===>   * {}: {}\n", tag, value));
+    }
+}
+
+
+/// Formats a span in the `FileTraceFormat::Compact` format.
+fn compact_trace(span: &FinishedSpan, context: &FileTracerContext) -> String {
+    let pair = |key: &str, value: &dyn ToString| {
+        format!("{}={}", compact_quote(key), compact_quote(&value.to_string()))
+    };
+    let references: Vec<String> = span.references().iter()
+        .map(|reference| format!("{}:{}", reference.kind(), reference_span_id(reference)))
+        .collect();
+    let mut baggage: Vec<String> = span.context().baggage_items()
+        .map(|(key, value)| pair(key, value))
+        .collect();
+    baggage.sort();
+    let mut tags: Vec<String> = span.tags().iter()
+        .map(|(tag, value)| pair(tag, value))
+        .collect();
+    tags.sort();
+    let logs: Vec<String> = span.logs().iter()
+        .map(|log| {
+            let mut fields: Vec<String> = log.iter()
+                .map(|(key, value)| pair(key, value))
+                .collect();
+            fields.sort();
+            format!("{}:{{{}}}", log_timestamp(log), fields.join(","))
+        })
+        .collect();
+    format!(
+        "trace_id={} span_id={} name={} start={} finish={} duration={} \
+         references=[{}] baggage={{{}}} tags={{{}}} logs=[{}]\n",
+        context.trace_id, context.span_id, compact_quote(span.name()),
+        epoch_seconds(span.start_time()),
+        epoch_seconds(span.finish_time()), duration_seconds(span), references.join(","),
+        baggage.join(","), tags.join(","), logs.join(",")
+    )
+}
+
+/// Quotes names, keys and values for the `FileTraceFormat::Compact` format, if needed.
+fn compact_quote(value: &str) -> String {
+    let special = |c: char| {
+        c.is_whitespace() || c.is_control() || "\"\\=,{}".contains(c)
+    };
+    if !value.is_empty() && !value.contains(special) {
+        return String::from(value);
+    }
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            c if c.is_control() => quoted.extend(c.escape_default()),
+            c => quoted.push(c),
         }
-        buffer.push_str("===> ]\n");
-
-        buffer.push_str("===> Logs: [\n");
-        for log in span.logs().iter() {
-            let timestamp = log.timestamp().unwrap()
-                .duration_since(UNIX_EPOCH).unwrap()
-                .as_secs();
-            buffer.push_str(&format!("===>   - {}:\n", timestamp));
-
-            let mut fields: Vec<(&String, &LogValue)> = log.iter().collect();
-            fields.sort_by_key(|&(k, _)| k);
-            for (key, value) in fields {
-                let value = match value {
-                    LogValue::Boolean(v) => v.to_string(),
-                    LogValue::Float(v) => v.to_string(),
-                    LogValue::Integer(v) => v.to_string(),
-                    LogValue::String(ref v) => v.clone(),
-                };
-                buffer.push_str(&format!("===>     * {}: {}\n", key, value));
-            }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Formats a span in the `FileTraceFormat::Verbose` format.
+fn verbose_trace(span: &FinishedSpan, context: &FileTracerContext) -> String {
+    let mut buffer = String::new();
+    buffer.push_str(&format!("==>> Trace ID: {}\n", context.trace_id));
+    buffer.push_str(&format!("===> Span ID: {}\n", context.span_id));
+    buffer.push_str(&format!("===> Operation Name: {}\n", span.name()));
+    buffer.push_str(&format!("===> Start Time: {}\n", epoch_seconds(span.start_time())));
+    buffer.push_str(&format!("===> Finish Time: {}\n", epoch_seconds(span.finish_time())));
+    buffer.push_str(&format!("===> Span Duration: {}\n", duration_seconds(span)));
+
+    buffer.push_str("===> References: [\n");
+    for reference in span.references() {
+        let ref_type = match reference {
+            SpanReference::ChildOf(_) => String::from("Child of span ID"),
+            SpanReference::FollowsFrom(_) => String::from("Follows from span ID"),
+            SpanReference::Custom(kind, _) => format!("Custom '{}' span ID", kind),
+        };
+        buffer.push_str(&format!("===>   * {}: {}\n", ref_type, reference_span_id(reference)));
+    }
+    buffer.push_str("===> ]\n");
+
+    buffer.push_str("===> Baggage items: [\n");
+    for (key, value) in span.context().baggage_items() {
+        buffer.push_str(&format!("===>   * {}: {}\n", key, value));
+    }
+    buffer.push_str("===> ]\n");
+
+    let mut tags: Vec<(&String, &TagValue)> = span.tags().iter().collect();
+    tags.sort_by_key(|&(k, _)| k);
+    buffer.push_str("===> Tags: [\n");
+    for (tag, value) in tags {
+        buffer.push_str(&format!("===>   * {}: {}\n", tag, value));
+    }
+    buffer.push_str("===> ]\n");
+
+    buffer.push_str("===> Logs: [\n");
+    for log in span.logs().iter() {
+        buffer.push_str(&format!("===>   - {}:\n", log_timestamp(log)));
+
+        let mut fields: Vec<(&String, &LogValue)> = log.iter().collect();
+        fields.sort_by_key(|&(k, _)| k);
+        for (key, value) in fields {
+            buffer.push_str(&format!("===>     * {}: {}\n", key, value));
         }
-        buffer.push_str("===> ]\n");
-        file.write_all(buffer.as_bytes())
     }
+    buffer.push_str("===> ]\n");
+    buffer
+}
+
+/// Returns the duration of the span in seconds.
+fn duration_seconds(span: &FinishedSpan) -> f64 {
+    let duration = span.duration();
+    duration.as_secs() as f64 + duration.subsec_nanos() as f64 * 1e-9
+}
+
+/// Formats the time of a log like the span's start and finish times (see `epoch_seconds`).
+fn log_timestamp(log: &Log) -> String {
+    match log.timestamp() {
+        Some(timestamp) => epoch_seconds(timestamp),
+        None => String::from("unknown time"),
+    }
+}
+
+/// Returns the span ID of the referenced `FileTracer` context.
+fn reference_span_id(reference: &SpanReference) -> u64 {
+    let context = reference.context().impl_context::<FileTracerContext>();
+    let context = context.expect(
+        "Unsupported span context, was it created by FileTracer?"
+    );
+    context.span_id
+}
+
+/// Formats a time as seconds since the UNIX epoch, with nanosecond precision.
+fn epoch_seconds(time: &SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    format!("{}.{:09}", since_epoch.as_secs(), since_epoch.subsec_nanos())
 }
 
 
 /// Inner `SpanContext` for `FileTracer`.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 struct FileTracerContext {
     trace_id: u64,
     span_id: u64
@@ -256,8 +473,18 @@ impl SpanReferenceAware for FileTracerContext {
                 );
                 self.trace_id = context.trace_id;
             }
+            // Linked spans stay in their own trace.
+            &SpanReference::Custom(_, _) => (),
         }
     }
+
+    fn span_id(&self) -> Option<String> {
+        Some(self.span_id.to_string())
+    }
+
+    fn trace_id(&self) -> Option<String> {
+        Some(self.trace_id.to_string())
+    }
 }
 
 
@@ -272,7 +499,7 @@ mod tests {
     use super::FileTracerContext;
 
     fn make_context(trace_id: u64, span_id: u64) -> SpanContext {
-        SpanContext::new(ImplContextBox::new(FileTracerContext {
+        SpanContext::new(ImplContextBox::hashable(FileTracerContext {
             trace_id,
             span_id
         }))
@@ -288,9 +515,12 @@ mod tests {
         use std::time::Duration;
 
         use super::super::super::super::Log;
+        use super::super::super::super::StartOptions;
 
+        use super::super::FileTraceFormat;
         use super::super::FileTracer;
         use super::super::FileTracerContext;
+        use super::super::FileTracerOptions;
         use super::make_context;
         use super::make_tracer;
 
@@ -301,6 +531,8 @@ mod tests {
 
             use super::super::super::super::super::Error;
             use super::super::super::super::super::ExtractFormat;
+            use super::super::super::super::super::propagation::binary::BinaryContext;
+            use super::super::super::super::super::propagation::binary::FLAG_SAMPLED;
 
             use super::FileTracerContext;
             use super::make_tracer;
@@ -344,6 +576,19 @@ mod tests {
                     }
                 }
 
+                #[test]
+                fn ignores_key_case() {
+                    let (tracer, _) = make_tracer();
+                    let mut map: HashMap<String, String> = HashMap::new();
+                    map.insert(String::from("traceid"), String::from("123"));
+                    map.insert(String::from("spanid"), String::from("456"));
+                    map.insert(String::from("baggage-item"), String::from("ab"));
+                    let context = tracer.extract(
+                        ExtractFormat::HttpHeaders(Box::new(&map))
+                    ).unwrap().unwrap();
+                    assert_eq!("ab", context.get_baggage_item("baggage-item").unwrap());
+                }
+
                 #[test]
                 fn returns_none_without_trace_id() {
                     let (tracer, _) = make_tracer();
@@ -379,13 +624,40 @@ mod tests {
             }
 
             #[test]
-            #[should_panic(expected = "Unsupported extraction format")]
-            fn binary_not_supported() {
+            fn binary() {
                 let (tracer, _) = make_tracer();
-                let mut stdin = io::stdin();
-                tracer.extract(
-                    ExtractFormat::Binary(Box::new(&mut stdin))
-                ).unwrap();
+                let mut buffer = Vec::new();
+                BinaryContext::new(1234, 5678)
+                    .baggage_item("Item1", "ab")
+                    .with_flags(FLAG_SAMPLED)
+                    .write(&mut buffer).unwrap();
+                let mut buffer = io::Cursor::new(buffer);
+
+                let context = tracer.extract(
+                    ExtractFormat::Binary(Box::new(&mut buffer))
+                ).unwrap().unwrap();
+                let inner = context.impl_context::<FileTracerContext>();
+                let inner = inner.unwrap();
+                assert_eq!(1234, inner.trace_id);
+                assert_eq!(5678, inner.span_id);
+                assert_eq!("ab", context.get_baggage_item("Item1").unwrap());
+                assert_eq!(Some(true), context.sampled());
+            }
+
+            #[test]
+            fn binary_trace_id_too_large() {
+                let (tracer, _) = make_tracer();
+                let mut buffer = Vec::new();
+                BinaryContext::new(u128::from(u64::MAX) + 1, 5678)
+                    .write(&mut buffer).unwrap();
+                let mut buffer = io::Cursor::new(buffer);
+                let context = tracer.extract(
+                    ExtractFormat::Binary(Box::new(&mut buffer))
+                );
+                match context {
+                    Err(Error::Msg(_)) => {},
+                    _ => panic!("Expected an error, got {:?}", context),
+                }
             }
 
             #[test]
@@ -414,6 +686,27 @@ mod tests {
                     context.get_baggage_item("Baggage-Item2").unwrap()
                 );
             }
+
+            #[test]
+            fn text_map() {
+                let (tracer, _) = make_tracer();
+                let mut map: HashMap<String, String> = HashMap::new();
+                map.insert(String::from("TraceID"), String::from("1234"));
+                map.insert(String::from("SpanID"), String::from("5678"));
+                map.insert(String::from("Baggage-Item1"), String::from("ab"));
+
+                let context = tracer.extract(
+                    ExtractFormat::TextMap(Box::new(&map))
+                ).unwrap().unwrap();
+                let inner = context.impl_context::<FileTracerContext>();
+                let inner = inner.unwrap();
+                assert_eq!(1234, inner.trace_id);
+                assert_eq!(5678, inner.span_id);
+                assert_eq!(
+                    "ab",
+                    context.get_baggage_item("Baggage-Item1").unwrap()
+                );
+            }
         }
 
 
@@ -421,21 +714,52 @@ mod tests {
             use std::collections::HashMap;
             use std::io;
 
+            use super::super::super::super::super::ExtractFormat;
             use super::super::super::super::super::InjectFormat;
+            use super::super::super::super::super::propagation::binary::BinaryContext;
             use super::make_context;
             use super::make_tracer;
 
 
             #[test]
-            #[should_panic(expected = "Unsupported injection format")]
-            fn binary_not_supported() {
+            fn binary() {
                 let (tracer, _) = make_tracer();
-                let context = make_context(1234, 1234);
-                let mut stdout = io::stdout();
+                let mut context = make_context(1234, 5678);
+                context.set_baggage_item(String::from("Item1"), String::from("ab"));
+                let mut buffer = Vec::new();
                 tracer.inject(
                     &context,
-                    InjectFormat::Binary(Box::new(&mut stdout))
+                    InjectFormat::Binary(Box::new(&mut buffer))
                 ).unwrap();
+
+                let binary = BinaryContext::read(&mut io::Cursor::new(buffer));
+                let binary = binary.unwrap().unwrap();
+                assert_eq!(1234, binary.trace_id());
+                assert_eq!(5678, binary.span_id());
+                assert_eq!(
+                    binary.baggage(),
+                    [(String::from("Item1"), String::from("ab"))]
+                );
+            }
+
+            #[test]
+            fn binary_sampling_round_trip() {
+                let (tracer, _) = make_tracer();
+                for sampled in [None, Some(false), Some(true)].iter() {
+                    let mut context = make_context(1234, 5678);
+                    context.set_sampled(*sampled);
+                    let mut buffer = Vec::new();
+                    tracer.inject(
+                        &context,
+                        InjectFormat::Binary(Box::new(&mut buffer))
+                    ).unwrap();
+
+                    let mut buffer = io::Cursor::new(buffer);
+                    let context = tracer.extract(
+                        ExtractFormat::Binary(Box::new(&mut buffer))
+                    ).unwrap().unwrap();
+                    assert_eq!(*sampled, context.sampled());
+                }
             }
 
             #[test]
@@ -487,7 +811,9 @@ mod tests {
         #[test]
         fn write() {
             let (tracer, receiver) = make_tracer();
-            let mut span = tracer.span("test1");
+            let start = UNIX_EPOCH + Duration::from_millis(1_500);
+            let options = StartOptions::default().start_time(start);
+            let mut span = tracer.span_with_options("test1", options);
             span.child_of(make_context(123456, 123));
             span.follows(make_context(123456, 456));
             span.set_baggage_item("TestKey", "Test Value");
@@ -506,6 +832,7 @@ mod tests {
                 .log("string", "message")
                 .at(UNIX_EPOCH + Duration::from_secs(654321))
             );
+            span.finish_time(UNIX_EPOCH + Duration::from_nanos(3_000_000_001));
             span.finish().unwrap();
 
             let mut buffer = Vec::new();
@@ -516,8 +843,12 @@ mod tests {
             let mut buffer = buffer.split('\n');
             assert_eq!(buffer.next().unwrap(), "==>> Trace ID: 123456");
 
-            let buffer: Vec<&str> = buffer.skip(2).collect();
+            let buffer: Vec<&str> = buffer.skip(1).collect();
             assert_eq!(buffer, [
+                "===> Operation Name: test1",
+                "===> Start Time: 1.500000000",
+                "===> Finish Time: 3.000000001",
+                "===> Span Duration: 1.500000001",
                 "===> References: [",
                 "===>   * Child of span ID: 123",
                 "===>   * Follows from span ID: 456",
@@ -532,15 +863,76 @@ mod tests {
                 "===>   * test.string: hello",
                 "===> ]",
                 "===> Logs: [",
-                "===>   - 123456:",
+                "===>   - 123456.000000000:",
                 "===>     * bool: false",
                 "===>     * float: 0.66",
-                "===>   - 654321:",
+                "===>   - 654321.000000000:",
                 "===>     * int: 66",
                 "===>     * string: message",
                 "===> ]",
                 ""
             ]);
         }
+
+        #[test]
+        fn write_compact() {
+            let (tracer, receiver) = make_tracer();
+            let options = StartOptions::default()
+                .child_of(make_context(123456, 123))
+                .start_time(UNIX_EPOCH + Duration::from_secs(1));
+            let mut span = tracer.span_with_options("test1", options);
+            span.set_baggage_item("TestKey", "Test Value");
+            span.tag("test.int", 5);
+            span.tag("test.bool", true);
+            span.tag("test.quoted", "say \"hi\",\nthen leave");
+            span.log(Log::new()
+                .log("int", 66)
+                .at(UNIX_EPOCH + Duration::from_secs(2))
+            );
+            span.finish_time(UNIX_EPOCH + Duration::from_secs(3));
+            span.finish().unwrap();
+
+            let mut buffer = Vec::new();
+            let span = receiver.recv().unwrap();
+            let span_id = span.context().impl_context::<FileTracerContext>().unwrap().span_id;
+            let options = FileTracerOptions::default()
+                .format(FileTraceFormat::Compact)
+                .prefix("[trace] ");
+            FileTracer::write_trace_with_options(span, &mut buffer, &options).unwrap();
+            assert_eq!(String::from_utf8(buffer).unwrap(), format!(
+                "[trace] trace_id=123456 span_id={} name=test1 start=1.000000000 \
+                 finish=3.000000000 duration=2 references=[child_of:123] \
+                 baggage={{TestKey=\"Test Value\"}} \
+                 tags={{test.bool=true,test.int=5,test.quoted=\"say \\\"hi\\\",\\nthen leave\"}} \
+                 logs=[2.000000000:{{int=66}}]\n",
+                span_id
+            ));
+        }
+
+        #[test]
+        fn write_pre_epoch_log() {
+            let (tracer, receiver) = make_tracer();
+            let mut span = tracer.span("test1");
+            span.log_at(UNIX_EPOCH - Duration::from_secs(1), Log::new().log("int", 66));
+            span.finish().unwrap();
+            let mut buffer = Vec::new();
+            let span = receiver.recv().unwrap();
+            FileTracer::write_trace::<Vec<u8>>(span, &mut buffer).unwrap();
+            let buffer = String::from_utf8(buffer).unwrap();
+            assert!(buffer.contains("===>   - 0.000000000:\n"));
+        }
+
+        #[test]
+        fn write_verbose_with_prefix() {
+            let (tracer, receiver) = make_tracer();
+            tracer.span("test1").finish().unwrap();
+            let mut buffer = Vec::new();
+            let span = receiver.recv().unwrap();
+            let options = FileTracerOptions::default().prefix("> ");
+            FileTracer::write_trace_with_options(span, &mut buffer, &options).unwrap();
+            let buffer = String::from_utf8(buffer).unwrap();
+            assert!(buffer.lines().all(|line| line.starts_with("> ")));
+            assert!(buffer.starts_with("> ==>> Trace ID: "));
+        }
     }
 }
@@ -1,5 +1,13 @@
 mod file;
+mod multi;
 mod noop;
+mod switch;
+mod wrapped;
 
+pub use self::file::FileTraceFormat;
 pub use self::file::FileTracer;
+pub use self::file::FileTracerOptions;
+pub use self::multi::MultiTracer;
 pub use self::noop::NoopTracer;
+pub use self::switch::SwitchTracer;
+pub use self::switch::TracerSwitch;
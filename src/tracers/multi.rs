@@ -0,0 +1,440 @@
+use std::io;
+use std::sync::Arc;
+
+use crossbeam_channel::unbounded;
+
+use super::super::Error;
+use super::super::ImplContextBox;
+use super::super::Result;
+
+use super::super::FinishedSpan;
+use super::super::Span;
+use super::super::SpanContext;
+use super::super::SpanReceiver;
+use super::super::SpanReference;
+use super::super::SpanReferenceAware;
+use super::super::SpanSender;
+use super::super::StartOptions;
+
+use super::super::ExtractFormat;
+use super::super::InjectFormat;
+use super::super::Tracer;
+use super::super::TracerInterface;
+
+use super::wrapped;
+use super::wrapped::with_baggage;
+
+
+/// A tracer that fans out to several tracers at once.
+///
+/// Useful to report to more than one distributed tracer, for example while
+/// migrating from one tracer to another.
+///
+/// Spans created by the `MultiTracer` hold a context for each of the wrapped tracers:
+///
+///   * Injecting a context injects the context of each tracer in turn.
+///   * Extracting a context extracts it with each tracer, skipping tracers
+///     that find no context or fail. Baggage items extracted by all tracers are merged.
+///     Errors are only returned if no tracer extracted a context.
+///   * Span references are passed on to each tracer's context.
+///     No span is started on the wrapped tracers (see `TracerInterface::context`).
+///   * Flushing and closing the `MultiTracer` flushes and closes every tracer,
+///     even if some of them fail.
+///
+/// `FinishedSpan`s are received from the `MultiTracer` receiver and must be split,
+/// with `MultiTracer::split`, into a `FinishedSpan` for each wrapped tracer before they
+/// are reported with the reporting function of the matching tracer.
+///
+/// # Examples
+///
+/// ```
+/// extern crate opentracingrust;
+///
+/// use std::io;
+///
+/// use opentracingrust::tracers::FileTracer;
+/// use opentracingrust::tracers::MultiTracer;
+/// use opentracingrust::tracers::NoopTracer;
+///
+///
+/// fn main() {
+///     let (old, _) = FileTracer::new();
+///     let (new, _) = NoopTracer::new();
+///     let (tracer, receiver) = MultiTracer::new(vec![old, new]);
+///     tracer.span("migrating").finish().unwrap();
+///
+///     let span = receiver.recv().unwrap();
+///     let mut spans = MultiTracer::split(&span).into_iter();
+///     if let Some(span) = spans.next() {
+///         FileTracer::write_trace(span, &mut io::stdout()).unwrap();
+///     }
+///     if let Some(span) = spans.next() {
+///         NoopTracer::report(span);
+///     }
+/// }
+/// ```
+pub struct MultiTracer {
+    sender: SpanSender,
+    tracers: Vec<Tracer>,
+}
+
+impl TracerInterface for MultiTracer {
+    fn extract(&self, mut fmt: ExtractFormat) -> Result<Option<SpanContext>> {
+        let mut contexts = Vec::with_capacity(self.tracers.len());
+        let mut errors = Vec::new();
+        for tracer in &self.tracers {
+            match tracer.extract(reborrow_extract(&mut fmt)) {
+                Ok(context) => contexts.push(context),
+                Err(error) => {
+                    contexts.push(None);
+                    errors.push(error);
+                }
+            }
+        }
+        if contexts.iter().all(Option::is_none) {
+            return collect_errors(errors).map(|_| None);
+        }
+        let mut context = SpanContext::new(ImplContextBox::new(MultiTracerContext {
+            contexts: contexts.clone()
+        }));
+        for inner in contexts.iter().flatten() {
+            for (key, value) in inner.baggage_items() {
+                context.set_baggage_item(key.clone(), value.clone());
+            }
+        }
+        Ok(Some(context))
+    }
+
+    fn inject(&self, context: &SpanContext, mut fmt: InjectFormat) -> Result<()> {
+        let inner = context.impl_context::<MultiTracerContext>();
+        let inner = inner.expect("Unsupported span, was it created by MultiTracer?");
+        for (tracer, inner) in self.tracers.iter().zip(&inner.contexts) {
+            if let Some(inner) = inner {
+                let inner = with_baggage(inner, context);
+                tracer.inject(&inner, reborrow_inject(&mut fmt))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn span(&self, name: Arc<str>, options: StartOptions) -> Span {
+        // References are passed on to the wrapped contexts by `Span::new`.
+        Span::new(name, self.context(&[]), options, Arc::clone(&self.sender))
+    }
+
+    fn context(&self, references: &[SpanReference]) -> SpanContext {
+        let contexts = self.tracers.iter().map(|tracer| Some(tracer.context(&[]))).collect();
+        let mut context = SpanContext::new(ImplContextBox::new(MultiTracerContext { contexts }));
+        for reference in references {
+            context.reference_span(reference);
+        }
+        context
+    }
+
+    fn flush(&self) -> Result<()> {
+        collect_errors(self.tracers.iter().filter_map(|tracer| tracer.flush().err()).collect())
+    }
+
+    fn close(&self) -> Result<()> {
+        collect_errors(self.tracers.iter().filter_map(|tracer| tracer.close().err()).collect())
+    }
+}
+
+impl MultiTracer {
+    /// Instantiate a new `MultiTracer` fanning out to the given tracers.
+    ///
+    /// The receivers of the wrapped tracers are not used and can be dropped.
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(tracers: Vec<Tracer>) -> (Tracer, SpanReceiver) {
+        let (sender, receiver) = unbounded();
+        let tracer = MultiTracer {
+            sender: Arc::new(sender),
+            tracers,
+        };
+        (Tracer::new(tracer), SpanReceiver::new(receiver))
+    }
+
+    /// Splits a `FinishedSpan` into a `FinishedSpan` for each wrapped tracer.
+    ///
+    /// Spans are returned in the order the tracers were given to `MultiTracer::new`.
+    /// References to contexts a tracer has no context for (because the caller did not
+    /// propagate a context for the tracer) are dropped from the tracer's span.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the span was not created by a `MultiTracer`.
+    pub fn split(span: &FinishedSpan) -> Vec<FinishedSpan> {
+        let inner = span.context().impl_context::<MultiTracerContext>();
+        let inner = inner.expect("Unsupported span, was it created by MultiTracer?");
+        inner.contexts.iter().enumerate()
+            .filter_map(|(index, context)| {
+                let context = with_baggage(context.as_ref()?, span.context());
+                let references = span.references().iter()
+                    .filter_map(|reference| inner_reference(reference, index))
+                    .collect();
+                Some(span.with_context(context, references))
+            })
+            .collect()
+    }
+}
+
+
+/// Context of spans created by the `MultiTracer`.
+#[derive(Clone, Debug)]
+struct MultiTracerContext {
+    contexts: Vec<Option<SpanContext>>,
+}
+
+impl SpanReferenceAware for MultiTracerContext {
+    fn reference_span(&mut self, reference: &SpanReference) {
+        for (index, context) in self.contexts.iter_mut().enumerate() {
+            if let (Some(context), Some(reference)) = (context, inner_reference(reference, index)) {
+                context.reference_span(&reference);
+            }
+        }
+    }
+
+    fn span_id(&self) -> Option<String> {
+        self.contexts.iter().flatten().find_map(SpanContext::span_id)
+    }
+
+    fn trace_id(&self) -> Option<String> {
+        self.contexts.iter().flatten().find_map(SpanContext::trace_id)
+    }
+}
+
+
+/// Maps a reference to a `MultiTracer` context to the context of the wrapped tracer at `index`.
+fn inner_reference(reference: &SpanReference, index: usize) -> Option<SpanReference> {
+    wrapped::inner_reference(reference, |context| {
+        let inner = context.impl_context::<MultiTracerContext>()?;
+        Some(with_baggage(inner.contexts.get(index)?.as_ref()?, context))
+    })
+}
+
+/// Fails with the error of the only failed tracer or an error listing all failures.
+#[allow(clippy::result_large_err)]
+fn collect_errors(mut errors: Vec<Error>) -> Result<()> {
+    match errors.len() {
+        0 => Ok(()),
+        1 => Err(errors.remove(0)),
+        _ => {
+            let errors: Vec<String> = errors.iter().map(Error::to_string).collect();
+            Err(Error::Msg(format!("{} tracers failed: {}", errors.len(), errors.join("; "))))
+        },
+    }
+}
+
+/// Borrows the carrier of an extract format for one of the wrapped tracers.
+fn reborrow_extract<'a>(fmt: &'a mut ExtractFormat) -> ExtractFormat<'a> {
+    match fmt {
+        ExtractFormat::Binary(carrier) => {
+            ExtractFormat::Binary(Box::new(&mut ***carrier as &mut dyn io::Read))
+        },
+        ExtractFormat::HttpHeaders(carrier) => ExtractFormat::HttpHeaders(Box::new(&***carrier)),
+        ExtractFormat::TextMap(carrier) => ExtractFormat::TextMap(Box::new(&***carrier)),
+        ExtractFormat::Custom(name, carrier) => ExtractFormat::Custom(name, Box::new(&***carrier)),
+    }
+}
+
+/// Borrows the carrier of an inject format for one of the wrapped tracers.
+fn reborrow_inject<'a>(fmt: &'a mut InjectFormat) -> InjectFormat<'a> {
+    match fmt {
+        InjectFormat::Binary(carrier) => {
+            InjectFormat::Binary(Box::new(&mut ***carrier as &mut dyn io::Write))
+        },
+        InjectFormat::HttpHeaders(carrier) => InjectFormat::HttpHeaders(Box::new(&mut ***carrier)),
+        InjectFormat::TextMap(carrier) => InjectFormat::TextMap(Box::new(&mut ***carrier)),
+        InjectFormat::Custom(name, carrier) => {
+            InjectFormat::Custom(name, Box::new(&mut ***carrier))
+        },
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering;
+
+    use super::super::super::Error;
+    use super::super::super::InjectFormat;
+    use super::super::super::ExtractFormat;
+    use super::super::super::Result;
+    use super::super::super::Span;
+    use super::super::super::SpanContext;
+    use super::super::super::SpanReference;
+    use super::super::super::StartOptions;
+    use super::super::super::Tracer;
+    use super::super::super::TracerInterface;
+    use super::super::FileTracer;
+    use super::super::NoopTracer;
+
+    use super::MultiTracer;
+
+
+    /// Counts flushes and delegates everything else to a `NoopTracer`.
+    ///
+    /// Extract and flush fail if `fail` is set.
+    struct FlushingTracer {
+        fail: bool,
+        flushes: Arc<AtomicUsize>,
+        tracer: Tracer,
+    }
+
+    impl FlushingTracer {
+        #[allow(clippy::new_ret_no_self)]
+        fn new(flushes: &Arc<AtomicUsize>, fail: bool) -> Tracer {
+            Tracer::new(FlushingTracer {
+                fail,
+                flushes: Arc::clone(flushes),
+                tracer: NoopTracer::new().0,
+            })
+        }
+    }
+
+    impl TracerInterface for FlushingTracer {
+        fn extract(&self, fmt: ExtractFormat) -> Result<Option<SpanContext>> {
+            if self.fail {
+                return Err(Error::Msg("extract failed".into()));
+            }
+            self.tracer.extract(fmt)
+        }
+
+        fn inject(&self, context: &SpanContext, fmt: InjectFormat) -> Result<()> {
+            self.tracer.inject(context, fmt)
+        }
+
+        fn span(&self, name: Arc<str>, options: StartOptions) -> Span {
+            self.tracer.span_with_options(name, options)
+        }
+
+        fn flush(&self) -> Result<()> {
+            self.flushes.fetch_add(1, Ordering::SeqCst);
+            if self.fail {
+                return Err(Error::Msg("flush failed".into()));
+            }
+            Ok(())
+        }
+    }
+
+
+    #[test]
+    fn split_spans_per_tracer() {
+        let (file, _) = FileTracer::new();
+        let (noop, _) = NoopTracer::new();
+        let (tracer, receiver) = MultiTracer::new(vec![file, noop]);
+        let mut parent = tracer.span("parent");
+        parent.set_baggage_item("key", "value");
+        let options = StartOptions::default().child_of(parent.context().clone());
+        tracer.span_with_options("child", options).finish().unwrap();
+        parent.finish().unwrap();
+
+        let child = receiver.recv().unwrap();
+        let parent = receiver.recv().unwrap();
+        let children = MultiTracer::split(&child);
+        let parents = MultiTracer::split(&parent);
+        assert_eq!(2, children.len());
+        for (child, parent) in children.iter().zip(&parents) {
+            assert_eq!("child", child.name());
+            assert_eq!("value", child.context().get_baggage_item("key").unwrap());
+            match child.references().as_slice() {
+                [SpanReference::ChildOf(context)] => assert!(context.same_span(parent.context())),
+                _ => panic!("Reference not mapped"),
+            }
+        }
+    }
+
+    #[test]
+    fn flush_and_close_all_tracers() {
+        let flushes = Arc::new(AtomicUsize::new(0));
+        let tracers = (0..2).map(|_| FlushingTracer::new(&flushes, false)).collect();
+        let (tracer, _) = MultiTracer::new(tracers);
+        tracer.flush().unwrap();
+        assert_eq!(2, flushes.load(Ordering::SeqCst));
+        tracer.close().unwrap();
+        assert_eq!(4, flushes.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn flush_all_tracers_on_error() {
+        let flushes = Arc::new(AtomicUsize::new(0));
+        let tracers = vec![
+            FlushingTracer::new(&flushes, true),
+            FlushingTracer::new(&flushes, false),
+            FlushingTracer::new(&flushes, true),
+        ];
+        let (tracer, _) = MultiTracer::new(tracers);
+        match tracer.flush() {
+            Err(Error::Msg(msg)) => assert_eq!("2 tracers failed: flush failed; flush failed", msg),
+            _ => panic!("Flush did not fail"),
+        }
+        assert_eq!(3, flushes.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn extract_with_all_tracers_on_error() {
+        let flushes = Arc::new(AtomicUsize::new(0));
+        let (file, _) = FileTracer::new();
+        let (tracer, _) = MultiTracer::new(vec![FlushingTracer::new(&flushes, true), file]);
+        let mut headers: HashMap<String, String> = HashMap::new();
+        let span = tracer.span("client");
+        tracer.inject(span.context(), InjectFormat::HttpHeaders(Box::new(&mut headers))).unwrap();
+        let context = tracer.extract(ExtractFormat::HttpHeaders(Box::new(&headers))).unwrap();
+        assert!(context.is_some());
+
+        let (tracer, _) = MultiTracer::new(vec![FlushingTracer::new(&flushes, true)]);
+        assert!(tracer.extract(ExtractFormat::HttpHeaders(Box::new(&headers))).is_err());
+    }
+
+    #[test]
+    fn span_skips_wrapped_tracers() {
+        let started = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&started);
+        let (file, _) = FileTracer::new();
+        let file = file.on_span_start(move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+        let (tracer, receiver) = MultiTracer::new(vec![file]);
+        let parent = tracer.span("parent");
+        let options = StartOptions::default().child_of(parent.context().clone());
+        tracer.span_with_options("child", options).finish().unwrap();
+        assert_eq!(0, started.load(Ordering::SeqCst));
+
+        parent.finish().unwrap();
+        let child = MultiTracer::split(&receiver.recv().unwrap());
+        let parent = MultiTracer::split(&receiver.recv().unwrap());
+        assert_eq!(child[0].context().trace_id(), parent[0].context().trace_id());
+    }
+
+    #[test]
+    fn inject_and_extract() {
+        let (file, _) = FileTracer::new();
+        let (noop, _) = NoopTracer::new();
+        let (tracer, receiver) = MultiTracer::new(vec![file, noop]);
+        let mut span = tracer.span("client");
+        span.set_baggage_item("key", "value");
+        let mut headers: HashMap<String, String> = HashMap::new();
+        tracer.inject(span.context(), InjectFormat::HttpHeaders(Box::new(&mut headers))).unwrap();
+        assert!(headers.contains_key("TraceID"));
+
+        let context = tracer.extract(ExtractFormat::HttpHeaders(Box::new(&headers)))
+            .unwrap().unwrap();
+        assert_eq!("value", context.get_baggage_item("Baggage-key").unwrap());
+        let options = StartOptions::default().child_of(context);
+        tracer.span_with_options("server", options).finish().unwrap();
+        let server = MultiTracer::split(&receiver.recv().unwrap());
+        span.finish().unwrap();
+        let client = MultiTracer::split(&receiver.recv().unwrap());
+
+        // The FileTracer propagates the context, the NoopTracer does not.
+        assert_eq!(2, server.len());
+        match server[0].references().as_slice() {
+            [SpanReference::ChildOf(context)] => assert!(context.same_span(client[0].context())),
+            _ => panic!("Context not propagated"),
+        }
+        assert!(server[1].references().is_empty());
+    }
+}
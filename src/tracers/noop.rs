@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use crossbeam_channel::unbounded;
 use rand::random;
 
@@ -21,14 +23,18 @@ use super::super::TracerInterface;
 
 /// A tracer that discards spans.
 ///
-/// Like for other tracers, spans are still collected in full and are cost memory
-/// until they are read from the `FinishedSpan` receiver and dropped.
-///
 /// Unlike other tracers, the `NoopTracer` will not propagate or extract tracing
 /// information and will discard all `FinishedSpan`s when they are `NoopTracer::report`ed.
 ///
-/// This is especially useful as a way to effectively disable tracing in applications built
-/// with OpenTracing support when the end user does not wish to collect tracing information.
+/// Tracers created with `NoopTracer::disabled` create `Span::discarded` spans:
+/// tags, logs and baggage are not stored and nothing is sent when spans finish.
+/// This is the way to effectively disable tracing in applications built with
+/// OpenTracing support when the end user does not wish to collect tracing information.
+///
+/// Tracers created with `NoopTracer::new` still collect spans in full, like other tracers,
+/// and spans cost memory until they are read from the `FinishedSpan` receiver and dropped.
+/// They are kept for compatibility with code that reads the receiver and to test
+/// instrumentation, but should not be used to disable tracing.
 ///
 /// # Examples
 ///
@@ -39,12 +45,14 @@ use super::super::TracerInterface;
 ///
 ///
 /// fn main() {
-///     let (tracer, receiver) = NoopTracer::new();
-///     // ... snip ...
+///     let tracer = NoopTracer::disabled();
+///     let mut span = tracer.span("request");
+///     span.tag("ignored", true);
+///     span.finish().unwrap();
 /// }
 /// ```
 pub struct NoopTracer {
-    sender: SpanSender
+    sender: Option<SpanSender>
 }
 
 impl TracerInterface for NoopTracer {
@@ -58,23 +66,54 @@ impl TracerInterface for NoopTracer {
         Ok(())
     }
 
-    fn span(&self, name: &str, options: StartOptions) -> Span {
-        let trace_id = random::<[u8; 16]>();
-        let span_id = random::<u64>();
-        let context = SpanContext::new(ImplContextBox::new(NoopTracerContext {
-            trace_id,
-            span_id
-        }));
-        Span::new(name, context, options, self.sender.clone())
+    fn span(&self, name: Arc<str>, options: StartOptions) -> Span {
+        let context = self.new_context();
+        match self.sender {
+            Some(ref sender) => Span::new(name, context, options, Arc::clone(sender)),
+            None => Span::discarded(name, context, options),
+        }
+    }
+
+    fn context(&self, references: &[SpanReference]) -> SpanContext {
+        let mut context = self.new_context();
+        for reference in references {
+            context.reference_span(reference);
+        }
+        context
     }
 }
 
 impl NoopTracer {
-    /// Instantiate a new `NoopTracer`.
+    /// Creates a context with random trace and span IDs.
+    ///
+    /// Discarded spans get random IDs too so unrelated contexts never compare equal.
+    fn new_context(&self) -> SpanContext {
+        let context = NoopTracerContext {
+            trace_id: random::<[u8; 16]>(),
+            span_id: random::<u64>(),
+        };
+        SpanContext::new(ImplContextBox::hashable(context))
+    }
+
+    /// Instantiate a new `NoopTracer` that collects spans.
+    ///
+    /// Unlike `NoopTracer::disabled`, spans are recorded in full and sent to the
+    /// returned `SpanReceiver` which must be drained to release their memory.
+    /// This constructor keeps collecting spans because code (and tests) built on it
+    /// read finished spans from the receiver: use `NoopTracer::disabled` to turn
+    /// tracing off.
+    #[allow(clippy::new_ret_no_self)]
     pub fn new() -> (Tracer, SpanReceiver) {
         let (sender, receiver) = unbounded();
-        let tracer = NoopTracer { sender };
-        (Tracer::new(tracer), receiver)
+        let tracer = NoopTracer { sender: Some(Arc::new(sender)) };
+        (Tracer::new(tracer), SpanReceiver::new(receiver))
+    }
+
+    /// Instantiate a `NoopTracer` that creates `Span::discarded` spans.
+    ///
+    /// Spans record nothing and are never sent so no receiver is needed to drain them.
+    pub fn disabled() -> Tracer {
+        Tracer::new(NoopTracer { sender: None })
     }
 
 
@@ -88,7 +127,7 @@ impl NoopTracer {
 }
 
 /// Inner NoopTracer context.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 struct NoopTracerContext {
     trace_id: [u8; 16],
     span_id: u64
@@ -105,8 +144,18 @@ impl SpanReferenceAware for NoopTracerContext {
                 );
                 self.trace_id = context.trace_id;
             }
+            // Linked spans stay in their own trace.
+            &SpanReference::Custom(_, _) => (),
         }
     }
+
+    fn span_id(&self) -> Option<String> {
+        Some(format!("{:016x}", self.span_id))
+    }
+
+    fn trace_id(&self) -> Option<String> {
+        Some(self.trace_id.iter().map(|byte| format!("{:02x}", byte)).collect())
+    }
 }
 
 
@@ -139,6 +188,18 @@ mod tests {
             assert_eq!(inner_parent.trace_id, inner_span.trace_id);
         }
 
+        #[test]
+        fn custom_keeps_trace_id() {
+            let (tracer, _) = NoopTracer::new();
+            let linked = tracer.span("test1");
+            let mut span = tracer.span("test2");
+            let trace_id = span.context().trace_id();
+            span.reference_span(SpanReference::Custom("link", linked.context().clone()));
+
+            assert_eq!(trace_id, span.context().trace_id());
+            assert_ne!(linked.context().trace_id(), span.context().trace_id());
+        }
+
         #[test]
         fn follows_updates_trace_id() {
             let (tracer, _) = NoopTracer::new();
@@ -160,4 +221,29 @@ mod tests {
             span.child_of(parent);
         }
     }
+    mod disabled {
+        use super::super::NoopTracer;
+
+        #[test]
+        fn spans_record_nothing() {
+            let tracer = NoopTracer::disabled();
+            let parent = tracer.span("parent");
+            let mut span = tracer.span("child");
+            span.child_of(parent.context().clone());
+            span.set_baggage_item("key", "value");
+            span.tag("key", "value");
+            assert!(span.references().is_empty());
+            assert!(span.get_baggage_item("key").is_none());
+            span.finish().unwrap();
+            parent.finish().unwrap();
+        }
+
+        #[test]
+        fn contexts_are_distinct() {
+            let tracer = NoopTracer::disabled();
+            let first = tracer.span("first");
+            let second = tracer.span("second");
+            assert_ne!(first.context(), second.context());
+        }
+    }
 }
@@ -0,0 +1,362 @@
+use std::sync::Arc;
+use std::sync::RwLock;
+
+use crossbeam_channel::unbounded;
+
+use super::super::Error;
+use super::super::ImplContextBox;
+use super::super::Result;
+
+use super::super::FinishedSpan;
+use super::super::Span;
+use super::super::SpanContext;
+use super::super::SpanReceiver;
+use super::super::SpanReference;
+use super::super::SpanReferenceAware;
+use super::super::SpanSender;
+use super::super::StartOptions;
+
+use super::super::ExtractFormat;
+use super::super::InjectFormat;
+use super::super::Tracer;
+use super::super::TracerInterface;
+
+use super::wrapped;
+use super::wrapped::with_baggage;
+
+
+/// A tracer whose inner tracer can be replaced at runtime.
+///
+/// Useful to enable (or change) tracing without restarting the process or
+/// re-initialising the `GlobalTracer`, for example when a feature flag flips.
+///
+/// The inner tracer is replaced with `TracerSwitch::switch`.
+/// Each switch starts a new generation of the `SwitchTracer`:
+///
+///   * New spans, injection and extraction use the current inner tracer.
+///   * References to contexts created before the switch are dropped from new spans
+///     because tracers can't reference contexts of other tracers.
+///   * Injecting a context created before the switch fails with an error.
+///
+/// `FinishedSpan`s are received from the `SwitchTracer` receiver and must be unwrapped,
+/// with `SwitchTracer::unwrap`, before they are reported with the reporting function
+/// of the tracer that created them (see `SwitchTracer::generation`).
+///
+/// # Examples
+///
+/// ```
+/// extern crate opentracingrust;
+///
+/// use std::io;
+///
+/// use opentracingrust::tracers::FileTracer;
+/// use opentracingrust::tracers::NoopTracer;
+/// use opentracingrust::tracers::SwitchTracer;
+///
+///
+/// fn main() {
+///     let (noop, _) = NoopTracer::new();
+///     let (tracer, receiver, switch) = SwitchTracer::new(noop);
+///     tracer.span("untraced").finish().unwrap();
+///
+///     let (file, _) = FileTracer::new();
+///     switch.switch(file).unwrap();
+///     tracer.span("traced").finish().unwrap();
+///
+///     for span in receiver.try_iter() {
+///         let generation = SwitchTracer::generation(&span);
+///         let span = SwitchTracer::unwrap(&span);
+///         if generation == 0 {
+///             NoopTracer::report(span);
+///         } else {
+///             FileTracer::write_trace(span, &mut io::stdout()).unwrap();
+///         }
+///     }
+/// }
+/// ```
+pub struct SwitchTracer {
+    current: Arc<RwLock<Generation>>,
+    sender: SpanSender,
+}
+
+impl TracerInterface for SwitchTracer {
+    fn extract(&self, fmt: ExtractFormat) -> Result<Option<SpanContext>> {
+        let current = self.current.read().expect("Failed to read lock SwitchTracer");
+        let context = current.tracer.extract(fmt)?;
+        Ok(context.map(|context| wrap(current.generation, context)))
+    }
+
+    fn inject(&self, context: &SpanContext, fmt: InjectFormat) -> Result<()> {
+        let current = self.current.read().expect("Failed to read lock SwitchTracer");
+        let inner = context.impl_context::<SwitchTracerContext>();
+        let inner = inner.expect("Unsupported span, was it created by SwitchTracer?");
+        if inner.generation != current.generation {
+            return Err(Error::Msg(String::from(
+                "Unable to inject a context created before the tracer was switched"
+            )));
+        }
+        current.tracer.inject(&with_baggage(&inner.context, context), fmt)
+    }
+
+    fn span(&self, name: Arc<str>, options: StartOptions) -> Span {
+        // References are passed on to the inner context by `Span::new`.
+        Span::new(name, self.context(&[]), options, Arc::clone(&self.sender))
+    }
+
+    fn context(&self, references: &[SpanReference]) -> SpanContext {
+        let current = self.current.read().expect("Failed to read lock SwitchTracer");
+        let mut context = wrap(current.generation, current.tracer.context(&[]));
+        for reference in references {
+            context.reference_span(reference);
+        }
+        context
+    }
+
+    fn flush(&self) -> Result<()> {
+        self.current.read().expect("Failed to read lock SwitchTracer").tracer.flush()
+    }
+
+    fn close(&self) -> Result<()> {
+        self.current.read().expect("Failed to read lock SwitchTracer").tracer.close()
+    }
+}
+
+impl SwitchTracer {
+    /// Instantiate a new `SwitchTracer` wrapping the given tracer.
+    ///
+    /// The receivers of the wrapped tracers are not used and can be dropped.
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(tracer: Tracer) -> (Tracer, SpanReceiver, TracerSwitch) {
+        let (sender, receiver) = unbounded();
+        let current = Arc::new(RwLock::new(Generation {
+            generation: 0,
+            tracer,
+        }));
+        let tracer = SwitchTracer {
+            current: Arc::clone(&current),
+            sender: Arc::new(sender),
+        };
+        let switch = TracerSwitch { current };
+        (Tracer::new(tracer), SpanReceiver::new(receiver), switch)
+    }
+
+    /// Returns the generation of the inner tracer that created the span.
+    ///
+    /// The first tracer is generation `0` and each switch increments the generation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the span was not created by a `SwitchTracer`.
+    pub fn generation(span: &FinishedSpan) -> u64 {
+        let inner = span.context().impl_context::<SwitchTracerContext>();
+        let inner = inner.expect("Unsupported span, was it created by SwitchTracer?");
+        inner.generation
+    }
+
+    /// Converts a `FinishedSpan` into the span of the inner tracer that created it.
+    ///
+    /// References to contexts created by other generations are dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the span was not created by a `SwitchTracer`.
+    pub fn unwrap(span: &FinishedSpan) -> FinishedSpan {
+        let inner = span.context().impl_context::<SwitchTracerContext>();
+        let inner = inner.expect("Unsupported span, was it created by SwitchTracer?");
+        let context = with_baggage(&inner.context, span.context());
+        let references = span.references().iter()
+            .filter_map(|reference| inner_reference(reference, inner.generation))
+            .collect();
+        span.with_context(context, references)
+    }
+}
+
+
+/// Replaces the inner tracer of a `SwitchTracer`.
+///
+/// Returned by `SwitchTracer::new` and can be shared across threads.
+pub struct TracerSwitch {
+    current: Arc<RwLock<Generation>>,
+}
+
+impl TracerSwitch {
+    /// Replaces the inner tracer, returning the generation of the new tracer.
+    ///
+    /// Spans that are already started are not affected by the switch.
+    /// The previous tracer is closed once replaced: the tracer is switched even if
+    /// closing the previous tracer fails, in which case the error is returned.
+    #[allow(clippy::result_large_err)]
+    pub fn switch(&self, tracer: Tracer) -> Result<u64> {
+        let (generation, previous) = {
+            let mut current = self.current.write().expect("Failed to write lock SwitchTracer");
+            current.generation += 1;
+            let previous = ::std::mem::replace(&mut current.tracer, tracer);
+            (current.generation, previous)
+        };
+        previous.close()?;
+        Ok(generation)
+    }
+}
+
+
+/// Current inner tracer of a `SwitchTracer`.
+struct Generation {
+    generation: u64,
+    tracer: Tracer,
+}
+
+
+/// Context of spans created by the `SwitchTracer`.
+#[derive(Clone, Debug)]
+struct SwitchTracerContext {
+    context: SpanContext,
+    generation: u64,
+}
+
+impl SpanReferenceAware for SwitchTracerContext {
+    fn reference_span(&mut self, reference: &SpanReference) {
+        if let Some(reference) = inner_reference(reference, self.generation) {
+            self.context.reference_span(&reference);
+        }
+    }
+
+    fn span_id(&self) -> Option<String> {
+        self.context.span_id()
+    }
+
+    fn trace_id(&self) -> Option<String> {
+        self.context.trace_id()
+    }
+}
+
+
+/// Maps a reference to a `SwitchTracer` context to the context of the inner tracer.
+///
+/// References to contexts of other generations are dropped.
+fn inner_reference(reference: &SpanReference, generation: u64) -> Option<SpanReference> {
+    wrapped::inner_reference(reference, |context| {
+        let inner = context.impl_context::<SwitchTracerContext>()?;
+        if inner.generation != generation {
+            return None;
+        }
+        Some(with_baggage(&inner.context, context))
+    })
+}
+
+/// Wraps a context of the inner tracer into a `SwitchTracer` context.
+fn wrap(generation: u64, context: SpanContext) -> SpanContext {
+    let mut outer = SpanContext::new(ImplContextBox::new(SwitchTracerContext {
+        context: context.clone(),
+        generation,
+    }));
+    for (key, value) in context.baggage_items() {
+        outer.set_baggage_item(key.clone(), value.clone());
+    }
+    outer
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::atomic::Ordering;
+
+    use super::super::super::ExtractFormat;
+    use super::super::super::InjectFormat;
+    use super::super::super::Result;
+    use super::super::super::Span;
+    use super::super::super::SpanContext;
+    use super::super::super::SpanReference;
+    use super::super::super::StartOptions;
+    use super::super::super::Tracer;
+    use super::super::super::TracerInterface;
+    use super::super::FileTracer;
+    use super::super::NoopTracer;
+
+    use super::SwitchTracer;
+
+
+    /// Records when it is closed and delegates everything else to a `NoopTracer`.
+    struct ClosingTracer {
+        closed: Arc<AtomicBool>,
+        tracer: Tracer,
+    }
+
+    impl TracerInterface for ClosingTracer {
+        fn extract(&self, fmt: ExtractFormat) -> Result<Option<SpanContext>> {
+            self.tracer.extract(fmt)
+        }
+
+        fn inject(&self, context: &SpanContext, fmt: InjectFormat) -> Result<()> {
+            self.tracer.inject(context, fmt)
+        }
+
+        fn span(&self, name: Arc<str>, options: StartOptions) -> Span {
+            self.tracer.span_with_options(name, options)
+        }
+
+        fn close(&self) -> Result<()> {
+            self.closed.store(true, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+
+    #[test]
+    fn switch_closes_previous_tracer() {
+        let closed = Arc::new(AtomicBool::new(false));
+        let previous = Tracer::new(ClosingTracer {
+            closed: Arc::clone(&closed),
+            tracer: NoopTracer::new().0,
+        });
+        let (_, _, switch) = SwitchTracer::new(previous);
+        let (file, _) = FileTracer::new();
+        assert_eq!(1, switch.switch(file).unwrap());
+        assert!(closed.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn switch_inner_tracer() {
+        let (noop, _) = NoopTracer::new();
+        let (tracer, receiver, switch) = SwitchTracer::new(noop);
+        let parent = tracer.span("parent");
+        let mut headers: HashMap<String, String> = HashMap::new();
+        tracer.inject(parent.context(), InjectFormat::HttpHeaders(Box::new(&mut headers)))
+            .unwrap();
+        assert!(headers.is_empty());
+
+        let (file, _) = FileTracer::new();
+        assert_eq!(1, switch.switch(file).unwrap());
+        let format = InjectFormat::HttpHeaders(Box::new(&mut headers));
+        assert!(tracer.inject(parent.context(), format).is_err());
+
+        let options = StartOptions::default().child_of(parent.context().clone());
+        let mut child = tracer.span_with_options("child", options);
+        tracer.inject(child.context(), InjectFormat::HttpHeaders(Box::new(&mut headers)))
+            .unwrap();
+        assert!(headers.contains_key("TraceID"));
+        let options = StartOptions::default().child_of(child.context().clone());
+        tracer.span_with_options("grandchild", options).finish().unwrap();
+        child.set_baggage_item("key", "value");
+        child.finish().unwrap();
+        parent.finish().unwrap();
+
+        let grandchild = receiver.recv().unwrap();
+        let child = receiver.recv().unwrap();
+        let parent = receiver.recv().unwrap();
+        assert_eq!(0, SwitchTracer::generation(&parent));
+        assert_eq!(1, SwitchTracer::generation(&child));
+
+        let grandchild = SwitchTracer::unwrap(&grandchild);
+        let child = SwitchTracer::unwrap(&child);
+        assert!(child.references().is_empty());
+        assert_eq!("value", child.context().get_baggage_item("key").unwrap());
+        match grandchild.references().as_slice() {
+            [SpanReference::ChildOf(context)] => assert!(context.same_span(child.context())),
+            _ => panic!("Reference not mapped"),
+        }
+        FileTracer::write_trace(child, &mut Vec::new()).unwrap();
+    }
+}
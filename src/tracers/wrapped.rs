@@ -0,0 +1,29 @@
+use super::super::SpanContext;
+use super::super::SpanReference;
+
+
+/// Maps a reference to a context of a wrapping tracer to a reference to a wrapped context.
+///
+/// `inner_context` looks up the wrapped context and returns `None` to drop the reference.
+pub fn inner_reference<F>(reference: &SpanReference, inner_context: F) -> Option<SpanReference>
+    where F: FnOnce(&SpanContext) -> Option<SpanContext>
+{
+    match reference {
+        SpanReference::ChildOf(context) => inner_context(context).map(SpanReference::ChildOf),
+        SpanReference::FollowsFrom(context) => {
+            inner_context(context).map(SpanReference::FollowsFrom)
+        },
+        SpanReference::Custom(kind, context) => {
+            inner_context(context).map(|context| SpanReference::Custom(kind, context))
+        },
+    }
+}
+
+/// Copies the baggage of the wrapping tracer's context into the wrapped tracer's context.
+pub fn with_baggage(inner: &SpanContext, context: &SpanContext) -> SpanContext {
+    let mut inner = inner.clone();
+    for (key, value) in context.baggage_items() {
+        inner.set_baggage_item(key.clone(), value.clone());
+    }
+    inner
+}
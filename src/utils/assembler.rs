@@ -0,0 +1,597 @@
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::fmt;
+use std::mem;
+use std::slice::Iter;
+use std::time::Duration;
+use std::time::Instant;
+
+use super::super::FinishedSpan;
+use super::super::TagValue;
+
+
+/// Tags naming the services involved in a trace.
+const SERVICE_TAGS: [&str; 2] = ["service.name", "peer.service"];
+
+/// Default time to wait for the root of a trace to finish.
+const TRACE_TIMEOUT_SEC_DEFAULT: u64 = 30;
+
+
+/// A trace assembled by a `TraceAssembler`.
+///
+/// Spans are stored in the order they were received.
+#[derive(Clone, Debug)]
+pub struct CompletedTrace {
+    complete: bool,
+    spans: Vec<FinishedSpan>,
+}
+
+impl CompletedTrace {
+    /// Returns the spans in the trace.
+    pub fn into_spans(self) -> Vec<FinishedSpan> {
+        self.spans
+    }
+
+    /// Returns `true` if the trace was emitted because its root span finished.
+    ///
+    /// Traces emitted because they timed out or were flushed are not complete.
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    /// Iterates over the spans in the trace.
+    pub fn iter(&self) -> Iter<'_, FinishedSpan> {
+        self.spans.iter()
+    }
+
+    /// Returns the root span of the trace, if it was received.
+    pub fn root(&self) -> Option<&FinishedSpan> {
+        self.spans.iter().find(|span| span.is_root())
+    }
+
+    /// Returns the spans in the trace.
+    pub fn spans(&self) -> &[FinishedSpan] {
+        &self.spans
+    }
+
+    /// Computes summary statistics for the trace.
+    pub fn stats(&self) -> TraceStats {
+        let root_duration = match self.root() {
+            Some(root) => root.duration(),
+            None => {
+                let start = self.spans.iter().map(|span| *span.start_time()).min();
+                let finish = self.spans.iter().map(|span| *span.finish_time()).max();
+                match (start, finish) {
+                    (Some(start), Some(finish)) => finish.duration_since(start).unwrap_or_default(),
+                    _ => Duration::default(),
+                }
+            }
+        };
+        let error_count = self.spans.iter()
+            .filter(|span| matches!(span.tags().get("error"), Some(&TagValue::Boolean(true))))
+            .count();
+        let services = self.spans.iter()
+            .flat_map(|span| SERVICE_TAGS.iter().filter_map(move |tag| span.tags().get(tag)))
+            .map(|service| service.to_string())
+            .collect();
+        TraceStats {
+            error_count,
+            root_duration,
+            services,
+            span_count: self.spans.len(),
+            total_duration: self.spans.iter().map(FinishedSpan::duration).sum(),
+        }
+    }
+}
+
+
+/// Summary statistics of a `CompletedTrace`.
+///
+/// The `Display` implementation renders the statistics as a single line
+/// suitable for logging a summary of each trace.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceStats {
+    error_count: usize,
+    root_duration: Duration,
+    services: BTreeSet<String>,
+    span_count: usize,
+    total_duration: Duration,
+}
+
+impl TraceStats {
+    /// Returns the number of spans tagged with `error` set to `true`.
+    pub fn error_count(&self) -> usize {
+        self.error_count
+    }
+
+    /// Returns the end-to-end duration of the trace.
+    ///
+    /// This is the duration of the root span or, if the root span was not received,
+    /// the time between the earliest start and the latest finish of the spans.
+    pub fn root_duration(&self) -> Duration {
+        self.root_duration
+    }
+
+    /// Returns the services involved in the trace.
+    ///
+    /// Services are collected from the `service.name` and `peer.service` tags.
+    pub fn services(&self) -> &BTreeSet<String> {
+        &self.services
+    }
+
+    /// Returns the number of spans in the trace.
+    pub fn span_count(&self) -> usize {
+        self.span_count
+    }
+
+    /// Returns the sum of the durations of all spans in the trace.
+    pub fn total_duration(&self) -> Duration {
+        self.total_duration
+    }
+}
+
+impl fmt::Display for TraceStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let services: Vec<&str> = self.services.iter().map(|service| &service[..]).collect();
+        write!(
+            f, "spans={} root={:?} total={:?} errors={} services=[{}]",
+            self.span_count, self.root_duration, self.total_duration,
+            self.error_count, services.join(", ")
+        )
+    }
+}
+
+
+/// Counters of incomplete traces emitted early by a `TraceAssembler`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AssemblerMetrics {
+    evicted_spans: u64,
+    evicted_traces: u64,
+    expired_traces: u64,
+}
+
+impl AssemblerMetrics {
+    /// Returns the number of spans in traces evicted to respect the memory budget.
+    pub fn evicted_spans(&self) -> u64 {
+        self.evicted_spans
+    }
+
+    /// Returns the number of traces evicted to respect the memory budget.
+    pub fn evicted_traces(&self) -> u64 {
+        self.evicted_traces
+    }
+
+    /// Returns the number of traces emitted because they timed out.
+    pub fn expired_traces(&self) -> u64 {
+        self.expired_traces
+    }
+}
+
+
+/// Spans of a trace still waiting for their root span.
+struct PendingTrace {
+    first_seen: Instant,
+    last_update: u64,
+    spans: Vec<FinishedSpan>,
+}
+
+impl PendingTrace {
+    fn new(span: FinishedSpan) -> PendingTrace {
+        PendingTrace {
+            first_seen: Instant::now(),
+            last_update: 0,
+            spans: vec![span],
+        }
+    }
+}
+
+
+/// Groups `FinishedSpan`s into traces on the receiving end of a tracer.
+///
+/// Spans are grouped by trace ID (see `SpanContext::trace_id`) so spans of concurrent
+/// traces are never mixed and each span is added to its trace in constant time.
+/// A trace is passed to the callback as a `CompletedTrace` when:
+///
+///   * Its root span is received: spans of the trace received later are treated as a new trace.
+///   * The first span of the trace was received longer than the timeout ago (30 seconds by default).
+///     Timed out traces are only emitted when new spans are pushed or when `expire` is called.
+///   * The number of pending spans exceeds the memory budget set with
+///     `TraceAssembler::max_pending_spans`: the least recently updated traces are evicted.
+///   * `TraceAssembler::flush` is called.
+///
+/// Traces emitted because they timed out or were evicted are counted in `AssemblerMetrics`.
+///
+/// Grouping requires the tracer to expose trace IDs, otherwise every span is emitted
+/// as its own trace as soon as it is pushed.
+///
+/// # Examples
+///
+/// ```
+/// extern crate opentracingrust;
+///
+/// use std::sync::Arc;
+/// use std::sync::Mutex;
+///
+/// use opentracingrust::StartOptions;
+/// use opentracingrust::tracers::NoopTracer;
+/// use opentracingrust::utils::TraceAssembler;
+///
+///
+/// fn main() {
+///     let (tracer, receiver) = NoopTracer::new();
+///     let sizes = Arc::new(Mutex::new(Vec::new()));
+///     let collected = Arc::clone(&sizes);
+///     let mut assembler = TraceAssembler::new(move |trace| {
+///         collected.lock().unwrap().push(trace.spans().len());
+///     });
+///
+///     let root = tracer.span("request");
+///     let options = StartOptions::default().child_of(root.context().clone());
+///     tracer.span_with_options("query", options).finish().unwrap();
+///     root.finish().unwrap();
+///
+///     for span in receiver.try_iter() {
+///         assembler.push(span);
+///     }
+///     assert_eq!(*sizes.lock().unwrap(), [2]);
+/// }
+/// ```
+pub struct TraceAssembler {
+    callback: Box<dyn FnMut(CompletedTrace) + Send>,
+    max_pending_spans: Option<usize>,
+    metrics: AssemblerMetrics,
+    pending: HashMap<String, PendingTrace>,
+    pending_spans: usize,
+    // Trace IDs in the order traces were first seen, to expire them.
+    seen: VecDeque<(Instant, String)>,
+    timeout: Duration,
+    // Trace IDs from the least to the most recently updated, to evict them.
+    updated: BTreeMap<u64, String>,
+    updates: u64,
+}
+
+impl TraceAssembler {
+    /// Creates a `TraceAssembler` that passes assembled traces to `callback`.
+    pub fn new<F>(callback: F) -> TraceAssembler
+        where F: FnMut(CompletedTrace) + Send + 'static
+    {
+        TraceAssembler {
+            callback: Box::new(callback),
+            max_pending_spans: None,
+            metrics: AssemblerMetrics::default(),
+            pending: HashMap::new(),
+            pending_spans: 0,
+            seen: VecDeque::new(),
+            timeout: Duration::from_secs(TRACE_TIMEOUT_SEC_DEFAULT),
+            updated: BTreeMap::new(),
+            updates: 0,
+        }
+    }
+
+    /// Sets the maximum number of spans waiting for their trace to be emitted.
+    ///
+    /// When a pushed span exceeds the budget the least recently updated traces
+    /// are emitted as incomplete until the budget is respected again.
+    /// There is no limit by default.
+    pub fn max_pending_spans(mut self, max_pending_spans: usize) -> TraceAssembler {
+        self.max_pending_spans = Some(max_pending_spans);
+        self
+    }
+
+    /// Sets how long to wait for the root of a trace to finish.
+    pub fn timeout(mut self, timeout: Duration) -> TraceAssembler {
+        self.timeout = timeout;
+        self
+    }
+}
+
+impl TraceAssembler {
+    /// Emits traces that waited for their root span longer than the timeout.
+    pub fn expire(&mut self) {
+        let now = Instant::now();
+        while let Some((first_seen, _)) = self.seen.front() {
+            if now.duration_since(*first_seen) < self.timeout {
+                break;
+            }
+            let (first_seen, trace_id) = self.seen.pop_front().expect("Trace to expire");
+            // The trace may have been emitted already and a new one started with the same ID.
+            let expired = self.pending.get(&trace_id)
+                .map(|trace| trace.first_seen == first_seen)
+                .unwrap_or(false);
+            if expired {
+                let trace = self.remove(&trace_id);
+                self.metrics.expired_traces += 1;
+                self.emit(trace, false);
+            }
+        }
+    }
+
+    /// Emits all pending traces, even if their root span was not received.
+    pub fn flush(&mut self) {
+        let updated: Vec<String> = mem::take(&mut self.updated).into_values().collect();
+        for trace_id in updated {
+            let trace = self.remove(&trace_id);
+            self.emit(trace, false);
+        }
+        self.seen.clear();
+    }
+
+    /// Returns the counters of incomplete traces emitted early.
+    pub fn metrics(&self) -> AssemblerMetrics {
+        self.metrics
+    }
+
+    /// Returns the number of spans waiting for their trace to be emitted.
+    pub fn pending_spans(&self) -> usize {
+        self.pending_spans
+    }
+
+    /// Adds a finished span to its trace.
+    ///
+    /// The trace is emitted if the span is its root.
+    pub fn push(&mut self, span: FinishedSpan) {
+        let is_root = span.is_root();
+        let trace_id = match span.context().trace_id() {
+            Some(trace_id) => trace_id,
+            None => {
+                self.emit(PendingTrace::new(span), is_root);
+                self.expire();
+                return;
+            }
+        };
+        self.updates += 1;
+        self.pending_spans += 1;
+        match self.pending.get_mut(&trace_id) {
+            Some(trace) => {
+                self.updated.remove(&trace.last_update);
+                trace.last_update = self.updates;
+                trace.spans.push(span);
+            },
+            None => {
+                let mut trace = PendingTrace::new(span);
+                trace.last_update = self.updates;
+                self.seen.push_back((trace.first_seen, trace_id.clone()));
+                self.pending.insert(trace_id.clone(), trace);
+            },
+        }
+        self.updated.insert(self.updates, trace_id.clone());
+
+        if is_root {
+            let trace = self.remove(&trace_id);
+            self.emit(trace, true);
+        }
+        self.evict();
+        self.expire();
+        // Drop the expiry entries of emitted traces once they outnumber pending traces.
+        if self.seen.len() > 2 * self.pending.len() + 16 {
+            let pending = &self.pending;
+            self.seen.retain(|(first_seen, trace_id)| {
+                pending.get(trace_id).map(|trace| trace.first_seen == *first_seen).unwrap_or(false)
+            });
+        }
+    }
+}
+
+impl TraceAssembler {
+    /// Emits the least recently updated traces until the memory budget is respected.
+    fn evict(&mut self) {
+        let max_pending_spans = match self.max_pending_spans {
+            None => return,
+            Some(max_pending_spans) => max_pending_spans,
+        };
+        while self.pending_spans > max_pending_spans {
+            let trace_id = match self.updated.values().next() {
+                None => break,
+                Some(trace_id) => trace_id.clone(),
+            };
+            let trace = self.remove(&trace_id);
+            self.metrics.evicted_spans += trace.spans.len() as u64;
+            self.metrics.evicted_traces += 1;
+            self.emit(trace, false);
+        }
+    }
+
+    /// Passes a trace to the callback.
+    fn emit(&mut self, trace: PendingTrace, complete: bool) {
+        let trace = CompletedTrace {
+            complete,
+            spans: trace.spans,
+        };
+        (self.callback)(trace);
+    }
+
+    /// Removes a pending trace.
+    ///
+    /// The trace is left in the expiry queue, which skips traces that are no longer pending.
+    fn remove(&mut self, trace_id: &str) -> PendingTrace {
+        let trace = self.pending.remove(trace_id).expect("Trace to remove should be pending");
+        self.updated.remove(&trace.last_update);
+        self.pending_spans -= trace.spans.len();
+        trace
+    }
+}
+
+impl Drop for TraceAssembler {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::Mutex;
+    use std::time::Duration;
+    use std::time::UNIX_EPOCH;
+
+    use super::super::super::StartOptions;
+    use super::super::super::tracers::NoopTracer;
+
+    use super::CompletedTrace;
+    use super::TraceAssembler;
+
+
+    fn collector() -> (TraceAssembler, Arc<Mutex<Vec<CompletedTrace>>>) {
+        let traces = Arc::new(Mutex::new(Vec::new()));
+        let collected = Arc::clone(&traces);
+        let assembler = TraceAssembler::new(move |trace| {
+            collected.lock().unwrap().push(trace);
+        });
+        (assembler, traces)
+    }
+
+    #[test]
+    fn emits_trace_on_root() {
+        let (tracer, receiver) = NoopTracer::new();
+        let (mut assembler, traces) = collector();
+        let root = tracer.span("root");
+        let options = StartOptions::default().child_of(root.context().clone());
+        let child = tracer.span_with_options("child", options);
+        let options = StartOptions::default().child_of(child.context().clone());
+        let grandchild = tracer.span_with_options("grandchild", options);
+        let other = tracer.span("other");
+
+        // Push the child before the grandchild to merge separate pending traces.
+        child.finish().unwrap();
+        grandchild.finish().unwrap();
+        root.finish().unwrap();
+        for span in receiver.try_iter() {
+            assembler.push(span);
+        }
+        {
+            let traces = traces.lock().unwrap();
+            assert_eq!(1, traces.len());
+            assert!(traces[0].is_complete());
+            assert_eq!("root", traces[0].root().unwrap().name());
+            let names: Vec<&str> = traces[0].iter().map(|span| span.name()).collect();
+            assert_eq!(names, ["child", "grandchild", "root"]);
+        }
+
+        other.finish().unwrap();
+        assembler.push(receiver.try_recv().unwrap());
+        assert_eq!(2, traces.lock().unwrap().len());
+        assert_eq!(0, assembler.pending_spans());
+    }
+
+    #[test]
+    fn group_concurrent_traces() {
+        let (tracer, receiver) = NoopTracer::new();
+        let (mut assembler, traces) = collector();
+        let first = tracer.span("first");
+        let second = tracer.span("second");
+        for root in &[&first, &second, &first, &second] {
+            let options = StartOptions::default().child_of(root.context().clone());
+            tracer.span_with_options("child", options).finish().unwrap();
+        }
+        let trace_id = first.context().trace_id();
+        first.finish().unwrap();
+        for span in receiver.try_iter() {
+            assembler.push(span);
+        }
+        assert_eq!(2, assembler.pending_spans());
+        let traces = traces.lock().unwrap();
+        assert_eq!(1, traces.len());
+        assert_eq!(3, traces[0].spans().len());
+        assert!(traces[0].iter().all(|span| span.context().trace_id() == trace_id));
+    }
+
+    #[test]
+    fn flush_incomplete_traces() {
+        let (tracer, receiver) = NoopTracer::new();
+        let (mut assembler, traces) = collector();
+        let root = tracer.span("root");
+        let options = StartOptions::default().child_of(root.context().clone());
+        tracer.span_with_options("child", options).finish().unwrap();
+        assembler.push(receiver.try_recv().unwrap());
+        assert_eq!(1, assembler.pending_spans());
+        assert!(traces.lock().unwrap().is_empty());
+
+        drop(assembler);
+        let traces = traces.lock().unwrap();
+        assert_eq!(1, traces.len());
+        assert!(!traces[0].is_complete());
+        assert!(traces[0].root().is_none());
+    }
+
+    #[test]
+    fn expire_incomplete_traces() {
+        let (tracer, receiver) = NoopTracer::new();
+        let (assembler, traces) = collector();
+        let mut assembler = assembler.timeout(Duration::from_secs(0));
+        let root = tracer.span("root");
+        let options = StartOptions::default().child_of(root.context().clone());
+        tracer.span_with_options("child", options).finish().unwrap();
+        assembler.push(receiver.try_recv().unwrap());
+        assert_eq!(0, assembler.pending_spans());
+        assert!(!traces.lock().unwrap()[0].is_complete());
+        assert_eq!(1, assembler.metrics().expired_traces());
+    }
+
+    #[test]
+    fn evict_least_recently_updated_traces() {
+        let (tracer, receiver) = NoopTracer::new();
+        let (assembler, traces) = collector();
+        let mut assembler = assembler.max_pending_spans(2);
+        let first = tracer.span("first");
+        let second = tracer.span("second");
+        let options = StartOptions::default().child_of(first.context().clone());
+        let first_child = tracer.span_with_options("first.child", options);
+        let options = StartOptions::default().child_of(second.context().clone());
+        tracer.span_with_options("second.child", options).finish().unwrap();
+        let options = StartOptions::default().child_of(first_child.context().clone());
+        tracer.span_with_options("first.grandchild", options).finish().unwrap();
+        first_child.finish().unwrap();
+
+        // The second trace is the least recently updated one once the first child is pushed.
+        for span in receiver.try_iter() {
+            assembler.push(span);
+        }
+        assert_eq!(2, assembler.pending_spans());
+        let metrics = assembler.metrics();
+        assert_eq!(1, metrics.evicted_spans());
+        assert_eq!(1, metrics.evicted_traces());
+        let traces = traces.lock().unwrap();
+        assert_eq!(1, traces.len());
+        assert!(!traces[0].is_complete());
+        let names: Vec<&str> = traces[0].iter().map(|span| span.name()).collect();
+        assert_eq!(names, ["second.child"]);
+    }
+
+    #[test]
+    fn trace_stats() {
+        let (tracer, receiver) = NoopTracer::new();
+        let (mut assembler, traces) = collector();
+        let at = |millis| UNIX_EPOCH + Duration::from_millis(millis);
+
+        let mut root = tracer.span_with_options("root", StartOptions::default().start_time(at(0)));
+        root.tag("service.name", "api");
+        root.finish_time(at(100));
+        for (index, service) in ["db", "cache"].iter().enumerate() {
+            let options = StartOptions::default()
+                .child_of(root.context().clone())
+                .start_time(at(10));
+            let mut child = tracer.span_with_options("call", options);
+            child.tag("peer.service", *service);
+            child.tag("error", index == 0);
+            child.finish_time(at(50));
+            child.finish().unwrap();
+        }
+        root.finish().unwrap();
+        for span in receiver.try_iter() {
+            assembler.push(span);
+        }
+
+        let stats = traces.lock().unwrap()[0].stats();
+        assert_eq!(3, stats.span_count());
+        assert_eq!(1, stats.error_count());
+        assert_eq!(Duration::from_millis(100), stats.root_duration());
+        assert_eq!(Duration::from_millis(180), stats.total_duration());
+        assert_eq!(
+            stats.to_string(),
+            "spans=3 root=100ms total=180ms errors=1 services=[api, cache, db]"
+        );
+    }
+}
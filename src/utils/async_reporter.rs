@@ -0,0 +1,169 @@
+use std::future::Future;
+use std::thread::Builder;
+
+use crossbeam_channel::Sender;
+use crossbeam_channel::select;
+use crossbeam_channel::unbounded;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use super::super::FinishedSpan;
+use super::super::SpanReceiver;
+
+
+/// A span reporter backed by a tokio task.
+///
+/// Requires the `tokio` feature.
+///
+/// Async applications can use the `AsyncReporter` instead of a `ReporterThread` to
+/// report spans from the tokio runtime.
+/// The reporter spawns a task on the current tokio runtime that receives `FinishedSpan`s
+/// and awaits the future returned by the reporting closure for each of them.
+///
+/// `SpanReceiver`s block when waiting for spans so a background thread waits for spans
+/// and forwards them to the task over an async channel: the task only wakes up
+/// when there are spans to report.
+///
+/// When `AsyncReporter::stop` is awaited the task reports all the `FinishedSpan`s
+/// still in the channel and stops.
+/// Dropping the reporter also stops the task once the spans in the channel are reported,
+/// without waiting for it.
+///
+/// # Examples
+///
+/// ```
+/// extern crate opentracingrust;
+/// extern crate tokio;
+///
+/// use opentracingrust::tracers::FileTracer;
+/// use opentracingrust::utils::AsyncReporter;
+///
+///
+/// fn main() {
+///     let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+///     runtime.block_on(async {
+///         let (tracer, receiver) = FileTracer::new();
+///         let reporter = AsyncReporter::spawn(receiver, |span| async move {
+///             // ... ship the span to the distributed tracer ...
+///         });
+///         tracer.span("request").finish().unwrap();
+///         reporter.stop().await;
+///     });
+/// }
+/// ```
+pub struct AsyncReporter {
+    stop_sender: Option<Sender<()>>,
+    task: JoinHandle<()>,
+}
+
+impl AsyncReporter {
+    /// Spawns a task reporting the spans received from the `receiver`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a tokio runtime.
+    pub fn spawn<ReporterFn, ReportFuture>(
+        receiver: SpanReceiver, mut reporter: ReporterFn
+    ) -> AsyncReporter
+        where ReporterFn: FnMut(FinishedSpan) -> ReportFuture + Send + 'static,
+              ReportFuture: Future<Output = ()> + Send + 'static
+    {
+        // The forwarding thread stops once the stop sender is dropped.
+        let (stop_sender, stop_receiver) = unbounded::<()>();
+        let (span_sender, mut span_receiver) = mpsc::unbounded_channel();
+        Builder::new().name("OpenTracingAsyncReporter".into()).spawn(move || {
+            let forward = |span| span_sender.send(span).is_ok();
+            loop {
+                select! {
+                    recv(receiver.channel()) -> message => match message {
+                        Ok(message) => {
+                            let mut forwarded = forward(receiver.unpack(message));
+                            while let Some(span) = receiver.pop_pending() {
+                                forwarded &= forward(span);
+                            }
+                            // The task is gone if the async channel is closed.
+                            if !forwarded {
+                                return;
+                            }
+                        },
+                        Err(_) => return,
+                    },
+                    recv(stop_receiver) -> _ => break,
+                }
+            }
+            for span in receiver.try_iter() {
+                if !forward(span) {
+                    return;
+                }
+            }
+        }).expect("Failed to spawn reporter thread");
+
+        // The task stops once the forwarding thread stops and drops the span sender.
+        let task = tokio::spawn(async move {
+            while let Some(span) = span_receiver.recv().await {
+                reporter(span).await;
+            }
+        });
+        AsyncReporter {
+            stop_sender: Some(stop_sender),
+            task,
+        }
+    }
+
+    /// Stops the task once all the spans in the channel are reported.
+    pub async fn stop(mut self) {
+        self.stop_sender.take();
+        let _ = (&mut self.task).await;
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::Mutex;
+
+    use tokio::runtime::Builder;
+    use tokio::sync::mpsc;
+
+    use super::super::super::tracers::NoopTracer;
+    use super::AsyncReporter;
+
+    #[test]
+    fn report_spans() {
+        let runtime = Builder::new_current_thread().build().unwrap();
+        let (tracer, receiver) = NoopTracer::new();
+        let names = Arc::new(Mutex::new(Vec::new()));
+        let inner_names = Arc::clone(&names);
+        runtime.block_on(async move {
+            let reporter = AsyncReporter::spawn(receiver, move |span| {
+                let names = Arc::clone(&inner_names);
+                async move {
+                    names.lock().unwrap().push(String::from(span.name()));
+                }
+            });
+            tracer.span("first").finish().unwrap();
+            tracer.span("second").finish().unwrap();
+            reporter.stop().await;
+        });
+        assert_eq!(*names.lock().unwrap(), ["first", "second"]);
+    }
+
+    #[test]
+    fn report_spans_as_they_finish() {
+        let runtime = Builder::new_current_thread().build().unwrap();
+        let (tracer, receiver) = NoopTracer::new();
+        runtime.block_on(async move {
+            let (sender, mut names) = mpsc::unbounded_channel();
+            let reporter = AsyncReporter::spawn(receiver, move |span| {
+                let _ = sender.send(String::from(span.name()));
+                async {}
+            });
+            tracer.span("first").finish().unwrap();
+            assert_eq!("first", names.recv().await.unwrap());
+            tracer.span("second").finish().unwrap();
+            assert_eq!("second", names.recv().await.unwrap());
+            reporter.stop().await;
+        });
+    }
+}
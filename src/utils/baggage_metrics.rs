@@ -0,0 +1,215 @@
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use super::super::FinishedSpan;
+
+
+/// Aggregated baggage statistics collected by `BaggageMetrics`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BaggageStats {
+    bytes: u64,
+    items: u64,
+    max_bytes: u64,
+    max_items: u64,
+    spans: u64,
+    spans_with_baggage: u64,
+    spans_with_local_items: u64,
+}
+
+impl BaggageStats {
+    /// Returns the total size of baggage keys and values carried by the recorded spans.
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+
+    /// Returns the total number of baggage items carried by the recorded spans.
+    pub fn items(&self) -> u64 {
+        self.items
+    }
+
+    /// Returns the largest baggage size carried by a single span.
+    pub fn max_bytes(&self) -> u64 {
+        self.max_bytes
+    }
+
+    /// Returns the largest number of baggage items carried by a single span.
+    pub fn max_items(&self) -> u64 {
+        self.max_items
+    }
+
+    /// Returns the number of recorded spans.
+    pub fn spans(&self) -> u64 {
+        self.spans
+    }
+
+    /// Returns the number of recorded spans carrying at least one baggage item.
+    pub fn spans_with_baggage(&self) -> u64 {
+        self.spans_with_baggage
+    }
+
+    /// Returns the number of recorded spans that added or changed baggage items
+    /// instead of only propagating the items of the spans they reference.
+    pub fn spans_with_local_items(&self) -> u64 {
+        self.spans_with_local_items
+    }
+}
+
+
+/// Records how much baggage finished spans carry.
+///
+/// Baggage is propagated to every span in a trace and across process boundaries
+/// so it can have a significant cost.
+/// Recording finished spans before they are reported shows how much baggage is
+/// carried, and where it is added, before `BaggageRestrictions` are tightened.
+///
+/// Items are considered local to a span if none of the span's references carries
+/// the same item with the same value.
+///
+/// `Tracer`s never see finished spans so the metrics are not part of the tracer's
+/// statistics: spans are recorded in the reporting pipeline instead, by wrapping the
+/// reporting closure with `BaggageMetrics::reporter` (or calling `BaggageMetrics::record`),
+/// and the statistics are read from the `BaggageMetrics`.
+/// Metrics are cheap to clone and clones share the statistics.
+///
+/// # Examples
+///
+/// ```
+/// extern crate opentracingrust;
+///
+/// use opentracingrust::StartOptions;
+/// use opentracingrust::tracers::NoopTracer;
+/// use opentracingrust::utils::BaggageMetrics;
+/// use opentracingrust::utils::ReporterThread;
+///
+///
+/// fn main() {
+///     let (tracer, receiver) = NoopTracer::new();
+///     let metrics = BaggageMetrics::new();
+///     let mut reporter = ReporterThread::new(receiver, metrics.reporter(|span| {
+///         // ... report the span ...
+///         # drop(span);
+///     }));
+///
+///     let mut parent = tracer.span("parent");
+///     parent.set_baggage_item("tenant", "acme");
+///     let options = StartOptions::default().child_of(parent.context().clone());
+///     tracer.span_with_options("child", options).finish().unwrap();
+///     parent.finish().unwrap();
+///
+///     reporter.stop();
+///     let stats = metrics.stats();
+///     assert_eq!(2, stats.spans_with_baggage());
+///     assert_eq!(1, stats.spans_with_local_items());
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct BaggageMetrics {
+    stats: Arc<Mutex<BaggageStats>>,
+}
+
+impl BaggageMetrics {
+    /// Creates empty baggage metrics.
+    pub fn new() -> BaggageMetrics {
+        BaggageMetrics::default()
+    }
+}
+
+impl BaggageMetrics {
+    /// Records the baggage carried by a finished span.
+    pub fn record(&self, span: &FinishedSpan) {
+        let mut items = 0;
+        let mut bytes = 0;
+        let mut local = false;
+        for (key, value) in span.context().baggage_items() {
+            items += 1;
+            bytes += (key.len() + value.len()) as u64;
+            let propagated = span.references().iter().any(|reference| {
+                reference.context().get_baggage_item(key) == Some(value)
+            });
+            local = local || !propagated;
+        }
+        let mut stats = self.stats.lock().expect("Failed to lock BaggageMetrics");
+        stats.spans += 1;
+        stats.items += items;
+        stats.bytes += bytes;
+        stats.max_items = stats.max_items.max(items);
+        stats.max_bytes = stats.max_bytes.max(bytes);
+        if items > 0 {
+            stats.spans_with_baggage += 1;
+        }
+        if local {
+            stats.spans_with_local_items += 1;
+        }
+    }
+
+    /// Wraps a reporting closure to record each span before it is reported.
+    ///
+    /// The returned closure can be passed to `ReporterThread::new`.
+    pub fn reporter<ReporterFn>(&self, mut reporter: ReporterFn) -> impl FnMut(FinishedSpan)
+        where ReporterFn: FnMut(FinishedSpan)
+    {
+        let metrics = self.clone();
+        move |span| {
+            metrics.record(&span);
+            reporter(span);
+        }
+    }
+
+    /// Returns a snapshot of the statistics.
+    pub fn stats(&self) -> BaggageStats {
+        *self.stats.lock().expect("Failed to lock BaggageMetrics")
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::StartOptions;
+    use super::super::super::tracers::NoopTracer;
+    use super::BaggageMetrics;
+
+    #[test]
+    fn record_baggage() {
+        let (tracer, receiver) = NoopTracer::new();
+        let metrics = BaggageMetrics::new();
+        let mut parent = tracer.span("parent");
+        parent.set_baggage_item("tenant", "acme");
+        let options = StartOptions::default().child_of(parent.context().clone());
+        let mut child = tracer.span_with_options("child", options);
+        child.set_baggage_item("user", "42");
+        child.finish().unwrap();
+        let options = StartOptions::default().child_of(parent.context().clone());
+        tracer.span_with_options("sibling", options).finish().unwrap();
+        parent.finish().unwrap();
+        tracer.span("empty").finish().unwrap();
+        for span in receiver.try_iter() {
+            metrics.record(&span);
+        }
+
+        let stats = metrics.stats();
+        assert_eq!(4, stats.spans());
+        assert_eq!(3, stats.spans_with_baggage());
+        assert_eq!(2, stats.spans_with_local_items());
+        assert_eq!(4, stats.items());
+        assert_eq!(2, stats.max_items());
+        assert_eq!(10 + 10 + 6 + 10, stats.bytes());
+        assert_eq!(16, stats.max_bytes());
+    }
+
+    #[test]
+    fn record_reported_spans() {
+        let (tracer, receiver) = NoopTracer::new();
+        let metrics = BaggageMetrics::new();
+        let mut reported = 0;
+        let mut reporter = metrics.reporter(|_| reported += 1);
+        let mut span = tracer.span("span");
+        span.set_baggage_item("tenant", "acme");
+        span.finish().unwrap();
+        for span in receiver.try_iter() {
+            reporter(span);
+        }
+        drop(reporter);
+        assert_eq!(1, reported);
+        assert_eq!(1, metrics.stats().spans_with_baggage());
+    }
+}
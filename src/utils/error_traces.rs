@@ -0,0 +1,144 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+
+use super::CompletedTrace;
+
+
+/// Keeps the most recent traces with errors in memory.
+///
+/// Traces assembled by a `TraceAssembler` are recorded before they are exported
+/// and the buffer retains those with at least one span tagged with `error` set to `true`.
+/// Once the buffer is full the oldest error traces are dropped to make room.
+///
+/// Retention does not depend on the outcome of the export so recent failures
+/// can be inspected (for example from a debug endpoint) even when the
+/// distributed tracer is unreachable.
+///
+/// Buffers are cheap to clone and clones share the retained traces.
+///
+/// # Examples
+///
+/// ```
+/// extern crate opentracingrust;
+///
+/// use opentracingrust::tracers::NoopTracer;
+/// use opentracingrust::utils::ErrorTraceBuffer;
+/// use opentracingrust::utils::TraceAssembler;
+///
+///
+/// fn main() {
+///     let (tracer, receiver) = NoopTracer::new();
+///     let errors = ErrorTraceBuffer::new(10);
+///     let recorder = errors.clone();
+///     let mut assembler = TraceAssembler::new(move |trace| {
+///         recorder.record(&trace);
+///         // ... export the trace ...
+///     });
+///
+///     let mut span = tracer.span("request");
+///     span.tag("error", true);
+///     span.finish().unwrap();
+///     tracer.span("healthy").finish().unwrap();
+///
+///     for span in receiver.try_iter() {
+///         assembler.push(span);
+///     }
+///     assert_eq!(1, errors.len());
+///     assert_eq!("request", errors.traces()[0].spans()[0].name());
+/// }
+/// ```
+#[derive(Clone)]
+pub struct ErrorTraceBuffer {
+    capacity: usize,
+    traces: Arc<Mutex<VecDeque<CompletedTrace>>>,
+}
+
+impl ErrorTraceBuffer {
+    /// Creates a buffer retaining up to `capacity` error traces.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is `0`.
+    pub fn new(capacity: usize) -> ErrorTraceBuffer {
+        assert!(capacity > 0, "ErrorTraceBuffer needs a capacity of at least one trace");
+        ErrorTraceBuffer {
+            capacity,
+            traces: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+        }
+    }
+}
+
+impl ErrorTraceBuffer {
+    /// Drops all retained traces.
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    /// Returns `true` if no trace is retained.
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Returns the number of retained traces.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Retains a copy of the trace if it has errors.
+    ///
+    /// Returns `true` if the trace was retained.
+    pub fn record(&self, trace: &CompletedTrace) -> bool {
+        if trace.stats().error_count() == 0 {
+            return false;
+        }
+        let mut traces = self.lock();
+        if traces.len() == self.capacity {
+            traces.pop_front();
+        }
+        traces.push_back(trace.clone());
+        true
+    }
+
+    /// Returns copies of the retained traces, from the oldest to the most recent.
+    pub fn traces(&self) -> Vec<CompletedTrace> {
+        self.lock().iter().cloned().collect()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, VecDeque<CompletedTrace>> {
+        self.traces.lock().expect("Failed to lock ErrorTraceBuffer")
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::tracers::NoopTracer;
+    use super::super::TraceAssembler;
+    use super::ErrorTraceBuffer;
+
+    #[test]
+    fn keeps_recent_errors() {
+        let buffer = ErrorTraceBuffer::new(2);
+        let (tracer, receiver) = NoopTracer::new();
+        for (name, error) in [("a", true), ("ok", false), ("b", true), ("c", true)] {
+            let mut span = tracer.span(name);
+            span.tag("error", error);
+            span.finish().unwrap();
+        }
+        let recorder = buffer.clone();
+        let mut assembler = TraceAssembler::new(move |trace| {
+            recorder.record(&trace);
+        });
+        for span in receiver.try_iter() {
+            assembler.push(span);
+        }
+        let names: Vec<String> = buffer.traces().iter()
+            .map(|trace| String::from(trace.spans()[0].name()))
+            .collect();
+        assert_eq!(names, ["b", "c"]);
+        buffer.clear();
+        assert!(buffer.is_empty());
+    }
+}
@@ -1,12 +1,18 @@
 use std::error::Error;
+use std::fmt;
+use std::fmt::Debug;
+use std::fmt::Display;
 
-use super::super::Log;
 use super::super::Span;
 
 
 /// Trait to make failing spans on error easier and nicer.
 ///
 /// The most common use is for [`Result`] instances in combination with the `?` operator.
+/// [`Option`]s are also supported: a `None` value fails the span with a `NoneError`.
+///
+/// Any error type that implements `Display` and `Debug` can fail spans, including
+/// `Box<dyn Error>` and error wrappers (such as `anyhow::Error`) that do not implement `Error`.
 ///
 /// # Examples
 ///
@@ -33,9 +39,10 @@ use super::super::Span;
 /// }
 /// ```
 ///
+/// [`Option`]: https://doc.rust-lang.org/std/option/enum.Option.html
 /// [`Result`]: https://doc.rust-lang.org/std/result/enum.Result.html
 pub trait FailSpan {
-    type Error: Error + ?Sized;
+    type Error: Debug + Display + ?Sized;
 
     /// Access the current error information, if any.
     ///
@@ -47,7 +54,7 @@ pub trait FailSpan {
     /// Tags a span as failed if there was an error.
     ///
     /// An `error` event should also be logged with the details
-    /// following the [OpenTracing specification].
+    /// following the [OpenTracing specification] (see `Span::log_error`).
     ///
     /// Nothing is done if there was no error (`error()` returns [`None`]).
     ///
@@ -57,7 +64,7 @@ pub trait FailSpan {
 }
 
 impl<T, E> FailSpan for Result<T, E> where
-    E: Error
+    E: Debug + Display
 {
     type Error = E;
 
@@ -75,20 +82,60 @@ impl<T, E> FailSpan for Result<T, E> where
         // Scope error variable so we can return self.
         {
             let error = self.error().unwrap();
-            let span = span.as_mut();
-            span.tag("error", true);
-            span.log(Log::new()
-                .log("event", "error")
-                .log("message", format!("{}", error))
-                .log("error.kind", error.to_string())
-                .log("error.object", format!("{:?}", error))
-            );
+            span.as_mut().log_error(error);
+        }
+        self
+    }
+}
+
+impl<T> FailSpan for Option<T> {
+    type Error = NoneError;
+
+    fn error(&self) -> Option<&NoneError> {
+        match self {
+            Some(_) => None,
+            None => Some(&NONE_ERROR),
+        }
+    }
+
+    fn fail_span<S>(self, mut span: S) -> Option<T> where S: AsMut<Span> {
+        if let Some(error) = self.error() {
+            span.as_mut().log_error(error);
         }
         self
     }
 }
 
 
+/// Shared `NoneError` returned by `FailSpan::error` for `None` values.
+static NONE_ERROR: NoneError = NoneError {};
+
+
+/// Error reported on spans failed by a `None` value.
+#[derive(Debug)]
+pub struct NoneError {}
+
+impl fmt::Display for NoneError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Expected a value but found None")
+    }
+}
+
+impl Error for NoneError {}
+
+
+/// Tags a span as failed because of the given error.
+///
+/// This is useful for errors that are not wrapped in a [`Result`],
+/// for example errors handled with a `match` or reported by callbacks.
+/// See `Span::log_error` for details on what is recorded.
+///
+/// [`Result`]: https://doc.rust-lang.org/std/result/enum.Result.html
+pub fn fail_span_with(span: &mut Span, error: &dyn Error) {
+    span.log_error(error);
+}
+
+
 
 #[cfg(test)]
 mod tests {
@@ -98,6 +145,7 @@ mod tests {
     use super::super::super::TagValue;
     use super::super::super::tracers::NoopTracer;
     use super::FailSpan;
+    use super::fail_span_with;
 
     #[derive(Debug)]
     struct SomeError {}
@@ -120,6 +168,64 @@ mod tests {
     }
 
     #[test]
+    fn fail_span_with_boxed_error() {
+        let (tracer, receiver) = NoopTracer::new();
+        let mut span = tracer.span("test");
+        let result: Result<(), Box<dyn Error>> = Err(Box::new(SomeError {}));
+        assert!(result.fail_span(&mut span).is_err());
+        span.finish().unwrap();
+        let span = receiver.recv().unwrap();
+        let message = span.logs()[0].iter()
+            .find(|&(key, _)| key == "message")
+            .map(|(_, value)| value.to_string());
+        assert_eq!(Some(String::from("SomeError")), message);
+    }
+
+    #[test]
+    fn fail_span_with_error() {
+        let (tracer, receiver) = NoopTracer::new();
+        let mut span = tracer.span("test");
+        fail_span_with(&mut span, &SomeError {});
+        span.finish().unwrap();
+        let span = receiver.recv().unwrap();
+        match span.tags().get("error") {
+            Some(&TagValue::Boolean(true)) => (),
+            _ => panic!("Error tag not set")
+        }
+    }
+
+    #[test]
+    fn fail_span_with_none() {
+        let (tracer, receiver) = NoopTracer::new();
+        let mut span = tracer.span("test");
+        let value: Option<i32> = None;
+        assert!(value.fail_span(&mut span).is_none());
+        assert_eq!(Some(3), Some(3).fail_span(&mut span));
+        span.finish().unwrap();
+        let span = receiver.recv().unwrap();
+        match span.tags().get("error") {
+            Some(&TagValue::Boolean(true)) => (),
+            _ => panic!("Error tag not set")
+        }
+        assert_eq!(1, span.logs().len());
+        let message = span.logs()[0].iter()
+            .find(|&(key, _)| key == "message")
+            .map(|(_, value)| value.to_string());
+        assert_eq!(Some(String::from("Expected a value but found None")), message);
+    }
+
+    #[test]
+    fn fail_span_with_some() {
+        let (tracer, receiver) = NoopTracer::new();
+        let mut span = tracer.span("test");
+        assert_eq!(Some(3), Some(3).fail_span(&mut span));
+        span.finish().unwrap();
+        let span = receiver.recv().unwrap();
+        assert!(span.tags().get("error").is_none());
+    }
+
+    #[test]
+    #[allow(clippy::needless_borrow, clippy::needless_borrowed_reference, clippy::single_match)]
     fn fail_spans() {
         let (tracer, receiver) = NoopTracer::new();
         let mut span = tracer.span("test");
@@ -1,10 +1,115 @@
+use std::sync::Arc;
 use std::sync::Mutex;
-use std::sync::MutexGuard;
+use std::sync::RwLock;
 
+use super::super::ExtractFormat;
+use super::super::FinishedSpan;
+use super::super::InjectFormat;
+use super::super::Result;
+use super::super::Span;
+use super::super::SpanContext;
+use super::super::SpanReceiver;
+use super::super::StartOptions;
 use super::super::Tracer;
+use super::super::TracerInterface;
+use super::super::tracers::NoopTracer;
 
 
-static mut GLOBAL_TRACER: Option<Mutex<Tracer>> = None;
+static GLOBAL_TRACER: RwLock<Option<Arc<Tracer>>> = RwLock::new(None);
+static PRE_INIT: RwLock<Option<PreInit>> = RwLock::new(None);
+
+
+/// Behaviour of `GlobalTracer::get` before the `GlobalTracer` is initialised.
+///
+/// Set with `GlobalTracer::pre_init_mode`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PreInitMode {
+    /// `GlobalTracer::get` panics (the default).
+    Panic,
+
+    /// `GlobalTracer::get` returns a temporary tracer that buffers finished spans.
+    ///
+    /// At most the given number of spans are buffered, additional spans are dropped.
+    /// Buffered spans are returned by `GlobalTracer::take_pre_init_spans` so they can
+    /// be reported once the real tracer is installed.
+    ///
+    /// Contexts of spans created by the temporary tracer are not propagated
+    /// and cannot be used as references for spans of the real tracer.
+    Buffer(usize),
+}
+
+
+/// Spans finished with the temporary tracer used in `PreInitMode::Buffer`.
+struct PreInitBuffer {
+    capacity: usize,
+    receiver: SpanReceiver,
+    spans: Mutex<(Vec<FinishedSpan>, u64)>,
+}
+
+impl PreInitBuffer {
+    /// Moves finished spans from the channel to the buffer, dropping any over capacity.
+    fn collect(&self) {
+        let mut buffer = self.spans.lock().expect("Failed to lock pre-init spans");
+        let (ref mut spans, ref mut dropped) = *buffer;
+        for span in self.receiver.try_iter() {
+            if spans.len() < self.capacity {
+                spans.push(span);
+            } else {
+                *dropped += 1;
+            }
+        }
+    }
+}
+
+
+/// State of the `PreInitMode::Buffer` mode.
+struct PreInit {
+    buffer: Arc<PreInitBuffer>,
+    tracer: Arc<Tracer>,
+}
+
+impl PreInit {
+    fn new(capacity: usize) -> PreInit {
+        let (inner, receiver) = NoopTracer::new();
+        let buffer = Arc::new(PreInitBuffer {
+            capacity,
+            receiver,
+            spans: Mutex::new((Vec::new(), 0)),
+        });
+        let tracer = PreInitTracer {
+            buffer: Arc::clone(&buffer),
+            inner,
+        };
+        PreInit {
+            buffer,
+            tracer: Arc::new(Tracer::new(tracer)),
+        }
+    }
+}
+
+
+/// Temporary tracer returned by `GlobalTracer::get` in `PreInitMode::Buffer`.
+///
+/// Finished spans are collected into the buffer every time a span is created.
+struct PreInitTracer {
+    buffer: Arc<PreInitBuffer>,
+    inner: Tracer,
+}
+
+impl TracerInterface for PreInitTracer {
+    fn extract(&self, fmt: ExtractFormat) -> Result<Option<SpanContext>> {
+        self.inner.extract(fmt)
+    }
+
+    fn inject(&self, context: &SpanContext, fmt: InjectFormat) -> Result<()> {
+        self.inner.inject(context, fmt)
+    }
+
+    fn span(&self, name: Arc<str>, options: StartOptions) -> Span {
+        self.buffer.collect();
+        self.inner.span_with_options(name, options)
+    }
+}
 
 
 /// Utility singleton to store the process's `Tracer`.
@@ -13,12 +118,11 @@ static mut GLOBAL_TRACER: Option<Mutex<Tracer>> = None;
 /// the same `Tracer` instance for the entire lifetime of the process.
 ///
 /// > *Applications should initialise the `GlobalTracer::init` as soon as possible!*
-/// >
-/// > *The `GlobalTracer::init` method is NOT thread safe and MUST be called
-/// > before any thread is spawned or threads will panic!*
 ///
-/// The `GlobalTracer` stores a mutually exclusive `Tracer`.
+/// The `GlobalTracer` stores a shared `Tracer`.
 /// This can then be requested by each thread with `GlobalTracer::get`.
+/// Threads are not serialised on access: each call returns a new reference
+/// to the same `Tracer` instance that can be held for as long as needed.
 ///
 /// Once initialised, the `GlobalTracer` cannot be changed or dropped.
 /// Be aware that the `GlobalTracer` is backed by a static global variable
@@ -44,47 +148,129 @@ impl GlobalTracer {
     /// Initialises the `GlobalTracer` to store the given `Tracer` instance.
     ///
     /// > *Applications should initialise the `GlobalTracer::init` as soon as possible!*
-    /// >
-    /// > *The `GlobalTracer::init` method is NOT thread safe and MUST be called
-    /// > before any thread is spawned or threads will panic!*
     ///
     /// # Panics
     ///
     /// Panics if the `GlobalTracer` is already initialised with a `Tracer`.
     pub fn init(tracer: Tracer) {
-        unsafe {
-            match GLOBAL_TRACER {
-                None => GLOBAL_TRACER = Some(Mutex::new(tracer)),
-                _ => panic!("GlobalTracer already initialised")
+        // Release the lock before panicking to avoid poisoning it.
+        let initialised = {
+            let mut global = GLOBAL_TRACER.write()
+                .expect("Failed to lock GlobalTracer");
+            match *global {
+                None => {
+                    *global = Some(Arc::new(tracer));
+                    false
+                },
+                _ => true
             }
+        };
+        if initialised {
+            panic!("GlobalTracer already initialised");
         }
     }
 
-    /// Exclusively access the singleton `Tracer` instance.
+    /// Access the singleton `Tracer` instance.
+    ///
+    /// Before the `GlobalTracer` is initialised the behaviour depends on the `PreInitMode`.
     ///
     /// # Panics
     ///
-    /// Panics if the singleton `Tracer` is requested before the `GlobalTracer` is initialised.
-    pub fn get() -> MutexGuard<'static, Tracer> {
-        unsafe {
-            let tracer = GLOBAL_TRACER.as_ref()
-                .expect("GlobalTracer not initialised, call GlobalTracer::init first");
-            tracer.lock().expect("Failed to lock GlobalTracer")
+    /// Panics if the singleton `Tracer` is requested before the `GlobalTracer` is initialised
+    /// unless a `PreInitMode::Buffer` mode is set.
+    pub fn get() -> Arc<Tracer> {
+        let global = GLOBAL_TRACER.read()
+            .expect("Failed to lock GlobalTracer");
+        if let Some(ref tracer) = *global {
+            return Arc::clone(tracer);
+        }
+        let pre_init = PRE_INIT.read().expect("Failed to lock GlobalTracer");
+        let pre_init = pre_init.as_ref()
+            .expect("GlobalTracer not initialised, call GlobalTracer::init first");
+        Arc::clone(&pre_init.tracer)
+    }
+
+    /// Sets the behaviour of `GlobalTracer::get` before the `GlobalTracer` is initialised.
+    ///
+    /// Changing the mode discards any span buffered by a previous `PreInitMode::Buffer` mode.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate opentracingrust;
+    ///
+    /// use opentracingrust::tracers::NoopTracer;
+    /// use opentracingrust::utils::GlobalTracer;
+    /// use opentracingrust::utils::PreInitMode;
+    ///
+    ///
+    /// fn main() {
+    ///     GlobalTracer::pre_init_mode(PreInitMode::Buffer(1024));
+    ///     // Threads racing initialisation no longer panic.
+    ///     GlobalTracer::get().span("startup").finish().unwrap();
+    ///
+    ///     let (tracer, receiver) = NoopTracer::new();
+    ///     GlobalTracer::init(tracer);
+    ///     for span in GlobalTracer::take_pre_init_spans() {
+    ///         // ... report the span along with the ones from the receiver ...
+    ///         NoopTracer::report(span);
+    ///     }
+    /// }
+    /// ```
+    pub fn pre_init_mode(mode: PreInitMode) {
+        let mut pre_init = PRE_INIT.write().expect("Failed to lock GlobalTracer");
+        *pre_init = match mode {
+            PreInitMode::Panic => None,
+            PreInitMode::Buffer(capacity) => Some(PreInit::new(capacity)),
+        };
+    }
+
+    /// Returns the number of spans dropped because the pre-init buffer was full.
+    pub fn pre_init_spans_dropped() -> u64 {
+        let pre_init = PRE_INIT.read().expect("Failed to lock GlobalTracer");
+        match *pre_init {
+            None => 0,
+            Some(ref pre_init) => {
+                pre_init.buffer.collect();
+                pre_init.buffer.spans.lock().expect("Failed to lock pre-init spans").1
+            }
+        }
+    }
+
+    /// Takes the spans buffered before the `GlobalTracer` was initialised.
+    ///
+    /// Spans created before initialisation but finished after it are still buffered
+    /// so this method can be called again to collect them.
+    /// Returns nothing unless a `PreInitMode::Buffer` mode is set.
+    pub fn take_pre_init_spans() -> Vec<FinishedSpan> {
+        let pre_init = PRE_INIT.read().expect("Failed to lock GlobalTracer");
+        match *pre_init {
+            None => Vec::new(),
+            Some(ref pre_init) => {
+                pre_init.buffer.collect();
+                let mut buffer = pre_init.buffer.spans.lock()
+                    .expect("Failed to lock pre-init spans");
+                buffer.0.drain(..).collect()
+            }
         }
     }
 
     /// Allow tests to clean up before they run.
     #[cfg(test)]
     pub fn reset() {
-        unsafe {
-            GLOBAL_TRACER = None
-        }
+        let mut global = GLOBAL_TRACER.write()
+            .expect("Failed to lock GlobalTracer");
+        *global = None;
+        let mut pre_init = PRE_INIT.write()
+            .expect("Failed to lock GlobalTracer");
+        *pre_init = None;
     }
 }
 
 
 #[cfg(test)]
 mod tests {
+    use std::sync::Arc;
     use std::thread;
     use std::time::Duration;
 
@@ -98,6 +284,7 @@ mod tests {
     use super::super::super::TracerInterface;
 
     use super::GlobalTracer;
+    use super::PreInitMode;
 
 
     struct DummyTracer {}
@@ -110,14 +297,14 @@ mod tests {
             panic!("Not Implemented");
         }
 
-        fn span(&self, _: &str, _: StartOptions) -> Span {
+        fn span(&self, _: Arc<str>, _: StartOptions) -> Span {
             panic!("Not Implemented");
         }
     }
 
 
     // *** SEQUENTIAL TESTS ***
-    // The following tests cannot run in parallel as they (unsafely)
+    // The following tests cannot run in parallel as they
     // manipulate the GLOBAL_TRACER singleton.
     // To avoid forcing all tests to be run serially these tests
     // sleep for increasing 5 ms increments.
@@ -162,4 +349,34 @@ mod tests {
         t1.join().unwrap();
         t2.join().unwrap();
     }
+
+    #[test]
+    fn tracer_is_shared() {
+        thread::sleep(Duration::from_millis(20));
+        GlobalTracer::reset();
+        GlobalTracer::init(Tracer::new(DummyTracer {}));
+        let tracer1 = GlobalTracer::get();
+        let tracer2 = GlobalTracer::get();
+        assert!(Arc::ptr_eq(&tracer1, &tracer2));
+    }
+
+    #[test]
+    fn pre_init_spans_are_buffered() {
+        thread::sleep(Duration::from_millis(25));
+        GlobalTracer::reset();
+        GlobalTracer::pre_init_mode(PreInitMode::Buffer(2));
+        let pre_init = GlobalTracer::get();
+        for name in &["first", "second", "third"] {
+            pre_init.span(*name).finish().unwrap();
+        }
+        GlobalTracer::init(Tracer::new(DummyTracer {}));
+        assert!(!Arc::ptr_eq(&pre_init, &GlobalTracer::get()));
+
+        let names: Vec<String> = GlobalTracer::take_pre_init_spans().into_iter()
+            .map(|span| String::from(span.name()))
+            .collect();
+        assert_eq!(names, ["first", "second"]);
+        assert_eq!(1, GlobalTracer::pre_init_spans_dropped());
+        assert!(GlobalTracer::take_pre_init_spans().is_empty());
+    }
 }
@@ -0,0 +1,151 @@
+//! Helpers to trace gRPC calls.
+//!
+//! These helpers are independent of the gRPC library in use
+//! so they can be shared by any RPC stack.
+use super::super::Log;
+use super::super::Span;
+use super::super::SpanKind;
+
+
+/// Canonical names of the gRPC status codes, indexed by code.
+const STATUS_NAMES: [&str; 17] = [
+    "OK",
+    "CANCELLED",
+    "UNKNOWN",
+    "INVALID_ARGUMENT",
+    "DEADLINE_EXCEEDED",
+    "NOT_FOUND",
+    "ALREADY_EXISTS",
+    "PERMISSION_DENIED",
+    "RESOURCE_EXHAUSTED",
+    "FAILED_PRECONDITION",
+    "ABORTED",
+    "OUT_OF_RANGE",
+    "UNIMPLEMENTED",
+    "INTERNAL",
+    "UNAVAILABLE",
+    "DATA_LOSS",
+    "UNAUTHENTICATED",
+];
+
+/// Status codes that indicate a server fault.
+const SERVER_ERROR_CODES: [i32; 6] = [2, 4, 12, 13, 14, 15];
+
+
+/// Returns the canonical name of a gRPC status code (`NOT_FOUND`, `INTERNAL`, ...).
+///
+/// Returns `None` for codes that are not defined by gRPC.
+pub fn status_name(code: i32) -> Option<&'static str> {
+    if code < 0 {
+        return None;
+    }
+    STATUS_NAMES.get(code as usize).cloned()
+}
+
+
+/// Tags a span with the status of a gRPC call.
+///
+/// The `rpc.grpc.status_code` tag is always set.
+/// If the status represents an error the span is also tagged with
+/// `error` and an `error` event is logged with the status message.
+///
+/// Which statuses are errors depends on the `SpanKind` of the span:
+///
+///   * For `SpanKind::Server` spans only codes that indicate a server fault
+///     (`UNKNOWN`, `DEADLINE_EXCEEDED`, `UNIMPLEMENTED`, `INTERNAL`,
+///     `UNAVAILABLE`, `DATA_LOSS`) are errors.
+///   * For all other spans any code other then `OK` is an error.
+///
+/// # Examples
+///
+/// ```
+/// extern crate opentracingrust;
+///
+/// use opentracingrust::tracers::NoopTracer;
+/// use opentracingrust::utils::grpc;
+///
+///
+/// fn main() {
+///     let (tracer, _receiver) = NoopTracer::new();
+///     let mut span = tracer.span("GetUser");
+///     grpc::tag_status(&mut span, 5, "user not found");
+///     span.finish().unwrap();
+/// }
+/// ```
+pub fn tag_status<S>(mut span: S, code: i32, message: &str) where S: AsMut<Span> {
+    let span = span.as_mut();
+    span.tag("rpc.grpc.status_code", code);
+    let is_error = match span.kind() {
+        Some(SpanKind::Server) => SERVER_ERROR_CODES.contains(&code),
+        _ => code != 0,
+    };
+    if !is_error {
+        return;
+    }
+    span.tag("error", true);
+    span.log(Log::new()
+        .log("event", "error")
+        .log("message", message)
+        .log("error.kind", status_name(code).unwrap_or("UNKNOWN"))
+    );
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::SpanKind;
+    use super::super::super::StartOptions;
+    use super::super::super::TagValue;
+    use super::super::super::tracers::NoopTracer;
+
+    use super::status_name;
+    use super::tag_status;
+
+    #[test]
+    fn client_errors() {
+        let (tracer, receiver) = NoopTracer::new();
+        let mut span = tracer.span("test");
+        tag_status(&mut span, 5, "not found");
+        span.finish().unwrap();
+        let span = receiver.recv().unwrap();
+        match span.tags().get("rpc.grpc.status_code") {
+            Some(&TagValue::Integer(code)) => assert_eq!(code, 5),
+            _ => panic!("Status code tag not set")
+        }
+        match span.tags().get("error") {
+            Some(&TagValue::Boolean(error)) => assert!(error),
+            _ => panic!("Error tag not set")
+        }
+        assert_eq!(1, span.logs().len());
+    }
+
+    #[test]
+    fn ok_is_not_an_error() {
+        let (tracer, receiver) = NoopTracer::new();
+        let mut span = tracer.span("test");
+        tag_status(&mut span, 0, "");
+        span.finish().unwrap();
+        let span = receiver.recv().unwrap();
+        assert!(span.tags().get("error").is_none());
+        assert!(span.logs().is_empty());
+    }
+
+    #[test]
+    fn server_client_faults_are_not_errors() {
+        let (tracer, receiver) = NoopTracer::new();
+        let options = StartOptions::default().span_kind(SpanKind::Server);
+        let mut span = tracer.span_with_options("test", options);
+        tag_status(&mut span, 5, "not found");
+        span.finish().unwrap();
+        let span = receiver.recv().unwrap();
+        assert!(span.tags().get("error").is_none());
+    }
+
+    #[test]
+    fn status_names() {
+        assert_eq!(Some("OK"), status_name(0));
+        assert_eq!(Some("UNAUTHENTICATED"), status_name(16));
+        assert_eq!(None, status_name(17));
+        assert_eq!(None, status_name(-1));
+    }
+}
@@ -0,0 +1,147 @@
+use std::sync::Arc;
+use std::thread::Builder;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crossbeam_channel::RecvTimeoutError;
+use crossbeam_channel::Sender;
+use crossbeam_channel::unbounded;
+
+use super::super::SpanContext;
+use super::super::StartOptions;
+use super::super::Tracer;
+
+use super::GlobalTracer;
+
+
+/// Operation name of heartbeat spans.
+const HEARTBEAT_SPAN_NAME: &str = "heartbeat";
+
+
+/// Emits heartbeat spans for a long-running operation using the `GlobalTracer`.
+///
+/// See `heartbeat_with_tracer` for details.
+///
+/// # Panics
+///
+/// Panics if the `GlobalTracer` is not initialised.
+pub fn heartbeat(context: SpanContext, interval: Duration) -> Heartbeat {
+    heartbeat_with_tracer(GlobalTracer::get(), context, interval)
+}
+
+
+/// Emits heartbeat spans for a long-running operation.
+///
+/// Spans are only reported when they finish so distributed tracers, especially those
+/// with limited retention windows, know nothing of operations running for hours.
+/// Until the returned `Heartbeat` is stopped or dropped a background thread starts
+/// and immediately finishes a `heartbeat` span every `interval`.
+///
+/// Heartbeat spans `FollowsFrom` the given context and are tagged with
+/// `heartbeat.count`: the number of the heartbeat, starting at 1.
+///
+/// # Examples
+///
+/// ```
+/// extern crate opentracingrust;
+///
+/// use std::sync::Arc;
+/// use std::time::Duration;
+///
+/// use opentracingrust::tracers::NoopTracer;
+/// use opentracingrust::utils::heartbeat_with_tracer;
+///
+///
+/// fn main() {
+///     let (tracer, _receiver) = NoopTracer::new();
+///     let tracer = Arc::new(tracer);
+///     let span = tracer.span("backfill");
+///     let heartbeat = heartbeat_with_tracer(
+///         Arc::clone(&tracer), span.context().clone(), Duration::from_secs(60)
+///     );
+///     // ... snip ...
+///     drop(heartbeat);
+///     span.finish().unwrap();
+/// }
+/// ```
+pub fn heartbeat_with_tracer(
+    tracer: Arc<Tracer>, context: SpanContext, interval: Duration
+) -> Heartbeat {
+    let (stop, stopping) = unbounded::<()>();
+    let thread = Builder::new().name("OpenTracingHeartbeat".into()).spawn(move || {
+        let mut count: u64 = 0;
+        while let Err(RecvTimeoutError::Timeout) = stopping.recv_timeout(interval) {
+            count += 1;
+            let options = StartOptions::default().follows(context.clone());
+            let mut span = tracer.span_with_options(HEARTBEAT_SPAN_NAME, options);
+            span.tag("heartbeat.count", count);
+            // There is no one to report the error to from the heartbeat thread.
+            let _ = span.finish();
+        }
+    }).expect("Failed to spawn heartbeat thread");
+    Heartbeat {
+        stop: Some(stop),
+        thread_handle: Some(thread),
+    }
+}
+
+
+/// Handle to the background thread emitting heartbeat spans.
+///
+/// Heartbeats stop when the handle is stopped or dropped.
+pub struct Heartbeat {
+    stop: Option<Sender<()>>,
+    thread_handle: Option<JoinHandle<()>>,
+}
+
+impl Heartbeat {
+    /// Stops the background thread and joins it.
+    pub fn stop(&mut self) {
+        self.stop.take();
+        if let Some(thread) = self.thread_handle.take() {
+            thread.join().expect("Failed to join heartbeat thread");
+        }
+    }
+}
+
+impl Drop for Heartbeat {
+    fn drop(&mut self) {
+        self.stop()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use super::super::super::SpanReference;
+    use super::super::super::TagValue;
+    use super::super::super::tracers::NoopTracer;
+
+    use super::heartbeat_with_tracer;
+
+    #[test]
+    fn beats_follow_the_operation() {
+        let (tracer, receiver) = NoopTracer::new();
+        let tracer = Arc::new(tracer);
+        let span = tracer.span("job");
+        let mut heartbeat = heartbeat_with_tracer(
+            Arc::clone(&tracer), span.context().clone(), Duration::from_millis(5)
+        );
+        let first = receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        let second = receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        heartbeat.stop();
+
+        assert_eq!("heartbeat", first.name());
+        match first.references().first() {
+            Some(SpanReference::FollowsFrom(parent)) => assert!(parent == span.context()),
+            _ => panic!("Heartbeat does not follow the operation")
+        }
+        match second.tags().get("heartbeat.count") {
+            Some(&TagValue::Unsigned(count)) => assert_eq!(2, count),
+            _ => panic!("Heartbeat count not set")
+        }
+    }
+}
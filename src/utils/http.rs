@@ -0,0 +1,114 @@
+//! Helpers to trace outgoing HTTP requests.
+//!
+//! These helpers are independent of the HTTP client in use
+//! so they can be shared by any HTTP client crate.
+//! See `integrations::reqwest` for a ready to use `reqwest` middleware.
+use super::super::InjectFormat;
+use super::super::MapCarrier;
+use super::super::Result;
+use super::super::Span;
+use super::super::SpanKind;
+use super::super::StartOptions;
+use super::super::Tracer;
+
+
+/// Starts a client span for an outgoing HTTP request and injects it into the request headers.
+///
+/// The span is a `SpanKind::Client` span named after the request method and
+/// tagged with the `http.method` and `http.url` tags.
+/// Its context is injected into `headers` with `InjectFormat::HttpHeaders`.
+///
+/// Failing to inject the context does not prevent the request from being traced:
+/// the error is logged on the returned span.
+///
+/// # Examples
+///
+/// ```
+/// extern crate opentracingrust;
+///
+/// use std::collections::HashMap;
+///
+/// use opentracingrust::StartOptions;
+/// use opentracingrust::tracers::FileTracer;
+/// use opentracingrust::utils::http::finish_http_request;
+/// use opentracingrust::utils::http::inject_http_request;
+///
+///
+/// fn main() {
+///     let (tracer, _receiver) = FileTracer::new();
+///     let mut headers: HashMap<String, String> = HashMap::new();
+///     let span = inject_http_request(
+///         &tracer, StartOptions::default(), "GET", "http://example.com/", &mut headers
+///     );
+///     assert!(headers.contains_key("TraceID"));
+///
+///     // ... send the request with the headers ...
+///     finish_http_request(span, 200).unwrap();
+/// }
+/// ```
+pub fn inject_http_request(
+    tracer: &Tracer, options: StartOptions, method: &str, url: &str,
+    headers: &mut dyn MapCarrier
+) -> Span {
+    let method = method.to_uppercase();
+    let options = options.span_kind(SpanKind::Client);
+    let mut span = tracer.span_with_options(format!("HTTP {}", method), options);
+    span.tag("http.method", method);
+    span.tag("http.url", url);
+    let format = InjectFormat::HttpHeaders(Box::new(headers));
+    if let Err(error) = tracer.inject(span.context(), format) {
+        span.log_error(&error);
+    }
+    span
+}
+
+/// Tags a client span with the response status and finishes it.
+///
+/// The `error` tag is set for server errors (5xx).
+#[allow(clippy::result_large_err)]
+pub fn finish_http_request(mut span: Span, status: u16) -> Result<()> {
+    span.tag("http.status_code", u32::from(status));
+    if status >= 500 {
+        span.tag("error", true);
+    }
+    span.finish()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::super::super::ExtractFormat;
+    use super::super::super::SpanKind;
+    use super::super::super::StartOptions;
+    use super::super::super::TagValue;
+    use super::super::super::tracers::FileTracer;
+
+    use super::finish_http_request;
+    use super::inject_http_request;
+
+    #[test]
+    fn injects_client_span() {
+        let (tracer, receiver) = FileTracer::new();
+        let mut headers: HashMap<String, String> = HashMap::new();
+        let span = inject_http_request(
+            &tracer, StartOptions::default(), "post", "http://example.com/", &mut headers
+        );
+        let injected = tracer.extract(ExtractFormat::HttpHeaders(Box::new(&headers))).unwrap();
+        assert_eq!(Some(span.context()), injected.as_ref());
+
+        finish_http_request(span, 503).unwrap();
+        let span = receiver.recv().unwrap();
+        assert_eq!("HTTP POST", span.name());
+        assert_eq!(Some(SpanKind::Client), span.kind());
+        match span.tags().get("http.status_code") {
+            Some(TagValue::Unsigned(503)) => (),
+            _ => panic!("Status code not tagged")
+        }
+        match span.tags().get("error") {
+            Some(TagValue::Boolean(true)) => (),
+            _ => panic!("Error tag not set")
+        }
+    }
+}
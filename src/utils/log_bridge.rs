@@ -0,0 +1,162 @@
+use ::log::Level;
+use ::log::log;
+
+use super::super::FinishedSpan;
+use super::super::Log;
+use super::super::LogValue;
+
+
+/// Default target for records emitted by the `LogBridge`.
+const TARGET_DEFAULT: &str = "opentracingrust";
+
+
+/// Mirrors span logs to the [log](https://crates.io/crates/log) crate.
+///
+/// Requires the `log` feature.
+///
+/// Every `Log` attached to a `FinishedSpan` is emitted as one record, formatted as
+/// the span's operation name followed by the log fields as `key=value` pairs sorted by key.
+/// Logs with an `event` field set to `error` are emitted at `Level::Error`,
+/// all other logs are emitted at the bridge's level (`Level::Info` by default).
+///
+/// The bridge is intended to be called by span reporters (such as `ReporterThread`)
+/// so spans are shipped to the distributed tracer and to the application logs at once.
+///
+/// # Examples
+///
+/// ```
+/// extern crate log;
+/// extern crate opentracingrust;
+///
+/// use log::Level;
+///
+/// use opentracingrust::Log;
+/// use opentracingrust::tracers::NoopTracer;
+/// use opentracingrust::utils::LogBridge;
+/// use opentracingrust::utils::ReporterThread;
+///
+///
+/// fn main() {
+///     let (tracer, receiver) = NoopTracer::new();
+///     let bridge = LogBridge::new().level(Level::Debug).target("traces");
+///     let _reporter = ReporterThread::new(receiver, move |span| {
+///         bridge.bridge(&span);
+///         // ... ship the span ...
+///     });
+///
+///     let mut span = tracer.span("request");
+///     span.log(Log::new().log("event", "cache-miss"));
+///     span.finish().unwrap();
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct LogBridge {
+    level: Level,
+    target: String,
+}
+
+impl Default for LogBridge {
+    fn default() -> LogBridge {
+        LogBridge::new()
+    }
+}
+
+impl LogBridge {
+    /// Creates a bridge emitting `Level::Info` records with the `opentracingrust` target.
+    pub fn new() -> LogBridge {
+        LogBridge {
+            level: Level::Info,
+            target: String::from(TARGET_DEFAULT),
+        }
+    }
+
+    /// Sets the level of emitted records, except for error events.
+    pub fn level(mut self, level: Level) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Sets the target of emitted records.
+    pub fn target<T: Into<String>>(mut self, target: T) -> Self {
+        self.target = target.into();
+        self
+    }
+}
+
+impl LogBridge {
+    /// Emits a record for each log attached to the span.
+    pub fn bridge(&self, span: &FinishedSpan) {
+        for entry in span.logs() {
+            let level = match entry.iter().find(|&(key, _)| key == "event") {
+                Some((_, LogValue::String(event))) if event == "error" => Level::Error,
+                _ => self.level,
+            };
+            log!(target: &self.target, level, "{}", LogBridge::message(span, entry));
+        }
+    }
+
+    /// Formats a span log as a single line message.
+    fn message(span: &FinishedSpan, entry: &Log) -> String {
+        let mut fields: Vec<(&String, &LogValue)> = entry.iter().collect();
+        fields.sort_by(|left, right| left.0.cmp(right.0));
+        let mut message = String::from(span.name());
+        for (key, value) in fields {
+            message.push_str(&format!(" {}={}", key, value));
+        }
+        message
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use ::log::Level;
+    use ::log::LevelFilter;
+    use ::log::Metadata;
+    use ::log::Record;
+
+    use super::super::super::Log;
+    use super::super::super::tracers::NoopTracer;
+
+    use super::LogBridge;
+
+
+    struct TestLogger(Mutex<Vec<(Level, String, String)>>);
+
+    impl ::log::Log for TestLogger {
+        fn enabled(&self, _: &Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &Record) {
+            let entry = (record.level(), record.target().to_string(), record.args().to_string());
+            self.0.lock().unwrap().push(entry);
+        }
+
+        fn flush(&self) {}
+    }
+
+    static LOGGER: TestLogger = TestLogger(Mutex::new(Vec::new()));
+
+    #[test]
+    fn mirrors_span_logs() {
+        ::log::set_logger(&LOGGER).unwrap();
+        ::log::set_max_level(LevelFilter::Trace);
+
+        let (tracer, receiver) = NoopTracer::new();
+        let mut span = tracer.span("request");
+        span.log(Log::new().log("event", "cache-miss").log("key", "user:42"));
+        span.log(Log::new().log("message", "timeout").log("event", "error"));
+        span.finish().unwrap();
+        let span = receiver.recv().unwrap();
+        LogBridge::new().target("traces").bridge(&span);
+
+        let records = LOGGER.0.lock().unwrap();
+        assert_eq!(*records, [
+            (Level::Info, "traces".to_string(), "request event=cache-miss key=user:42".to_string()),
+            (Level::Error, "traces".to_string(), "request event=error message=timeout".to_string()),
+        ]);
+    }
+}
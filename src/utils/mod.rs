@@ -1,7 +1,50 @@
+mod assembler;
+mod baggage_metrics;
+#[cfg(feature = "tokio")]
+mod async_reporter;
+mod error_traces;
 mod fail;
 mod global_tracer;
+mod heartbeat;
+#[cfg(feature = "log")]
+mod log_bridge;
 mod reporter;
+mod retry;
+mod scheduled;
+mod spool;
+mod watchdog;
 
+pub mod grpc;
+pub mod http;
+pub mod redis;
+
+pub use self::assembler::AssemblerMetrics;
+pub use self::assembler::CompletedTrace;
+pub use self::assembler::TraceAssembler;
+pub use self::assembler::TraceStats;
+#[cfg(feature = "tokio")]
+pub use self::async_reporter::AsyncReporter;
+pub use self::baggage_metrics::BaggageMetrics;
+pub use self::baggage_metrics::BaggageStats;
+pub use self::error_traces::ErrorTraceBuffer;
 pub use self::fail::FailSpan;
+pub use self::fail::NoneError;
+pub use self::fail::fail_span_with;
 pub use self::global_tracer::GlobalTracer;
+pub use self::global_tracer::PreInitMode;
+pub use self::heartbeat::Heartbeat;
+pub use self::heartbeat::heartbeat;
+pub use self::heartbeat::heartbeat_with_tracer;
+#[cfg(feature = "log")]
+pub use self::log_bridge::LogBridge;
+pub use self::reporter::ReportCtx;
+pub use self::reporter::ReportEvent;
+pub use self::reporter::ReporterErrorPolicy;
+pub use self::reporter::ReporterFlusher;
 pub use self::reporter::ReporterThread;
+pub use self::retry::RetryReporter;
+pub use self::scheduled::ScheduledJobs;
+pub use self::scheduled::scheduled_span;
+pub use self::spool::SpoolReporter;
+pub use self::watchdog::SpanWatchdog;
+pub use self::watchdog::WatchedSpan;
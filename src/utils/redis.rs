@@ -0,0 +1,159 @@
+//! Helpers to trace redis commands.
+//!
+//! These helpers are independent of the redis client in use
+//! so they can be shared by any redis client crate.
+use std::error::Error;
+
+use super::super::Span;
+use super::super::SpanKind;
+use super::super::StartOptions;
+use super::super::Tracer;
+
+
+/// Configuration for traced redis commands.
+///
+/// Spans for redis commands are `SpanKind::Client` spans tagged with:
+///
+///   * `db.type`: always `redis`.
+///   * `db.statement`: the command name and, if enabled, the keys it operates on.
+///   * `db.redis.key_count`: the number of keys the command operates on.
+///
+/// Keys can carry sensitive information so they are redacted by default.
+/// Use `RedisTracing::record_keys` to include them in the `db.statement` tag.
+///
+/// # Examples
+///
+/// ```
+/// extern crate opentracingrust;
+///
+/// use std::io;
+///
+/// use opentracingrust::StartOptions;
+/// use opentracingrust::tracers::NoopTracer;
+/// use opentracingrust::utils::redis::RedisTracing;
+///
+///
+/// fn main() {
+///     let (tracer, _receiver) = NoopTracer::new();
+///     let redis = RedisTracing::default();
+///     let value: Result<String, io::Error> = redis.trace(
+///         &tracer, StartOptions::default(), "GET", &["user:42"],
+///         || Ok(String::from("someone"))  // Call the redis client here.
+///     );
+///     assert_eq!("someone", value.unwrap());
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct RedisTracing {
+    record_keys: bool,
+}
+
+impl RedisTracing {
+    /// Sets whether keys are recorded in the `db.statement` tag.
+    pub fn record_keys(mut self, record_keys: bool) -> Self {
+        self.record_keys = record_keys;
+        self
+    }
+}
+
+impl RedisTracing {
+    /// Starts a span for a redis command.
+    ///
+    /// The span is named after the command and is not finished.
+    pub fn start_span(
+        &self, tracer: &Tracer, options: StartOptions, command: &str, keys: &[&str]
+    ) -> Span {
+        let command = command.to_uppercase();
+        let options = options.span_kind(SpanKind::Client);
+        let mut span = tracer.span_with_options(format!("redis {}", command), options);
+        let statement = if self.record_keys && !keys.is_empty() {
+            format!("{} {}", command, keys.join(" "))
+        } else {
+            command
+        };
+        span.tag("db.type", "redis");
+        span.tag("db.statement", statement);
+        span.tag("db.redis.key_count", keys.len() as u64);
+        span
+    }
+
+    /// Traces the execution of a redis command.
+    ///
+    /// A span is started with `RedisTracing::start_span` and `execute` is called.
+    /// If `execute` fails the error is logged with `Span::log_error`.
+    /// The span is finished once `execute` returns, ignoring errors sending it.
+    pub fn trace<T, E, F>(
+        &self, tracer: &Tracer, options: StartOptions, command: &str, keys: &[&str],
+        execute: F
+    ) -> Result<T, E>
+        where E: Error, F: FnOnce() -> Result<T, E>
+    {
+        let mut span = self.start_span(tracer, options, command, keys).auto_finish();
+        let result = execute();
+        if let Err(ref error) = result {
+            span.log_error(error);
+        }
+        result
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::super::super::StartOptions;
+    use super::super::super::TagValue;
+    use super::super::super::tracers::NoopTracer;
+
+    use super::RedisTracing;
+
+    fn statement(value: Option<&TagValue>) -> String {
+        match value {
+            Some(TagValue::String(statement)) => statement.clone(),
+            _ => panic!("Statement tag not set")
+        }
+    }
+
+    #[test]
+    fn keys_are_redacted() {
+        let (tracer, receiver) = NoopTracer::new();
+        let span = RedisTracing::default().start_span(
+            &tracer, StartOptions::default(), "mget", &["a", "b"]
+        );
+        span.finish().unwrap();
+        let span = receiver.recv().unwrap();
+        assert_eq!("redis MGET", span.name());
+        assert_eq!("MGET", statement(span.tags().get("db.statement")));
+        match span.tags().get("db.redis.key_count") {
+            Some(&TagValue::Unsigned(count)) => assert_eq!(count, 2),
+            _ => panic!("Key count tag not set")
+        }
+    }
+
+    #[test]
+    fn keys_are_recorded() {
+        let (tracer, receiver) = NoopTracer::new();
+        let span = RedisTracing::default().record_keys(true).start_span(
+            &tracer, StartOptions::default(), "mget", &["a", "b"]
+        );
+        span.finish().unwrap();
+        let span = receiver.recv().unwrap();
+        assert_eq!("MGET a b", statement(span.tags().get("db.statement")));
+    }
+
+    #[test]
+    fn trace_logs_errors() {
+        let (tracer, receiver) = NoopTracer::new();
+        let result: Result<(), io::Error> = RedisTracing::default().trace(
+            &tracer, StartOptions::default(), "GET", &["a"],
+            || Err(io::Error::other("connection lost"))
+        );
+        assert!(result.is_err());
+        let span = receiver.recv().unwrap();
+        match span.tags().get("error") {
+            Some(&TagValue::Boolean(error)) => assert!(error),
+            _ => panic!("Error tag not set")
+        }
+    }
+}
@@ -1,20 +1,19 @@
+use std::mem;
 use std::sync::Arc;
-use std::sync::atomic::AtomicBool;
-use std::sync::atomic::Ordering;
-
-use crossbeam_channel::RecvTimeoutError;
-
 use std::thread;
 use std::thread::Builder;
 use std::thread::JoinHandle;
 use std::time::Duration;
 
+use crossbeam_channel::Receiver;
+use crossbeam_channel::RecvError;
+use crossbeam_channel::Sender;
+use crossbeam_channel::select;
+use crossbeam_channel::unbounded;
+
 use super::super::FinishedSpan;
 use super::super::SpanReceiver;
-
-
-const STOP_DEALY_SEC_DEFAULT: u64 = 2;
-const RECV_TIMEOUT_MSEC_DEFAULT: u64 = 50;
+use super::super::TracerStats;
 
 
 /// A basic span reporter backed by a background thread.
@@ -26,48 +25,204 @@ const RECV_TIMEOUT_MSEC_DEFAULT: u64 = 50;
 /// The `ReporterThread` also supports clean shutdown of the receiver thread.
 /// When `ReporterThread::stop` is called or an instance is dropped:
 ///
-///   1. The calling thread is paused for the `stop_delay` duration.
-///      This allows the reporter thread to process any `FinishedSpan`s still in the channel.
+///   1. The calling thread is paused for the `stop_delay` duration (none by default).
+///      This gives other threads a chance to finish spans before the reporter is stopped.
 ///   2. The background thread is informend to shutdown and the calling thread joins it.
-///   3. As soon as any `FinishedSpan` is processed or receiving times out the thread is stopped.
-///      Receiving spans times out every 50 milliseconds.
-// If https://github.com/rust-lang/rust/issues/27800 leads to a stable API
-// rework this to be more efficient with shutdowns.
+///   3. The background thread reports all the `FinishedSpan`s still in the channel and stops.
+///
+/// Receiving spans fails once all the senders are dropped (the tracer and all its spans).
+/// By default the background thread stops when that happens: use
+/// `ReporterThread::new_with_on_error` to handle errors differently.
+///
+/// Backends where reporting each span is slow can use `ReporterThread::new_pooled`
+/// to report spans from several background threads at once.
+///
+/// Reporters that batch or retry spans can use `ReporterThread::new_with_context`
+/// to also receive flush requests and a `ReportCtx` describing the reporter's state,
+/// or `ReporterThread::new_with_stats` to also access the `TracerStats`.
 pub struct ReporterThread {
+    flush_senders: Vec<Sender<Sender<()>>>,
     stop_delay: Duration,
-    stopping: Arc<AtomicBool>,
-    thread_handle: Option<JoinHandle<()>>,
+    stop_sender: Option<Sender<()>>,
+    thread_handles: Vec<JoinHandle<()>>,
 }
 
 impl ReporterThread {
     /// Creates a new reporter waiting for spans on the `receiver`.
     ///
     /// The reporter starts with a spawned thread and runs until stopped or dropped.
-    pub fn new<ReporterFn>(receiver: SpanReceiver, mut reporter: ReporterFn) -> ReporterThread
-        where ReporterFn: FnMut(FinishedSpan) -> () + Send + 'static
+    pub fn new<ReporterFn>(receiver: SpanReceiver, reporter: ReporterFn) -> ReporterThread
+        where ReporterFn: FnMut(FinishedSpan) + Send + 'static
     {
-        // Stopping flag.
-        let stopping = Arc::new(AtomicBool::new(false));
-        let inner_stopping = Arc::clone(&stopping);
-
-        // Reporter thread loop.
-        let thread = Builder::new().name("OpenTracingReporter".into()).spawn(move || {
-            while !inner_stopping.load(Ordering::Relaxed) {
-                let timeout = Duration::from_millis(RECV_TIMEOUT_MSEC_DEFAULT);
-                let span = receiver.recv_timeout(timeout);
-                match span {
-                    Ok(span) => { reporter(span); },
-                    Err(RecvTimeoutError::Timeout) => continue,
-                    _ => panic!("Failed to receive span")
-                }
+        ReporterThread::new_with_on_error(receiver, reporter, |_| ReporterErrorPolicy::Stop)
+    }
+
+    /// Version of `new` that calls `on_error` when receiving spans fails.
+    ///
+    /// The `ReporterErrorPolicy` returned by `on_error` decides what the thread does next.
+    pub fn new_with_on_error<ReporterFn, ErrorFn>(
+        receiver: SpanReceiver, reporter: ReporterFn, on_error: ErrorFn
+    ) -> ReporterThread
+        where ReporterFn: FnMut(FinishedSpan) + Send + 'static,
+              ErrorFn: FnMut(RecvError) -> ReporterErrorPolicy + Send + 'static
+    {
+        let mut reporter = reporter;
+        let reporter = move |event, _: &ReportCtx| {
+            if let ReportEvent::Span(span) = event {
+                reporter(span);
             }
-        }).expect("Failed to spawn reporter thread");
+        };
+        ReporterThread::spawn(receiver, None, reporter, on_error)
+    }
 
-        // Return a wrapper around the thread.
+    /// Creates a new reporter that receives `ReportEvent`s along with a `ReportCtx`.
+    ///
+    /// Besides spans, the reporter closure receives `ReportEvent::Flush` events
+    /// when `ReporterThread::flush` is called and once more when the reporter stops,
+    /// after all spans in the channel are reported.
+    /// This allows batching reporters to ship partial batches when asked to.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate opentracingrust;
+    ///
+    /// use opentracingrust::tracers::NoopTracer;
+    /// use opentracingrust::utils::ReportEvent;
+    /// use opentracingrust::utils::ReporterThread;
+    ///
+    ///
+    /// fn main() {
+    ///     let (tracer, receiver) = NoopTracer::new();
+    ///     let mut batch = Vec::new();
+    ///     let mut reporter = ReporterThread::new_with_context(receiver, move |event, ctx| {
+    ///         match event {
+    ///             ReportEvent::Span(span) => batch.push(span),
+    ///             ReportEvent::Flush => (),
+    ///         }
+    ///         if batch.len() >= 100 || ctx.is_flush() {
+    ///             // ... ship the batch ...
+    ///             batch.clear();
+    ///         }
+    ///     });
+    ///
+    ///     tracer.span("test").finish().unwrap();
+    ///     reporter.flush();
+    ///     reporter.stop();
+    /// }
+    /// ```
+    pub fn new_with_context<ReporterFn>(
+        receiver: SpanReceiver, reporter: ReporterFn
+    ) -> ReporterThread
+        where ReporterFn: FnMut(ReportEvent, &ReportCtx) + Send + 'static
+    {
+        ReporterThread::spawn(receiver, None, reporter, |_| ReporterErrorPolicy::Stop)
+    }
+
+    /// Version of `new_with_context` that gives the reporter access to the tracer's statistics.
+    ///
+    /// The `stats` handle is returned by `Tracer::stats` and is available to the
+    /// reporter with `ReportCtx::tracer_stats`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate opentracingrust;
+    ///
+    /// use opentracingrust::InFlightPolicy;
+    /// use opentracingrust::tracers::NoopTracer;
+    /// use opentracingrust::utils::ReporterThread;
+    ///
+    ///
+    /// fn main() {
+    ///     let (tracer, receiver) = NoopTracer::new();
+    ///     let tracer = tracer.with_max_in_flight(1000, InFlightPolicy::RefuseRecording);
+    ///     let reporter = ReporterThread::new_with_stats(receiver, tracer.stats(), |_, ctx| {
+    ///         let stats = ctx.tracer_stats().unwrap();
+    ///         if stats.in_flight_spans() > Some(900) {
+    ///             // ... ship smaller batches more often ...
+    ///         }
+    ///     });
+    ///     // ... snip ...
+    /// #   drop(reporter);
+    /// }
+    /// ```
+    pub fn new_with_stats<ReporterFn>(
+        receiver: SpanReceiver, stats: TracerStats, reporter: ReporterFn
+    ) -> ReporterThread
+        where ReporterFn: FnMut(ReportEvent, &ReportCtx) + Send + 'static
+    {
+        ReporterThread::spawn(receiver, Some(stats), reporter, |_| ReporterErrorPolicy::Stop)
+    }
+
+    /// Spawns a single worker thread.
+    fn spawn<ReporterFn, ErrorFn>(
+        receiver: SpanReceiver, stats: Option<TracerStats>, reporter: ReporterFn,
+        on_error: ErrorFn
+    ) -> ReporterThread
+        where ReporterFn: FnMut(ReportEvent, &ReportCtx) + Send + 'static,
+              ErrorFn: FnMut(RecvError) -> ReporterErrorPolicy + Send + 'static
+    {
+        // Control channels: the thread stops once the stop sender is dropped.
+        let (flush_sender, flush_receiver) = unbounded::<Sender<()>>();
+        let (stop_sender, stop_receiver) = unbounded::<()>();
+        let workers = Workers {
+            flush_receiver,
+            receiver,
+            stats,
+            stop_receiver,
+        };
+        let thread = spawn_worker(workers, reporter, on_error);
         ReporterThread {
-            stop_delay: Duration::from_secs(STOP_DEALY_SEC_DEFAULT),
-            stopping,
-            thread_handle: Some(thread),
+            flush_senders: vec![flush_sender],
+            stop_delay: Duration::from_secs(0),
+            stop_sender: Some(stop_sender),
+            thread_handles: vec![thread],
+        }
+    }
+
+    /// Creates a new reporter with `workers` threads waiting for spans on the `receiver`.
+    ///
+    /// Each span is reported by one of the threads so the reporter closure is shared
+    /// and must be thread safe. Spans may be reported out of order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `workers` is `0`.
+    pub fn new_pooled<ReporterFn>(
+        receiver: SpanReceiver, workers: usize, reporter: ReporterFn
+    ) -> ReporterThread
+        where ReporterFn: Fn(FinishedSpan) + Send + Sync + 'static
+    {
+        assert!(workers > 0, "ReporterThread needs at least one worker");
+        let (stop_sender, stop_receiver) = unbounded::<()>();
+        let reporter = Arc::new(reporter);
+        // Each worker has its own flush channel so that every worker is flushed.
+        let mut flush_senders = Vec::with_capacity(workers);
+        let thread_handles = (0..workers)
+            .map(|_| {
+                let (flush_sender, flush_receiver) = unbounded::<Sender<()>>();
+                flush_senders.push(flush_sender);
+                let reporter = Arc::clone(&reporter);
+                let workers = Workers {
+                    flush_receiver,
+                    receiver: receiver.clone(),
+                    stats: None,
+                    stop_receiver: stop_receiver.clone(),
+                };
+                let reporter = move |event, _: &ReportCtx| {
+                    if let ReportEvent::Span(span) = event {
+                        reporter(span);
+                    }
+                };
+                spawn_worker(workers, reporter, |_| ReporterErrorPolicy::Stop)
+            })
+            .collect();
+        ReporterThread {
+            flush_senders,
+            stop_delay: Duration::from_secs(0),
+            stop_sender: Some(stop_sender),
+            thread_handles,
         }
     }
 
@@ -75,24 +230,48 @@ impl ReporterThread {
     pub fn new_with_duration<ReporterFn>(
         receiver: SpanReceiver, stop_delay: Duration, reporter: ReporterFn
     ) -> ReporterThread
-        where ReporterFn: FnMut(FinishedSpan) -> () + Send + 'static
+        where ReporterFn: FnMut(FinishedSpan) + Send + 'static
     {
         let mut reporter = ReporterThread::new(receiver, reporter);
         reporter.stop_delay(stop_delay);
         reporter
     }
 
+    /// Asks the reporter to flush any spans it buffered and waits for it to do so.
+    ///
+    /// Returns once the spans already in the channel are reported.
+    /// Only reporters created with `ReporterThread::new_with_context` receive the request,
+    /// as a `ReportEvent::Flush` event, after the spans already in the channel.
+    pub fn flush(&self) {
+        self.flusher().flush()
+    }
+
+    /// Returns a `ReporterFlusher` to flush the reporter from other threads.
+    ///
+    /// Register it with `Tracer::on_flush` so that flushing the tracer flushes the reporter.
+    pub fn flusher(&self) -> ReporterFlusher {
+        ReporterFlusher {
+            senders: self.flush_senders.clone(),
+        }
+    }
+
     /// Updates the `stop_delay` for when the thread is stopped.
+    ///
+    /// Spans in the channel are reported when the thread is stopped regardless
+    /// of the delay: the delay is only needed to wait for spans that are not finished yet.
     pub fn stop_delay(&mut self, stop_delay: Duration) {
         self.stop_delay = stop_delay;
     }
 
-    /// Stops the background thread and joins it.
+    /// Stops the background threads and joins them.
     pub fn stop(&mut self) {
-        if let Some(thread) = self.thread_handle.take() {
+        let threads = mem::take(&mut self.thread_handles);
+        if !threads.is_empty() {
             thread::sleep(self.stop_delay);
-            self.stopping.store(true, Ordering::Relaxed);
-            thread.join().expect("Failed to join reporter thread");
+            self.stop_sender.take();
+            for thread in threads {
+                thread.join().expect("Failed to join reporter thread");
+            }
         }
     }
 }
@@ -104,15 +283,188 @@ impl Drop for ReporterThread {
 }
 
 
+/// Flushes a `ReporterThread` from any thread (see `ReporterThread::flusher`).
+#[derive(Clone, Debug)]
+pub struct ReporterFlusher {
+    senders: Vec<Sender<Sender<()>>>,
+}
+
+impl ReporterFlusher {
+    /// Asks the reporter to flush and blocks until each worker thread did so.
+    ///
+    /// See `ReporterThread::flush` for details.
+    pub fn flush(&self) {
+        let (ack_sender, ack_receiver) = unbounded();
+        // Workers may have stopped already, in which case there is nothing to flush.
+        let requested = self.senders.iter()
+            .filter(|sender| sender.send(ack_sender.clone()).is_ok())
+            .count();
+        drop(ack_sender);
+        // Receiving fails once all workers stopped, even if they did not acknowledge.
+        for _ in 0..requested {
+            if ack_receiver.recv().is_err() {
+                return;
+            }
+        }
+    }
+}
+
+
+/// Channels a reporter thread waits on and the tracer statistics passed to its reporter.
+struct Workers {
+    flush_receiver: Receiver<Sender<()>>,
+    receiver: SpanReceiver,
+    stats: Option<TracerStats>,
+    stop_receiver: Receiver<()>,
+}
+
+/// Spawns a reporter thread waiting for spans until `stop_receiver` is disconnected.
+fn spawn_worker<ReporterFn, ErrorFn>(
+    workers: Workers, mut reporter: ReporterFn, mut on_error: ErrorFn
+) -> JoinHandle<()>
+    where ReporterFn: FnMut(ReportEvent, &ReportCtx) + Send + 'static,
+          ErrorFn: FnMut(RecvError) -> ReporterErrorPolicy + Send + 'static
+{
+    let Workers { flush_receiver, receiver, stats, stop_receiver } = workers;
+    Builder::new().name("OpenTracingReporter".into()).spawn(move || {
+        let mut ctx = ReportCtx {
+            stats,
+            ..ReportCtx::default()
+        };
+        let mut report = |event, ctx: &mut ReportCtx| {
+            ctx.flush = matches!(event, ReportEvent::Flush);
+            ctx.pending = receiver.channel().len();
+            reporter(event, ctx);
+            if !ctx.flush {
+                ctx.reported += 1;
+            }
+        };
+        loop {
+            select! {
+                recv(receiver.channel()) -> message => match message {
+                    Ok(message) => {
+                        report(ReportEvent::Span(receiver.unpack(message)), &mut ctx);
+                        while let Some(span) = receiver.pop_pending() {
+                            report(ReportEvent::Span(span), &mut ctx);
+                        }
+                    },
+                    Err(error) => match on_error(error) {
+                        ReporterErrorPolicy::Continue => {
+                            let _ = stop_receiver.recv();
+                            break;
+                        },
+                        ReporterErrorPolicy::Stop => break,
+                    },
+                },
+                recv(flush_receiver) -> ack => {
+                    // Messages are picked at random when several channels are ready:
+                    // report the spans already in the channel before the flush.
+                    for span in receiver.try_iter() {
+                        report(ReportEvent::Span(span), &mut ctx);
+                    }
+                    report(ReportEvent::Flush, &mut ctx);
+                    if let Ok(ack) = ack {
+                        let _ = ack.send(());
+                    }
+                },
+                recv(stop_receiver) -> _ => break,
+            }
+        }
+        ctx.stopping = true;
+        for span in receiver.try_iter() {
+            report(ReportEvent::Span(span), &mut ctx);
+        }
+        report(ReportEvent::Flush, &mut ctx);
+    }).expect("Failed to spawn reporter thread")
+}
+
+
+/// Events received by reporters created with `ReporterThread::new_with_context`.
+// Spans are the common event: boxing them would allocate for every span.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug)]
+pub enum ReportEvent {
+    /// The reporter is asked to ship any span it buffered.
+    Flush,
+
+    /// A span to report.
+    Span(FinishedSpan),
+}
+
+
+/// State of a `ReporterThread` worker passed to reporters along with each `ReportEvent`.
+///
+/// The context describes the worker and its channel and, for reporters created with
+/// `ReporterThread::new_with_stats`, gives access to the `TracerStats`.
+#[derive(Debug, Default)]
+pub struct ReportCtx {
+    flush: bool,
+    pending: usize,
+    reported: u64,
+    stats: Option<TracerStats>,
+    stopping: bool,
+}
+
+impl ReportCtx {
+    /// Checks if the event is a flush request.
+    pub fn is_flush(&self) -> bool {
+        self.flush
+    }
+
+    /// Checks if the reporter is stopping.
+    ///
+    /// Once stopping, the remaining spans are reported and a final flush is requested.
+    pub fn is_stopping(&self) -> bool {
+        self.stopping
+    }
+
+    /// Returns the number of messages waiting in the channel.
+    ///
+    /// A growing number of pending messages means the reporter can't keep up:
+    /// reporters can react by sending larger batches or dropping spans.
+    pub fn pending(&self) -> usize {
+        self.pending
+    }
+
+    /// Returns the number of spans this worker reported so far.
+    pub fn reported(&self) -> u64 {
+        self.reported
+    }
+
+    /// Returns the statistics of the tracer the spans come from.
+    ///
+    /// Only available to reporters created with `ReporterThread::new_with_stats`.
+    pub fn tracer_stats(&self) -> Option<&TracerStats> {
+        self.stats.as_ref()
+    }
+}
+
+
+/// What a `ReporterThread` does after it fails to receive spans.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ReporterErrorPolicy {
+    /// Keep the background thread running until the reporter is stopped.
+    Continue,
+
+    /// Stop the background thread.
+    Stop,
+}
+
+
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
     use std::sync::Mutex;
     use std::time::Duration;
 
+    use crossbeam_channel::unbounded;
+
     use super::super::super::FinishedSpan;
+    use super::super::super::InFlightPolicy;
     use super::super::super::tracers::NoopTracer;
 
+    use super::ReportEvent;
+    use super::ReporterErrorPolicy;
     use super::ReporterThread;
 
     #[test]
@@ -136,4 +488,147 @@ mod tests {
         // Check the span was received.
         assert_eq!(1, spans.lock().unwrap().len());
     }
+
+    #[test]
+    fn report_batches() {
+        let (tracer, receiver) = NoopTracer::new();
+        let (sender, reported) = unbounded();
+        let _reporter = ReporterThread::new(receiver, move |span: FinishedSpan| {
+            sender.send(String::from(span.name())).unwrap();
+        });
+
+        let child = tracer.span("child");
+        tracer.span("parent").finish_children_then_self(vec![child]).unwrap();
+        let timeout = Duration::from_secs(1);
+        assert_eq!("child", reported.recv_timeout(timeout).unwrap());
+        assert_eq!("parent", reported.recv_timeout(timeout).unwrap());
+    }
+
+    #[test]
+    fn report_with_context() {
+        let (tracer, receiver) = NoopTracer::new();
+        let (sender, reported) = unbounded();
+        let mut reporter = ReporterThread::new_with_context(receiver, move |event, ctx| {
+            let event = match event {
+                ReportEvent::Flush => String::from("flush"),
+                ReportEvent::Span(span) => String::from(span.name()),
+            };
+            sender.send((event, ctx.reported(), ctx.is_stopping())).unwrap();
+        });
+
+        tracer.span("first").finish().unwrap();
+        let timeout = Duration::from_secs(1);
+        assert_eq!(
+            (String::from("first"), 0, false), reported.recv_timeout(timeout).unwrap()
+        );
+        reporter.flush();
+        assert_eq!(
+            (String::from("flush"), 1, false), reported.recv_timeout(timeout).unwrap()
+        );
+        reporter.stop();
+        assert_eq!((String::from("flush"), 1, true), reported.recv_timeout(timeout).unwrap());
+    }
+
+    #[test]
+    fn report_with_tracer_stats() {
+        let (tracer, receiver) = NoopTracer::new();
+        let tracer = tracer.with_max_in_flight(10, InFlightPolicy::TagRoot);
+        let (sender, reported) = unbounded();
+        let mut reporter = ReporterThread::new_with_stats(
+            receiver, tracer.stats(), move |event, ctx| {
+                if let ReportEvent::Span(_) = event {
+                    let stats = ctx.tracer_stats().unwrap();
+                    sender.send((stats.spans_started(), stats.in_flight_spans())).unwrap();
+                }
+            }
+        );
+
+        let open = tracer.span("open");
+        tracer.span("finished").finish().unwrap();
+        let timeout = Duration::from_secs(1);
+        assert_eq!((2, Some(1)), reported.recv_timeout(timeout).unwrap());
+        drop(open);
+        reporter.stop();
+    }
+
+    #[test]
+    fn flush_after_queued_spans() {
+        let (tracer, receiver) = NoopTracer::new();
+        let (sender, flushes) = unbounded();
+        let mut reporter = ReporterThread::new_with_context(receiver, move |event, ctx| {
+            if let ReportEvent::Flush = event {
+                sender.send(ctx.reported()).unwrap();
+            }
+        });
+
+        for _ in 0..100 {
+            tracer.span("test").finish().unwrap();
+        }
+        reporter.flush();
+        assert_eq!(100, flushes.recv_timeout(Duration::from_secs(1)).unwrap());
+        reporter.stop();
+    }
+
+    #[test]
+    fn flush_tracer_waits_for_reporter() {
+        let (tracer, receiver) = NoopTracer::new();
+        let spans: Arc<Mutex<Vec<FinishedSpan>>> = Arc::new(Mutex::new(Vec::new()));
+        let reported = Arc::clone(&spans);
+        let reporter = ReporterThread::new_pooled(receiver, 2, move |span| {
+            reported.lock().unwrap().push(span);
+        });
+        let flusher = reporter.flusher();
+        let tracer = tracer.on_flush(move || flusher.flush());
+
+        for _ in 0..100 {
+            tracer.span("test").finish().unwrap();
+        }
+        tracer.flush().unwrap();
+        assert_eq!(100, spans.lock().unwrap().len());
+    }
+
+    #[test]
+    fn disconnected_senders() {
+        let (tracer, receiver) = NoopTracer::new();
+        let (sender, errors) = unbounded();
+        let mut reporter = ReporterThread::new_with_on_error(receiver, |_| (), move |error| {
+            sender.send(error).unwrap();
+            ReporterErrorPolicy::Continue
+        });
+        drop(tracer);
+        assert!(errors.recv_timeout(Duration::from_secs(1)).is_ok());
+        reporter.stop();
+    }
+
+    #[test]
+    fn pooled_workers() {
+        let (tracer, receiver) = NoopTracer::new();
+        let spans: Arc<Mutex<Vec<FinishedSpan>>> = Arc::new(Mutex::new(Vec::new()));
+        let inner_spans = Arc::clone(&spans);
+        let mut reporter = ReporterThread::new_pooled(receiver, 4, move |span| {
+            inner_spans.lock().unwrap().push(span);
+        });
+
+        for _ in 0..100 {
+            tracer.span("test").finish().unwrap();
+        }
+        reporter.stop();
+        assert_eq!(100, spans.lock().unwrap().len());
+    }
+
+    #[test]
+    fn drain_spans_on_stop() {
+        let (tracer, receiver) = NoopTracer::new();
+        let spans: Arc<Mutex<Vec<FinishedSpan>>> = Arc::new(Mutex::new(Vec::new()));
+        let inner_spans = Arc::clone(&spans);
+        let mut reporter = ReporterThread::new(receiver, move |span| {
+            inner_spans.lock().unwrap().push(span);
+        });
+
+        for _ in 0..100 {
+            tracer.span("test").finish().unwrap();
+        }
+        reporter.stop();
+        assert_eq!(100, spans.lock().unwrap().len());
+    }
 }
@@ -0,0 +1,161 @@
+use std::thread;
+use std::time::Duration;
+
+use super::super::FinishedSpan;
+
+
+const INITIAL_BACKOFF_MSEC_DEFAULT: u64 = 100;
+const MAX_BACKOFF_MSEC_DEFAULT: u64 = 10_000;
+const MAX_RETRIES_DEFAULT: u32 = 3;
+
+
+/// Wraps a fallible reporting closure to retry failed spans with exponential backoff.
+///
+/// Transient outages of the distributed tracer's collectors should not cause spans to
+/// be lost: when the wrapped closure fails the span is reported again after a delay.
+/// The delay starts at the initial backoff (100 milliseconds by default) and doubles
+/// after each attempt up to the maximum backoff (10 seconds by default).
+///
+/// Spans that still fail after the maximum number of retries (3 by default) are
+/// passed to the dead-letter callback, if one is set, with the last error.
+/// Otherwise they are dropped.
+///
+/// Backoff delays block the calling thread (usually a `ReporterThread`)
+/// so spans are reported in order.
+///
+/// # Examples
+///
+/// ```
+/// extern crate opentracingrust;
+///
+/// use std::time::Duration;
+///
+/// use opentracingrust::FinishedSpan;
+/// use opentracingrust::tracers::NoopTracer;
+/// use opentracingrust::utils::ReporterThread;
+/// use opentracingrust::utils::RetryReporter;
+///
+///
+/// fn send(span: FinishedSpan) -> Result<(), String> {
+///     // ... ship the span to the distributed tracer ...
+///     Ok(())
+/// }
+///
+/// fn main() {
+///     let (tracer, receiver) = NoopTracer::new();
+///     let mut retry = RetryReporter::new(send)
+///         .max_retries(5)
+///         .backoff(Duration::from_millis(50), Duration::from_secs(5))
+///         .dead_letter(|span, error| eprintln!("Dropped {}: {}", span.name(), error));
+///     let _reporter = ReporterThread::new(receiver, move |span| retry.report(span));
+///     // ... snip ...
+/// }
+/// ```
+pub struct RetryReporter<ReporterFn, E> {
+    dead_letter: Option<Box<dyn FnMut(FinishedSpan, E) + Send>>,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    max_retries: u32,
+    reporter: ReporterFn,
+}
+
+impl<ReporterFn, E> RetryReporter<ReporterFn, E>
+    where ReporterFn: FnMut(FinishedSpan) -> Result<(), E>
+{
+    /// Wraps the given reporting closure with the default retry options.
+    pub fn new(reporter: ReporterFn) -> RetryReporter<ReporterFn, E> {
+        RetryReporter {
+            dead_letter: None,
+            initial_backoff: Duration::from_millis(INITIAL_BACKOFF_MSEC_DEFAULT),
+            max_backoff: Duration::from_millis(MAX_BACKOFF_MSEC_DEFAULT),
+            max_retries: MAX_RETRIES_DEFAULT,
+            reporter,
+        }
+    }
+
+    /// Sets the delay before the first retry and the maximum delay between retries.
+    pub fn backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    /// Sets the callback for spans that could not be reported.
+    pub fn dead_letter<DeadLetterFn>(mut self, dead_letter: DeadLetterFn) -> Self
+        where DeadLetterFn: FnMut(FinishedSpan, E) + Send + 'static
+    {
+        self.dead_letter = Some(Box::new(dead_letter));
+        self
+    }
+
+    /// Sets the number of times a failed span is retried.
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Reports a span, retrying on failure.
+    pub fn report(&mut self, span: FinishedSpan) {
+        let mut backoff = self.initial_backoff;
+        for _ in 0..self.max_retries {
+            if (self.reporter)(span.clone()).is_ok() {
+                return;
+            }
+            thread::sleep(backoff);
+            backoff = (backoff * 2).min(self.max_backoff);
+        }
+        if let Err(error) = (self.reporter)(span.clone()) {
+            if let Some(dead_letter) = self.dead_letter.as_mut() {
+                dead_letter(span, error);
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    use super::super::super::FinishedSpan;
+    use super::super::super::tracers::NoopTracer;
+    use super::RetryReporter;
+
+    fn make_span() -> FinishedSpan {
+        let (tracer, receiver) = NoopTracer::new();
+        tracer.span("test").finish().unwrap();
+        receiver.recv().unwrap()
+    }
+
+    #[test]
+    fn retries_failures() {
+        let mut attempts = 0;
+        let mut retry = RetryReporter::new(|_| {
+            attempts += 1;
+            if attempts < 3 { Err("unavailable") } else { Ok(()) }
+        }).backoff(Duration::from_millis(0), Duration::from_millis(0));
+        retry.report(make_span());
+        drop(retry);
+        assert_eq!(3, attempts);
+    }
+
+    #[test]
+    fn dead_letter_after_retries() {
+        let dead = Arc::new(Mutex::new(Vec::new()));
+        let inner_dead = Arc::clone(&dead);
+        let mut attempts = 0;
+        let mut retry = RetryReporter::new(|_| {
+            attempts += 1;
+            Err(attempts)
+        })
+            .max_retries(2)
+            .backoff(Duration::from_millis(0), Duration::from_millis(0))
+            .dead_letter(move |span, error| {
+                inner_dead.lock().unwrap().push((String::from(span.name()), error));
+            });
+        retry.report(make_span());
+        assert_eq!(*dead.lock().unwrap(), [(String::from("test"), 3)]);
+    }
+}
@@ -0,0 +1,157 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::sync::PoisonError;
+
+use super::super::Span;
+use super::super::SpanContext;
+use super::super::StartOptions;
+use super::super::Tracer;
+
+use super::GlobalTracer;
+
+
+/// Previous runs of the jobs scheduled with `scheduled_span`.
+static GLOBAL_JOBS: ScheduledJobs = ScheduledJobs::new();
+
+
+/// Creates a span for a run of a periodic job using the `GlobalTracer`.
+///
+/// Previous runs are tracked, by job name, for the lifetime of the process
+/// separately from any `ScheduledJobs` instance.
+/// See `ScheduledJobs::span` for details.
+///
+/// # Panics
+///
+/// Panics if the `GlobalTracer` is not initialised.
+pub fn scheduled_span(job_name: &str, schedule: &str) -> Span {
+    let tracer = GlobalTracer::get();
+    GLOBAL_JOBS.span(&tracer, job_name, schedule)
+}
+
+
+/// Tracks the runs of periodic jobs to link their spans.
+///
+/// Runs of scheduled jobs have no parent but the span for each run
+/// `FollowsFrom` the span of the previous run of the same job.
+/// This makes it possible to navigate the history of a recurring job
+/// and spot missed or overlapping runs in the distributed tracer.
+///
+/// Previous runs are tracked, by job name, for the lifetime of the `ScheduledJobs`.
+/// Use one instance for each `Tracer` so spans never reference contexts of other tracers.
+///
+/// # Examples
+///
+/// ```
+/// extern crate opentracingrust;
+///
+/// use opentracingrust::tracers::NoopTracer;
+/// use opentracingrust::utils::ScheduledJobs;
+///
+///
+/// fn main() {
+///     let (tracer, _receiver) = NoopTracer::new();
+///     let jobs = ScheduledJobs::new();
+///     let span = jobs.span(&tracer, "cleanup", "*/5 * * * *");
+///     // ... snip ...
+///     span.finish().unwrap();
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct ScheduledJobs {
+    last_runs: Mutex<BTreeMap<String, (SpanContext, u64)>>,
+}
+
+impl ScheduledJobs {
+    /// Creates a tracker with no previous runs.
+    pub const fn new() -> ScheduledJobs {
+        ScheduledJobs {
+            last_runs: Mutex::new(BTreeMap::new()),
+        }
+    }
+}
+
+impl ScheduledJobs {
+    /// Creates a span for a run of a periodic job.
+    ///
+    /// The span is named after the job, `FollowsFrom` the previous run, if any, and
+    /// is tagged with:
+    ///
+    ///   * `job.name`: the name of the job.
+    ///   * `job.schedule`: the schedule of the job (for example a cron expression).
+    ///   * `job.run`: the number of the run since tracking started, starting at 1.
+    pub fn span(&self, tracer: &Tracer, job_name: &str, schedule: &str) -> Span {
+        // The lock is not held while the span is created so hooks and samplers
+        // can schedule spans of their own.
+        let previous = self.lock().get(job_name).cloned();
+        let mut options = StartOptions::default();
+        let mut run = 1;
+        if let Some((previous, previous_run)) = previous {
+            options = options.follows(previous);
+            run = previous_run + 1;
+        }
+        let mut span = tracer.span_with_options(job_name, options);
+        span.tag("job.name", job_name);
+        span.tag("job.schedule", schedule);
+        span.tag("job.run", run);
+        self.lock().insert(String::from(job_name), (span.context().clone(), run));
+        span
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<String, (SpanContext, u64)>> {
+        // Contexts are only inserted once complete so the map is usable after a panic.
+        self.last_runs.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::SpanReference;
+    use super::super::super::TagValue;
+    use super::super::super::tracers::NoopTracer;
+
+    use super::ScheduledJobs;
+
+    #[test]
+    fn first_run_has_no_references() {
+        let (tracer, receiver) = NoopTracer::new();
+        let jobs = ScheduledJobs::new();
+        let span = jobs.span(&tracer, "first_run_job", "@hourly");
+        span.finish().unwrap();
+        let span = receiver.recv().unwrap();
+        assert!(span.references().is_empty());
+        match span.tags().get("job.schedule") {
+            Some(TagValue::String(schedule)) => assert_eq!(schedule, "@hourly"),
+            _ => panic!("Schedule tag not set")
+        }
+    }
+
+    #[test]
+    fn runs_follow_previous_run() {
+        let (tracer, receiver) = NoopTracer::new();
+        let jobs = ScheduledJobs::new();
+        jobs.span(&tracer, "follow_job", "@daily").finish().unwrap();
+        jobs.span(&tracer, "follow_job", "@daily").finish().unwrap();
+        let _first = receiver.recv().unwrap();
+        let second = receiver.recv().unwrap();
+        match second.references().first() {
+            Some(SpanReference::FollowsFrom(_)) => (),
+            _ => panic!("Run does not follow the previous run")
+        }
+        match second.tags().get("job.run") {
+            Some(&TagValue::Unsigned(run)) => assert_eq!(run, 2),
+            _ => panic!("Run tag not set")
+        }
+    }
+
+    #[test]
+    fn jobs_are_tracked_per_instance() {
+        let (tracer, receiver) = NoopTracer::new();
+        ScheduledJobs::new().span(&tracer, "job", "@daily").finish().unwrap();
+        ScheduledJobs::new().span(&tracer, "job", "@daily").finish().unwrap();
+        for span in receiver.try_iter() {
+            assert!(span.references().is_empty());
+        }
+    }
+}
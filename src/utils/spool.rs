@@ -0,0 +1,318 @@
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+use super::super::FinishedSpan;
+use super::super::Result;
+
+
+const SPOOL_EXTENSION: &str = "span";
+const SPOOL_TMP_EXTENSION: &str = "tmp";
+
+
+/// Spools spans to a local directory while the distributed tracer is unreachable.
+///
+/// Spans are encoded with the encoding closure and the payload is sent with the
+/// sending closure.
+/// When sending fails the payload is written to a file in the spool directory
+/// instead of being dropped.
+/// Spooled payloads are replayed, oldest first, before the next span is sent and
+/// each file is removed only once its payload is delivered (at-least-once delivery).
+///
+/// `SpanContext`s are tracer specific so spans can't be decoded from the spool.
+/// The spool stores the encoded payloads and the encoding closure should produce
+/// what the distributed tracer's collectors expect.
+///
+/// Payloads spooled by a previous process are replayed when the reporter is created
+/// and before the next span is sent, so the spool directory should be dedicated to
+/// a single reporter.
+/// Spooled payloads are otherwise only replayed when spans are reported: call
+/// `SpoolReporter::replay` periodically (for example on `ReportEvent::Flush`)
+/// to deliver them while no spans are reported.
+///
+/// The spool is unbounded by default: set `SpoolReporter::max_files` and
+/// `SpoolReporter::max_bytes` to drop the oldest payloads once the spool is full.
+///
+/// # Examples
+///
+/// ```
+/// extern crate opentracingrust;
+///
+/// use std::env;
+/// use std::fs;
+///
+/// use opentracingrust::FinishedSpan;
+/// use opentracingrust::tracers::NoopTracer;
+/// use opentracingrust::utils::ReportEvent;
+/// use opentracingrust::utils::ReporterThread;
+/// use opentracingrust::utils::SpoolReporter;
+///
+///
+/// fn encode(span: &FinishedSpan) -> Vec<u8> {
+///     // ... encode the span for the distributed tracer ...
+///     span.name().as_bytes().to_vec()
+/// }
+///
+/// fn send(payload: &[u8]) -> Result<(), String> {
+///     // ... ship the payload to the distributed tracer ...
+///     Ok(())
+/// }
+///
+/// fn main() {
+///     let (tracer, receiver) = NoopTracer::new();
+///     let directory = env::temp_dir().join("opentracingrust-spool-example");
+///     let mut spool = SpoolReporter::new(&directory, encode, send).unwrap()
+///         .max_files(10_000)
+///         .max_bytes(64 * 1024 * 1024);
+///     let reporter = ReporterThread::new_with_context(receiver, move |event, _| {
+///         let result = match event {
+///             ReportEvent::Span(span) => spool.report(span),
+///             ReportEvent::Flush => spool.replay().map(|_| ()),
+///         };
+///         if let Err(error) = result {
+///             eprintln!("Failed to spool span: {}", error);
+///         }
+///     });
+///     // ... snip ...
+///     drop(reporter);
+///     fs::remove_dir_all(&directory).unwrap();
+/// }
+/// ```
+pub struct SpoolReporter<EncodeFn, SendFn> {
+    directory: PathBuf,
+    dropped: u64,
+    encode: EncodeFn,
+    max_bytes: Option<u64>,
+    max_files: Option<usize>,
+    pending: bool,
+    send: SendFn,
+    sequence: u64,
+}
+
+impl<EncodeFn, SendFn, E> SpoolReporter<EncodeFn, SendFn>
+    where EncodeFn: FnMut(&FinishedSpan) -> Vec<u8>,
+          SendFn: FnMut(&[u8]) -> ::std::result::Result<(), E>
+{
+    /// Creates a reporter spooling to the given directory.
+    ///
+    /// The directory is created if it does not exist and payloads spooled by a previous
+    /// reporter are replayed right away.
+    #[allow(clippy::result_large_err)]
+    pub fn new<P: AsRef<Path>>(
+        directory: P, encode: EncodeFn, send: SendFn
+    ) -> Result<SpoolReporter<EncodeFn, SendFn>> {
+        let directory = directory.as_ref().to_path_buf();
+        fs::create_dir_all(&directory)?;
+        let spooled = spooled_files(&directory)?;
+        let sequence = spooled.last()
+            .and_then(|path| path.file_stem())
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.parse::<u64>().ok())
+            .map(|sequence| sequence + 1)
+            .unwrap_or(0);
+        let mut spool = SpoolReporter {
+            directory,
+            dropped: 0,
+            encode,
+            max_bytes: None,
+            max_files: None,
+            pending: !spooled.is_empty(),
+            send,
+            sequence,
+        };
+        spool.replay()?;
+        Ok(spool)
+    }
+
+    /// Limits the total size of the spooled payloads, dropping the oldest ones first.
+    pub fn max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Limits the number of spooled payloads, dropping the oldest ones first.
+    pub fn max_files(mut self, max_files: usize) -> Self {
+        self.max_files = Some(max_files);
+        self
+    }
+
+    /// Returns the number of spooled payloads dropped because the spool was full.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Reports a span, spooling it if it can't be sent.
+    ///
+    /// Spooled payloads are replayed first and, if any of them still fails,
+    /// the span is spooled without being sent to preserve the reporting order.
+    ///
+    /// Fails only if the spool directory can't be read or written.
+    #[allow(clippy::result_large_err)]
+    pub fn report(&mut self, span: FinishedSpan) -> Result<()> {
+        let payload = (self.encode)(&span);
+        if !self.replay()? || (self.send)(&payload).is_err() {
+            self.spool(&payload)?;
+        }
+        Ok(())
+    }
+
+    /// Sends the spooled payloads, oldest first, until one fails.
+    ///
+    /// Returns `true` if the spool is empty.
+    #[allow(clippy::result_large_err)]
+    pub fn replay(&mut self) -> Result<bool> {
+        if !self.pending {
+            return Ok(true);
+        }
+        for path in spooled_files(&self.directory)? {
+            let payload = fs::read(&path)?;
+            if (self.send)(&payload).is_err() {
+                return Ok(false);
+            }
+            fs::remove_file(&path)?;
+        }
+        self.pending = false;
+        Ok(true)
+    }
+
+    /// Writes a payload to the spool directory.
+    ///
+    /// Payloads are written to a temporary file first so partial writes are never replayed.
+    #[allow(clippy::result_large_err)]
+    fn spool(&mut self, payload: &[u8]) -> Result<()> {
+        let name = format!("{:020}", self.sequence);
+        let path = self.directory.join(&name).with_extension(SPOOL_EXTENSION);
+        let tmp = self.directory.join(&name).with_extension(SPOOL_TMP_EXTENSION);
+        fs::write(&tmp, payload)?;
+        fs::rename(&tmp, &path)?;
+        self.sequence += 1;
+        self.pending = true;
+        self.enforce_limits()
+    }
+
+    /// Removes the oldest spooled payloads until the spool is within its limits.
+    #[allow(clippy::result_large_err)]
+    fn enforce_limits(&mut self) -> Result<()> {
+        if self.max_bytes.is_none() && self.max_files.is_none() {
+            return Ok(());
+        }
+        let spooled = spooled_files(&self.directory)?;
+        let mut files = spooled.len();
+        let mut bytes = 0;
+        for path in &spooled {
+            bytes += fs::metadata(path)?.len();
+        }
+        for path in spooled {
+            let over_files = self.max_files.map(|max| files > max).unwrap_or(false);
+            let over_bytes = self.max_bytes.map(|max| bytes > max).unwrap_or(false);
+            if !over_files && !over_bytes {
+                break;
+            }
+            bytes -= fs::metadata(&path)?.len();
+            fs::remove_file(&path)?;
+            files -= 1;
+            self.dropped += 1;
+        }
+        Ok(())
+    }
+}
+
+
+/// Returns the paths of the spooled payloads, oldest first.
+#[allow(clippy::result_large_err)]
+fn spooled_files(directory: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path.extension().map(|ext| ext == SPOOL_EXTENSION).unwrap_or(false) {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::cell::RefCell;
+    use std::env;
+    use std::fs;
+    use std::process;
+
+    use super::super::super::FinishedSpan;
+    use super::super::super::tracers::NoopTracer;
+    use super::SpoolReporter;
+
+    fn make_span(name: &str) -> FinishedSpan {
+        let (tracer, receiver) = NoopTracer::new();
+        tracer.span(name).finish().unwrap();
+        receiver.recv().unwrap()
+    }
+
+    #[test]
+    fn spool_and_replay() {
+        let directory = env::temp_dir()
+            .join(format!("opentracingrust-spool-test-{}", process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        let available = Cell::new(false);
+        let sent = RefCell::new(Vec::new());
+        let encode = |span: &FinishedSpan| span.name().as_bytes().to_vec();
+        let send = |payload: &[u8]| {
+            if !available.get() {
+                return Err("unavailable");
+            }
+            sent.borrow_mut().push(String::from_utf8(payload.to_vec()).unwrap());
+            Ok(())
+        };
+        let mut spool = SpoolReporter::new(&directory, encode, send).unwrap();
+        spool.report(make_span("first")).unwrap();
+        spool.report(make_span("second")).unwrap();
+        assert!(!spool.replay().unwrap());
+        drop(spool);
+        assert_eq!(2, fs::read_dir(&directory).unwrap().count());
+
+        // Payloads spooled by a previous reporter are replayed first.
+        let mut spool = SpoolReporter::new(&directory, encode, send).unwrap();
+        available.set(true);
+        spool.report(make_span("third")).unwrap();
+        drop(spool);
+        assert_eq!(*sent.borrow(), ["first", "second", "third"]);
+        assert_eq!(0, fs::read_dir(&directory).unwrap().count());
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn drop_oldest_when_full() {
+        let directory = env::temp_dir()
+            .join(format!("opentracingrust-spool-limits-test-{}", process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        let available = Cell::new(false);
+        let sent = RefCell::new(Vec::new());
+        let encode = |span: &FinishedSpan| span.name().as_bytes().to_vec();
+        let send = |payload: &[u8]| {
+            if !available.get() {
+                return Err("unavailable");
+            }
+            sent.borrow_mut().push(String::from_utf8(payload.to_vec()).unwrap());
+            Ok(())
+        };
+        let mut spool = SpoolReporter::new(&directory, encode, send).unwrap()
+            .max_files(3)
+            .max_bytes(10);
+        for name in &["first", "second", "third", "fourth"] {
+            spool.report(make_span(name)).unwrap();
+        }
+        // "third" and "fourth" are 11 bytes so only "fourth" fits.
+        assert_eq!(3, spool.dropped());
+        drop(spool);
+
+        // Spooled payloads are replayed when the reporter is created.
+        available.set(true);
+        let spool = SpoolReporter::new(&directory, encode, send).unwrap();
+        assert_eq!(*sent.borrow(), ["fourth"]);
+        assert_eq!(0, spool.dropped());
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,205 @@
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::thread::Builder;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use std::time::Instant;
+
+use crossbeam_channel::RecvTimeoutError;
+use crossbeam_channel::Sender;
+use crossbeam_channel::unbounded;
+
+use super::super::Result;
+use super::super::Span;
+
+
+const CHECK_INTERVAL_MSEC_DEFAULT: u64 = 100;
+
+/// Tag set on spans finished by the `SpanWatchdog`.
+const FORCED_FINISH_TAG: &str = "timeout.forced_finish";
+
+
+/// A span that may be finished by the `SpanWatchdog` watching it.
+type SharedSlot = Arc<Mutex<Option<Span>>>;
+
+
+/// Finishes spans that run for longer than a maximum duration.
+///
+/// Operations that hang (or spans that are lost without being finished) are never
+/// reported and so never show up in the distributed tracer.
+/// Spans passed to `SpanWatchdog::watch` are instead finished by a background thread
+/// once they exceed the maximum duration and tagged with `timeout.forced_finish=true`.
+///
+/// The maximum duration is measured from the time the span starts being watched.
+/// Watched spans are checked for timeouts every 100 milliseconds by default.
+///
+/// When the watchdog is stopped or dropped the background thread is stopped
+/// and spans still running are no longer watched.
+///
+/// # Examples
+///
+/// ```
+/// extern crate opentracingrust;
+///
+/// use std::time::Duration;
+///
+/// use opentracingrust::tracers::NoopTracer;
+/// use opentracingrust::utils::SpanWatchdog;
+///
+///
+/// fn main() {
+///     let (tracer, _receiver) = NoopTracer::new();
+///     let watchdog = SpanWatchdog::new(Duration::from_secs(30));
+///     let span = watchdog.watch(tracer.span("job"));
+///     span.with(|span| span.tag("job.id", 42));
+///     // ... snip ...
+///     span.finish().unwrap();
+/// }
+/// ```
+pub struct SpanWatchdog {
+    max_duration: Duration,
+    spans: Arc<Mutex<Vec<(Instant, SharedSlot)>>>,
+    stop: Option<Sender<()>>,
+    thread_handle: Option<JoinHandle<()>>,
+}
+
+impl SpanWatchdog {
+    /// Creates a watchdog finishing spans running for longer than `max_duration`.
+    pub fn new(max_duration: Duration) -> SpanWatchdog {
+        let interval = Duration::from_millis(CHECK_INTERVAL_MSEC_DEFAULT);
+        SpanWatchdog::new_with_interval(max_duration, interval)
+    }
+
+    /// Version of `new` that also sets how often spans are checked for timeouts.
+    pub fn new_with_interval(max_duration: Duration, interval: Duration) -> SpanWatchdog {
+        let spans: Arc<Mutex<Vec<(Instant, SharedSlot)>>> = Arc::new(Mutex::new(Vec::new()));
+        let inner_spans = Arc::clone(&spans);
+        let (stop, stopping) = unbounded::<()>();
+        let thread = Builder::new().name("OpenTracingWatchdog".into()).spawn(move || {
+            loop {
+                match stopping.recv_timeout(interval) {
+                    Err(RecvTimeoutError::Timeout) => (),
+                    _ => return,
+                }
+                let now = Instant::now();
+                let mut spans = inner_spans.lock().expect("Failed to lock watched spans");
+                spans.retain(|(deadline, slot)| {
+                    let mut slot = slot.lock().expect("Failed to lock watched span");
+                    if *deadline <= now {
+                        if let Some(mut span) = slot.take() {
+                            span.tag(FORCED_FINISH_TAG, true);
+                            // There is no one to report the error to from the watchdog.
+                            let _ = span.finish();
+                        }
+                    }
+                    slot.is_some()
+                });
+            }
+        }).expect("Failed to spawn watchdog thread");
+        SpanWatchdog {
+            max_duration,
+            spans,
+            stop: Some(stop),
+            thread_handle: Some(thread),
+        }
+    }
+
+    /// Stops the background thread and joins it.
+    pub fn stop(&mut self) {
+        self.stop.take();
+        if let Some(thread) = self.thread_handle.take() {
+            thread.join().expect("Failed to join watchdog thread");
+        }
+    }
+
+    /// Watches the given span and returns a handle to operate on it.
+    pub fn watch(&self, span: Span) -> WatchedSpan {
+        let slot = Arc::new(Mutex::new(Some(span)));
+        let deadline = Instant::now() + self.max_duration;
+        self.spans.lock().expect("Failed to lock watched spans")
+            .push((deadline, Arc::clone(&slot)));
+        WatchedSpan { slot }
+    }
+}
+
+impl Drop for SpanWatchdog {
+    fn drop(&mut self) {
+        self.stop()
+    }
+}
+
+
+/// A span watched by a `SpanWatchdog`.
+///
+/// Once the watchdog finishes the span operations on it are ignored.
+pub struct WatchedSpan {
+    slot: SharedSlot,
+}
+
+impl WatchedSpan {
+    /// Finishes the span unless the watchdog already did.
+    #[allow(clippy::result_large_err)]
+    pub fn finish(self) -> Result<()> {
+        match self.slot.lock().expect("Failed to lock watched span").take() {
+            Some(span) => span.finish(),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks if the watchdog finished the span.
+    pub fn is_forced_finished(&self) -> bool {
+        self.slot.lock().expect("Failed to lock watched span").is_none()
+    }
+
+    /// Calls `block` with the span unless the watchdog finished it.
+    pub fn with<F, R>(&self, block: F) -> Option<R>
+        where F: FnOnce(&mut Span) -> R
+    {
+        self.slot.lock().expect("Failed to lock watched span").as_mut().map(block)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use std::time::Duration;
+
+    use super::super::super::TagValue;
+    use super::super::super::tracers::NoopTracer;
+
+    use super::SpanWatchdog;
+
+
+    #[test]
+    fn finishes_hung_spans() {
+        let (tracer, receiver) = NoopTracer::new();
+        let watchdog = SpanWatchdog::new_with_interval(
+            Duration::from_millis(10), Duration::from_millis(5)
+        );
+        let span = watchdog.watch(tracer.span("hung"));
+        let finished = receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        match finished.tags().get("timeout.forced_finish") {
+            Some(TagValue::Boolean(true)) => (),
+            _ => panic!("Forced finish not tagged"),
+        }
+        assert!(span.is_forced_finished());
+        assert!(span.with(|span| span.tag("ignored", true)).is_none());
+        span.finish().unwrap();
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn spans_finished_in_time() {
+        let (tracer, receiver) = NoopTracer::new();
+        let watchdog = SpanWatchdog::new_with_interval(
+            Duration::from_millis(20), Duration::from_millis(5)
+        );
+        let span = watchdog.watch(tracer.span("quick"));
+        span.finish().unwrap();
+        thread::sleep(Duration::from_millis(50));
+        let finished = receiver.try_recv().unwrap();
+        assert!(finished.tags().get("timeout.forced_finish").is_none());
+        assert!(receiver.try_recv().is_err());
+    }
+}